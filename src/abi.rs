@@ -0,0 +1,151 @@
+use crate::{to_mime, Bytes32, Constants, Descriptor, Matter, Rev, Vec, OID};
+use thiserror::Error;
+
+// Flat little-endian layouts for host function boundaries. Fixed-size types map to `[u8; N]`
+// and variable-size data to length-prefixed byte buffers (u32 LE length, then payload), which
+// both pass through `#[runtime_interface]` without a codec.
+
+#[derive(Clone, Copy, Debug, Error, PartialEq, Eq)]
+pub enum AbiError {
+	#[error("invalid buffer length: expected {expect} bytes, got {got} bytes")]
+	BadLength { expect: usize, got: usize },
+	#[error("buffer truncated")]
+	Truncated,
+	#[error("length {len} exceeds maximum of {max}")]
+	TooLarge { len: usize, max: usize },
+}
+
+pub const OID_FLAT_SIZE: usize = 24;
+pub const DESCRIPTOR_FLAT_SIZE: usize = 32;
+pub const LEN_PREFIX_SIZE: usize = 4;
+
+impl OID {
+	pub fn to_flat(&self) -> [u8; OID_FLAT_SIZE] {
+		let mut out = [0u8; OID_FLAT_SIZE];
+		out[0..8].copy_from_slice(&self.universe.to_le_bytes());
+		out[8..16].copy_from_slice(&self.set.to_le_bytes());
+		out[16..24].copy_from_slice(&self.id.to_le_bytes());
+		out
+	}
+
+	pub fn from_flat(buf: &[u8; OID_FLAT_SIZE]) -> Self {
+		OID {
+			universe: u64::from_le_bytes(buf[0..8].try_into().unwrap()),
+			set: u64::from_le_bytes(buf[8..16].try_into().unwrap()),
+			id: u64::from_le_bytes(buf[16..24].try_into().unwrap()),
+		}
+	}
+}
+
+impl Descriptor {
+	pub fn to_flat(&self) -> [u8; DESCRIPTOR_FLAT_SIZE] {
+		let mut out = [0u8; DESCRIPTOR_FLAT_SIZE];
+		out[0..4].copy_from_slice(&self.traits.to_le_bytes());
+		out[4..8].copy_from_slice(&self.rev.get().to_le_bytes());
+		out[8..12].copy_from_slice(&self.krev.get().to_le_bytes());
+		out[12..16].copy_from_slice(&self.srev.get().to_le_bytes());
+		out[16..24].copy_from_slice(&self.kind.to_le_bytes());
+		out[24..28].copy_from_slice(&self.trev.get().to_le_bytes());
+		out[28..32].copy_from_slice(&self.fsum.to_le_bytes());
+		out
+	}
+
+	pub fn from_flat(buf: &[u8; DESCRIPTOR_FLAT_SIZE]) -> Self {
+		let u32_at = |i: usize| u32::from_le_bytes(buf[i..i + 4].try_into().unwrap());
+		Descriptor {
+			traits: u32_at(0),
+			rev: Rev(u32_at(4)),
+			krev: Rev(u32_at(8)),
+			srev: Rev(u32_at(12)),
+			kind: u64::from_le_bytes(buf[16..24].try_into().unwrap()),
+			trev: Rev(u32_at(24)),
+			fsum: u32_at(28),
+		}
+	}
+}
+
+/// `[count: u32][count * 32 bytes]`; fails past `ELEM_SPEC_CAPACITY`, which `decode_elems`
+/// rejects.
+pub fn encode_elems(elems: &[Bytes32]) -> Result<Vec<u8>, AbiError> {
+	if elems.len() > Constants::ELEM_SPEC_CAPACITY {
+		return Err(AbiError::TooLarge { len: elems.len(), max: Constants::ELEM_SPEC_CAPACITY });
+	}
+	let mut out = Vec::with_capacity(LEN_PREFIX_SIZE + elems.len() * 32);
+	out.extend_from_slice(&(elems.len() as u32).to_le_bytes());
+	for elem in elems {
+		out.extend_from_slice(elem);
+	}
+	Ok(out)
+}
+
+pub fn decode_elems(buf: &[u8]) -> Result<Vec<Bytes32>, AbiError> {
+	let (count, body) = split_len_prefix(buf)?;
+	if count > Constants::ELEM_SPEC_CAPACITY {
+		return Err(AbiError::TooLarge { len: count, max: Constants::ELEM_SPEC_CAPACITY });
+	}
+	let expect = count * 32;
+	if body.len() != expect {
+		return Err(AbiError::BadLength { expect, got: body.len() });
+	}
+	Ok(body.chunks_exact(32).map(|c| c.try_into().unwrap()).collect())
+}
+
+/// `[form: u8][mime: 31 bytes][len: u32][blob]`
+pub fn encode_matter(matter: &Matter) -> Vec<u8> {
+	let mut out =
+		Vec::with_capacity(Constants::MATTER_SPEC_SIZE + LEN_PREFIX_SIZE + matter.blob.len());
+	out.push(matter.form);
+	out.extend_from_slice(matter.mime.as_bytes());
+	out.extend_from_slice(&(matter.blob.len() as u32).to_le_bytes());
+	out.extend_from_slice(&matter.blob);
+	out
+}
+
+pub fn decode_matter(buf: &[u8]) -> Result<Matter, AbiError> {
+	let spec = buf.get(..Constants::MATTER_SPEC_SIZE).ok_or(AbiError::Truncated)?;
+	let (len, blob) = split_len_prefix(&buf[Constants::MATTER_SPEC_SIZE..])?;
+	if len > Constants::MATTER_BLOB_MAX {
+		return Err(AbiError::TooLarge { len, max: Constants::MATTER_BLOB_MAX });
+	}
+	if blob.len() != len {
+		return Err(AbiError::BadLength { expect: len, got: blob.len() });
+	}
+	Ok(Matter { form: spec[0], mime: to_mime(&spec[1..]), blob: blob.to_vec() })
+}
+
+fn split_len_prefix(buf: &[u8]) -> Result<(usize, &[u8]), AbiError> {
+	let prefix = buf.get(..LEN_PREFIX_SIZE).ok_or(AbiError::Truncated)?;
+	let len = u32::from_le_bytes(prefix.try_into().unwrap()) as usize;
+	Ok((len, &buf[LEN_PREFIX_SIZE..]))
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{decode_elems, encode_elems, AbiError, Constants};
+
+	#[test]
+	fn elems_round_trip() {
+		let elems = vec![[1; 32], [2; 32], [3; 32]];
+		let buf = encode_elems(&elems).unwrap();
+		assert_eq!(buf.len(), 4 + 3 * 32);
+		assert_eq!(decode_elems(&buf), Ok(elems));
+		assert_eq!(decode_elems(&encode_elems(&[]).unwrap()), Ok(vec![]));
+		assert_eq!(
+			decode_elems(&buf[..buf.len() - 1]),
+			Err(AbiError::BadLength { expect: 96, got: 95 })
+		);
+		assert_eq!(decode_elems(&buf[..3]), Err(AbiError::Truncated));
+	}
+
+	#[test]
+	fn elems_over_capacity() {
+		let max = Constants::ELEM_SPEC_CAPACITY;
+		let full = vec![[7; 32]; max];
+		assert_eq!(decode_elems(&encode_elems(&full).unwrap()), Ok(full));
+		let over = vec![[7; 32]; max + 1];
+		assert_eq!(encode_elems(&over), Err(AbiError::TooLarge { len: max + 1, max }));
+		let mut buf = ((max + 1) as u32).to_le_bytes().to_vec();
+		buf.extend_from_slice(&over.concat());
+		assert_eq!(decode_elems(&buf), Err(AbiError::TooLarge { len: max + 1, max }));
+	}
+}
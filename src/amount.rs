@@ -0,0 +1,252 @@
+use crate::{Bytes32, Unique, Value};
+use core::{
+	cmp::Ordering,
+	fmt,
+	hash::{Hash, Hasher},
+};
+use thiserror::Error;
+
+pub use primitive_types::U256;
+
+#[derive(Clone, Copy, Debug, Error, PartialEq, Eq)]
+pub enum AmountError {
+	#[error("amounts with {left} and {right} decimals")]
+	DecimalsMismatch { left: u8, right: u8 },
+	#[error("amount overflow")]
+	Overflow,
+	#[error("amount underflow")]
+	Underflow,
+	#[error("rescaling drops nonzero digits")]
+	Inexact,
+}
+
+/// A quantity of `raw` base units with `decimals` fractional digits, as held in `Value.data`
+/// and `Unique.data` (big-endian). Amounts compare and hash by the quantity, so `1.0` with one
+/// decimal equals `1` with none.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Amount {
+	raw: U256,
+	decimals: u8,
+}
+
+impl Amount {
+	pub const fn new(raw: U256, decimals: u8) -> Self {
+		Self { raw, decimals }
+	}
+
+	pub fn from_bytes(bytes: &Bytes32, decimals: u8) -> Self {
+		Self::new(U256::from_big_endian(bytes), decimals)
+	}
+
+	pub fn to_bytes(&self) -> Bytes32 {
+		self.raw.to_big_endian()
+	}
+
+	pub fn raw(&self) -> U256 {
+		self.raw
+	}
+
+	pub fn decimals(&self) -> u8 {
+		self.decimals
+	}
+
+	pub fn is_zero(&self) -> bool {
+		self.raw.is_zero()
+	}
+
+	pub fn checked_add(self, other: Amount) -> Result<Amount, AmountError> {
+		let rhs = self.same_scale(other)?;
+		let raw = self.raw.checked_add(rhs).ok_or(AmountError::Overflow)?;
+		Ok(Self::new(raw, self.decimals))
+	}
+
+	pub fn checked_sub(self, other: Amount) -> Result<Amount, AmountError> {
+		let rhs = self.same_scale(other)?;
+		let raw = self.raw.checked_sub(rhs).ok_or(AmountError::Underflow)?;
+		Ok(Self::new(raw, self.decimals))
+	}
+
+	/// Scales by a plain factor, keeping the decimals.
+	pub fn checked_mul(self, factor: impl Into<U256>) -> Result<Amount, AmountError> {
+		let raw = self.raw.checked_mul(factor.into()).ok_or(AmountError::Overflow)?;
+		Ok(Self::new(raw, self.decimals))
+	}
+
+	/// The same quantity with `decimals` fractional digits. Fails with `Inexact` when that
+	/// would drop nonzero digits; see `rescale_floor`.
+	pub fn rescale(self, decimals: u8) -> Result<Amount, AmountError> {
+		let out = self.rescale_floor(decimals)?;
+		if decimals < self.decimals && out.rescale_floor(self.decimals)? != self {
+			return Err(AmountError::Inexact);
+		}
+		Ok(out)
+	}
+
+	/// `rescale`, rounding down when digits are dropped.
+	pub fn rescale_floor(self, decimals: u8) -> Result<Amount, AmountError> {
+		let raw = if decimals >= self.decimals {
+			let scale = pow10(decimals - self.decimals)?;
+			self.raw.checked_mul(scale).ok_or(AmountError::Overflow)?
+		} else {
+			// 10^78 and up exceed U256, so every digit goes.
+			pow10(self.decimals - decimals).map_or(U256::zero(), |scale| self.raw / scale)
+		};
+		Ok(Self::new(raw, decimals))
+	}
+
+	fn same_scale(&self, other: Amount) -> Result<U256, AmountError> {
+		if self.decimals != other.decimals {
+			return Err(AmountError::DecimalsMismatch {
+				left: self.decimals,
+				right: other.decimals,
+			});
+		}
+		Ok(other.raw)
+	}
+}
+
+impl Ord for Amount {
+	fn cmp(&self, other: &Self) -> Ordering {
+		if self.decimals > other.decimals {
+			return other.cmp(self).reverse();
+		}
+		if self.raw.is_zero() {
+			return U256::zero().cmp(&other.raw);
+		}
+		// Scale up the side with fewer decimals; past U256 it is the larger one.
+		match self.rescale_floor(other.decimals) {
+			Ok(scaled) => scaled.raw.cmp(&other.raw),
+			Err(_) => Ordering::Greater,
+		}
+	}
+}
+
+impl PartialOrd for Amount {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+impl PartialEq for Amount {
+	fn eq(&self, other: &Self) -> bool {
+		self.cmp(other) == Ordering::Equal
+	}
+}
+
+impl Eq for Amount {}
+
+impl Hash for Amount {
+	fn hash<H: Hasher>(&self, state: &mut H) {
+		// Equal quantities hash alike: drop trailing fractional zeros first.
+		let (mut raw, mut decimals) = (self.raw, self.decimals);
+		if raw.is_zero() {
+			decimals = 0;
+		}
+		while decimals > 0 && (raw % 10).is_zero() {
+			raw /= 10;
+			decimals -= 1;
+		}
+		raw.hash(state);
+		decimals.hash(state);
+	}
+}
+
+fn pow10(exp: u8) -> Result<U256, AmountError> {
+	U256::from(10).checked_pow(U256::from(exp)).ok_or(AmountError::Overflow)
+}
+
+/// Decimal with trailing fractional zeros dropped, such as `12.5` or `3`.
+impl fmt::Display for Amount {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let (int, mut frac) = match pow10(self.decimals) {
+			Ok(scale) => (self.raw / scale, self.raw % scale),
+			// Below one whole unit; every digit is fractional.
+			Err(_) => (U256::zero(), self.raw),
+		};
+		if frac.is_zero() {
+			return write!(f, "{int}");
+		}
+		let mut width = self.decimals as usize;
+		while (frac % 10).is_zero() {
+			frac /= 10;
+			width -= 1;
+		}
+		write!(f, "{int}.{frac:0>width$}")
+	}
+}
+
+impl Value {
+	pub fn amount(&self) -> Amount {
+		Amount::from_bytes(&self.data, self.decimals)
+	}
+
+	/// Stores `amount` rescaled to this value's decimals.
+	pub fn set_amount(&mut self, amount: Amount) -> Result<(), AmountError> {
+		self.data = amount.rescale(self.decimals)?.to_bytes();
+		Ok(())
+	}
+}
+
+impl Unique {
+	pub fn amount(&self) -> Amount {
+		Amount::from_bytes(&self.data, self.decimals)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{Amount, AmountError, Value, U256};
+
+	#[test]
+	fn adds_rescales_and_displays() {
+		let a = Amount::new(U256::from(1_250u64), 2);
+		let b = Amount::new(U256::from(5u64), 2);
+		assert_eq!(a.checked_add(b).unwrap().to_string(), "12.55");
+		assert_eq!(a.checked_sub(b).unwrap().checked_mul(2u64).unwrap().to_string(), "24.9");
+		assert_eq!(b.checked_sub(a), Err(AmountError::Underflow));
+		assert_eq!(
+			a.checked_add(Amount::new(U256::one(), 6)),
+			Err(AmountError::DecimalsMismatch { left: 2, right: 6 })
+		);
+		assert_eq!(Amount::new(U256::MAX, 0).checked_mul(2u64), Err(AmountError::Overflow));
+
+		assert_eq!(a.rescale(6).unwrap().raw(), U256::from(12_500_000u64));
+		assert_eq!(a.rescale(1).unwrap().to_string(), "12.5");
+		assert_eq!(a.rescale(0), Err(AmountError::Inexact));
+		assert_eq!(a.rescale_floor(0).unwrap().to_string(), "12");
+		assert_eq!(Amount::new(U256::from(7u64), 3).to_string(), "0.007");
+		assert_eq!(Amount::new(U256::zero(), 3).to_string(), "0");
+		// 10^80 exceeds U256, so every digit is fractional.
+		assert_eq!(Amount::new(U256::zero(), 80).to_string(), "0");
+		let tiny = format!("0.{}25", "0".repeat(78));
+		assert_eq!(Amount::new(U256::from(2_500u64), 82).to_string(), tiny);
+
+		let mut value = Value {
+			std: 0,
+			decimals: 18,
+			symbol: Default::default(),
+			code: [0; 32],
+			data: [0; 32],
+		};
+		value.set_amount(a).unwrap();
+		assert_eq!(value.amount().to_string(), "12.5");
+		assert_eq!(value.data[31], 0);
+	}
+
+	#[test]
+	fn compares_across_decimals() {
+		use std::collections::HashSet;
+		let amount = |raw: u64, decimals| Amount::new(U256::from(raw), decimals);
+		assert_eq!(amount(10, 1), amount(1, 0));
+		assert_eq!(amount(0, 0), amount(0, 90));
+		assert!(amount(15, 1) < amount(2, 0));
+		assert!(amount(3, 0) > amount(299, 2));
+		assert!(amount(1, 3) < amount(1, 2));
+		// 1 scaled to 80 decimals exceeds U256, and so exceeds anything held there.
+		assert!(amount(1, 0) > Amount::new(U256::MAX, 80));
+		assert!(Amount::new(U256::MAX, 80) < amount(1, 0));
+
+		let set: HashSet<_> = [amount(1, 0), amount(100, 2), amount(0, 5), amount(0, 0)].into();
+		assert_eq!(set.len(), 2);
+	}
+}
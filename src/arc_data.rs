@@ -0,0 +1,102 @@
+use crate::Arc;
+use thiserror::Error;
+
+#[cfg(feature = "scale")]
+use codec::{Decode, DecodeWithMemTracking, Encode, MaxEncodedLen};
+#[cfg(feature = "scale")]
+use scale_info::TypeInfo;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, Error, PartialEq, Eq)]
+pub enum ArcDataError {
+	#[error("reserved arc data bits are set: {0:#018x}")]
+	ReservedBits(u64),
+}
+
+/// Structured view of `Arc::data`.
+///
+/// Bits, from most significant: slot (8), flags (8), reserved (16, zero), weight (32).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "scale", derive(Encode, Decode, TypeInfo, DecodeWithMemTracking))]
+#[cfg_attr(feature = "scale", derive(MaxEncodedLen))]
+pub struct ArcData {
+	/// Position of the tail among the head's attachments, e.g. an equipment slot.
+	pub slot: u8,
+	pub flags: u8,
+	pub weight: u32,
+}
+
+impl ArcData {
+	/// The attachment cannot be removed by the tail owner alone.
+	pub const LOCKED: u8 = 0x01;
+	/// The attachment is hidden from default views.
+	pub const HIDDEN: u8 = 0x02;
+
+	const RESERVED_MASK: u64 = 0x0000_FFFF_0000_0000;
+
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	pub fn with_slot(self, slot: u8) -> Self {
+		Self { slot, ..self }
+	}
+
+	pub fn with_flags(self, flags: u8) -> Self {
+		Self { flags, ..self }
+	}
+
+	pub fn with_weight(self, weight: u32) -> Self {
+		Self { weight, ..self }
+	}
+
+	pub fn has(&self, flag: u8) -> bool {
+		self.flags & flag == flag
+	}
+
+	pub fn decode(v: u64) -> Result<Self, ArcDataError> {
+		if v & Self::RESERVED_MASK != 0 {
+			return Err(ArcDataError::ReservedBits(v & Self::RESERVED_MASK));
+		}
+		Ok(Self { slot: (v >> 56) as u8, flags: (v >> 48) as u8, weight: v as u32 })
+	}
+
+	pub fn encode(&self) -> u64 {
+		(self.slot as u64) << 56 | (self.flags as u64) << 48 | self.weight as u64
+	}
+}
+
+impl From<ArcData> for u64 {
+	fn from(data: ArcData) -> Self {
+		data.encode()
+	}
+}
+
+impl TryFrom<u64> for ArcData {
+	type Error = ArcDataError;
+	fn try_from(v: u64) -> Result<Self, Self::Error> {
+		ArcData::decode(v)
+	}
+}
+
+impl Arc {
+	pub fn arc_data(&self) -> Result<ArcData, ArcDataError> {
+		ArcData::decode(self.data)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{ArcData, ArcDataError};
+
+	#[test]
+	fn arc_data_roundtrip() {
+		let data = ArcData::new().with_slot(3).with_flags(ArcData::LOCKED).with_weight(500);
+		assert_eq!(data.encode(), 0x0301_0000_0000_01F4);
+		assert_eq!(ArcData::decode(data.encode()), Ok(data));
+		assert!(data.has(ArcData::LOCKED) && !data.has(ArcData::HIDDEN));
+		assert_eq!(ArcData::decode(1 << 40), Err(ArcDataError::ReservedBits(1 << 40)));
+	}
+}
@@ -0,0 +1,118 @@
+use crate::{Matter, StateReader, Vec, H256};
+use thiserror::Error;
+
+#[derive(Clone, Copy, Debug, Error, PartialEq, Eq)]
+pub enum ArchiveFault {
+	#[error("matter not found")]
+	Missing,
+	#[error("blob length mismatch: expected {expect} bytes, got {got} bytes")]
+	LengthMismatch { expect: u64, got: u64 },
+	#[error("content hash mismatch: got {got:02x?}")]
+	HashMismatch { got: H256 },
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ArchiveReport {
+	pub checked: usize,
+	pub faults: Vec<(H256, ArchiveFault)>, // (manifest hash, fault), manifest order
+}
+
+impl ArchiveReport {
+	#[inline]
+	pub fn is_ok(&self) -> bool {
+		self.faults.is_empty()
+	}
+
+	#[inline]
+	pub fn passed(&self) -> usize {
+		self.checked - self.faults.len()
+	}
+}
+
+/// Checks every `(hash, blob_len)` manifest entry against `reader`.
+///
+/// `hasher` computes the content hash of a loaded matter, so the check follows whatever hashing
+/// scheme the archive was produced with. `progress` is called after each entry with
+/// `(done, total)`.
+pub fn verify_archive<E, S, H, P>(
+	manifest: &[(H256, u64)],
+	reader: &S,
+	hasher: H,
+	mut progress: P,
+) -> ArchiveReport
+where
+	S: StateReader<E>,
+	H: Fn(&Matter) -> H256,
+	P: FnMut(usize, usize),
+{
+	let total = manifest.len();
+	let mut report = ArchiveReport::default();
+	for (i, (hash, len)) in manifest.iter().enumerate() {
+		if let Err(fault) = verify_one(hash, *len, reader, &hasher) {
+			report.faults.push((*hash, fault));
+		}
+		report.checked += 1;
+		progress(i + 1, total);
+	}
+	report
+}
+
+fn verify_one<E, S, H>(hash: &H256, len: u64, reader: &S, hasher: &H) -> Result<(), ArchiveFault>
+where
+	S: StateReader<E>,
+	H: Fn(&Matter) -> H256,
+{
+	let matter = reader.get_matter(hash).map_err(|_| ArchiveFault::Missing)?;
+	let got = matter.blob.len() as u64;
+	if got != len {
+		return Err(ArchiveFault::LengthMismatch { expect: len, got });
+	}
+	let got = hasher(&matter);
+	if got != *hash {
+		return Err(ArchiveFault::HashMismatch { got });
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{to_mime, verify_archive, ArchiveFault, Matter, Prefetched, H256};
+
+	// Every byte of the content hash is the wrapping sum of the blob.
+	fn sum_hash(matter: &Matter) -> H256 {
+		[matter.blob.iter().fold(0u8, |a, b| a.wrapping_add(*b)); 32]
+	}
+
+	#[test]
+	fn reports_faults_in_manifest_order() {
+		let matter = |blob: Vec<u8>| Matter { form: 0xD0, mime: to_mime(b"x"), blob };
+		let mut state = Prefetched::default();
+		// Intact, truncated and corrupted in place; 4 is intact but only checked on its own.
+		state.insert_matter([6; 32], matter(vec![1, 2, 3]));
+		state.insert_matter([7; 32], matter(vec![3, 4]));
+		state.insert_matter([9; 32], matter(vec![1, 2, 5]));
+		state.insert_matter([4; 32], matter(vec![2, 2]));
+		let manifest = [([6; 32], 3), ([7; 32], 3), ([8; 32], 3), ([9; 32], 3), ([5; 32], 2)];
+		let mut calls = vec![];
+		let report =
+			verify_archive(&manifest, &state, sum_hash, |done, total| calls.push((done, total)));
+		assert_eq!(
+			report.faults,
+			vec![
+				([7; 32], ArchiveFault::LengthMismatch { expect: 3, got: 2 }),
+				([8; 32], ArchiveFault::Missing),
+				([9; 32], ArchiveFault::HashMismatch { got: [8; 32] }),
+				([5; 32], ArchiveFault::Missing),
+			]
+		);
+		assert_eq!((report.checked, report.passed(), report.is_ok()), (5, 1, false));
+		assert_eq!(calls, (1..=5).map(|i| (i, 5)).collect::<Vec<_>>());
+
+		let report = verify_archive(&manifest[..1], &state, sum_hash, |_, _| {});
+		assert!(report.is_ok());
+		let report = verify_archive(&[([4; 32], 2)], &state, sum_hash, |_, _| {});
+		assert!(report.is_ok());
+		let report = verify_archive(&[([4; 32], 2)], &state, |_: &Matter| [0; 32], |_, _| {});
+		assert_eq!(report.faults, vec![([4; 32], ArchiveFault::HashMismatch { got: [0; 32] })]);
+	}
+}
@@ -0,0 +1,268 @@
+use crate::{
+	state::OidRev, trace::span, Arc, Bytes32, CollectionMatter, Descriptor, ElementError,
+	ElementPicker, Facet, KindSpec, Matter, MatterCache, OidRange, Rev, SetSpec, StateReader, Time,
+	Unique, Value, Vec, H256, OID,
+};
+use sp_std::collections::btree_map::{self, BTreeMap};
+use thiserror::Error;
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+/// Outcome of resolving one object of a batch.
+pub type BatchResult = Result<Vec<Bytes32>, ElementError>;
+
+/// A read `Prefetched` cannot serve.
+#[derive(Clone, Copy, Debug, Error, PartialEq, Eq)]
+#[error("not prefetched")]
+pub struct NotPrefetched;
+
+/// One read `ElementPicker::resolve` makes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReadRequest {
+	Matter(H256),
+	Snapshot(OID, Rev),
+	/// The collection matter named by the set spec in this snapshot, known once the snapshot is
+	/// read.
+	SetCollection(OID, Rev),
+	/// As `SetCollection`, for a kind spec.
+	KindCollection(OID, Rev),
+}
+
+/// Matters and snapshots read up front by `ElementPicker::prefetch`. It never changes once
+/// built, so it can be read from many threads.
+#[derive(Debug, Clone, Default)]
+pub struct Prefetched {
+	matters: BTreeMap<H256, Matter>,
+	snapshots: BTreeMap<OidRev, (Descriptor, Vec<Bytes32>)>,
+}
+
+impl Prefetched {
+	pub fn matter_count(&self) -> usize {
+		self.matters.len()
+	}
+
+	pub fn snapshot_count(&self) -> usize {
+		self.snapshots.len()
+	}
+
+	pub fn insert_matter(&mut self, hash: H256, matter: Matter) {
+		self.matters.insert(hash, matter);
+	}
+
+	pub fn insert_snapshot(&mut self, oid: &OID, rev: Rev, desc: Descriptor, elems: Vec<Bytes32>) {
+		self.snapshots.insert(OidRev::new(oid, rev), (desc, elems));
+	}
+
+	/// Reads `read` from `state` unless it is already here, along with the snapshot a collection
+	/// read depends on.
+	pub fn fetch<E, S: StateReader<E>>(&mut self, state: &S, read: &ReadRequest) {
+		let coll = match read {
+			ReadRequest::Matter(hash) => Some(*hash),
+			ReadRequest::Snapshot(oid, rev) => {
+				self.fetch_snapshot(state, oid, *rev);
+				None
+			},
+			ReadRequest::SetCollection(oid, rev) => self
+				.fetch_snapshot(state, oid, *rev)
+				.and_then(|elems| SetSpec::from_elems(elems).ok().map(|set| *set.collection())),
+			ReadRequest::KindCollection(oid, rev) => self
+				.fetch_snapshot(state, oid, *rev)
+				.and_then(|elems| KindSpec::from_elems(elems).ok().map(|kind| *kind.collection())),
+		};
+		if let Some(hash) = coll {
+			self.fetch_matter(state, &hash);
+		}
+	}
+
+	fn fetch_matter<E, S: StateReader<E>>(&mut self, state: &S, hash: &H256) {
+		if !self.matters.contains_key(hash) {
+			if let Ok(matter) = state.get_matter(hash) {
+				self.matters.insert(*hash, matter);
+			}
+		}
+	}
+
+	fn fetch_snapshot<E, S: StateReader<E>>(
+		&mut self,
+		state: &S,
+		oid: &OID,
+		rev: Rev,
+	) -> Option<&[Bytes32]> {
+		let key = OidRev::new(oid, rev);
+		let snapshot = match self.snapshots.entry(key) {
+			btree_map::Entry::Occupied(e) => e.into_mut(),
+			btree_map::Entry::Vacant(e) => e.insert(state.get_snapshot(oid, rev).ok()?),
+		};
+		Some(&snapshot.1)
+	}
+}
+
+impl StateReader<NotPrefetched> for Prefetched {
+	fn get_matter(&self, hash: &H256) -> Result<Matter, NotPrefetched> {
+		self.matters.get(hash).cloned().ok_or(NotPrefetched)
+	}
+
+	fn get_value(&self, _tid: &OID, _rev: Rev) -> Result<Value, NotPrefetched> {
+		Err(NotPrefetched)
+	}
+
+	fn get_unique(&self, _tid: &OID, _rev: Rev) -> Result<Unique, NotPrefetched> {
+		Err(NotPrefetched)
+	}
+
+	fn get_descriptor(&self, oid: &OID, rev: Rev) -> Result<Descriptor, NotPrefetched> {
+		self.get_snapshot(oid, rev).map(|(desc, _)| desc)
+	}
+
+	fn get_snapshot(
+		&self,
+		oid: &OID,
+		rev: Rev,
+	) -> Result<(Descriptor, Vec<Bytes32>), NotPrefetched> {
+		let key = OidRev::new(oid, rev);
+		self.snapshots.get(&key).cloned().ok_or(NotPrefetched)
+	}
+
+	fn get_snapshot_at(
+		&self,
+		_oid: &OID,
+		_time: Time,
+	) -> Result<(Descriptor, Vec<Bytes32>), NotPrefetched> {
+		Err(NotPrefetched)
+	}
+
+	fn get_tails(&self, _oid: &OID, _rev: Rev) -> Result<Vec<Arc>, NotPrefetched> {
+		Err(NotPrefetched)
+	}
+
+	fn get_heads(&self, _oid: &OID, _rev: Rev) -> Result<Vec<Arc>, NotPrefetched> {
+		Err(NotPrefetched)
+	}
+
+	fn get_facets(&self, _oid: &OID, _rev: Rev) -> Result<Vec<Facet>, NotPrefetched> {
+		Err(NotPrefetched)
+	}
+
+	fn get_facet(&self, _oid: &OID, _rev: Rev, _sel: u32) -> Result<Matter, NotPrefetched> {
+		Err(NotPrefetched)
+	}
+
+	fn get_kind_contract(&self, _oid: &OID, _rev: Rev) -> Result<Matter, NotPrefetched> {
+		Err(NotPrefetched)
+	}
+}
+
+impl ElementPicker {
+	/// `resolve` for each of `objects`, in order, parsing every collection matter once.
+	pub fn resolve_batch<E, S: StateReader<E>>(
+		&self,
+		state: &S,
+		objects: &[(OID, Descriptor)],
+	) -> Vec<BatchResult> {
+		span!("resolve_batch", objects = objects.len());
+		let cache = MatterCache::<CollectionMatter>::new(usize::MAX);
+		objects
+			.iter()
+			.map(|(oid, desc)| self.resolve_cached(state, oid, desc, &cache))
+			.collect()
+	}
+
+	/// `resolve_batch` for the objects of `range`, all at `desc`, such as a freshly minted run.
+	pub fn resolve_range<E, S: StateReader<E>>(
+		&self,
+		state: &S,
+		range: OidRange,
+		desc: &Descriptor,
+	) -> Vec<BatchResult> {
+		let objects: Vec<_> = range.map(|oid| (oid, desc.clone())).collect();
+		self.resolve_batch(state, &objects)
+	}
+
+	/// `resolve_batch` on the rayon pool. `state` is read once through `prefetch`, the objects
+	/// are resolved in parallel against that, and schema matter checks then read `state` in
+	/// order.
+	#[cfg(feature = "rayon")]
+	pub fn par_resolve_batch<E, S: StateReader<E>>(
+		&self,
+		state: &S,
+		objects: &[(OID, Descriptor)],
+	) -> Vec<BatchResult> {
+		span!("par_resolve_batch", objects = objects.len());
+		let ctx = self.prefetch(state, objects);
+		let picked: Vec<BatchResult> = objects
+			.par_iter()
+			.map_init(
+				|| MatterCache::<CollectionMatter>::new(usize::MAX),
+				|cache, (oid, desc)| self.pick_elems(&ctx, oid, desc, Some(cache)),
+			)
+			.collect();
+		picked
+			.into_iter()
+			.map(|elems| {
+				let elems = elems?;
+				self.check_schema(state, &elems)?;
+				Ok(elems)
+			})
+			.collect()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{
+		test_vectors::{ENUM_BLOB, ENUM_MATTER_HASH, ENUM_ROW_0, HERE_COLLECTION_FLAGS},
+		to_mime, Descriptor, ElementError, ElementPicker, Matter, Mime, OidRange, PickFrom,
+		PickerFlags, Prefetched, ReadRequest, Rev, OID,
+	};
+
+	#[test]
+	fn resolves_batches() {
+		let mut state = Prefetched::default();
+		let blob = ENUM_BLOB.to_vec();
+		let matter = Matter { form: 208, mime: Mime::ENUM, blob };
+		state.matters.insert(ENUM_MATTER_HASH, matter);
+
+		let picker = ElementPicker::new(HERE_COLLECTION_FLAGS, vec![ENUM_MATTER_HASH]).unwrap();
+		let desc = Descriptor { rev: Rev(1), ..Default::default() };
+		let objects: Vec<_> = [1, 2, 1000]
+			.into_iter()
+			.map(|id| (OID { universe: 1, set: 17, id }, desc.clone()))
+			.collect();
+		let out = picker.resolve_batch(&state, &objects);
+		assert_eq!(out[0], Ok(ENUM_ROW_0.to_vec()));
+		assert!(out[1].is_ok());
+		assert_eq!(out[2], Err(ElementError::EnumMatterRowAt));
+		let minted = OidRange::mint(OID::of_set(1, 17), 1, 2).unwrap();
+		assert_eq!(picker.resolve_range(&state, minted, &desc), out[..2]);
+
+		let ctx = picker.prefetch(&state, &objects);
+		assert_eq!((ctx.matter_count(), ctx.snapshot_count()), (1, 0));
+		#[cfg(feature = "rayon")]
+		assert_eq!(picker.par_resolve_batch(&state, &objects), out);
+	}
+
+	#[test]
+	fn plans_reads() {
+		let oid = OID { universe: 1, set: 17, id: 3 };
+		let desc = Descriptor { rev: Rev(2), srev: Rev(4), kind: 9, ..Default::default() };
+		let flags = |src| PickerFlags::new().with_row_from(src).encode();
+		let reads =
+			|src| ElementPicker::new(flags(src), vec![]).unwrap().required_reads(&oid, &desc);
+
+		assert_eq!(reads(PickFrom::HereElements), vec![]);
+		assert_eq!(reads(PickFrom::ObjectData), vec![ReadRequest::Snapshot(oid, Rev(1))]);
+		assert_eq!(
+			reads(PickFrom::SetData),
+			vec![
+				ReadRequest::Snapshot(oid.set_oid(), Rev(4)),
+				ReadRequest::SetCollection(oid.set_oid(), Rev(4)),
+			]
+		);
+
+		let mut ctx = Prefetched::default();
+		ctx.insert_snapshot(&oid, Rev(1), desc.clone(), vec![[7; 32]]);
+		let picker = ElementPicker::new(flags(PickFrom::ObjectData), vec![]).unwrap();
+		assert_eq!(picker.resolve(&ctx, &oid, &desc), Ok(vec![[7; 32]]));
+	}
+}
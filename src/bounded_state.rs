@@ -0,0 +1,149 @@
+#![cfg(feature = "scale")]
+
+//! Stored lists with their capacity in the type, so `MaxEncodedLen` follows from the bound
+//! instead of being computed by hand.
+
+use crate::{
+	state::{Arcs, Facets, Snapshot},
+	Arc, Constants, Descriptor, Facet, H256,
+};
+use bounded_collections::{BoundedVec, ConstU32, Get};
+use codec::{Decode, DecodeWithMemTracking, Encode, MaxEncodedLen};
+use scale_info::TypeInfo;
+use thiserror::Error;
+
+pub use bounded_collections;
+
+pub type ElemLimit = ConstU32<{ Constants::ELEM_SPEC_CAPACITY as u32 }>;
+pub type TailLimit = ConstU32<{ Constants::TAIL_CAPACITY as u32 }>;
+pub type FacetLimit = ConstU32<{ Constants::FACET_CAPACITY as u32 }>;
+
+#[derive(Clone, Copy, Debug, Error, PartialEq, Eq)]
+#[error("{len} items exceed the bound of {bound}")]
+pub struct BoundExceeded {
+	pub len: usize,
+	pub bound: u32,
+}
+
+fn bounded<T, S: Get<u32>>(items: crate::Vec<T>) -> Result<BoundedVec<T, S>, BoundExceeded> {
+	let len = items.len();
+	BoundedVec::try_from(items).map_err(|_| BoundExceeded { len, bound: S::get() })
+}
+
+#[derive(Debug, Clone, Encode, Decode, DecodeWithMemTracking, TypeInfo, MaxEncodedLen)]
+#[scale_info(skip_type_params(S))]
+#[codec(mel_bound(S: Get<u32>))]
+pub struct BoundedSnapshot<S: Get<u32> = ElemLimit> {
+	pub desc: Descriptor,
+	pub mt: u128,
+	pub elems: BoundedVec<H256, S>,
+}
+
+#[derive(Debug, Clone, Encode, Decode, DecodeWithMemTracking, TypeInfo, MaxEncodedLen)]
+#[scale_info(skip_type_params(S))]
+#[codec(mel_bound(S: Get<u32>))]
+pub struct BoundedArcs<S: Get<u32> = TailLimit> {
+	pub arcs: BoundedVec<Arc, S>,
+}
+
+#[derive(Debug, Clone, Encode, Decode, DecodeWithMemTracking, TypeInfo, MaxEncodedLen)]
+#[scale_info(skip_type_params(S))]
+#[codec(mel_bound(S: Get<u32>))]
+pub struct BoundedFacets<S: Get<u32> = FacetLimit> {
+	pub facets: BoundedVec<Facet, S>,
+}
+
+// The bounds only limit length, so values compare by content whatever the bound type.
+
+impl<S: Get<u32>> PartialEq for BoundedSnapshot<S> {
+	fn eq(&self, other: &Self) -> bool {
+		(&self.desc, self.mt, &self.elems) == (&other.desc, other.mt, &other.elems)
+	}
+}
+
+impl<S: Get<u32>> PartialEq for BoundedArcs<S> {
+	fn eq(&self, other: &Self) -> bool {
+		self.arcs == other.arcs
+	}
+}
+
+impl<S: Get<u32>> PartialEq for BoundedFacets<S> {
+	fn eq(&self, other: &Self) -> bool {
+		self.facets == other.facets
+	}
+}
+
+impl<S: Get<u32>> TryFrom<Snapshot> for BoundedSnapshot<S> {
+	type Error = BoundExceeded;
+
+	fn try_from(snapshot: Snapshot) -> Result<Self, BoundExceeded> {
+		let Snapshot { desc, mt, elems } = snapshot;
+		Ok(Self { desc, mt, elems: bounded(elems)? })
+	}
+}
+
+impl<S: Get<u32>> TryFrom<Arcs> for BoundedArcs<S> {
+	type Error = BoundExceeded;
+
+	fn try_from(arcs: Arcs) -> Result<Self, BoundExceeded> {
+		Ok(Self { arcs: bounded(arcs.arcs)? })
+	}
+}
+
+impl<S: Get<u32>> TryFrom<Facets> for BoundedFacets<S> {
+	type Error = BoundExceeded;
+
+	fn try_from(facets: Facets) -> Result<Self, BoundExceeded> {
+		Ok(Self { facets: bounded(facets.facets)? })
+	}
+}
+
+impl<S: Get<u32>> From<BoundedSnapshot<S>> for Snapshot {
+	fn from(snapshot: BoundedSnapshot<S>) -> Self {
+		Snapshot { desc: snapshot.desc, mt: snapshot.mt, elems: snapshot.elems.into_inner() }
+	}
+}
+
+impl<S: Get<u32>> From<BoundedArcs<S>> for Arcs {
+	fn from(arcs: BoundedArcs<S>) -> Self {
+		Arcs { arcs: arcs.arcs.into_inner() }
+	}
+}
+
+impl<S: Get<u32>> From<BoundedFacets<S>> for Facets {
+	fn from(facets: BoundedFacets<S>) -> Self {
+		Facets { facets: facets.facets.into_inner() }
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{
+		bounded_state::{BoundExceeded, BoundedArcs, BoundedFacets, BoundedSnapshot},
+		state::{Arcs, Facets, Snapshot},
+		Facet,
+	};
+	use bounded_collections::ConstU32;
+	use codec::{Decode, Encode, MaxEncodedLen};
+
+	#[test]
+	fn converts_and_matches_unbounded_layout() {
+		assert_eq!(<BoundedSnapshot>::max_encoded_len(), Snapshot::max_encoded_len());
+		assert_eq!(<BoundedArcs>::max_encoded_len(), Arcs::max_encoded_len());
+		assert_eq!(<BoundedFacets>::max_encoded_len(), Facets::max_encoded_len());
+
+		let snapshot = Snapshot { mt: 5, elems: vec![[1; 32], [2; 32]], ..Default::default() };
+		let bounded = BoundedSnapshot::<ConstU32<2>>::try_from(snapshot.clone()).unwrap();
+		assert_eq!(bounded.encode(), snapshot.encode());
+		assert_eq!(Snapshot::from(bounded), snapshot);
+		assert_eq!(
+			BoundedSnapshot::<ConstU32<1>>::try_from(snapshot.clone()),
+			Err(BoundExceeded { len: 2, bound: 1 })
+		);
+
+		let facets = Facets { facets: vec![Facet { sel: 1, hash: [3; 32] }; 3] };
+		assert!(BoundedFacets::<ConstU32<2>>::try_from(facets.clone()).is_err());
+		let decoded = BoundedFacets::<ConstU32<2>>::decode(&mut &facets.encode()[..]);
+		assert!(decoded.is_err());
+	}
+}
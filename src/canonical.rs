@@ -0,0 +1,104 @@
+use crate::{
+	state::{Snapshot, Sota},
+	Descriptor, Vec,
+};
+
+// Byte layouts for commitments and signatures. They are frozen: unlike the SCALE encoding they
+// do not follow field changes, and a new layout needs a new method. Integers are big-endian and
+// fields are packed with no padding or tags.
+
+pub const DESCRIPTOR_CANONICAL_SIZE: usize = 32;
+pub const SOTA_CANONICAL_SIZE: usize = 96;
+
+impl Descriptor {
+	/// `traits: u32 ‖ rev: u32 ‖ krev: u32 ‖ srev: u32 ‖ kind: u64 ‖ trev: u32 ‖ fsum: u32`
+	pub fn canonical_bytes(&self) -> [u8; DESCRIPTOR_CANONICAL_SIZE] {
+		let mut out = [0u8; DESCRIPTOR_CANONICAL_SIZE];
+		out[0..4].copy_from_slice(&self.traits.to_be_bytes());
+		out[4..8].copy_from_slice(&self.rev.get().to_be_bytes());
+		out[8..12].copy_from_slice(&self.krev.get().to_be_bytes());
+		out[12..16].copy_from_slice(&self.srev.get().to_be_bytes());
+		out[16..24].copy_from_slice(&self.kind.to_be_bytes());
+		out[24..28].copy_from_slice(&self.trev.get().to_be_bytes());
+		out[28..32].copy_from_slice(&self.fsum.to_be_bytes());
+		out
+	}
+}
+
+impl Sota {
+	/// `desc: 32 bytes ‖ owner: 32 bytes ‖ pos: u128 ‖ mt: u128`
+	pub fn canonical_bytes(&self) -> [u8; SOTA_CANONICAL_SIZE] {
+		let mut out = [0u8; SOTA_CANONICAL_SIZE];
+		out[0..32].copy_from_slice(&self.desc.canonical_bytes());
+		out[32..64].copy_from_slice(self.owner.as_bytes());
+		out[64..80].copy_from_slice(&self.pos.to_be_bytes());
+		out[80..96].copy_from_slice(&self.mt.to_be_bytes());
+		out
+	}
+}
+
+impl Snapshot {
+	/// `desc: 32 bytes ‖ mt: u128 ‖ count: u32 ‖ count × 32-byte elements`
+	pub fn canonical_bytes(&self) -> Vec<u8> {
+		let mut out = Vec::with_capacity(DESCRIPTOR_CANONICAL_SIZE + 20 + self.elems.len() * 32);
+		out.extend_from_slice(&self.desc.canonical_bytes());
+		out.extend_from_slice(&self.mt.to_be_bytes());
+		out.extend_from_slice(&(self.elems.len() as u32).to_be_bytes());
+		self.elems.iter().for_each(|elem| out.extend_from_slice(elem));
+		out
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{
+		state::{Snapshot, Sota},
+		Descriptor, Owner, Rev,
+	};
+
+	fn desc() -> Descriptor {
+		Descriptor {
+			traits: 0x0102_0304,
+			rev: Rev(5),
+			krev: Rev(6),
+			srev: Rev(7),
+			kind: 0x0809_0a0b_0c0d_0e0f,
+			trev: Rev(0x10),
+			fsum: 0x1112_1314,
+		}
+	}
+
+	// Golden vectors: a change here breaks every commitment made with the old layout.
+	#[test]
+	fn golden_vectors() {
+		assert_eq!(
+			hex::encode(desc().canonical_bytes()),
+			"0102030400000005000000060000000708090a0b0c0d0e0f0000001011121314"
+		);
+
+		let sota = Sota { desc: desc(), owner: Owner([0xaa; 32]), pos: 1 << 64 | 2, mt: 3 };
+		assert_eq!(
+			hex::encode(sota.canonical_bytes()),
+			[
+				"0102030400000005000000060000000708090a0b0c0d0e0f0000001011121314",
+				&"aa".repeat(32),
+				"00000000000000010000000000000002",
+				"00000000000000000000000000000003",
+			]
+			.concat()
+		);
+
+		let snapshot = Snapshot { desc: desc(), mt: 0x99, elems: vec![[0x11; 32], [0x22; 32]] };
+		assert_eq!(
+			hex::encode(snapshot.canonical_bytes()),
+			[
+				"0102030400000005000000060000000708090a0b0c0d0e0f0000001011121314",
+				"00000000000000000000000000000099",
+				"00000002",
+				&"11".repeat(32),
+				&"22".repeat(32),
+			]
+			.concat()
+		);
+	}
+}
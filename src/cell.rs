@@ -0,0 +1,142 @@
+use crate::Bytes32;
+use core::fmt;
+use thiserror::Error;
+
+#[derive(Clone, Copy, Debug, Error, PartialEq, Eq)]
+pub enum HexError {
+	#[error("expected {expect} hex digits, got {got}")]
+	Length { expect: usize, got: usize },
+	#[error("invalid hex digit at {0}")]
+	Digit(usize),
+}
+
+/// Decodes `N` bytes of hex, with or without `0x`, in either case. A `const fn`, which is what
+/// lets `bytes32!` build constants.
+pub const fn parse_hex<const N: usize>(s: &str) -> Result<[u8; N], HexError> {
+	let mut out = [0u8; N];
+	match parse_hex_into(s, &mut out) {
+		Ok(()) => Ok(out),
+		Err(e) => Err(e),
+	}
+}
+
+/// `parse_hex` into a buffer of any length, such as a matter blob; `s` must fill it exactly.
+pub const fn parse_hex_into(s: &str, out: &mut [u8]) -> Result<(), HexError> {
+	const fn nibble(c: u8) -> Option<u8> {
+		match c {
+			b'0'..=b'9' => Some(c - b'0'),
+			b'a'..=b'f' => Some(c - b'a' + 10),
+			b'A'..=b'F' => Some(c - b'A' + 10),
+			_ => None,
+		}
+	}
+	let s = s.as_bytes();
+	let skip = if s.len() >= 2 && s[0] == b'0' && s[1] == b'x' { 2 } else { 0 };
+	if s.len() - skip != out.len() * 2 {
+		return Err(HexError::Length { expect: out.len() * 2, got: s.len() - skip });
+	}
+	let mut i = skip;
+	while i < s.len() {
+		let Some(hi) = nibble(s[i]) else { return Err(HexError::Digit(i)) };
+		let Some(lo) = nibble(s[i + 1]) else { return Err(HexError::Digit(i + 1)) };
+		out[(i - skip) / 2] = (hi << 4) | lo;
+		i += 2;
+	}
+	Ok(())
+}
+
+/// A `Bytes32` from 64 hex digits, optionally `0x`-prefixed. Usable in constants, where bad
+/// input fails the build.
+#[macro_export]
+macro_rules! bytes32 {
+	($hex:expr) => {
+		match $crate::parse_hex::<32>($hex) {
+			Ok(bytes) => bytes,
+			Err(_) => panic!("bytes32!: expected 64 hex digits"),
+		}
+	};
+}
+
+/// Displays bytes as `0x`-prefixed lowercase hex.
+pub struct Hex<'a>(pub &'a [u8]);
+
+impl fmt::Display for Hex<'_> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str("0x")?;
+		self.0.iter().try_for_each(|b| write!(f, "{b:02x}"))
+	}
+}
+
+/// Building and printing `Bytes32` cells.
+pub trait CellExt: Sized {
+	fn hex(&self) -> Hex<'_>;
+
+	#[cfg(feature = "std")]
+	fn to_hex(&self) -> String {
+		self.hex().to_string()
+	}
+
+	fn from_hex(s: &str) -> Result<Self, HexError>;
+
+	/// `v` in the last 8 bytes, zero-padded on the left, as a `uint256` holds it.
+	fn from_u64_be(v: u64) -> Self;
+
+	/// `v` in the last 16 bytes, zero-padded on the left.
+	fn from_u128_be(v: u128) -> Self;
+
+	/// `v` in the first 8 bytes, zero-padded on the right.
+	fn from_u64_be_prefix(v: u64) -> Self;
+}
+
+impl CellExt for Bytes32 {
+	fn hex(&self) -> Hex<'_> {
+		Hex(self)
+	}
+
+	fn from_hex(s: &str) -> Result<Self, HexError> {
+		parse_hex(s)
+	}
+
+	fn from_u64_be(v: u64) -> Self {
+		let mut cell = [0u8; 32];
+		cell[24..].copy_from_slice(&v.to_be_bytes());
+		cell
+	}
+
+	fn from_u128_be(v: u128) -> Self {
+		let mut cell = [0u8; 32];
+		cell[16..].copy_from_slice(&v.to_be_bytes());
+		cell
+	}
+
+	fn from_u64_be_prefix(v: u64) -> Self {
+		let mut cell = [0u8; 32];
+		cell[..8].copy_from_slice(&v.to_be_bytes());
+		cell
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{parse_hex, parse_hex_into, Bytes32, CellExt, HexError};
+
+	const ONE: Bytes32 =
+		bytes32!("0x0000000000000000000000000000000000000000000000000000000000000001");
+
+	#[test]
+	fn parses_and_prints() {
+		assert_eq!(ONE, Bytes32::from_u64_be(1));
+		assert_eq!(Bytes32::from_u128_be(1), ONE);
+		assert_eq!(Bytes32::from_u64_be_prefix(1)[7], 1);
+		let text = format!("0x{}", "Ab".repeat(32));
+		assert_eq!(Bytes32::from_hex(&text), Ok([0xab; 32]));
+		assert_eq!(Bytes32::from_hex(&text[2..]), Ok([0xab; 32]));
+		assert_eq!([0xab; 32].to_hex(), text.to_lowercase());
+		assert_eq!(parse_hex::<2>("0x12"), Err(HexError::Length { expect: 4, got: 2 }));
+		assert_eq!(parse_hex::<1>("0g"), Err(HexError::Digit(1)));
+		let mut blob = [0u8; 3];
+		assert_eq!(parse_hex_into("0x0a0B0c", &mut blob), Ok(()));
+		assert_eq!(blob, [10, 11, 12]);
+		assert_eq!(parse_hex_into("0a0b", &mut blob), Err(HexError::Length { expect: 6, got: 4 }));
+	}
+}
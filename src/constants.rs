@@ -31,6 +31,9 @@ impl Constants {
 	pub const REV_NEW: u32 = 1;
 	pub const REV_DESTROYED: u32 = u32::MAX;
 
+	// Storage layout version, see `migrations`
+	pub const STORAGE_VERSION: u16 = 1;
+
 	// Capacities
 	pub const ELEM_SPEC_CAPACITY: usize = 16;
 	pub const REL_SPEC_CAPACITY: usize = 8;
@@ -0,0 +1,251 @@
+#![cfg(feature = "ipfs")]
+
+//! DAG-CBOR blocks and CIDv1 for mirroring matters and snapshots to IPFS.
+//!
+//! A matter becomes two blocks: its blob as a `raw` block and a DAG-CBOR block holding `form`,
+//! `mime` and a link to the blob. CIDs use a caller-chosen [`Multihasher`]; with the function
+//! the chain hashes blobs with, a blob CID's digest is the on-chain H256 itself.
+
+use crate::{slice_from_fixed, state::Snapshot, Descriptor, Matter, Vec, H256};
+use core::fmt;
+
+/// Multicodec of DAG-CBOR blocks.
+pub const DAG_CBOR: u64 = 0x71;
+/// Multicodec of raw bytes.
+pub const RAW: u64 = 0x55;
+
+/// A 32-byte hash function with its multihash code.
+pub trait Multihasher {
+	/// e.g. 0x12 for sha2-256, 0x1b for keccak-256, 0xb220 for blake2b-256.
+	const CODE: u64;
+	fn digest(data: &[u8]) -> H256;
+}
+
+/// A version 1 content identifier with a 32-byte digest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cid {
+	pub codec: u64,
+	pub hash_code: u64,
+	pub digest: H256,
+}
+
+impl Cid {
+	pub fn of<H: Multihasher>(codec: u64, data: &[u8]) -> Self {
+		Cid { codec, hash_code: H::CODE, digest: H::digest(data) }
+	}
+
+	/// `version ‖ codec ‖ hash code ‖ digest length ‖ digest`, the numbers as unsigned varints.
+	pub fn to_bytes(&self) -> Vec<u8> {
+		let mut out = Vec::with_capacity(40);
+		for n in [1, self.codec, self.hash_code, 32] {
+			put_varint(&mut out, n);
+		}
+		out.extend_from_slice(&self.digest);
+		out
+	}
+}
+
+/// Multibase base32 (`b` prefix, lowercase, no padding), the usual text form of CIDv1.
+impl fmt::Display for Cid {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		const ALPHABET: &[u8; 32] = b"abcdefghijklmnopqrstuvwxyz234567";
+		f.write_str("b")?;
+		let (mut acc, mut bits) = (0u32, 0);
+		for b in self.to_bytes() {
+			acc = (acc << 8) | b as u32;
+			bits += 8;
+			while bits >= 5 {
+				bits -= 5;
+				write!(f, "{}", ALPHABET[(acc >> bits) as usize & 31] as char)?;
+			}
+		}
+		if bits > 0 {
+			write!(f, "{}", ALPHABET[(acc << (5 - bits)) as usize & 31] as char)?;
+		}
+		Ok(())
+	}
+}
+
+/// An encoded block and its CID.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Block {
+	pub cid: Cid,
+	pub data: Vec<u8>,
+}
+
+impl Block {
+	pub fn raw<H: Multihasher>(data: Vec<u8>) -> Self {
+		Block { cid: Cid::of::<H>(RAW, &data), data }
+	}
+
+	pub fn dag_cbor<H: Multihasher>(data: Vec<u8>) -> Self {
+		Block { cid: Cid::of::<H>(DAG_CBOR, &data), data }
+	}
+}
+
+impl Matter {
+	/// The DAG-CBOR block `{blob: <link>, form, mime}` followed by the raw blob block it links.
+	pub fn to_dag_cbor<H: Multihasher>(&self) -> [Block; 2] {
+		let blob = Block::raw::<H>(self.blob.clone());
+		let mut out = Vec::new();
+		put_head(&mut out, MAP, 3);
+		put_text(&mut out, "blob");
+		put_link(&mut out, &blob.cid);
+		put_text(&mut out, "form");
+		put_head(&mut out, UINT, self.form as u64);
+		put_text(&mut out, "mime");
+		put_head(&mut out, TEXT, slice_from_fixed(self.mime.as_bytes()).len() as u64);
+		out.extend_from_slice(slice_from_fixed(self.mime.as_bytes()));
+		[Block::dag_cbor::<H>(out), blob]
+	}
+}
+
+impl Snapshot {
+	/// `{mt, desc, elems}`, with `mt` as 16 big-endian bytes since CBOR integers stop at 64 bits
+	/// and each element as 32 bytes.
+	pub fn to_dag_cbor<H: Multihasher>(&self) -> Block {
+		let mut out = Vec::new();
+		put_head(&mut out, MAP, 3);
+		put_text(&mut out, "mt");
+		put_bytes(&mut out, &self.mt.to_be_bytes());
+		put_text(&mut out, "desc");
+		put_descriptor(&mut out, &self.desc);
+		put_text(&mut out, "elems");
+		put_head(&mut out, ARRAY, self.elems.len() as u64);
+		self.elems.iter().for_each(|elem| put_bytes(&mut out, elem));
+		Block::dag_cbor::<H>(out)
+	}
+}
+
+const UINT: u8 = 0;
+const BYTES: u8 = 2;
+const TEXT: u8 = 3;
+const ARRAY: u8 = 4;
+const MAP: u8 = 5;
+const TAG: u8 = 6;
+/// CBOR tag of IPLD links.
+const CID_TAG: u64 = 42;
+
+/// Map keys must be written sorted by length, then bytewise.
+fn put_descriptor(out: &mut Vec<u8>, desc: &Descriptor) {
+	put_head(out, MAP, 7);
+	let fields = [
+		("rev", desc.rev.get() as u64),
+		("fsum", desc.fsum as u64),
+		("kind", desc.kind),
+		("krev", desc.krev.get() as u64),
+		("srev", desc.srev.get() as u64),
+		("trev", desc.trev.get() as u64),
+		("traits", desc.traits as u64),
+	];
+	for (key, value) in fields {
+		put_text(out, key);
+		put_head(out, UINT, value);
+	}
+}
+
+/// Shortest-form head, as DAG-CBOR requires.
+fn put_head(out: &mut Vec<u8>, major: u8, n: u64) {
+	let major = major << 5;
+	match n {
+		0..=23 => out.push(major | n as u8),
+		24..=0xff => out.extend_from_slice(&[major | 24, n as u8]),
+		0x100..=0xffff => {
+			out.push(major | 25);
+			out.extend_from_slice(&(n as u16).to_be_bytes());
+		},
+		0x1_0000..=0xffff_ffff => {
+			out.push(major | 26);
+			out.extend_from_slice(&(n as u32).to_be_bytes());
+		},
+		_ => {
+			out.push(major | 27);
+			out.extend_from_slice(&n.to_be_bytes());
+		},
+	}
+}
+
+fn put_text(out: &mut Vec<u8>, text: &str) {
+	put_head(out, TEXT, text.len() as u64);
+	out.extend_from_slice(text.as_bytes());
+}
+
+fn put_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+	put_head(out, BYTES, bytes.len() as u64);
+	out.extend_from_slice(bytes);
+}
+
+/// Tag 42 over the CID bytes with the identity multibase prefix 0x00.
+fn put_link(out: &mut Vec<u8>, cid: &Cid) {
+	let cid = cid.to_bytes();
+	put_head(out, TAG, CID_TAG);
+	put_head(out, BYTES, cid.len() as u64 + 1);
+	out.push(0);
+	out.extend_from_slice(&cid);
+}
+
+fn put_varint(out: &mut Vec<u8>, mut n: u64) {
+	while n >= 0x80 {
+		out.push(n as u8 | 0x80);
+		n >>= 7;
+	}
+	out.push(n as u8);
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{
+		dag_cbor::{Cid, Multihasher, DAG_CBOR, RAW},
+		state::Snapshot,
+		to_mime, Matter,
+	};
+
+	/// Identity-like stand-in: the digest is the data, zero-padded.
+	struct Pad;
+
+	impl Multihasher for Pad {
+		const CODE: u64 = 0xb220;
+
+		fn digest(data: &[u8]) -> [u8; 32] {
+			crate::to_fixed(&data[..data.len().min(32)])
+		}
+	}
+
+	#[test]
+	fn encodes_blocks_and_cids() {
+		let cid = Cid { codec: RAW, hash_code: 0x12, digest: [0; 32] };
+		assert_eq!(hex::encode(cid.to_bytes()), format!("01551220{}", "00".repeat(32)));
+		assert_eq!(cid.to_string(), format!("bafkreia{}", "a".repeat(51)));
+		let cid = Cid { codec: DAG_CBOR, hash_code: 0xb220, digest: [0; 32] };
+		assert_eq!(hex::encode(&cid.to_bytes()[..5]), "0171a0e402");
+
+		let matter = Matter { form: 1, mime: to_mime(b"image/png"), blob: vec![1, 2, 3] };
+		let [block, blob] = matter.to_dag_cbor::<Pad>();
+		assert_eq!(blob.data, vec![1, 2, 3]);
+		assert_eq!(
+			blob.cid,
+			Cid { codec: RAW, hash_code: 0xb220, digest: Pad::digest(&[1, 2, 3]) }
+		);
+		let mut link = vec![0xd8, 42, 0x58, 39, 0];
+		link.extend_from_slice(&blob.cid.to_bytes());
+		let expect = [
+			&[0xa3, 0x64][..],
+			b"blob",
+			&link,
+			&[0x64],
+			b"form",
+			&[0x01, 0x64],
+			b"mime",
+			&[0x69],
+			b"image/png",
+		]
+		.concat();
+		assert_eq!(block.data, expect);
+		assert_eq!(block.cid.codec, DAG_CBOR);
+
+		let snapshot = Snapshot { mt: 1, elems: vec![[7; 32]], ..Default::default() };
+		let data = snapshot.to_dag_cbor::<Pad>().data;
+		assert_eq!(&data[..4], &[0xa3, 0x62, b'm', b't']);
+		assert_eq!(&data[data.len() - 35..data.len() - 32], &[0x81, 0x58, 32]);
+	}
+}
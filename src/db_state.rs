@@ -0,0 +1,377 @@
+#![cfg(all(feature = "std", feature = "storage"))]
+
+use crate::{
+	reader::{BlockRef, StateProvider, StateReaderAt},
+	writer::{StateWriter, WriteError},
+	H256,
+};
+use std::{cell::RefCell, collections::BTreeMap};
+use thiserror::Error;
+
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+#[error("kv backend error: {0}")]
+pub struct KvError(pub String);
+
+/// Why `DbState` cannot serve a block through `StateReaderAt::at`.
+#[derive(Clone, Copy, Debug, Error, PartialEq, Eq)]
+pub enum AtBlockError {
+	#[error("no head block is recorded")]
+	NoHead,
+	#[error("only head block {head} is stored, not {block:?}")]
+	NotHead { block: BlockRef, head: u64 },
+}
+
+/// Changes for `KvWrite::write`; `None` removes the key.
+pub type KvBatch = Vec<(Vec<u8>, Option<Vec<u8>>)>;
+
+pub trait KvRead {
+	fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, KvError>;
+	/// The smallest key strictly after `key`.
+	fn next_key(&self, key: &[u8]) -> Result<Option<Vec<u8>>, KvError>;
+}
+
+pub trait KvWrite: KvRead {
+	/// Applies all changes atomically.
+	fn write(&mut self, batch: KvBatch) -> Result<(), KvError>;
+}
+
+pub trait KvSnapshot: KvRead {
+	type Snapshot<'a>: KvRead
+	where
+		Self: 'a;
+
+	/// A read-only view of the store as it is now, unaffected by later writes.
+	fn snapshot(&self) -> Self::Snapshot<'_>;
+}
+
+/// Every state kept in a local key-value store under the same keys the pallet uses.
+///
+/// `StateProvider` cannot report backend failures, so the first one is kept and reads fail as
+/// missing until it is taken with `take_error`. A failed batch write also fails `apply_batch`
+/// with `WriteError::Backend` and keeps its `KvError` the same way.
+///
+/// Only the latest state is kept. An indexer records which block that is with `set_head`, and
+/// `StateReaderAt::at` serves that block from a snapshot; other blocks fail with
+/// `AtBlockError::NotHead`.
+pub struct DbState<K> {
+	kv: K,
+	head: Option<(u64, H256)>,
+	error: RefCell<Option<KvError>>,
+}
+
+impl<K> DbState<K> {
+	pub fn new(kv: K) -> Self {
+		Self { kv, head: None, error: RefCell::new(None) }
+	}
+
+	/// Number and hash of the block whose state the store holds.
+	pub fn head(&self) -> Option<(u64, H256)> {
+		self.head
+	}
+
+	pub fn set_head(&mut self, number: u64, hash: H256) {
+		self.head = Some((number, hash));
+	}
+
+	pub fn kv(&self) -> &K {
+		&self.kv
+	}
+
+	pub fn into_inner(self) -> K {
+		self.kv
+	}
+
+	pub fn take_error(&mut self) -> Option<KvError> {
+		self.error.get_mut().take()
+	}
+
+	fn check<T>(&self, result: Result<Option<T>, KvError>) -> Option<T> {
+		match result {
+			Ok(value) => value,
+			Err(err) => {
+				self.error.borrow_mut().get_or_insert(err);
+				None
+			},
+		}
+	}
+}
+
+impl<K: KvSnapshot> DbState<K> {
+	pub fn snapshot(&self) -> DbState<K::Snapshot<'_>> {
+		DbState::new(self.kv.snapshot())
+	}
+}
+
+impl<K: KvSnapshot> StateReaderAt for DbState<K> {
+	type Error = AtBlockError;
+	type View<'a>
+		= DbState<K::Snapshot<'a>>
+	where
+		K: 'a;
+
+	/// Serves the head block only. Fails with `AtBlockError::NoHead` before `set_head` and with
+	/// `AtBlockError::NotHead` for any other block, older states not being kept.
+	fn at(&mut self, block: BlockRef) -> Result<DbState<K::Snapshot<'_>>, AtBlockError> {
+		let (number, hash) = self.head.ok_or(AtBlockError::NoHead)?;
+		let is_head = match block {
+			BlockRef::Number(n) => n == number,
+			BlockRef::Hash(h) => h == hash,
+		};
+		if !is_head {
+			return Err(AtBlockError::NotHead { block, head: number });
+		}
+		let mut view = self.snapshot();
+		view.head = self.head;
+		Ok(view)
+	}
+}
+
+impl<K: KvRead> StateProvider for DbState<K> {
+	fn _get(&self, key: &[u8]) -> Option<Vec<u8>> {
+		self.check(self.kv.get(key))
+	}
+
+	fn _next_key(&self, key: &[u8]) -> Option<Vec<u8>> {
+		self.check(self.kv.next_key(key))
+	}
+}
+
+impl<K: KvWrite> StateWriter for DbState<K> {
+	fn _put(&mut self, key: &[u8], value: Vec<u8>) {
+		let _ = self._commit(BTreeMap::from([(key.to_vec(), Some(value))]));
+	}
+
+	fn _remove(&mut self, key: &[u8]) {
+		let _ = self._commit(BTreeMap::from([(key.to_vec(), None)]));
+	}
+
+	fn _commit(&mut self, changes: BTreeMap<Vec<u8>, Option<Vec<u8>>>) -> Result<(), WriteError> {
+		self.kv.write(changes.into_iter().collect()).map_err(|err| {
+			self.error.get_mut().get_or_insert(err);
+			WriteError::Backend
+		})
+	}
+}
+
+/// In-memory store, for tests and short-lived tools.
+#[derive(Clone, Debug, Default)]
+pub struct MemKv(pub BTreeMap<Vec<u8>, Vec<u8>>);
+
+impl KvRead for MemKv {
+	fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, KvError> {
+		Ok(self.0.get(key).cloned())
+	}
+
+	fn next_key(&self, key: &[u8]) -> Result<Option<Vec<u8>>, KvError> {
+		let after = (std::ops::Bound::Excluded(key), std::ops::Bound::Unbounded);
+		Ok(self.0.range::<[u8], _>(after).next().map(|(k, _)| k.clone()))
+	}
+}
+
+impl KvWrite for MemKv {
+	fn write(&mut self, batch: KvBatch) -> Result<(), KvError> {
+		for (key, value) in batch {
+			match value {
+				Some(value) => self.0.insert(key, value),
+				None => self.0.remove(&key),
+			};
+		}
+		Ok(())
+	}
+}
+
+impl KvSnapshot for MemKv {
+	type Snapshot<'a> = MemKv;
+
+	fn snapshot(&self) -> MemKv {
+		self.clone()
+	}
+}
+
+/// sled has no snapshots, so only reads and batch writes are supported.
+#[cfg(feature = "sled")]
+mod sled_kv {
+	use super::{KvBatch, KvError, KvRead, KvWrite};
+	use std::ops::Bound;
+
+	impl From<sled::Error> for KvError {
+		fn from(err: sled::Error) -> Self {
+			KvError(err.to_string())
+		}
+	}
+
+	impl KvRead for sled::Tree {
+		fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, KvError> {
+			Ok(sled::Tree::get(self, key)?.map(|v| v.to_vec()))
+		}
+
+		fn next_key(&self, key: &[u8]) -> Result<Option<Vec<u8>>, KvError> {
+			match self.range::<&[u8], _>((Bound::Excluded(key), Bound::Unbounded)).next() {
+				Some(entry) => Ok(Some(entry?.0.to_vec())),
+				None => Ok(None),
+			}
+		}
+	}
+
+	impl KvWrite for sled::Tree {
+		fn write(&mut self, batch: KvBatch) -> Result<(), KvError> {
+			let mut out = sled::Batch::default();
+			for (key, value) in batch {
+				match value {
+					Some(value) => out.insert(key, value),
+					None => out.remove(key),
+				}
+			}
+			Ok(self.apply_batch(out)?)
+		}
+	}
+}
+
+#[cfg(feature = "rocksdb")]
+mod rocksdb_kv {
+	use super::{KvBatch, KvError, KvRead, KvSnapshot, KvWrite};
+	use rocksdb::{DBAccess, DBIteratorWithThreadMode, Direction, IteratorMode, WriteBatch, DB};
+
+	impl From<rocksdb::Error> for KvError {
+		fn from(err: rocksdb::Error) -> Self {
+			KvError(err.into_string())
+		}
+	}
+
+	fn first_after<D: DBAccess>(
+		iter: DBIteratorWithThreadMode<'_, D>,
+		key: &[u8],
+	) -> Result<Option<Vec<u8>>, KvError> {
+		// The iterator starts at `key` itself when it is stored.
+		for entry in iter {
+			let (k, _) = entry?;
+			if &*k != key {
+				return Ok(Some(k.into_vec()));
+			}
+		}
+		Ok(None)
+	}
+
+	impl KvRead for DB {
+		fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, KvError> {
+			Ok(DB::get(self, key)?)
+		}
+
+		fn next_key(&self, key: &[u8]) -> Result<Option<Vec<u8>>, KvError> {
+			first_after(self.iterator(IteratorMode::From(key, Direction::Forward)), key)
+		}
+	}
+
+	impl KvWrite for DB {
+		fn write(&mut self, batch: KvBatch) -> Result<(), KvError> {
+			let mut out = WriteBatch::default();
+			for (key, value) in batch {
+				match value {
+					Some(value) => out.put(key, value),
+					None => out.delete(key),
+				}
+			}
+			Ok(DB::write(self, out)?)
+		}
+	}
+
+	impl KvSnapshot for DB {
+		type Snapshot<'a> = rocksdb::Snapshot<'a>;
+
+		fn snapshot(&self) -> rocksdb::Snapshot<'_> {
+			DB::snapshot(self)
+		}
+	}
+
+	impl KvRead for rocksdb::Snapshot<'_> {
+		fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, KvError> {
+			Ok(rocksdb::Snapshot::get(self, key)?)
+		}
+
+		fn next_key(&self, key: &[u8]) -> Result<Option<Vec<u8>>, KvError> {
+			first_after(self.iterator(IteratorMode::From(key, Direction::Forward)), key)
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{
+		db_state::{AtBlockError, DbState, KvBatch, KvError, KvRead, KvWrite, MemKv},
+		reader::{BlockRef, StateError, StateProvider, StateReaderAt},
+		state::{ObjectKey, ObjectValue, OidRev, Sota},
+		writer::{StateWriter, WriteError, WriteOp},
+		Descriptor, Rev, StateReader, OID,
+	};
+
+	fn put_sota(id: u64, rev: u32) -> WriteOp {
+		let desc = Descriptor { rev: Rev(rev), ..Default::default() };
+		let key = ObjectKey::Sota(OidRev::new(&OID { universe: 1, set: 2, id }, Rev::LATEST));
+		WriteOp::PutObject(key, ObjectValue::Sota(Sota { desc, ..Default::default() }))
+	}
+
+	#[test]
+	fn writes_batches_and_reads_snapshots() {
+		let mut db = DbState::new(MemKv::default());
+		db.apply_batch(vec![put_sota(3, 1), put_sota(4, 1)]).unwrap();
+		let before = db.snapshot();
+		db.apply_batch(vec![put_sota(3, 2)]).unwrap();
+
+		let oid = OID { universe: 1, set: 2, id: 3 };
+		let rev = |db: &DbState<MemKv>| {
+			StateReader::<StateError>::get_descriptor(db, &oid, Rev::LATEST).unwrap().rev
+		};
+		assert_eq!(rev(&db), Rev(2));
+		assert_eq!(rev(&before), Rev(1));
+		assert_eq!(db.iter_objects_in_set(1, 2).count(), 2);
+		assert_eq!(db.take_error(), None);
+
+		assert_eq!(db.at(BlockRef::Number(7)).err(), Some(AtBlockError::NoHead));
+		db.set_head(7, [7; 32]);
+		let view = db.at(BlockRef::Hash([7; 32])).unwrap();
+		assert_eq!(rev(&view), Rev(2));
+		let old = BlockRef::Number(6);
+		assert_eq!(db.at(old).err(), Some(AtBlockError::NotHead { block: old, head: 7 }));
+		let other = BlockRef::Hash([6; 32]);
+		assert_eq!(db.at(other).err(), Some(AtBlockError::NotHead { block: other, head: 7 }));
+	}
+
+	/// Reads from a `MemKv` and rejects every write.
+	struct ReadOnlyKv(MemKv);
+
+	impl KvRead for ReadOnlyKv {
+		fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, KvError> {
+			self.0.get(key)
+		}
+
+		fn next_key(&self, key: &[u8]) -> Result<Option<Vec<u8>>, KvError> {
+			self.0.next_key(key)
+		}
+	}
+
+	impl KvWrite for ReadOnlyKv {
+		fn write(&mut self, _: KvBatch) -> Result<(), KvError> {
+			Err(KvError("read only".into()))
+		}
+	}
+
+	#[test]
+	fn failed_write_fails_batch() {
+		let mut db = DbState::new(ReadOnlyKv(MemKv::default()));
+		assert_eq!(db.apply_batch(vec![put_sota(3, 1)]), Err(WriteError::Backend));
+		assert_eq!(db.take_error(), Some(KvError("read only".into())));
+		assert_eq!(db.iter_objects_in_set(1, 2).count(), 0);
+	}
+
+	#[cfg(feature = "sled")]
+	#[test]
+	fn sled_round_trip() {
+		let tree = sled::Config::new().temporary(true).open().unwrap().open_tree("every").unwrap();
+		let mut db = DbState::new(tree);
+		db.apply_batch(vec![put_sota(3, 1), put_sota(4, 1)]).unwrap();
+		let oid = OID { universe: 1, set: 2, id: 4 };
+		let desc = StateReader::<StateError>::get_descriptor(&db, &oid, Rev::LATEST);
+		assert_eq!(desc.unwrap().rev, Rev(1));
+		assert_eq!(db.iter_objects_in_set(1, 2).count(), 2);
+	}
+}
@@ -0,0 +1,262 @@
+use crate::{state::Sota, Constants, Descriptor, Rev, StateReader, TraitFlags, Vec, OID};
+use thiserror::Error;
+
+#[derive(Clone, Copy, Debug, Error, PartialEq, Eq)]
+pub enum DescriptorError {
+	#[error("revision {0} is not a stored revision")]
+	BadRev(Rev),
+	#[error("revision {got} does not follow {prev}")]
+	NotNextRev { prev: Rev, got: Rev },
+	#[error("object is destroyed")]
+	Destroyed,
+	#[error("kind changed from {prev} to {got}")]
+	KindChanged { prev: u64, got: u64 },
+	#[error("kind revision {got} is outside {min}..={max}")]
+	BadKindRev { min: Rev, max: Rev, got: Rev },
+	#[error("set revision {got} is outside {min}..={max}")]
+	BadSetRev { min: Rev, max: Rev, got: Rev },
+}
+
+/// A reference of a descriptor to its kind or set object that does not hold up.
+#[derive(Clone, Copy, Debug, Error, PartialEq, Eq)]
+pub enum ConsistencyIssue {
+	#[error("kind {0} is not a valid object id")]
+	KindOutOfRange(u64),
+	#[error("kind {kind} has no revision {krev}")]
+	KindRevMissing { kind: OID, krev: Rev },
+	#[error("{kind} is of kind {got}, not a kind")]
+	NotAKind { kind: OID, got: u64 },
+	#[error("set {set} has no revision {srev}")]
+	SetRevMissing { set: OID, srev: Rev },
+	#[error("{set} is of kind {got}, not a set")]
+	NotASet { set: OID, got: u64 },
+}
+
+/// Outcome of `check_consistency`; empty when every reference resolves.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConsistencyReport {
+	pub issues: Vec<ConsistencyIssue>,
+}
+
+impl ConsistencyReport {
+	pub fn is_ok(&self) -> bool {
+		self.issues.is_empty()
+	}
+}
+
+/// Checks that `desc.krev` and `desc.srev` are stored revisions of the kind and set objects of
+/// `oid`, and that those objects are a kind and a set. These are the reads `ElementPicker`
+/// makes, so a clean report rules out its lookup errors on stale references.
+pub fn check_consistency<E, S: StateReader<E>>(
+	state: &S,
+	oid: &OID,
+	desc: &Descriptor,
+) -> ConsistencyReport {
+	let stored = |oid: &OID, rev: Rev| {
+		let readable = !rev.is_latest() && !rev.is_destroyed();
+		readable
+			.then(|| state.get_descriptor(oid, rev).ok())
+			.flatten()
+			.filter(|d| d.rev == rev)
+	};
+	let mut issues = Vec::new();
+	if (Constants::ID_MIN..=Constants::ID_MAX).contains(&desc.kind) {
+		let kind = oid.kind_oid(desc.kind);
+		match stored(&kind, desc.krev) {
+			None => issues.push(ConsistencyIssue::KindRevMissing { kind, krev: desc.krev }),
+			Some(d) if d.kind != Constants::ID_KIND_OF_KIND =>
+				issues.push(ConsistencyIssue::NotAKind { kind, got: d.kind }),
+			Some(_) => {},
+		}
+	} else {
+		issues.push(ConsistencyIssue::KindOutOfRange(desc.kind));
+	}
+	let set = oid.set_oid();
+	match stored(&set, desc.srev) {
+		None => issues.push(ConsistencyIssue::SetRevMissing { set, srev: desc.srev }),
+		Some(d) if d.kind != Constants::ID_KIND_OF_SET =>
+			issues.push(ConsistencyIssue::NotASet { set, got: d.kind }),
+		Some(_) => {},
+	}
+	ConsistencyReport { issues }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DescriptorBuilder {
+	desc: Descriptor,
+}
+
+impl DescriptorBuilder {
+	/// Starts a descriptor of `kind` at `Rev::NEW` with kind and set revisions `Rev::NEW`.
+	pub fn new(kind: u64) -> Self {
+		let desc = Descriptor {
+			kind,
+			rev: Rev::NEW,
+			krev: Rev::NEW,
+			srev: Rev::NEW,
+			..Default::default()
+		};
+		Self { desc }
+	}
+
+	/// Starts the descriptor of the revision after `prev`, keeping everything else.
+	pub fn next_of(prev: &Descriptor) -> Self {
+		Self { desc: Descriptor { rev: prev.rev.saturating_next(), ..prev.clone() } }
+	}
+
+	pub fn traits(mut self, traits: TraitFlags) -> Self {
+		self.desc.traits = traits.into();
+		self
+	}
+
+	pub fn rev(mut self, rev: Rev) -> Self {
+		self.desc.rev = rev;
+		self
+	}
+
+	pub fn krev(mut self, krev: Rev) -> Self {
+		self.desc.krev = krev;
+		self
+	}
+
+	pub fn srev(mut self, srev: Rev) -> Self {
+		self.desc.srev = srev;
+		self
+	}
+
+	pub fn trev(mut self, trev: Rev) -> Self {
+		self.desc.trev = trev;
+		self
+	}
+
+	pub fn fsum(mut self, fsum: u32) -> Self {
+		self.desc.fsum = fsum;
+		self
+	}
+
+	pub fn build(self) -> Descriptor {
+		self.desc
+	}
+}
+
+impl Descriptor {
+	/// Checks this descriptor as the next state of an object whose current state is `sota`
+	/// (`None` for a new object), given the current descriptors of its kind and set objects.
+	///
+	/// The revision must be the one after the current one, or `Rev::DESTROYED`; nothing follows
+	/// a destroyed revision. The kind stays the same, and kind and set revisions lie between
+	/// the ones already referenced and the current revision of the kind and set.
+	pub fn validate_against(
+		&self,
+		sota: Option<&Sota>,
+		kind_desc: &Descriptor,
+		set_desc: &Descriptor,
+	) -> Result<(), DescriptorError> {
+		let prev = sota.map(|s| &s.desc);
+		match prev {
+			Some(prev) if prev.rev.is_destroyed() => return Err(DescriptorError::Destroyed),
+			Some(prev) if prev.kind != self.kind =>
+				return Err(DescriptorError::KindChanged { prev: prev.kind, got: self.kind }),
+			Some(prev) if !self.rev.is_destroyed() && prev.rev.next() != Some(self.rev) =>
+				return Err(DescriptorError::NotNextRev { prev: prev.rev, got: self.rev }),
+			None if !self.rev.is_new() => return Err(DescriptorError::BadRev(self.rev)),
+			_ => {},
+		}
+
+		let in_range = |min: Rev, max: Rev, got: Rev| {
+			!max.is_latest() && !max.is_destroyed() && min <= got && got <= max
+		};
+		let kmin = prev.map_or(Rev::NEW, |p| p.krev);
+		if !in_range(kmin, kind_desc.rev, self.krev) {
+			return Err(DescriptorError::BadKindRev {
+				min: kmin,
+				max: kind_desc.rev,
+				got: self.krev,
+			});
+		}
+		let smin = prev.map_or(Rev::NEW, |p| p.srev);
+		if !in_range(smin, set_desc.rev, self.srev) {
+			return Err(DescriptorError::BadSetRev { min: smin, max: set_desc.rev, got: self.srev });
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{
+		check_consistency, state::Sota, ConsistencyIssue, Constants, Descriptor, DescriptorBuilder,
+		DescriptorError, Prefetched, Rev, TraitFlags, OID,
+	};
+
+	#[test]
+	fn validates_transitions() {
+		let meta = |rev| Descriptor { rev: Rev(rev), ..Default::default() };
+		let first =
+			DescriptorBuilder::new(17).traits(TraitFlags::TRANSFERABLE).krev(Rev(2)).build();
+		assert_eq!(first.validate_against(None, &meta(3), &meta(1)), Ok(()));
+		assert_eq!(
+			first.validate_against(None, &meta(1), &meta(1)),
+			Err(DescriptorError::BadKindRev { min: Rev(1), max: Rev(1), got: Rev(2) })
+		);
+
+		let sota = Sota { desc: first.clone(), ..Default::default() };
+		let next = DescriptorBuilder::next_of(&first).build();
+		assert_eq!(next.rev, Rev(2));
+		assert_eq!(next.validate_against(Some(&sota), &meta(3), &meta(1)), Ok(()));
+		let stale = DescriptorBuilder::next_of(&first).krev(Rev(1)).build();
+		assert!(stale.validate_against(Some(&sota), &meta(3), &meta(1)).is_err());
+		let skip = DescriptorBuilder::next_of(&first).rev(Rev(3)).build();
+		assert_eq!(
+			skip.validate_against(Some(&sota), &meta(3), &meta(1)),
+			Err(DescriptorError::NotNextRev { prev: Rev(1), got: Rev(3) })
+		);
+
+		let gone = DescriptorBuilder::next_of(&first).rev(Rev::DESTROYED).build();
+		assert_eq!(gone.validate_against(Some(&sota), &meta(3), &meta(1)), Ok(()));
+		let sota = Sota { desc: gone, ..Default::default() };
+		assert_eq!(
+			next.validate_against(Some(&sota), &meta(3), &meta(1)),
+			Err(DescriptorError::Destroyed)
+		);
+	}
+
+	#[test]
+	fn checks_references() {
+		let oid = OID { universe: 1, set: 17, id: 5 };
+		let meta = |kind, rev| Descriptor { kind, rev: Rev(rev), ..Default::default() };
+		let mut state = Prefetched::default();
+		state.insert_snapshot(
+			&oid.kind_oid(9),
+			Rev(2),
+			meta(Constants::ID_KIND_OF_KIND, 2),
+			vec![],
+		);
+		state.insert_snapshot(&oid.set_oid(), Rev(1), meta(Constants::ID_KIND_OF_SET, 1), vec![]);
+		state.insert_snapshot(&oid.kind_oid(8), Rev(1), meta(Constants::ID_KIND_OF_SET, 1), vec![]);
+
+		let desc = DescriptorBuilder::new(9).krev(Rev(2)).srev(Rev(1)).build();
+		assert!(check_consistency(&state, &oid, &desc).is_ok());
+		let stale = DescriptorBuilder::new(9).krev(Rev(3)).srev(Rev::LATEST).build();
+		assert_eq!(
+			check_consistency(&state, &oid, &stale).issues,
+			vec![
+				ConsistencyIssue::KindRevMissing { kind: oid.kind_oid(9), krev: Rev(3) },
+				ConsistencyIssue::SetRevMissing { set: oid.set_oid(), srev: Rev::LATEST },
+			]
+		);
+		let wrong = DescriptorBuilder::new(8).srev(Rev(1)).krev(Rev(1)).build();
+		assert_eq!(
+			check_consistency(&state, &oid, &wrong).issues,
+			vec![ConsistencyIssue::NotAKind {
+				kind: oid.kind_oid(8),
+				got: Constants::ID_KIND_OF_SET
+			}]
+		);
+		let bad = DescriptorBuilder::new(0).srev(Rev(1)).build();
+		assert_eq!(
+			check_consistency(&state, &oid, &bad).issues,
+			vec![ConsistencyIssue::KindOutOfRange(0)]
+		);
+	}
+}
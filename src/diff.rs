@@ -0,0 +1,210 @@
+use crate::{Arc, Bytes32, Facet, Rev, StateReader, Vec, OID};
+
+#[cfg(feature = "scale")]
+use codec::{Decode, DecodeWithMemTracking, Encode};
+#[cfg(feature = "scale")]
+use scale_info::TypeInfo;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "scale", derive(Encode, Decode, TypeInfo, DecodeWithMemTracking))]
+pub struct ElemDiff {
+	pub index: u32,
+	#[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes_option"))]
+	pub before: Option<Bytes32>,
+	#[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes_option"))]
+	pub after: Option<Bytes32>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "scale", derive(Encode, Decode, TypeInfo, DecodeWithMemTracking))]
+pub struct FacetDiff {
+	pub sel: u32,
+	#[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes_option"))]
+	pub before: Option<Bytes32>,
+	#[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes_option"))]
+	pub after: Option<Bytes32>,
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "scale", derive(Encode, Decode, TypeInfo, DecodeWithMemTracking))]
+pub struct ArcsDiff {
+	pub added: Vec<Arc>,
+	pub removed: Vec<Arc>,
+}
+
+impl ArcsDiff {
+	pub fn is_empty(&self) -> bool {
+		self.added.is_empty() && self.removed.is_empty()
+	}
+}
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "scale", derive(Encode, Decode, TypeInfo, DecodeWithMemTracking))]
+pub struct SnapshotStreamItem {
+	pub oid: OID,
+	pub from_rev: Rev,
+	pub to_rev: Rev,
+	pub elems_diff: Vec<ElemDiff>,
+	pub facets_diff: Vec<FacetDiff>,
+	pub tails_diff: ArcsDiff,
+}
+
+impl SnapshotStreamItem {
+	pub fn is_empty(&self) -> bool {
+		self.elems_diff.is_empty() && self.facets_diff.is_empty() && self.tails_diff.is_empty()
+	}
+
+	/// Reads both revisions of `oid` and diffs elements, facets and tails.
+	pub fn between<E, S: StateReader<E>>(
+		state: &S,
+		oid: &OID,
+		from_rev: Rev,
+		to_rev: Rev,
+	) -> Result<Self, E> {
+		let (_, from_elems) = state.get_snapshot(oid, from_rev)?;
+		let (_, to_elems) = state.get_snapshot(oid, to_rev)?;
+		let from_facets = state.get_facets(oid, from_rev)?;
+		let to_facets = state.get_facets(oid, to_rev)?;
+		let from_tails = state.get_tails(oid, from_rev)?;
+		let to_tails = state.get_tails(oid, to_rev)?;
+		Ok(Self {
+			oid: *oid,
+			from_rev,
+			to_rev,
+			elems_diff: diff_elems(&from_elems, &to_elems),
+			facets_diff: diff_facets(&from_facets, &to_facets),
+			tails_diff: diff_arcs(&from_tails, &to_tails),
+		})
+	}
+}
+
+pub fn diff_elems(before: &[Bytes32], after: &[Bytes32]) -> Vec<ElemDiff> {
+	(0..before.len().max(after.len()))
+		.filter_map(|i| {
+			let (b, a) = (before.get(i).copied(), after.get(i).copied());
+			(b != a).then_some(ElemDiff { index: i as u32, before: b, after: a })
+		})
+		.collect()
+}
+
+pub fn diff_facets(before: &[Facet], after: &[Facet]) -> Vec<FacetDiff> {
+	let find = |set: &[Facet], sel: u32| set.iter().find(|f| f.sel == sel).map(|f| f.hash);
+	let mut sels: Vec<u32> = before.iter().chain(after).map(|f| f.sel).collect();
+	sels.sort_unstable();
+	sels.dedup();
+	sels.into_iter()
+		.filter_map(|sel| {
+			let (b, a) = (find(before, sel), find(after, sel));
+			(b != a).then_some(FacetDiff { sel, before: b, after: a })
+		})
+		.collect()
+}
+
+pub fn diff_arcs(before: &[Arc], after: &[Arc]) -> ArcsDiff {
+	ArcsDiff {
+		added: after.iter().filter(|a| !before.contains(a)).cloned().collect(),
+		removed: before.iter().filter(|a| !after.contains(a)).cloned().collect(),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{
+		diff_arcs, diff_elems, diff_facets,
+		test_state::{Missing, TestObject, TestState},
+		Arc, ArcsDiff, Descriptor, ElemDiff, Facet, FacetDiff, Rev, SnapshotStreamItem, OID,
+	};
+
+	fn arc(id: u64) -> Arc {
+		Arc { kind: 7, data: 0, rel: 2, set: 20, id }
+	}
+
+	fn facet(sel: u32, byte: u8) -> Facet {
+		Facet { sel, hash: [byte; 32] }
+	}
+
+	#[test]
+	fn diffs_elems() {
+		assert_eq!(diff_elems(&[[1; 32], [2; 32]], &[[1; 32], [2; 32]]), vec![]);
+		assert_eq!(
+			diff_elems(&[[1; 32], [2; 32]], &[[1; 32], [3; 32], [4; 32]]),
+			vec![
+				ElemDiff { index: 1, before: Some([2; 32]), after: Some([3; 32]) },
+				ElemDiff { index: 2, before: None, after: Some([4; 32]) },
+			]
+		);
+		assert_eq!(
+			diff_elems(&[[1; 32], [2; 32]], &[[1; 32]]),
+			vec![ElemDiff { index: 1, before: Some([2; 32]), after: None }]
+		);
+	}
+
+	#[test]
+	fn diffs_facets() {
+		let before = [facet(3, 1), facet(1, 1), facet(5, 5)];
+		assert_eq!(diff_facets(&before, &[facet(5, 5), facet(1, 1), facet(3, 1)]), vec![]);
+		assert_eq!(
+			diff_facets(&before, &[facet(5, 5), facet(1, 2), facet(9, 9)]),
+			vec![
+				FacetDiff { sel: 1, before: Some([1; 32]), after: Some([2; 32]) },
+				FacetDiff { sel: 3, before: Some([1; 32]), after: None },
+				FacetDiff { sel: 9, before: None, after: Some([9; 32]) },
+			]
+		);
+	}
+
+	#[test]
+	fn diffs_arcs() {
+		assert!(diff_arcs(&[arc(1), arc(2)], &[arc(2), arc(1)]).is_empty());
+		assert_eq!(
+			diff_arcs(&[arc(1), arc(2)], &[arc(2), arc(3)]),
+			ArcsDiff { added: vec![arc(3)], removed: vec![arc(1)] }
+		);
+	}
+
+	// Revision 1 and 2 of one object; every other revision is missing.
+	fn history(oid: OID) -> TestState {
+		let mut state = TestState::default();
+		let revs = [
+			(1, vec![[1; 32], [2; 32]], vec![arc(1)]),
+			(2, vec![[1; 32], [3; 32]], vec![arc(1), arc(2)]),
+		];
+		for (rev, elems, tails) in revs {
+			let desc = Descriptor { rev: Rev(rev), ..Default::default() };
+			let object = TestObject::new(desc).elems(elems).tails(tails).facets(vec![facet(1, 1)]);
+			state.insert(oid, object);
+		}
+		state
+	}
+
+	#[test]
+	fn diffs_between_revisions() {
+		let oid = OID::of_set(1, 17);
+		let history = history(oid);
+		let item = SnapshotStreamItem::between(&history, &oid, Rev(1), Rev(2)).unwrap();
+		assert_eq!(
+			item,
+			SnapshotStreamItem {
+				oid,
+				from_rev: Rev(1),
+				to_rev: Rev(2),
+				elems_diff: vec![ElemDiff {
+					index: 1,
+					before: Some([2; 32]),
+					after: Some([3; 32])
+				}],
+				facets_diff: vec![],
+				tails_diff: ArcsDiff { added: vec![arc(2)], removed: vec![] },
+			}
+		);
+		assert!(!item.is_empty());
+		assert!(SnapshotStreamItem::between(&history, &oid, Rev(2), Rev(2)).unwrap().is_empty());
+		assert_eq!(SnapshotStreamItem::between(&history, &oid, Rev(1), Rev(3)), Err(Missing(0)));
+	}
+}
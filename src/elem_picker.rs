@@ -1,10 +1,23 @@
 use crate::{
-	Bytes32, Descriptor, EnumMatter, Matter, MatterForm, PermMatter, Result, StateReader, Vec,
-	H256, OID,
+	trace::{event, span},
+	validate_elements, Bytes32, CollectionSource, Constants, Descriptor, EnumMatter, ErrorCode,
+	Formula, FormulaError, KindSpec, Matter, MatterCache, MatterForm, MatterFormRegistry,
+	MerkleHasher, MetaSpecError, PermMatter, Prefetched, RangeMatter, ReadRequest, Result, Rev,
+	RowSource, Schema, SchemaError, SetSpec, Shared, SparseMatter, StateReader, Vec, H256, OID,
+};
+use sp_std::{
+	boxed::Box,
+	collections::btree_map::{self, BTreeMap},
 };
-use sp_std::collections::btree_map::BTreeMap;
 use thiserror::Error;
 
+#[cfg(feature = "scale")]
+use codec::{Decode, DecodeWithMemTracking, Encode, MaxEncodedLen};
+#[cfg(feature = "scale")]
+use scale_info::TypeInfo;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
 macro_rules! ensure {
 	($cond:expr, $err:expr) => {
 		if !$cond {
@@ -35,10 +48,11 @@ pub enum ElementError {
 	NoCustomPicker,
 	#[error("previous revision does not exist")]
 	NoPreviousRevision,
+	/// Holds the code of the reader's error when it has one; see `StateReader::error_code`.
 	#[error("state access error")]
-	StateReaderGetMatter,
+	StateReaderGetMatter(Option<ErrorCode>),
 	#[error("state access error")]
-	StateReaderGetSnapshot,
+	StateReaderGetSnapshot(Option<ErrorCode>),
 	#[error("failed to get cached row")]
 	CacheGet,
 	#[error("out of row bounds")]
@@ -51,11 +65,41 @@ pub enum ElementError {
 	ResultLengthMismatch,
 	#[error("invalid element count")]
 	InvalidElementLength,
+	#[error("too many here elements: {got} exceeds capacity {max}")]
+	TooManyElements { got: usize, max: usize },
+	#[error("not a formula matter")]
+	NotFormula,
+	#[error("formula error: {0}")]
+	Formula(FormulaError),
+	#[error("failed to load sparse matter")]
+	SparseMatterFrom,
+	#[error("failed to read sparse row")]
+	SparseMatterRowAt,
+	#[error("failed to load range matter")]
+	RangeMatterFrom,
+	#[error("failed to compute range row")]
+	RangeMatterRowAt,
+	#[error("schema error: {0}")]
+	Schema(SchemaError),
+	#[error("meta object error: {0}")]
+	MetaSpec(MetaSpecError),
+	#[error("custom matter rejected by its registered validator")]
+	CustomMatterInvalid,
+	#[error("reserved picker flag bits set: {0:#06x}")]
+	ReservedFlagBits(u32),
+	#[error("unsupported picker flags version {0}")]
+	UnsupportedFlagsVersion(u8),
+	#[error("cannot allocate a sample of {0} rows")]
+	SampleTooLarge(u64),
 }
 
 #[repr(u8)]
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "scale", derive(Encode, Decode, TypeInfo, DecodeWithMemTracking))]
+#[cfg_attr(feature = "scale", derive(MaxEncodedLen))]
 pub enum PickFrom {
+	#[default]
 	HereElements = 0,
 	HereCollection = 1,
 	SetData = 2,
@@ -63,12 +107,6 @@ pub enum PickFrom {
 	ObjectData = 8,
 }
 
-impl Default for PickFrom {
-	fn default() -> Self {
-		PickFrom::HereElements
-	}
-}
-
 impl PickFrom {
 	#[inline]
 	pub fn from_nibble(n: u8) -> Result<Self, ElementError> {
@@ -84,15 +122,70 @@ impl PickFrom {
 	}
 }
 
-#[derive(Clone, Copy, Default)]
+/// Where one resolved element came from: column `col` of row `row` of source `src`. `row` is 0
+/// for sources that are not collections.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "scale", derive(Encode, Decode, TypeInfo, DecodeWithMemTracking))]
+#[cfg_attr(feature = "scale", derive(MaxEncodedLen))]
+pub struct ElementOrigin {
+	pub src: PickFrom,
+	pub row: u64,
+	pub col: u8,
+}
+
+impl ElementOrigin {
+	fn new(src: PickFrom, row: u64, col: u8) -> Self {
+		let row = match src {
+			PickFrom::HereElements | PickFrom::ObjectData => 0,
+			_ => row,
+		};
+		Self { src, row, col }
+	}
+}
+
+/// Cache key for a pick source, ordered by the source's wire nibble.
+///
+/// The nibble is part of the encoding and never changes, so ordering stays fixed regardless of how
+/// `PickFrom` variants are declared.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SourceKey(u8);
+
+impl SourceKey {
+	#[inline]
+	pub const fn of(src: PickFrom) -> Self {
+		Self(src as u8)
+	}
+
+	#[inline]
+	pub fn source(&self) -> PickFrom {
+		PickFrom::from_nibble(self.0).expect("SourceKey is only built from a PickFrom")
+	}
+}
+
+impl From<PickFrom> for SourceKey {
+	fn from(src: PickFrom) -> Self {
+		Self::of(src)
+	}
+}
+
+/// Bits 0..4 hold the row source, bit 4 the custom picker, bits 5..12 are reserved, bits 12..16
+/// the layout version and bits 16..32 the mut bits.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 pub struct PickerFlags {
 	mut_bits: u16,
 	custom: bool,
 	here_coll: bool,
 	row_from: PickFrom,
+	version: u8,
 }
 
 impl PickerFlags {
+	/// Newest layout version `decode` accepts.
+	pub const VERSION: u8 = 0;
+	/// Bits kept for future flags; `decode` rejects them while they are unassigned.
+	pub const RESERVED: u32 = 0x0000_0FE0;
+
 	pub fn new() -> Self {
 		PickerFlags::default()
 	}
@@ -102,7 +195,7 @@ impl PickerFlags {
 	}
 
 	pub fn with_row_from(self, row_from: PickFrom) -> Self {
-		let here_coll = if row_from == PickFrom::HereCollection { true } else { false };
+		let here_coll = row_from == PickFrom::HereCollection;
 		Self { here_coll, row_from, ..self }
 	}
 
@@ -110,19 +203,161 @@ impl PickerFlags {
 		Self { custom: true, ..self }
 	}
 
+	pub fn with_mut_mask(self, mask: MutMask) -> Self {
+		Self { mut_bits: mask.into(), ..self }
+	}
+
+	pub fn mut_mask(&self) -> MutMask {
+		MutMask::from(self.mut_bits)
+	}
+
+	pub fn version(&self) -> u8 {
+		self.version
+	}
+
+	/// Decodes `v`, rejecting reserved bits and versions newer than `VERSION`.
 	pub fn decode(v: u32) -> Result<Self, ElementError> {
+		ensure!(v & Self::RESERVED == 0, ElementError::ReservedFlagBits(v & Self::RESERVED));
+		let flags = Self::decode_lenient(v)?;
+		ensure!(
+			(..=Self::VERSION).contains(&flags.version),
+			ElementError::UnsupportedFlagsVersion(flags.version)
+		);
+		Ok(flags)
+	}
+
+	/// Decodes `v` ignoring reserved bits and accepting any version, for tools that inspect
+	/// flags written by newer code.
+	pub fn decode_lenient(v: u32) -> Result<Self, ElementError> {
 		let row_from = PickFrom::from_nibble((v & 0x0F) as u8)?;
-		let here_coll = if row_from == PickFrom::HereCollection { true } else { false };
+		let here_coll = row_from == PickFrom::HereCollection;
 		Ok(Self {
 			mut_bits: ((v >> 16) & 0xFFFF) as u16,
 			custom: (v & 0b0001_0000) != 0,
 			here_coll,
 			row_from,
+			version: ((v >> 12) & 0x0F) as u8,
 		})
 	}
 
 	pub fn encode(&self) -> u32 {
-		((self.mut_bits as u32) << 16) | (u32::from(self.custom) << 4) | (self.row_from as u32)
+		((self.mut_bits as u32) << 16) |
+			((self.version as u32) << 12) |
+			(u32::from(self.custom) << 4) |
+			(self.row_from as u32)
+	}
+}
+
+/// Elements a revision takes from its picker, the rest being kept from the previous revision.
+/// Slot `i` is bit `15 - i` of the wire form, so the first slots are the highest bits. An empty
+/// mask replaces every element.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct MutMask(u16);
+
+impl MutMask {
+	pub const SLOTS: usize = 16;
+
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	fn bit(slot: usize) -> u16 {
+		1 << (Self::SLOTS - 1 - slot)
+	}
+
+	/// Fails with `InvalidMutBits` past the last slot.
+	pub fn set_mutable(&mut self, slot: usize) -> Result<(), ElementError> {
+		ensure!(slot < Self::SLOTS, ElementError::InvalidMutBits);
+		self.0 |= Self::bit(slot);
+		Ok(())
+	}
+
+	pub fn is_mutable(&self, slot: usize) -> bool {
+		slot < Self::SLOTS && self.0 & Self::bit(slot) != 0
+	}
+
+	pub fn count(&self) -> usize {
+		self.0.count_ones() as usize
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.0 == 0
+	}
+
+	/// Mutable slots in ascending order.
+	pub fn slots(&self) -> impl Iterator<Item = usize> + '_ {
+		(0..Self::SLOTS).filter(|&i| self.is_mutable(i))
+	}
+
+	/// Checks the mask against an object of `n` elements: at most `SLOTS` of them, and no
+	/// mutable slot past the last.
+	pub fn validate(&self, n: usize) -> Result<(), ElementError> {
+		ensure!(n <= Self::SLOTS, ElementError::InvalidElementLength);
+		ensure!(self.slots().all(|i| i < n), ElementError::InvalidMutBits);
+		Ok(())
+	}
+}
+
+impl From<u16> for MutMask {
+	fn from(bits: u16) -> Self {
+		Self(bits)
+	}
+}
+
+impl From<MutMask> for u16 {
+	fn from(mask: MutMask) -> Self {
+		mask.0
+	}
+}
+
+/// The shape of a mutation, checked before any cell is resolved: which slots of an object of
+/// `len` elements a `MutMask` writes. `ElementPicker::patch` is `new` followed by `apply`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PatchPlan {
+	len: usize,
+	mask: MutMask,
+}
+
+impl PatchPlan {
+	pub fn new(prev_len: usize, mask: MutMask) -> Result<Self, ElementError> {
+		mask.validate(prev_len)?;
+		Ok(Self { len: prev_len, mask })
+	}
+
+	/// Whether every element is replaced, as with an empty mask.
+	pub fn is_full(&self) -> bool {
+		self.mask.is_empty()
+	}
+
+	/// Slots to be written, in the order the resolved cells fill them.
+	pub fn slots(&self) -> impl Iterator<Item = usize> + '_ {
+		let all = self.is_full();
+		(0..self.len).filter(move |&i| all || self.mask.is_mutable(i))
+	}
+
+	/// Number of resolved cells `apply` expects.
+	pub fn writes(&self) -> usize {
+		if self.is_full() {
+			self.len
+		} else {
+			self.mask.count()
+		}
+	}
+
+	pub fn apply(
+		&self,
+		mut prev: Vec<Bytes32>,
+		resolved: Vec<Bytes32>,
+	) -> Result<Vec<Bytes32>, ElementError> {
+		ensure!(prev.len() == self.len, ElementError::InvalidElementLength);
+		ensure!(resolved.len() == self.writes(), ElementError::ResultLengthMismatch);
+		if self.is_full() {
+			return Ok(resolved);
+		}
+		for (i, elem) in self.slots().zip(resolved) {
+			prev[i] = elem;
+		}
+		Ok(prev)
 	}
 }
 
@@ -187,10 +422,20 @@ pub struct ElementPicker {
 	here_elems: Vec<Bytes32>,
 	here_coll: Option<Bytes32>,
 	custom: Option<PickMany>,
+	schema: Option<Schema>,
+	forms: Option<Shared<MatterFormRegistry>>,
 }
 
 impl ElementPicker {
-	pub fn new(flags: u32, mut elems: Vec<Bytes32>) -> Result<Self, ElementError> {
+	pub fn new(flags: u32, elems: Vec<Bytes32>) -> Result<Self, ElementError> {
+		Self::new_with_capacity(flags, elems, Constants::ELEM_SPEC_CAPACITY)
+	}
+
+	pub fn new_with_capacity(
+		flags: u32,
+		mut elems: Vec<Bytes32>,
+		capacity: usize,
+	) -> Result<Self, ElementError> {
 		let flags = PickerFlags::decode(flags)?;
 		// elems = [here_elems] [here_coll?] [custom_picker?]
 		let custom = if flags.custom {
@@ -207,171 +452,448 @@ impl ElementPicker {
 		};
 
 		let here_elems = elems;
-		Ok(Self { flags, here_elems, here_coll, custom })
+		ensure!(
+			here_elems.len() <= capacity,
+			ElementError::TooManyElements { got: here_elems.len(), max: capacity }
+		);
+		Ok(Self { flags, here_elems, here_coll, custom, schema: None, forms: None })
+	}
+
+	/// Verifies every resolved element list against `schema`, the kind's declared element spec.
+	pub fn with_schema(mut self, schema: Schema) -> Self {
+		self.schema = Some(schema);
+		self
+	}
+
+	/// Opens collection matters of the custom forms in `forms` as well as the built-in ones, on
+	/// every resolve path.
+	pub fn with_forms(mut self, forms: Shared<MatterFormRegistry>) -> Self {
+		self.forms = Some(forms);
+		self
+	}
+
+	/// Number of elements `resolve` yields, when known without reading state.
+	pub fn element_count(&self) -> Option<usize> {
+		match (&self.custom, self.flags.row_from) {
+			(Some(picker), _) => Some(picker.picks.len()),
+			(None, PickFrom::HereElements) => Some(self.here_elems.len()),
+			(None, _) => None,
+		}
 	}
 
 	pub fn resolve<E, S: StateReader<E>>(
 		&self,
-		state: &mut S,
+		state: &S,
+		oid: &OID,
+		desc: &Descriptor,
+	) -> Result<Vec<Bytes32>, ElementError> {
+		self.resolve_with::<CollectionMatter, E, S>(state, oid, desc)
+	}
+
+	/// `resolve` with collection matters opened as `C`, for callers that support forms beyond
+	/// the built-in ones.
+	pub fn resolve_with<C: CollectionSource, E, S: StateReader<E>>(
+		&self,
+		state: &S,
+		oid: &OID,
+		desc: &Descriptor,
+	) -> Result<Vec<Bytes32>, ElementError> {
+		self.resolve_in::<C, E, S>(state, oid, desc, None)
+	}
+
+	/// `resolve_with`, taking parsed collection matters from `cache`.
+	pub fn resolve_cached<C: CollectionSource, E, S: StateReader<E>>(
+		&self,
+		state: &S,
+		oid: &OID,
+		desc: &Descriptor,
+		cache: &MatterCache<C>,
+	) -> Result<Vec<Bytes32>, ElementError> {
+		self.resolve_in::<C, E, S>(state, oid, desc, Some(cache))
+	}
+
+	fn resolve_in<C: CollectionSource, E, S: StateReader<E>>(
+		&self,
+		state: &S,
 		oid: &OID,
 		desc: &Descriptor,
+		cache: Option<&MatterCache<C>>,
+	) -> Result<Vec<Bytes32>, ElementError> {
+		span!("resolve", %oid, rev = %desc.rev);
+		let elems = self.pick_elems::<C, E, S>(state, oid, desc, cache)?;
+		self.check_schema(state, &elems)?;
+		event!(elems = elems.len(), "resolved");
+		Ok(elems)
+	}
+
+	/// Picked elements before the schema check.
+	pub(crate) fn pick_elems<C: CollectionSource, E, S: StateReader<E>>(
+		&self,
+		state: &S,
+		oid: &OID,
+		desc: &Descriptor,
+		cache: Option<&MatterCache<C>>,
+	) -> Result<Vec<Bytes32>, ElementError> {
+		self.pick_traced::<C, E, S>(state, oid, desc, cache, None)
+	}
+
+	/// `pick_elems`, also pushing the origin of each element to `origins` when given.
+	fn pick_traced<C: CollectionSource, E, S: StateReader<E>>(
+		&self,
+		state: &S,
+		oid: &OID,
+		desc: &Descriptor,
+		cache: Option<&MatterCache<C>>,
+		mut origins: Option<&mut Vec<ElementOrigin>>,
 	) -> Result<Vec<Bytes32>, ElementError> {
 		let row_index = oid.id.saturating_sub(1);
-		if self.custom.is_none() {
-			return self.pick_row(state, oid, desc, self.flags.row_from, row_index);
-		} else {
-			let picker = self.custom.as_ref().unwrap();
-			let mut cache: BTreeMap<PickFrom, Vec<Bytes32>> = BTreeMap::new();
+		let elems = if let Some(picker) = self.custom.as_ref() {
+			let mut rows: BTreeMap<SourceKey, Vec<Bytes32>> = BTreeMap::new();
 			let mut elems = Vec::with_capacity(picker.picks.len());
 			for p in picker.picks.iter() {
-				let row = self.pick_row_cached(state, oid, desc, p.src, row_index, &mut cache)?;
+				let key = SourceKey::of(p.src);
+				if let btree_map::Entry::Vacant(e) = rows.entry(key) {
+					e.insert(self.pick_row::<C, E, S>(state, oid, desc, p.src, row_index, cache)?);
+				}
+				let row = rows.get(&key).ok_or(ElementError::CacheGet)?;
 				let elem = *row.get(p.idx as usize).ok_or(ElementError::ColOutOfBounds)?;
 				elems.push(elem);
+				if let Some(origins) = origins.as_deref_mut() {
+					origins.push(ElementOrigin::new(p.src, row_index, p.idx));
+				}
 			}
-			Ok(elems)
-		}
+			elems
+		} else {
+			let src = self.flags.row_from;
+			let elems = self.pick_row::<C, E, S>(state, oid, desc, src, row_index, cache)?;
+			if let Some(origins) = origins {
+				origins
+					.extend((0..elems.len()).map(|i| ElementOrigin::new(src, row_index, i as u8)));
+			}
+			elems
+		};
+		Ok(elems)
 	}
 
-	pub fn patch(
-		mut prev: Vec<Bytes32>,
-		resolved: Vec<Bytes32>,
-		flags_mut_bits: u16,
-	) -> Result<Vec<Bytes32>, ElementError> {
-		let n = prev.len();
-		ensure!(n <= 16, ElementError::InvalidElementLength);
-		let mask = !0u16 << (16 - n); // highest n bits
+	pub(crate) fn check_schema<E, S: StateReader<E>>(
+		&self,
+		state: &S,
+		elems: &[Bytes32],
+	) -> Result<(), ElementError> {
+		if let Some(schema) = self.schema.as_ref() {
+			validate_elements(schema, elems).map_err(ElementError::Schema)?;
+			schema.check_matters(state, elems).map_err(ElementError::Schema)?;
+		}
+		Ok(())
+	}
 
-		let mut_bits = flags_mut_bits & mask;
-		ensure!(mut_bits == flags_mut_bits, ElementError::InvalidMutBits);
+	/// Reads resolving `oid` at `desc` makes, apart from the matters a schema checks, so they can
+	/// be fetched together and served from a `Prefetched`.
+	pub fn required_reads(&self, oid: &OID, desc: &Descriptor) -> Vec<ReadRequest> {
+		use PickFrom::*;
+		let mut reads = Vec::new();
+		for src in self.sources() {
+			match src {
+				HereElements => {},
+				HereCollection => reads.extend(self.here_coll.map(ReadRequest::Matter)),
+				SetData => {
+					reads.push(ReadRequest::Snapshot(oid.set_oid(), desc.srev));
+					reads.push(ReadRequest::SetCollection(oid.set_oid(), desc.srev));
+				},
+				KindData => {
+					let kind = oid.kind_oid(desc.kind);
+					reads.push(ReadRequest::Snapshot(kind, desc.krev));
+					reads.push(ReadRequest::KindCollection(kind, desc.krev));
+				},
+				ObjectData =>
+					if let Some(prev) = desc.rev.prev() {
+						reads.push(ReadRequest::Snapshot(*oid, prev));
+					},
+			}
+		}
+		reads
+	}
 
-		// Full replace.
-		if mut_bits == 0 {
-			ensure!(resolved.len() == n, ElementError::ResultLengthMismatch);
-			return Ok(resolved);
+	/// Reads the `required_reads` of every object into one context. Failed reads are left out
+	/// and fail again when the object is resolved.
+	pub fn prefetch<E, S: StateReader<E>>(
+		&self,
+		state: &S,
+		objects: &[(OID, Descriptor)],
+	) -> Prefetched {
+		let mut ctx = Prefetched::default();
+		for (oid, desc) in objects {
+			for read in self.required_reads(oid, desc) {
+				ctx.fetch(state, &read);
+			}
 		}
+		ctx
+	}
 
-		// Partial replace.
-		ensure!(
-			resolved.len() == mut_bits.count_ones() as usize,
-			ElementError::ResultLengthMismatch
-		);
-		let mut j = 0;
-		for i in 0..n {
-			if (mut_bits & (1u16 << (15 - i))) != 0 {
-				prev[i] = resolved[j];
-				j += 1;
+	/// Distinct sources `resolve` picks from.
+	fn sources(&self) -> Vec<PickFrom> {
+		let mut sources = Vec::new();
+		let picks = self.custom.iter().flat_map(|p| p.picks.iter().map(|p| p.src));
+		for src in picks.chain(self.custom.is_none().then_some(self.flags.row_from)) {
+			if !sources.contains(&src) {
+				sources.push(src);
 			}
 		}
-		Ok(prev)
+		sources
 	}
 
-	fn pick_row_cached<'cache, E, S: StateReader<E>>(
+	/// Resolves the picked elements, then feeds them to the formula matter `formula` and returns
+	/// what it emits. `budget` caps the interpreter's work; see `FormulaOp::cost`.
+	pub fn resolve_derived<E, S: StateReader<E>, H: MerkleHasher>(
 		&self,
-		state: &mut S,
+		state: &S,
 		oid: &OID,
 		desc: &Descriptor,
-		src: PickFrom,
-		row: u64,
-		cache: &'cache mut BTreeMap<PickFrom, Vec<Bytes32>>,
-	) -> Result<&'cache Vec<Bytes32>, ElementError> {
-		if !cache.contains_key(&src) {
-			let row_data = self.pick_row(state, oid, desc, src, row)?;
-			cache.insert(src, row_data);
+		formula: &H256,
+		budget: u32,
+	) -> Result<Vec<Bytes32>, ElementError> {
+		let inputs = self.resolve(state, oid, desc)?;
+		let matter = state
+			.get_matter(formula)
+			.map_err(|err| ElementError::StateReaderGetMatter(state.error_code(&err)))?;
+		ensure!(matter.form == MatterForm::Formula as u8, ElementError::NotFormula);
+		let formula = Formula::from(&matter.blob).map_err(ElementError::Formula)?;
+		formula.eval::<H>(&inputs, budget).map_err(ElementError::Formula)
+	}
+
+	/// Like `resolve`, also returning the origin of each element, recorded as each is picked.
+	pub fn resolve_traced<E, S: StateReader<E>>(
+		&self,
+		state: &S,
+		oid: &OID,
+		desc: &Descriptor,
+	) -> Result<(Vec<Bytes32>, Vec<ElementOrigin>), ElementError> {
+		span!("resolve_traced", %oid, rev = %desc.rev);
+		let mut origins = Vec::new();
+		let elems =
+			self.pick_traced::<CollectionMatter, E, S>(state, oid, desc, None, Some(&mut origins))?;
+		self.check_schema(state, &elems)?;
+		Ok((elems, origins))
+	}
+
+	/// Origins of the elements `patch` produces, placed by the same `PatchPlan`; `None` marks an
+	/// element kept from the previous `n`.
+	pub fn patch_origins(
+		n: usize,
+		resolved: &[ElementOrigin],
+		mask: MutMask,
+	) -> Result<Vec<Option<ElementOrigin>>, ElementError> {
+		let plan = PatchPlan::new(n, mask)?;
+		ensure!(resolved.len() == plan.writes(), ElementError::ResultLengthMismatch);
+		let mut out = sp_std::vec![None; n];
+		for (i, origin) in plan.slots().zip(resolved) {
+			out[i] = Some(*origin);
 		}
-		cache.get(&src).ok_or_else(|| ElementError::CacheGet)
+		Ok(out)
 	}
 
-	fn pick_row<E, S: StateReader<E>>(
+	pub fn patch(
+		prev: Vec<Bytes32>,
+		resolved: Vec<Bytes32>,
+		mask: MutMask,
+	) -> Result<Vec<Bytes32>, ElementError> {
+		PatchPlan::new(prev.len(), mask)?.apply(prev, resolved)
+	}
+
+	fn pick_row<C: CollectionSource, E, S: StateReader<E>>(
 		&self,
-		state: &mut S,
+		state: &S,
 		oid: &OID,
 		desc: &Descriptor,
 		src: PickFrom,
 		row: u64,
+		cache: Option<&MatterCache<C>>,
 	) -> Result<Vec<Bytes32>, ElementError> {
 		use PickFrom::*;
+		event!(?src, row, "pick row");
 		match src {
 			HereElements => Ok(self.here_elems.clone()),
 			HereCollection => {
 				let hash = self.here_coll.as_ref().ok_or(ElementError::NoHereCollection)?;
-				self.pick_coll_row(state, hash, row)
+				self.pick_coll_row::<C, E, S>(state, hash, row, cache)
 			},
 			SetData => {
 				let (_, elems) = state
 					.get_snapshot(&oid.set_oid(), desc.srev)
-					.map_err(|_| ElementError::StateReaderGetSnapshot)?;
-				self.pick_coll_row(state, &elems[1], row)
+					.map_err(|err| ElementError::StateReaderGetSnapshot(state.error_code(&err)))?;
+				let set = SetSpec::from_elems(&elems).map_err(ElementError::MetaSpec)?;
+				self.pick_coll_row::<C, E, S>(state, set.collection(), row, cache)
 			},
 			KindData => {
 				let (_, elems) = state
 					.get_snapshot(&oid.kind_oid(desc.kind), desc.krev)
-					.map_err(|_| ElementError::StateReaderGetSnapshot)?;
-				self.pick_coll_row(state, &elems[1], row)
+					.map_err(|err| ElementError::StateReaderGetSnapshot(state.error_code(&err)))?;
+				let kind = KindSpec::from_elems(&elems).map_err(ElementError::MetaSpec)?;
+				self.pick_coll_row::<C, E, S>(state, kind.collection(), row, cache)
 			},
 			ObjectData => {
-				if desc.rev <= 1 {
-					return Err(ElementError::NoPreviousRevision);
-				}
+				let prev = desc.rev.prev().ok_or(ElementError::NoPreviousRevision)?;
 				let (_, prev_elems) = state
-					.get_snapshot(oid, desc.rev - 1)
-					.map_err(|_| ElementError::StateReaderGetSnapshot)?;
+					.get_snapshot(oid, prev)
+					.map_err(|err| ElementError::StateReaderGetSnapshot(state.error_code(&err)))?;
 				Ok(prev_elems)
 			},
 		}
 	}
 
-	fn pick_coll_row<E, S: StateReader<E>>(
+	fn pick_coll_row<C: CollectionSource, E, S: StateReader<E>>(
 		&self,
-		state: &mut S,
+		state: &S,
 		hash: &H256,
 		row: u64,
+		cache: Option<&MatterCache<C>>,
 	) -> Result<Vec<Bytes32>, ElementError> {
-		let matter = state.get_matter(hash).map_err(|_| ElementError::StateReaderGetMatter)?;
-		let coll = CollectionMatter::from_matter(&matter)?;
-		coll.row_at(row)
+		let forms = self.forms.as_deref();
+		if let Some(cache) = cache {
+			return cache.get_or_parse_in(state, hash, forms)?.row_at(row);
+		}
+		let matter = state
+			.get_matter(hash)
+			.map_err(|err| ElementError::StateReaderGetMatter(state.error_code(&err)))?;
+		C::open(&matter, forms)?.row_at(row)
 	}
 }
 
 pub enum CollectionMatter {
 	Enum(EnumMatter),
 	Perm(PermMatter),
+	Sparse(SparseMatter),
+	Range(RangeMatter),
+	Custom(Box<dyn RowSource>),
 }
 
 impl CollectionMatter {
 	pub fn from_matter(matter: &Matter) -> Result<Self, ElementError> {
+		span!("parse_matter", form = matter.form, bytes = matter.blob.len());
 		match matter.form {
 			x if x == MatterForm::Enum as u8 => EnumMatter::from(&matter.blob)
 				.map_err(|_| ElementError::EnumMatterFrom)
 				.map(Self::Enum),
-			x if x == MatterForm::Perm as u8 => PermMatter::from(&matter.blob)
+			x if x == MatterForm::Perm as u8 => PermMatter::from_strict(&matter.blob)
 				.map_err(|_| ElementError::PermMatterFrom)
 				.map(Self::Perm),
+			x if x == MatterForm::Sparse as u8 => SparseMatter::from(&matter.blob)
+				.map_err(|_| ElementError::SparseMatterFrom)
+				.map(Self::Sparse),
+			x if x == MatterForm::Range as u8 => RangeMatter::from(&matter.blob)
+				.map_err(|_| ElementError::RangeMatterFrom)
+				.map(Self::Range),
 			_ => Err(ElementError::NotCollection),
 		}
 	}
 
-	pub fn row_at(&self, row: u64) -> Result<Vec<Bytes32>, ElementError> {
-		let Ok(row) = usize::try_from(row) else {
-			return Err(ElementError::RowOutOfBounds);
+	/// Like `from_matter`, also accepting collection forms registered in `registry`.
+	pub fn from_matter_in(
+		matter: &Matter,
+		registry: &MatterFormRegistry,
+	) -> Result<Self, ElementError> {
+		let Some(custom) = registry.lookup(matter.form) else {
+			return Self::from_matter(matter);
 		};
+		ensure!((custom.validate)(&matter.blob), ElementError::CustomMatterInvalid);
+		let open = custom.row_source.ok_or(ElementError::NotCollection)?;
+		open(&matter.blob).map(Self::Custom).ok_or(ElementError::CustomMatterInvalid)
+	}
+
+	fn source(&self) -> &dyn RowSource {
 		match self {
-			CollectionMatter::Enum(m) => m
-				.row_at(row)
-				.map_err(|_| ElementError::EnumMatterRowAt)
-				.map(|v| v.into_iter().copied().collect()),
-			CollectionMatter::Perm(m) => m
-				.row_at(row)
-				.map_err(|_| ElementError::PermMatterRowAt)
-				.map(|v| v.into_iter().copied().collect()),
+			CollectionMatter::Enum(m) => m,
+			CollectionMatter::Perm(m) => m,
+			CollectionMatter::Sparse(m) => m,
+			CollectionMatter::Range(m) => m,
+			CollectionMatter::Custom(m) => m.as_ref(),
 		}
 	}
+
+	/// Picks `count` distinct rows, in draw order, with a partial Fisher-Yates shuffle driven by
+	/// `H(seed ++ counter)`. The same seed always yields the same rows, so anyone holding the
+	/// seed can check an assignment.
+	pub fn sample<H: MerkleHasher>(
+		&self,
+		seed: &[u8; 32],
+		count: u64,
+	) -> Result<Vec<u64>, ElementError> {
+		let rows = self.rows();
+		ensure!(count <= rows, ElementError::RowOutOfBounds);
+		let mut counter = 0u64;
+		let mut draw = |bound: u64| {
+			// Rejection sampling keeps the draw uniform over 0..bound.
+			let zone = u64::MAX - (u64::MAX - bound + 1) % bound;
+			loop {
+				let mut input = [0u8; 40];
+				input[..32].copy_from_slice(seed);
+				input[32..].copy_from_slice(&counter.to_le_bytes());
+				counter += 1;
+				let v = u64::from_le_bytes(H::hash(&input)[..8].try_into().unwrap());
+				if v <= zone {
+					return v % bound;
+				}
+			}
+		};
+		// Only displaced positions are stored, so huge perm matters cost O(count).
+		let mut swapped: BTreeMap<u64, u64> = BTreeMap::new();
+		let mut out = Vec::new();
+		usize::try_from(count)
+			.ok()
+			.and_then(|n| out.try_reserve_exact(n).ok())
+			.ok_or(ElementError::SampleTooLarge(count))?;
+		for i in 0..count {
+			let j = i + draw(rows - i);
+			let at_j = swapped.get(&j).copied().unwrap_or(j);
+			let at_i = swapped.get(&i).copied().unwrap_or(i);
+			swapped.insert(j, at_i);
+			out.push(at_j);
+		}
+		Ok(out)
+	}
+}
+
+impl CollectionSource for CollectionMatter {
+	fn from_matter(matter: &Matter) -> Result<Self, ElementError> {
+		CollectionMatter::from_matter(matter)
+	}
+
+	fn from_matter_in(
+		matter: &Matter,
+		registry: &MatterFormRegistry,
+	) -> Result<Self, ElementError> {
+		CollectionMatter::from_matter_in(matter, registry)
+	}
+}
+
+impl RowSource for CollectionMatter {
+	fn rows(&self) -> u64 {
+		self.source().rows()
+	}
+
+	fn cols(&self) -> usize {
+		self.source().cols()
+	}
+
+	fn cell_at(&self, row: u64, col: usize) -> Result<Bytes32, ElementError> {
+		self.source().cell_at(row, col)
+	}
+
+	fn row_at(&self, row: u64) -> Result<Vec<Bytes32>, ElementError> {
+		self.source().row_at(row)
+	}
 }
 
 #[cfg(test)]
 mod tests {
 	use crate::{
-		to_mime, Arc, Bytes32, Descriptor, ElementPicker, EnumMatter, Facet, Matter, MatterForm,
-		PermMatter, PickFrom, PickerFlags, StateReader, Unique, Value, Vec, H256, OID,
+		test_vectors::{
+			enum_blob, SumHasher, ENUM_BLOB, ENUM_MATTER_HASH, ENUM_ROW_0, HERE_COLLECTION_FLAGS,
+		},
+		to_mime, Arc, Bytes32, CollectionMatter, Descriptor, ElementError, ElementOrigin,
+		ElementPicker, EnumMatter, Facet, Matter, MatterCache, MatterForm, MerkleHasher, Mime,
+		MutMask, PatchPlan, PermMatter, PickFrom, PickerFlags, Prefetched, Rev, RowSource,
+		StateReader, Time, Unique, Value, Vec, H256, OID,
 	};
 	use anyhow::Result;
 	use mockall::mock;
@@ -381,56 +903,234 @@ mod tests {
 		pub MyState {}
 
 		impl StateReader<E> for MyState {
-			fn get_matter(&mut self, hash: &H256) -> Result<Matter, E>;
-			fn get_value(&mut self, tid: &OID, rev: u32) -> Result<Value, E>;
-			fn get_unique(&mut self, tid: &OID, rev: u32) -> Result<Unique, E>;
+			fn get_matter(&self, hash: &H256) -> Result<Matter, E>;
+			fn get_value(&self, tid: &OID, rev: Rev) -> Result<Value, E>;
+			fn get_unique(&self, tid: &OID, rev: Rev) -> Result<Unique, E>;
 			// objects
-			fn get_descriptor(&mut self, oid: &OID, rev: u32) -> Result<Descriptor, E>;
-			fn get_snapshot(&mut self, oid: &OID, rev: u32) -> Result<(Descriptor, Vec<Bytes32>), E>;
-			fn get_tails(&mut self, oid: &OID, rev: u32) -> Result<Vec<Arc>, E>;
-			fn get_facets(&mut self, oid: &OID, rev: u32) -> Result<Vec<Facet>, E>;
-			fn get_facet(&mut self, oid: &OID, rev: u32, sel: u32) -> Result<Matter, E>;
+			fn get_descriptor(&self, oid: &OID, rev: Rev) -> Result<Descriptor, E>;
+			fn get_snapshot(&self, oid: &OID, rev: Rev) -> Result<(Descriptor, Vec<Bytes32>), E>;
+			fn get_snapshot_at(&self, oid: &OID, time: Time) -> Result<(Descriptor, Vec<Bytes32>), E>;
+			fn get_tails(&self, oid: &OID, rev: Rev) -> Result<Vec<Arc>, E>;
+			fn get_heads(&self, oid: &OID, rev: Rev) -> Result<Vec<Arc>, E>;
+			fn get_facets(&self, oid: &OID, rev: Rev) -> Result<Vec<Facet>, E>;
+			fn get_facet(&self, oid: &OID, rev: Rev, sel: u32) -> Result<Matter, E>;
 
 			// helpers
-			fn get_kind_contract(&mut self, oid: &OID, rev: u32) -> Result<Matter, E>;
+			fn get_kind_contract(&self, oid: &OID, rev: Rev) -> Result<Matter, E>;
 		}
 	}
 
 	#[test]
 	fn resolve_from_coll() -> Result<()> {
-		let flags = PickerFlags {
-			mut_bits: 0,
-			here_coll: true,
-			custom: false,
-			row_from: PickFrom::HereCollection,
-		};
-		let encoded = flags.encode();
-		println!("encoded = {}", encoded);
-
-		use hex::FromHex;
-		let coll =
-			Bytes32::from_hex("31422881b15f078500f6012a56e41c2564c266d5bbc59e7638cc4e5864211481")?;
-
-		let coll_content = Vec::<u8>::from_hex("454e554d10020200000000000000000002ff0000000000000000000000000000cb8dd44f076c2a2bc61da2fe9bd5be9201357571a98fcea73737779070cafa780000000000000000000000000000000000000000000000000000000000123456561592b3c5d66e46c470f2b9ac36a855c4d91531239d61f0ec3e571ca51059e80000000000000000000000000000000000000000000000000000000000123456")?;
-		let mut elems = vec![coll.clone()];
-
-		let resolver = ElementPicker::new(encoded, elems)?;
+		assert_eq!(PickerFlags::new().with_here_coll().encode(), HERE_COLLECTION_FLAGS);
+		let resolver = ElementPicker::new(HERE_COLLECTION_FLAGS, vec![ENUM_MATTER_HASH])?;
 		let oid = OID { universe: 31337, set: 17, id: 1 };
-		let desc = Descriptor { traits: 0, rev: 1, krev: 1, srev: 1, kind: 17, trev: 0, fsum: 0 };
+		let desc = Descriptor {
+			traits: 0,
+			rev: Rev(1),
+			krev: Rev(1),
+			srev: Rev(1),
+			kind: 17,
+			trev: Rev(0),
+			fsum: 0,
+		};
 		let mut state = MockMyState::new();
-		let m =
-			Matter { form: 208, mime: to_mime(b"application/vnd.every.enum"), blob: coll_content };
+		let blob = ENUM_BLOB.to_vec();
+		let m = Matter { form: 208, mime: Mime::ENUM, blob };
 		state
 			.expect_get_matter()
-			.withf(move |h| h == &coll)
+			.withf(move |h| h == &ENUM_MATTER_HASH)
 			.returning(move |_| Ok(m.clone()));
 
-		let out = resolver.resolve::<E, _>(&mut state, &oid, &desc)?;
-		let expected = vec![
-			Bytes32::from_hex("cb8dd44f076c2a2bc61da2fe9bd5be9201357571a98fcea73737779070cafa78")?,
-			Bytes32::from_hex("0000000000000000000000000000000000000000000000000000000000123456")?,
-		];
-		assert_eq!(out, expected);
+		let out = resolver.resolve::<E, _>(&state, &oid, &desc)?;
+		assert_eq!(out, ENUM_ROW_0.to_vec());
 		Ok(())
 	}
+
+	#[test]
+	fn pickers_share_matter_cache() -> Result<()> {
+		let oid = OID { universe: 31337, set: 17, id: 1 };
+		let desc = Descriptor { rev: Rev(1), krev: Rev(1), srev: Rev(1), ..Default::default() };
+		let mut state = MockMyState::new();
+		let m = Matter { form: 208, mime: Mime::ENUM, blob: ENUM_BLOB.to_vec() };
+		state.expect_get_matter().times(1).returning(move |_| Ok(m.clone()));
+
+		let cache = MatterCache::<CollectionMatter>::new(4);
+		for _ in 0..2 {
+			let picker = ElementPicker::new(HERE_COLLECTION_FLAGS, vec![ENUM_MATTER_HASH])?;
+			let out = picker.resolve_cached::<_, E, _>(&state, &oid, &desc, &cache)?;
+			assert_eq!(out, ENUM_ROW_0.to_vec());
+		}
+		assert!(cache.contains(&ENUM_MATTER_HASH));
+		Ok(())
+	}
+
+	#[cfg(all(feature = "std", feature = "storage"))]
+	#[test]
+	fn state_errors_keep_their_cause() {
+		use crate::{
+			db_state::{DbState, MemKv},
+			reader::StateError,
+			storage_key::matter_storage_key,
+			writer::StateWriter,
+			Error,
+		};
+		let picker = ElementPicker::new(HERE_COLLECTION_FLAGS, vec![ENUM_MATTER_HASH]).unwrap();
+		let oid = OID { universe: 31337, set: 17, id: 1 };
+		let desc = Descriptor { rev: Rev(1), ..Default::default() };
+		let mut db = DbState::new(MemKv::default());
+
+		let missing = Error::from(picker.resolve::<StateError, _>(&db, &oid, &desc).unwrap_err());
+		assert!(missing.is_not_found() && !missing.is_corrupt());
+
+		db._put(&matter_storage_key(&ENUM_MATTER_HASH), vec![0xff]);
+		let corrupt = Error::from(picker.resolve::<StateError, _>(&db, &oid, &desc).unwrap_err());
+		assert!(corrupt.is_corrupt() && !corrupt.is_not_found());
+		assert_eq!(corrupt.code(), ElementError::StateReaderGetMatter(None).code());
+	}
+
+	#[test]
+	fn sample_is_deterministic_and_distinct() {
+		let blob = enum_blob(10, 1);
+		let m = Matter { form: MatterForm::Enum as u8, mime: Mime::ENUM, blob };
+		let coll = CollectionMatter::from_matter(&m).unwrap();
+
+		let seed = [7u8; 32];
+		let all = coll.sample::<SumHasher>(&seed, 10).unwrap();
+		let mut sorted = all.clone();
+		sorted.sort();
+		assert_eq!(sorted, (0..10).collect::<Vec<u64>>());
+		assert_eq!(coll.sample::<SumHasher>(&seed, 3).unwrap(), all[..3]);
+		assert_ne!(coll.sample::<SumHasher>(&[8u8; 32], 10).unwrap(), all);
+		assert_eq!(coll.sample::<SumHasher>(&seed, 11), Err(ElementError::RowOutOfBounds));
+
+		struct Endless;
+		impl RowSource for Endless {
+			fn rows(&self) -> u64 {
+				u64::MAX
+			}
+			fn cols(&self) -> usize {
+				0
+			}
+			fn cell_at(&self, _: u64, _: usize) -> Result<Bytes32, ElementError> {
+				Err(ElementError::ColOutOfBounds)
+			}
+		}
+		let endless = CollectionMatter::Custom(Box::new(Endless));
+		assert_eq!(endless.sample::<SumHasher>(&seed, 2).unwrap().len(), 2);
+		assert_eq!(
+			endless.sample::<SumHasher>(&seed, u64::MAX),
+			Err(ElementError::SampleTooLarge(u64::MAX))
+		);
+	}
+
+	#[test]
+	fn picker_flags_reject_unknown_bits() {
+		let flags = PickerFlags::new().with_row_from(PickFrom::KindData).with_picker();
+		assert_eq!(PickerFlags::decode(flags.encode()), Ok(flags));
+		let v = 0xABCD_0000 | HERE_COLLECTION_FLAGS;
+		assert_eq!(PickerFlags::decode(v).map(|f| f.encode()), Ok(v));
+
+		assert_eq!(PickerFlags::decode(v | 0x20), Err(ElementError::ReservedFlagBits(0x20)));
+		let lenient = PickerFlags::decode_lenient(v | 0x0800).unwrap();
+		assert_eq!(lenient.encode(), v);
+
+		let future = v | 0x1000;
+		assert_eq!(PickerFlags::decode(future), Err(ElementError::UnsupportedFlagsVersion(1)));
+		let lenient = PickerFlags::decode_lenient(future).unwrap();
+		assert_eq!((lenient.version(), lenient.encode()), (1, future));
+		assert!(ElementPicker::new(future, vec![ENUM_MATTER_HASH]).is_err());
+	}
+
+	#[test]
+	fn mut_mask_patches_slots() {
+		let mut mask = MutMask::new();
+		mask.set_mutable(0).unwrap();
+		mask.set_mutable(2).unwrap();
+		assert_eq!(mask.set_mutable(16), Err(ElementError::InvalidMutBits));
+		assert_eq!(u16::from(mask), 0b1010_0000_0000_0000);
+		assert!(mask.is_mutable(2) && !mask.is_mutable(1) && !mask.is_mutable(99));
+		assert_eq!((mask.count(), mask.slots().collect::<Vec<_>>()), (2, vec![0, 2]));
+		let flags = PickerFlags::new().with_mut_mask(mask);
+		assert_eq!(PickerFlags::decode(flags.encode()).unwrap().mut_mask(), mask);
+
+		let prev = vec![[1; 32], [2; 32], [3; 32]];
+		let patched = ElementPicker::patch(prev.clone(), vec![[7; 32], [9; 32]], mask);
+		assert_eq!(patched, Ok(vec![[7; 32], [2; 32], [9; 32]]));
+		assert_eq!(
+			ElementPicker::patch(prev[..2].to_vec(), vec![[7; 32], [9; 32]], mask),
+			Err(ElementError::InvalidMutBits)
+		);
+		assert_eq!(ElementPicker::patch(vec![], vec![], MutMask::new()), Ok(vec![]));
+
+		let plan = PatchPlan::new(3, mask).unwrap();
+		assert_eq!((plan.writes(), plan.slots().collect::<Vec<_>>()), (2, vec![0, 2]));
+		assert_eq!(plan.apply(prev[..2].to_vec(), vec![]), Err(ElementError::InvalidElementLength));
+		assert_eq!(
+			plan.apply(prev.clone(), vec![[7; 32]]),
+			Err(ElementError::ResultLengthMismatch)
+		);
+		let full = PatchPlan::new(3, MutMask::new()).unwrap();
+		assert!(full.is_full());
+		assert_eq!((full.writes(), full.slots().count()), (3, 3));
+		assert_eq!(PatchPlan::new(2, mask), Err(ElementError::InvalidMutBits));
+		assert_eq!(MutMask::new().validate(17), Err(ElementError::InvalidElementLength));
+	}
+
+	#[test]
+	fn traced_origins_follow_resolve() {
+		let mut state = Prefetched::default();
+		let blob = ENUM_BLOB.to_vec();
+		state.insert_matter(ENUM_MATTER_HASH, Matter { form: 208, mime: Mime::default(), blob });
+		let oid = OID { universe: 1, set: 17, id: 1 };
+		let desc = Descriptor { rev: Rev(2), ..Default::default() };
+		let origin = |src, col| ElementOrigin { src, row: 0, col };
+
+		let flags = PickerFlags::new().with_here_coll().with_picker().encode();
+		// Column 1 of the collection row, the first here element, then column 0.
+		let picks = [0xE1, 0xF0, 0xE0].iter().chain(&[0; 29]).copied().collect::<Vec<_>>();
+		let elems = vec![[5; 32], ENUM_MATTER_HASH, picks.try_into().unwrap()];
+		let custom = ElementPicker::new(flags, elems).unwrap();
+		let plain = ElementPicker::new(HERE_COLLECTION_FLAGS, vec![ENUM_MATTER_HASH]).unwrap();
+		for picker in [&custom, &plain] {
+			let (elems, origins) = picker.resolve_traced(&state, &oid, &desc).unwrap();
+			assert_eq!(elems, picker.resolve(&state, &oid, &desc).unwrap());
+			for (elem, o) in elems.iter().zip(&origins) {
+				let from = if o.src == PickFrom::HereElements {
+					[5; 32]
+				} else {
+					ENUM_ROW_0[o.col as usize]
+				};
+				assert_eq!(*elem, from);
+			}
+		}
+		let (_, origins) = custom.resolve_traced(&state, &oid, &desc).unwrap();
+		assert_eq!(
+			origins,
+			[
+				origin(PickFrom::HereCollection, 1),
+				origin(PickFrom::HereElements, 0),
+				origin(PickFrom::HereCollection, 0)
+			]
+		);
+
+		// Patched slots take the resolved origins in order; the rest keep `None`.
+		let (elems, origins) = plain.resolve_traced(&state, &oid, &desc).unwrap();
+		let mut mask = MutMask::new();
+		mask.set_mutable(1).unwrap();
+		mask.set_mutable(3).unwrap();
+		let prev = vec![[1; 32]; 4];
+		let patched = ElementPicker::patch(prev.clone(), elems.clone(), mask).unwrap();
+		let traced = ElementPicker::patch_origins(4, &origins, mask).unwrap();
+		assert_eq!(traced, [None, Some(origins[0]), None, Some(origins[1])]);
+		for (elem, o) in patched.iter().zip(&traced) {
+			assert_eq!(*elem, o.map_or([1; 32], |o| ENUM_ROW_0[o.col as usize]));
+		}
+		let unpatched = ElementPicker::patch_origins(2, &origins, MutMask::new()).unwrap();
+		assert_eq!(unpatched, origins.iter().copied().map(Some).collect::<Vec<_>>());
+		assert_eq!(
+			ElementPicker::patch_origins(4, &origins[..1], mask),
+			Err(ElementError::ResultLengthMismatch)
+		);
+	}
 }
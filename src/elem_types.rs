@@ -1,3 +1,4 @@
+use crate::Mime;
 use core::convert::TryFrom;
 use derive_more::Display;
 
@@ -15,11 +16,15 @@ pub enum ElementType {
 	// Simple
 	Json = 0x01,
 	Image = 0x02,
+	Schema = 0x03,
 	// Code
 	Wasm = 0xC0,
+	Formula = 0xC1,
 	// Data Collection
 	Enum = 0xD0,
 	Perm = 0xD1,
+	Sparse = 0xD2,
+	Range = 0xD3,
 	// Meta objects
 	Set = 0xE1,
 	Kind = 0xE2,
@@ -38,11 +43,15 @@ pub enum MatterForm {
 	// Simple
 	Json = 0x01,
 	Image = 0x02,
+	Schema = 0x03,
 	// Code
 	Wasm = 0xC0,
+	Formula = 0xC1,
 	// Data Collection
 	Enum = 0xD0,
 	Perm = 0xD1,
+	Sparse = 0xD2,
+	Range = 0xD3,
 }
 
 impl TryFrom<u8> for ElementType {
@@ -51,9 +60,13 @@ impl TryFrom<u8> for ElementType {
 		Ok(match v {
 			0x01 => ElementType::Json,
 			0x02 => ElementType::Image,
+			0x03 => ElementType::Schema,
 			0xC0 => ElementType::Wasm,
+			0xC1 => ElementType::Formula,
 			0xD0 => ElementType::Enum,
 			0xD1 => ElementType::Perm,
+			0xD2 => ElementType::Sparse,
+			0xD3 => ElementType::Range,
 			0xE1 => ElementType::Set,
 			0xE2 => ElementType::Kind,
 			0xE3 => ElementType::Relation,
@@ -78,9 +91,13 @@ impl TryFrom<u8> for MatterForm {
 		Ok(match v {
 			0x01 => MatterForm::Json,
 			0x02 => MatterForm::Image,
+			0x03 => MatterForm::Schema,
 			0xC0 => MatterForm::Wasm,
+			0xC1 => MatterForm::Formula,
 			0xD0 => MatterForm::Enum,
 			0xD1 => MatterForm::Perm,
+			0xD2 => MatterForm::Sparse,
+			0xD3 => MatterForm::Range,
 			_ => return Err(ElementTypeError::UnknownDiscriminant(v)),
 		})
 	}
@@ -97,9 +114,13 @@ impl From<MatterForm> for ElementType {
 		match f {
 			MatterForm::Json => ElementType::Json,
 			MatterForm::Image => ElementType::Image,
+			MatterForm::Schema => ElementType::Schema,
 			MatterForm::Wasm => ElementType::Wasm,
+			MatterForm::Formula => ElementType::Formula,
 			MatterForm::Enum => ElementType::Enum,
 			MatterForm::Perm => ElementType::Perm,
+			MatterForm::Sparse => ElementType::Sparse,
+			MatterForm::Range => ElementType::Range,
 		}
 	}
 }
@@ -110,10 +131,89 @@ impl TryFrom<ElementType> for MatterForm {
 		match e {
 			ElementType::Json => Ok(MatterForm::Json),
 			ElementType::Image => Ok(MatterForm::Image),
+			ElementType::Schema => Ok(MatterForm::Schema),
 			ElementType::Wasm => Ok(MatterForm::Wasm),
+			ElementType::Formula => Ok(MatterForm::Formula),
 			ElementType::Enum => Ok(MatterForm::Enum),
 			ElementType::Perm => Ok(MatterForm::Perm),
+			ElementType::Sparse => Ok(MatterForm::Sparse),
+			ElementType::Range => Ok(MatterForm::Range),
 			other => Err(ElementTypeError::NotAMatterForm(other)),
 		}
 	}
 }
+
+impl MatterForm {
+	pub fn sniff(blob: &[u8]) -> Option<(MatterForm, Mime)> {
+		let text = trim_ascii_start(blob);
+		Some(match blob {
+			[b'E', b'N', b'U', b'M', ..] => (MatterForm::Enum, Mime::ENUM),
+			[b'P', b'E', b'R', b'M', ..] => (MatterForm::Perm, Mime::PERM),
+			[b'S', b'P', b'R', b'S', ..] => (MatterForm::Sparse, Mime::SPARSE),
+			[b'R', b'A', b'N', b'G', ..] => (MatterForm::Range, Mime::RANGE),
+			[b'S', b'C', b'H', b'M', ..] => (MatterForm::Schema, Mime::SCHEMA),
+			[0x00, b'a', b's', b'm', ..] => (MatterForm::Wasm, Mime::WASM),
+			[b'F', b'R', b'M', b'L', ..] => (MatterForm::Formula, Mime::FORMULA),
+			[b'J', b'D', b'Z', b'1', ..] => (MatterForm::Json, Mime::JSONZ),
+			[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, ..] => (MatterForm::Image, Mime::PNG),
+			[0xFF, 0xD8, 0xFF, ..] => (MatterForm::Image, Mime::JPEG),
+			[b'G', b'I', b'F', b'8', b'7' | b'9', b'a', ..] => (MatterForm::Image, Mime::GIF),
+			_ if is_svg(text) => (MatterForm::Image, Mime::SVG),
+			_ if matches!(text.first(), Some(b'{' | b'[')) => (MatterForm::Json, Mime::JSON),
+			_ => return None,
+		})
+	}
+}
+
+pub fn sniff_form(blob: &[u8]) -> Option<MatterForm> {
+	MatterForm::sniff(blob).map(|(form, _)| form)
+}
+
+fn trim_ascii_start(blob: &[u8]) -> &[u8] {
+	let start = blob.iter().position(|b| !b.is_ascii_whitespace()).unwrap_or(blob.len());
+	&blob[start..]
+}
+
+fn is_svg(text: &[u8]) -> bool {
+	const SCAN_LIMIT: usize = 1024;
+	if text.starts_with(b"<svg") {
+		return true;
+	}
+	if !text.starts_with(b"<?xml") && !text.starts_with(b"<!--") && !text.starts_with(b"<!DOCTYPE")
+	{
+		return false;
+	}
+	let head = &text[..text.len().min(SCAN_LIMIT)];
+	head.windows(4).any(|w| w == b"<svg")
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{sniff_form, MatterForm, Mime};
+
+	#[test]
+	fn sniff_known_magics() {
+		assert_eq!(
+			MatterForm::sniff(b"ENUM\x10\x00\x00\x00"),
+			Some((MatterForm::Enum, Mime::ENUM))
+		);
+		assert_eq!(
+			MatterForm::sniff(b"PERM\x10\x00\x00\x00"),
+			Some((MatterForm::Perm, Mime::PERM))
+		);
+		assert_eq!(
+			MatterForm::sniff(b"RANG\x01\x00\x00\x00"),
+			Some((MatterForm::Range, Mime::RANGE))
+		);
+		assert_eq!(MatterForm::sniff(b"\0asm\x01\0\0\0"), Some((MatterForm::Wasm, Mime::WASM)));
+		assert_eq!(MatterForm::sniff(b"\x89PNG\r\n\x1a\n"), Some((MatterForm::Image, Mime::PNG)));
+		assert_eq!(MatterForm::sniff(b"GIF89a"), Some((MatterForm::Image, Mime::GIF)));
+		assert_eq!(
+			MatterForm::sniff(b"<?xml version=\"1.0\"?>\n<svg xmlns=\"\"/>"),
+			Some((MatterForm::Image, Mime::SVG))
+		);
+		assert_eq!(sniff_form(b"  \n{\"a\": 1}"), Some(MatterForm::Json));
+		assert_eq!(sniff_form(b"hello"), None);
+		assert_eq!(sniff_form(b""), None);
+	}
+}
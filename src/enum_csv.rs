@@ -0,0 +1,161 @@
+#![cfg(feature = "std")]
+
+use crate::{
+	parse_hex, Bytes32, ElementType, EnumMatter, EnumMatterError, EnumMatterHeader, Hex, Vec,
+};
+use std::io::{BufRead, Write};
+use thiserror::Error;
+
+// One CSV line per row, one field per column, no header and no quoting. Cells are written as
+// `0x` followed by 64 hex digits. Cells of `Info` columns may instead be plain text of at most
+// 32 bytes, zero padded on the right; that form is used on export whenever it reads back to the
+// same cell. A matter without columns is one empty line per row. Aux cells are not part of the
+// CSV.
+
+#[derive(Debug, Error)]
+pub enum EnumCsvError {
+	#[error("io error: {0}")]
+	Io(#[from] std::io::Error),
+	#[error("line {line}: expected {expect} cells, got {got}")]
+	ColCount { line: usize, expect: usize, got: usize },
+	#[error("line {line}: invalid cell in column {col}")]
+	BadCell { line: usize, col: usize },
+	#[error("too many rows")]
+	TooManyRows,
+	#[error("enum matter error: {0}")]
+	Matter(EnumMatterError),
+}
+
+impl EnumMatter {
+	pub fn to_csv<W: Write>(&self, mut writer: W) -> Result<(), EnumCsvError> {
+		for row in 0..self.rows() {
+			let cells = self.row_at(row).map_err(EnumCsvError::Matter)?;
+			let fields: Vec<String> = cells
+				.iter()
+				.enumerate()
+				.map(|(col, cell)| match is_text_col(self.header.col_types[col]) {
+					true => cell_to_text(cell).unwrap_or_else(|| Hex(*cell).to_string()),
+					false => Hex(*cell).to_string(),
+				})
+				.collect();
+			writeln!(writer, "{}", fields.join(","))?;
+		}
+		Ok(())
+	}
+
+	/// Reads rows written by `to_csv` (or by hand) into a matter with the given column types and
+	/// no aux cells.
+	pub fn from_csv<R: BufRead>(reader: R, col_types: &[u8]) -> Result<Self, EnumCsvError> {
+		let mut types = [0u8; 16];
+		types
+			.get_mut(..col_types.len())
+			.ok_or(EnumCsvError::Matter(EnumMatterError::BadColCount(col_types.len() as u8)))?
+			.copy_from_slice(col_types);
+
+		let mut rows = 0u16;
+		let mut body = Vec::new();
+		for (i, line) in reader.lines().enumerate() {
+			let line = line?;
+			let line = line.trim_end_matches('\r');
+			// Splitting would read an empty line as one empty field.
+			let fields: Vec<&str> = match col_types.is_empty() && line.is_empty() {
+				true => Vec::new(),
+				false => line.split(',').collect(),
+			};
+			if fields.len() != col_types.len() {
+				return Err(EnumCsvError::ColCount {
+					line: i + 1,
+					expect: col_types.len(),
+					got: fields.len(),
+				});
+			}
+			for (col, field) in fields.into_iter().enumerate() {
+				let cell = match field.starts_with("0x") {
+					true => parse_hex(field).ok(),
+					false if is_text_col(col_types[col]) => cell_from_text(field),
+					false => None,
+				}
+				.ok_or(EnumCsvError::BadCell { line: i + 1, col })?;
+				body.extend_from_slice(&cell);
+			}
+			rows = rows.checked_add(1).ok_or(EnumCsvError::TooManyRows)?;
+		}
+
+		let header = EnumMatterHeader {
+			magic: EnumMatterHeader::MAGIC,
+			ver_aux: 0x10,
+			cols: col_types.len() as u8,
+			rows,
+			aux_types: [0u8; 8],
+			col_types: types,
+		};
+		let mut blob = header.encode().to_vec();
+		blob.append(&mut body);
+		EnumMatter::from(&blob).map_err(EnumCsvError::Matter)
+	}
+}
+
+fn is_text_col(col_type: u8) -> bool {
+	col_type == ElementType::Info as u8
+}
+
+fn cell_to_text(cell: &Bytes32) -> Option<String> {
+	let end = cell.iter().rposition(|&b| b != 0).map_or(0, |i| i + 1);
+	let text = core::str::from_utf8(&cell[..end]).ok()?;
+	let plain = !text.starts_with("0x") && !text.contains(['\0', ',', '\n', '\r']);
+	plain.then(|| text.to_string())
+}
+
+fn cell_from_text(text: &str) -> Option<Bytes32> {
+	let mut cell = [0u8; 32];
+	cell.get_mut(..text.len())?.copy_from_slice(text.as_bytes());
+	Some(cell)
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{enum_csv::EnumCsvError, ElementType, EnumMatter, EnumMatterError};
+
+	#[test]
+	fn csv_roundtrip() {
+		let text = ElementType::Info as u8;
+		let csv = format!(
+			"Gold,0x{}\n0x{},0x{}\n",
+			"ab".repeat(32),
+			"00".repeat(31) + "ff",
+			"01".repeat(32)
+		);
+		let m = EnumMatter::from_csv(csv.as_bytes(), &[text, 1]).unwrap();
+		let m = EnumMatter::from(&m.to_blob()).unwrap();
+		assert_eq!(m.rows(), 2);
+		assert_eq!(&m.cell_at(0, 0).unwrap()[..5], b"Gold\0");
+
+		let mut out = Vec::new();
+		m.to_csv(&mut out).unwrap();
+		assert_eq!(String::from_utf8(out).unwrap(), csv);
+		assert!(EnumMatter::from_csv("Gold,Silver\n".as_bytes(), &[text, 1]).is_err());
+		assert!(matches!(
+			EnumMatter::from_csv("0x01\n".as_bytes(), &[text, 1]),
+			Err(EnumCsvError::ColCount { line: 1, expect: 2, got: 1 })
+		));
+		assert!(matches!(
+			EnumMatter::from_csv(csv.as_bytes(), &[text, 0]),
+			Err(EnumCsvError::Matter(EnumMatterError::BadColTypes))
+		));
+	}
+
+	#[test]
+	fn csv_roundtrip_without_columns() {
+		let m = EnumMatter::from_csv("\n\r\n\n".as_bytes(), &[]).unwrap();
+		assert_eq!(m.rows(), 3);
+
+		let mut out = Vec::new();
+		m.to_csv(&mut out).unwrap();
+		assert_eq!(out, b"\n\n\n");
+		assert_eq!(EnumMatter::from_csv(out.as_slice(), &[]).unwrap().to_blob(), m.to_blob());
+		assert!(matches!(
+			EnumMatter::from_csv("0x01\n".as_bytes(), &[]),
+			Err(EnumCsvError::ColCount { line: 1, expect: 0, got: 1 })
+		));
+	}
+}
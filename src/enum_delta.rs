@@ -0,0 +1,264 @@
+use crate::{Bytes32, EnumMatter, EnumMatterError, EnumMatterHeader, Vec};
+use thiserror::Error;
+
+#[derive(Clone, Copy, Debug, Error, PartialEq, Eq)]
+pub enum EnumDeltaError {
+	#[error("invalid EnumDelta header")]
+	BadHeader,
+
+	#[error("bad magic: expected 'EDLT' (45 44 4C 54), got {0:02X?}")]
+	BadMagic([u8; 4]),
+
+	#[error("unsupported version {0} (expected 1)")]
+	BadVersion(u8),
+
+	#[error("invalid body length: expected {expect} bytes, got {got} bytes")]
+	BadBody { expect: usize, got: usize },
+
+	#[error("base mismatch: delta expects {cols} cols x {rows} rows")]
+	BaseMismatch { cols: u8, rows: u16 },
+
+	#[error("replaced cells must be strictly ordered by (row, col)")]
+	Unordered,
+
+	#[error("replaced cell out of base bounds at (row={row}, col={col})")]
+	OobCell { row: u16, col: u8 },
+
+	#[error("row count overflows u16")]
+	TooManyRows,
+
+	#[error("more than 65535 replaced cells")]
+	TooManyPatches,
+
+	#[error("enum matter error: {0}")]
+	Matter(EnumMatterError),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CellPatch {
+	pub row: u16,
+	pub col: u8,
+	pub cell: Bytes32,
+}
+
+/// Changes from one EnumMatter revision to the next: cells replaced in the base rows, then rows
+/// appended after them. Aux cells and column types are carried over unchanged.
+///
+/// Layout: magic "EDLT", version (1), cols, base rows (u16 LE), appended rows (u16 LE), replaced
+/// count (u16 LE), then `replaced` entries of row (u16 LE), col, 0, cell[32], then the appended
+/// rows' cells, row major.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnumDelta {
+	pub cols: u8,
+	pub base_rows: u16,
+	/// Kept apart from `appended` so rows without columns are still counted.
+	pub appended_rows: u16,
+	pub replaced: Vec<CellPatch>,
+	pub appended: Vec<u8>, // len = appended_rows * cols * 32
+}
+
+impl EnumDelta {
+	pub const MAGIC: [u8; 4] = *b"EDLT";
+	pub const HEADER_SIZE: usize = 12;
+	pub const PATCH_SIZE: usize = 36;
+
+	pub fn from(blob: &[u8]) -> Result<Self, EnumDeltaError> {
+		if blob.len() < Self::HEADER_SIZE {
+			return Err(EnumDeltaError::BadHeader);
+		}
+		let magic: [u8; 4] = blob[0..4].try_into().unwrap();
+		if magic != Self::MAGIC {
+			return Err(EnumDeltaError::BadMagic(magic));
+		}
+		if blob[4] != 1 {
+			return Err(EnumDeltaError::BadVersion(blob[4]));
+		}
+		let cols = blob[5];
+		let base_rows = u16::from_le_bytes(blob[6..8].try_into().unwrap());
+		let appended_rows = u16::from_le_bytes(blob[8..10].try_into().unwrap());
+		let replaced_count = u16::from_le_bytes(blob[10..12].try_into().unwrap()) as usize;
+
+		// Both counts are u16 and cols <= u8::MAX, so none of this can overflow usize.
+		let patches_end = Self::HEADER_SIZE + replaced_count * Self::PATCH_SIZE;
+		let expect =
+			patches_end + appended_rows as usize * cols as usize * EnumMatterHeader::CELL_SIZE;
+		if blob.len() != expect {
+			return Err(EnumDeltaError::BadBody { expect, got: blob.len() });
+		}
+
+		let replaced = blob[Self::HEADER_SIZE..patches_end]
+			.chunks_exact(Self::PATCH_SIZE)
+			.map(|p| CellPatch {
+				row: u16::from_le_bytes([p[0], p[1]]),
+				col: p[2],
+				cell: p[4..36].try_into().unwrap(),
+			})
+			.collect();
+		let appended = blob[patches_end..].to_vec();
+		let delta = Self { cols, base_rows, appended_rows, replaced, appended };
+		delta.validate()?;
+		Ok(delta)
+	}
+
+	/// Fails only if `replaced` does not fit its u16 count.
+	pub fn to_blob(&self) -> Result<Vec<u8>, EnumDeltaError> {
+		let count =
+			u16::try_from(self.replaced.len()).map_err(|_| EnumDeltaError::TooManyPatches)?;
+		let mut blob = Vec::with_capacity(
+			Self::HEADER_SIZE + self.replaced.len() * Self::PATCH_SIZE + self.appended.len(),
+		);
+		blob.extend_from_slice(&Self::MAGIC);
+		blob.push(1);
+		blob.push(self.cols);
+		blob.extend_from_slice(&self.base_rows.to_le_bytes());
+		blob.extend_from_slice(&self.appended_rows.to_le_bytes());
+		blob.extend_from_slice(&count.to_le_bytes());
+		for p in &self.replaced {
+			blob.extend_from_slice(&p.row.to_le_bytes());
+			blob.push(p.col);
+			blob.push(0);
+			blob.extend_from_slice(&p.cell);
+		}
+		blob.extend_from_slice(&self.appended);
+		Ok(blob)
+	}
+
+	/// Checks the parts of the delta that do not depend on the base. Replacements must be in
+	/// strictly increasing (row, col) order so every delta has exactly one encoding.
+	pub fn validate(&self) -> Result<(), EnumDeltaError> {
+		if self.replaced.len() > u16::MAX as usize {
+			return Err(EnumDeltaError::TooManyPatches);
+		}
+		let mut last: Option<(u16, u8)> = None;
+		for p in &self.replaced {
+			if p.row >= self.base_rows || p.col >= self.cols {
+				return Err(EnumDeltaError::OobCell { row: p.row, col: p.col });
+			}
+			if last.is_some_and(|l| l >= (p.row, p.col)) {
+				return Err(EnumDeltaError::Unordered);
+			}
+			last = Some((p.row, p.col));
+		}
+		let row_size = self.cols as usize * EnumMatterHeader::CELL_SIZE;
+		let expect = self.appended_rows as usize * row_size;
+		if self.appended.len() != expect {
+			return Err(EnumDeltaError::BadBody { expect, got: self.appended.len() });
+		}
+		if self.base_rows.checked_add(self.appended_rows).is_none() {
+			return Err(EnumDeltaError::TooManyRows);
+		}
+		Ok(())
+	}
+
+	/// Delta turning `base` into `next`. `next` must keep base's aux cells and column types and
+	/// have at least as many rows.
+	pub fn diff(base: &EnumMatter, next: &EnumMatter) -> Result<Self, EnumDeltaError> {
+		let (b, n) = (&base.header, &next.header);
+		if n.ver_aux != b.ver_aux ||
+			n.cols != b.cols ||
+			n.col_types != b.col_types ||
+			n.aux_types != b.aux_types ||
+			next.aux_data != base.aux_data ||
+			n.rows < b.rows
+		{
+			return Err(EnumDeltaError::BaseMismatch { cols: n.cols, rows: b.rows });
+		}
+		let mut replaced = Vec::new();
+		for row in 0..base.rows() {
+			for col in 0..base.cols() {
+				let cell = next.cell_at(row, col).map_err(EnumDeltaError::Matter)?;
+				if cell != base.cell_at(row, col).map_err(EnumDeltaError::Matter)? {
+					replaced.push(CellPatch { row: row as u16, col: col as u8, cell: *cell });
+				}
+			}
+		}
+		if replaced.len() > u16::MAX as usize {
+			return Err(EnumDeltaError::TooManyPatches);
+		}
+		let split = base.row_data.len();
+		Ok(Self {
+			cols: b.cols,
+			base_rows: b.rows,
+			appended_rows: n.rows - b.rows,
+			replaced,
+			appended: next.row_data[split..].to_vec(),
+		})
+	}
+
+	/// Applies the delta to `base`, which must have exactly the shape the delta was built for.
+	pub fn apply(&self, base: &EnumMatter) -> Result<EnumMatter, EnumDeltaError> {
+		self.validate()?;
+		if base.header.cols != self.cols || base.header.rows != self.base_rows {
+			return Err(EnumDeltaError::BaseMismatch { cols: self.cols, rows: self.base_rows });
+		}
+		let mut row_data = Vec::with_capacity(base.row_data.len() + self.appended.len());
+		row_data.extend_from_slice(&base.row_data);
+		for p in &self.replaced {
+			let offset = (p.row as usize * self.cols as usize + p.col as usize) *
+				EnumMatterHeader::CELL_SIZE;
+			row_data[offset..offset + EnumMatterHeader::CELL_SIZE].copy_from_slice(&p.cell);
+		}
+		row_data.extend_from_slice(&self.appended);
+		let rows = self.base_rows + self.appended_rows;
+		let header = EnumMatterHeader { rows, ..base.header };
+		Ok(EnumMatter { header, aux_data: base.aux_data.clone(), row_data })
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{test_vectors::enum_blob, CellPatch, EnumDelta, EnumDeltaError, EnumMatter};
+
+	#[test]
+	fn diff_apply_roundtrip() {
+		let base = EnumMatter::from(&enum_blob(3, 2)).unwrap();
+		let mut next = EnumMatter::from(&enum_blob(5, 2)).unwrap();
+		// Cell (1, 0) changes; rows 3 and 4 are appended.
+		next.row_data[2 * 32 + 31] = 9;
+		let delta = EnumDelta::diff(&base, &next).unwrap();
+		assert_eq!(delta.replaced.len(), 1);
+		assert_eq!(delta.appended_rows, 2);
+
+		let delta = EnumDelta::from(&delta.to_blob().unwrap()).unwrap();
+		let applied = delta.apply(&base).unwrap();
+		assert_eq!(applied.to_blob(), next.to_blob());
+		assert_eq!(
+			delta.apply(&next).unwrap_err(),
+			EnumDeltaError::BaseMismatch { cols: 2, rows: 3 }
+		);
+
+		let mut bad = delta.clone();
+		bad.replaced.push(bad.replaced[0]);
+		assert_eq!(
+			EnumDelta::from(&bad.to_blob().unwrap()).unwrap_err(),
+			EnumDeltaError::Unordered
+		);
+	}
+
+	#[test]
+	fn counts_rows_without_columns() {
+		let base = EnumMatter::from(&enum_blob(2, 0)).unwrap();
+		let next = EnumMatter::from(&enum_blob(5, 0)).unwrap();
+		let delta = EnumDelta::diff(&base, &next).unwrap();
+		assert_eq!(delta.appended_rows, 3);
+		assert!(delta.appended.is_empty());
+
+		let delta = EnumDelta::from(&delta.to_blob().unwrap()).unwrap();
+		assert_eq!(delta.appended_rows, 3);
+		assert_eq!(delta.apply(&base).unwrap().rows(), 5);
+	}
+
+	#[test]
+	fn rejects_uncountable_patches() {
+		let patch = |i: usize| CellPatch { row: (i / 2) as u16, col: (i % 2) as u8, cell: [0; 32] };
+		let delta = EnumDelta {
+			cols: 2,
+			base_rows: 40_000,
+			appended_rows: 0,
+			replaced: (0..=u16::MAX as usize).map(patch).collect(),
+			appended: Vec::new(),
+		};
+		assert_eq!(delta.validate(), Err(EnumDeltaError::TooManyPatches));
+		assert_eq!(delta.to_blob(), Err(EnumDeltaError::TooManyPatches));
+	}
+}
@@ -1,4 +1,8 @@
-use crate::{Result, Vec};
+use crate::{
+	merkle::{self, MerkleHasher},
+	Bytes32, Result, Vec, H256,
+};
+use sp_std::collections::btree_map::BTreeMap;
 use thiserror::Error;
 
 #[derive(Clone, Copy, Debug, Error, PartialEq, Eq)]
@@ -103,7 +107,7 @@ impl EnumMatterHeader {
 		let aux_types: [u8; 8] = blob[8..16].try_into().unwrap();
 		{
 			let (active, pad) = aux_types.split_at(aux as usize);
-			if active.iter().any(|&t| t == 0) || pad.iter().any(|&t| t != 0) {
+			if active.contains(&0) || pad.iter().any(|&t| t != 0) {
 				return Err(EnumMatterError::BadAuxTypes);
 			}
 		}
@@ -112,13 +116,24 @@ impl EnumMatterHeader {
 		let col_types: [u8; 16] = blob[16..32].try_into().unwrap();
 		{
 			let (active, pad) = col_types.split_at(cols as usize);
-			if active.iter().any(|&t| t == 0) || pad.iter().any(|&t| t != 0) {
+			if active.contains(&0) || pad.iter().any(|&t| t != 0) {
 				return Err(EnumMatterError::BadColTypes);
 			}
 		}
 
 		Ok(Self { magic, ver_aux, cols, rows, aux_types, col_types })
 	}
+
+	pub fn encode(&self) -> [u8; Self::HEADER_SIZE] {
+		let mut out = [0u8; Self::HEADER_SIZE];
+		out[0..4].copy_from_slice(&self.magic);
+		out[4] = self.ver_aux;
+		out[5] = self.cols;
+		out[6..8].copy_from_slice(&self.rows.to_le_bytes());
+		out[8..16].copy_from_slice(&self.aux_types);
+		out[16..32].copy_from_slice(&self.col_types);
+		out
+	}
 }
 
 #[derive(Debug, Clone)]
@@ -164,6 +179,29 @@ impl EnumMatter {
 		Ok(Self { header, aux_data, row_data })
 	}
 
+	/// Like `from`, but skips the magic, version and type checks and ignores trailing bytes, for
+	/// blobs already validated when they were stored. A blob shorter than its declared layout
+	/// still fails.
+	pub fn from_unchecked(blob: &[u8]) -> Result<Self, EnumMatterError> {
+		let head = blob.get(..EnumMatterHeader::HEADER_SIZE).ok_or(EnumMatterError::BadHeader)?;
+		let header = EnumMatterHeader {
+			magic: head[0..4].try_into().unwrap(),
+			ver_aux: head[4],
+			cols: head[5],
+			rows: u16::from_le_bytes(head[6..8].try_into().unwrap()),
+			aux_types: head[8..16].try_into().unwrap(),
+			col_types: head[16..32].try_into().unwrap(),
+		};
+		// At most 15 aux cells and 255 x 65535 rows, so none of this overflows.
+		let aux_end = EnumMatterHeader::HEADER_SIZE + header.aux() * EnumMatterHeader::CELL_SIZE;
+		let row_end = aux_end + header.cols() * header.rows() * EnumMatterHeader::CELL_SIZE;
+		let body = blob
+			.get(EnumMatterHeader::HEADER_SIZE..row_end)
+			.ok_or(EnumMatterError::BadBody { expect: row_end, got: blob.len() })?;
+		let (aux_data, row_data) = body.split_at(aux_end - EnumMatterHeader::HEADER_SIZE);
+		Ok(Self { header, aux_data: aux_data.to_vec(), row_data: row_data.to_vec() })
+	}
+
 	#[inline]
 	pub fn aux(&self) -> usize {
 		self.header.aux()
@@ -249,4 +287,202 @@ impl EnumMatter {
 		}
 		Ok(out)
 	}
+
+	pub fn to_blob(&self) -> Vec<u8> {
+		let mut blob = Vec::with_capacity(
+			EnumMatterHeader::HEADER_SIZE + self.aux_data.len() + self.row_data.len(),
+		);
+		blob.extend_from_slice(&self.header.encode());
+		blob.extend_from_slice(&self.aux_data);
+		blob.extend_from_slice(&self.row_data);
+		blob
+	}
+
+	/// New matter with only `cols`, in the given order; aux cells are kept as is.
+	pub fn project(&self, cols: &[usize]) -> Result<EnumMatter, EnumMatterError> {
+		if cols.len() > 16 {
+			return Err(EnumMatterError::BadColCount(cols.len().min(u8::MAX as usize) as u8));
+		}
+		let mut col_types = [0u8; 16];
+		for (i, &col) in cols.iter().enumerate() {
+			if col >= self.cols() {
+				return Err(EnumMatterError::OobCell { row: 0, col });
+			}
+			col_types[i] = self.header.col_types[col];
+		}
+		let mut row_data =
+			Vec::with_capacity(self.rows() * cols.len() * EnumMatterHeader::CELL_SIZE);
+		for row in 0..self.rows() {
+			for &col in cols {
+				row_data.extend_from_slice(self.cell_at(row, col)?);
+			}
+		}
+		let header = EnumMatterHeader { cols: cols.len() as u8, col_types, ..self.header };
+		Ok(EnumMatter { header, aux_data: self.aux_data.clone(), row_data })
+	}
+
+	/// New matter with only the rows for which `keep(row, cells)` returns true.
+	pub fn filter_rows<F>(&self, mut keep: F) -> Result<EnumMatter, EnumMatterError>
+	where
+		F: FnMut(usize, &[&[u8; 32]]) -> bool,
+	{
+		let mut rows = 0u16;
+		let mut row_data = Vec::new();
+		for row in 0..self.rows() {
+			let cells = self.row_at(row)?;
+			if keep(row, &cells) {
+				rows += 1;
+				for cell in cells {
+					row_data.extend_from_slice(cell);
+				}
+			}
+		}
+		let header = EnumMatterHeader { rows, ..self.header };
+		Ok(EnumMatter { header, aux_data: self.aux_data.clone(), row_data })
+	}
+
+	pub fn find_rows(&self, col: usize, cell: &[u8; 32]) -> Result<Vec<usize>, EnumMatterError> {
+		if col >= self.cols() {
+			return Err(EnumMatterError::OobCell { row: 0, col });
+		}
+		let mut rows = Vec::new();
+		for row in 0..self.rows() {
+			if self.cell_at(row, col)? == cell {
+				rows.push(row);
+			}
+		}
+		Ok(rows)
+	}
+
+	pub fn column_index(&self, col: usize) -> Result<ColumnIndex, EnumMatterError> {
+		ColumnIndex::build(self, col)
+	}
+
+	pub fn merkle_root<H: MerkleHasher>(&self) -> Result<H256, EnumMatterError> {
+		let levels = merkle::merkle_levels::<H>(self.row_leaves::<H>()?);
+		Ok(levels[levels.len() - 1][0])
+	}
+
+	pub fn prove_row<H: MerkleHasher>(&self, row: usize) -> Result<Vec<H256>, EnumMatterError> {
+		if row >= self.rows() {
+			return Err(EnumMatterError::OobCell { row, col: 0 });
+		}
+		let levels = merkle::merkle_levels::<H>(self.row_leaves::<H>()?);
+		Ok(merkle::merkle_proof(&levels, row))
+	}
+
+	pub fn verify_row_proof<H: MerkleHasher>(
+		root: &H256,
+		row: usize,
+		cells: &[&[u8; 32]],
+		proof: &[H256],
+	) -> bool {
+		merkle::merkle_verify::<H>(root, merkle::leaf_hash::<H>(cells), row, proof)
+	}
+
+	fn row_leaves<H: MerkleHasher>(&self) -> Result<Vec<H256>, EnumMatterError> {
+		(0..self.rows())
+			.map(|row| Ok(merkle::leaf_hash::<H>(&self.row_at(row)?)))
+			.collect()
+	}
+}
+
+/// Rows of one column grouped by cell value, for repeated lookups on the same matter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnIndex {
+	pub col: usize,
+	pub rows: BTreeMap<Bytes32, Vec<usize>>, // row lists ascending
+}
+
+impl ColumnIndex {
+	pub fn build(matter: &EnumMatter, col: usize) -> Result<Self, EnumMatterError> {
+		if col >= matter.cols() {
+			return Err(EnumMatterError::OobCell { row: 0, col });
+		}
+		let mut rows: BTreeMap<Bytes32, Vec<usize>> = BTreeMap::new();
+		for row in 0..matter.rows() {
+			rows.entry(*matter.cell_at(row, col)?).or_default().push(row);
+		}
+		Ok(Self { col, rows })
+	}
+
+	pub fn find(&self, cell: &Bytes32) -> &[usize] {
+		self.rows.get(cell).map_or(&[], |rows| rows.as_slice())
+	}
+
+	pub fn values(&self) -> impl Iterator<Item = &Bytes32> {
+		self.rows.keys()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{
+		test_vectors::{enum_blob, enum_cell, SumHasher},
+		EnumMatter, EnumMatterError,
+	};
+
+	#[test]
+	fn row_proofs_verify() {
+		for rows in [1u16, 2, 5, 8] {
+			let m = EnumMatter::from(&enum_blob(rows, 2)).unwrap();
+			let root = m.merkle_root::<SumHasher>().unwrap();
+			for row in 0..rows as usize {
+				let proof = m.prove_row::<SumHasher>(row).unwrap();
+				let cells = m.row_at(row).unwrap();
+				assert!(EnumMatter::verify_row_proof::<SumHasher>(&root, row, &cells, &proof));
+				let other = (row + 1) % rows as usize;
+				if other != row {
+					assert!(!EnumMatter::verify_row_proof::<SumHasher>(
+						&root, other, &cells, &proof
+					));
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn project_and_filter_produce_valid_blobs() {
+		let m = EnumMatter::from(&enum_blob(4, 3)).unwrap();
+		let p = EnumMatter::from(&m.project(&[2, 0]).unwrap().to_blob()).unwrap();
+		assert_eq!(p.cols(), 2);
+		assert_eq!(p.rows(), 4);
+		assert_eq!(p.cell_at(3, 0).unwrap(), &enum_cell(3, 2));
+		assert_eq!(p.cell_at(3, 1).unwrap(), &enum_cell(3, 0));
+
+		let f = m.filter_rows(|row, _| row % 2 == 1).unwrap();
+		let f = EnumMatter::from(&f.to_blob()).unwrap();
+		assert_eq!(f.rows(), 2);
+		assert_eq!(f.cell_at(1, 1).unwrap(), &enum_cell(3, 1));
+	}
+
+	#[test]
+	fn column_index_matches_find_rows() {
+		// Column 1 of rows 1, 3 and 4 repeats row 0's cell.
+		let mut blob = enum_blob(6, 2);
+		for row in [1, 3, 4] {
+			let at = 32 + (row * 2 + 1) * 32;
+			blob[at..at + 32].copy_from_slice(&enum_cell(0, 1));
+		}
+		let m = EnumMatter::from(&blob).unwrap();
+		assert_eq!(m.find_rows(1, &enum_cell(0, 1)).unwrap(), vec![0, 1, 3, 4]);
+		assert_eq!(m.find_rows(1, &enum_cell(5, 1)).unwrap(), vec![5]);
+		assert_eq!(m.find_rows(0, &enum_cell(2, 0)).unwrap(), vec![2]);
+		assert_eq!(m.find_rows(0, &enum_cell(0, 1)).unwrap(), Vec::<usize>::new());
+		assert_eq!(
+			m.find_rows(2, &enum_cell(0, 0)),
+			Err(EnumMatterError::OobCell { row: 0, col: 2 })
+		);
+		assert_eq!(m.column_index(2), Err(EnumMatterError::OobCell { row: 0, col: 2 }));
+
+		for col in 0..m.cols() {
+			let index = m.column_index(col).unwrap();
+			assert_eq!(index.col, col);
+			for cell in index.values() {
+				assert_eq!(index.find(cell), m.find_rows(col, cell).unwrap());
+			}
+			assert_eq!(index.find(&[0xff; 32]), &[] as &[usize]);
+		}
+		assert_eq!(m.column_index(1).unwrap().values().count(), 3);
+	}
 }
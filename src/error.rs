@@ -0,0 +1,187 @@
+use crate::{ElementError, EnumMatterError, ErrorCode, PermMatterError};
+use core::fmt;
+use sp_std::boxed::Box;
+
+#[cfg(feature = "storage")]
+use crate::reader::{ProviderError, StateError};
+
+/// Any error of this crate, with the chain of errors that caused it.
+///
+/// Each link keeps its `ErrorCode`; with `std` it also keeps the original error, boxed, for its
+/// message and `source()`. Wrap a lower-level failure with `caused_by` instead of replacing it,
+/// so `is_not_found` and `is_corrupt` can look at the root cause.
+#[derive(Debug)]
+pub struct Error {
+	code: ErrorCode,
+	#[cfg(feature = "std")]
+	error: Option<Box<dyn std::error::Error + Send + Sync>>,
+	source: Option<Box<Error>>,
+}
+
+impl Error {
+	pub fn new(code: ErrorCode) -> Self {
+		Self {
+			code,
+			#[cfg(feature = "std")]
+			error: None,
+			source: None,
+		}
+	}
+
+	pub fn code(&self) -> ErrorCode {
+		self.code
+	}
+
+	/// Records `source` as the cause of this error, below any cause already recorded.
+	pub fn caused_by(mut self, source: impl Into<Error>) -> Self {
+		let source = source.into();
+		self.source = Some(Box::new(match self.source.take() {
+			Some(cause) => cause.caused_by(source),
+			None => source,
+		}));
+		self
+	}
+
+	/// The error directly below this one.
+	pub fn cause(&self) -> Option<&Error> {
+		self.source.as_deref()
+	}
+
+	/// The innermost error of the chain.
+	pub fn root_cause(&self) -> &Error {
+		let mut err = self;
+		while let Some(cause) = err.cause() {
+			err = cause;
+		}
+		err
+	}
+
+	/// Codes of the chain, outermost first.
+	pub fn codes(&self) -> impl Iterator<Item = ErrorCode> + '_ {
+		core::iter::successors(Some(self), |err| err.cause()).map(|err| err.code)
+	}
+
+	/// Whether the root cause is data missing from the state.
+	pub fn is_not_found(&self) -> bool {
+		self.root_cause().code.is_not_found()
+	}
+
+	/// Whether the root cause is stored data that does not decode or is malformed.
+	pub fn is_corrupt(&self) -> bool {
+		self.root_cause().code.is_corrupt()
+	}
+}
+
+impl fmt::Display for Error {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		#[cfg(feature = "std")]
+		if let Some(error) = &self.error {
+			return error.fmt(f);
+		}
+		match self.code.name() {
+			Some(name) => write!(f, "{name} ({:#06x})", self.code.0),
+			None => write!(f, "error {:#06x}", self.code.0),
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		self.source.as_deref().map(|err| err as _)
+	}
+}
+
+macro_rules! impl_from {
+	($($ty:ty),*) => {$(
+		impl From<$ty> for Error {
+			fn from(err: $ty) -> Self {
+				Self {
+					code: err.code(),
+					#[cfg(feature = "std")]
+					error: Some(Box::new(err)),
+					source: None,
+				}
+			}
+		}
+	)*};
+}
+
+impl_from!(EnumMatterError, PermMatterError);
+
+/// Keeps the code of a failed state read as the cause.
+impl From<ElementError> for Error {
+	fn from(err: ElementError) -> Self {
+		let cause = match err {
+			ElementError::StateReaderGetMatter(cause) |
+			ElementError::StateReaderGetSnapshot(cause) => cause,
+			_ => None,
+		};
+		let this = Self {
+			code: err.code(),
+			#[cfg(feature = "std")]
+			error: Some(Box::new(err)),
+			source: None,
+		};
+		match cause {
+			Some(cause) => this.caused_by(Error::new(cause)),
+			None => this,
+		}
+	}
+}
+
+#[cfg(feature = "storage")]
+impl_from!(ProviderError);
+
+/// Keeps the provider failure behind `StateError::ProviderError` as the cause.
+#[cfg(feature = "storage")]
+impl From<StateError> for Error {
+	fn from(err: StateError) -> Self {
+		let cause = match &err {
+			StateError::ProviderError(cause) => Some(*cause),
+			_ => None,
+		};
+		let this = Self {
+			code: err.code(),
+			#[cfg(feature = "std")]
+			error: Some(Box::new(err)),
+			source: None,
+		};
+		match cause {
+			Some(cause) => this.caused_by(cause),
+			None => this,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{ElementError, EnumMatterError, Error};
+
+	#[test]
+	fn keeps_the_cause() {
+		let err = Error::from(ElementError::EnumMatterFrom).caused_by(EnumMatterError::BadHeader);
+		assert!(err.is_corrupt());
+		assert!(!err.is_not_found());
+		assert_eq!(err.codes().count(), 2);
+		assert_eq!(err.root_cause().code(), EnumMatterError::BadHeader.code());
+		#[cfg(feature = "std")]
+		{
+			assert_eq!(err.to_string(), "failed to load enum matter");
+			let source = std::error::Error::source(&err).unwrap();
+			assert_eq!(source.to_string(), "invalid EnumMatter header");
+		}
+
+		#[cfg(feature = "storage")]
+		{
+			use crate::reader::{ProviderError, StateError};
+			let err = Error::from(ElementError::StateReaderGetMatter(None))
+				.caused_by(StateError::ProviderError(ProviderError::ItemNotFound));
+			assert!(err.is_not_found());
+			assert_eq!(err.codes().count(), 3);
+			let err = Error::from(ElementError::StateReaderGetSnapshot(None))
+				.caused_by(StateError::DecodeFailed);
+			assert!(err.is_corrupt());
+		}
+	}
+}
@@ -0,0 +1,228 @@
+use crate::{ElementError, EnumMatterError, PermMatterError};
+use thiserror::Error;
+
+#[cfg(feature = "storage")]
+use crate::reader::{ProviderError, StateError};
+#[cfg(feature = "scale")]
+use codec::{Decode, DecodeWithMemTracking, Encode, MaxEncodedLen};
+#[cfg(feature = "scale")]
+use scale_info::TypeInfo;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// A stable number for an error variant: the high byte is its `ErrorDomain`, the low byte the
+/// variant. Codes are never renumbered or reused; a new variant takes the next free index.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
+#[cfg_attr(feature = "scale", derive(Encode, Decode, TypeInfo, DecodeWithMemTracking))]
+#[cfg_attr(feature = "scale", derive(MaxEncodedLen))]
+pub struct ErrorCode(pub u16);
+
+#[repr(u8)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ErrorDomain {
+	Element = 1,
+	EnumMatter = 2,
+	PermMatter = 3,
+	State = 4,
+	Provider = 5,
+}
+
+#[derive(Clone, Copy, Debug, Error, PartialEq, Eq)]
+#[error("unknown error code {0:#06x}")]
+pub struct UnknownErrorCode(pub u16);
+
+impl ErrorCode {
+	pub const fn new(domain: ErrorDomain, index: u8) -> Self {
+		Self((domain as u16) << 8 | index as u16)
+	}
+
+	pub fn domain(&self) -> Option<ErrorDomain> {
+		match self.0 >> 8 {
+			1 => Some(ErrorDomain::Element),
+			2 => Some(ErrorDomain::EnumMatter),
+			3 => Some(ErrorDomain::PermMatter),
+			4 => Some(ErrorDomain::State),
+			5 => Some(ErrorDomain::Provider),
+			_ => None,
+		}
+	}
+
+	/// Name of the variant the code stands for.
+	pub fn name(&self) -> Option<&'static str> {
+		let table = match self.domain()? {
+			ErrorDomain::Element => ELEMENT_CODES,
+			ErrorDomain::EnumMatter => ENUM_MATTER_CODES,
+			ErrorDomain::PermMatter => PERM_MATTER_CODES,
+			ErrorDomain::State => STATE_CODES,
+			ErrorDomain::Provider => PROVIDER_CODES,
+		};
+		let index = self.0 as u8;
+		table.iter().find(|(i, _)| *i == index).map(|(_, name)| *name)
+	}
+
+	/// Data missing from the state.
+	pub fn is_not_found(&self) -> bool {
+		self.name().is_some_and(|name| name.ends_with("NotFound"))
+	}
+
+	/// Stored data that does not decode, or a matter blob with a malformed header or body.
+	pub fn is_corrupt(&self) -> bool {
+		match self.domain() {
+			Some(ErrorDomain::EnumMatter | ErrorDomain::PermMatter) =>
+				self.name().is_some_and(|name| name.starts_with("Bad")),
+			Some(ErrorDomain::State | ErrorDomain::Provider) => matches!(
+				self.name(),
+				Some("DataInvalid" | "DecodeFailed" | "UnexpectdVariant" | "FacetAssetInvalid")
+			),
+			_ => false,
+		}
+	}
+}
+
+impl TryFrom<u16> for ErrorCode {
+	type Error = UnknownErrorCode;
+
+	fn try_from(code: u16) -> Result<Self, UnknownErrorCode> {
+		let code = ErrorCode(code);
+		code.name().map(|_| code).ok_or(UnknownErrorCode(code.0))
+	}
+}
+
+impl From<ErrorCode> for u16 {
+	fn from(code: ErrorCode) -> Self {
+		code.0
+	}
+}
+
+// Declares the codes of one error enum. The names are known in every build, so codes decode the
+// same way whichever features are on; `code()` only exists where the enum does.
+macro_rules! error_codes {
+	($(#[$meta:meta])* $ty:ident, $table:ident, $domain:expr, { $($index:literal => $variant:ident,)* }) => {
+		const $table: &[(u8, &str)] = &[$(($index, stringify!($variant)),)*];
+
+		$(#[$meta])*
+		impl $ty {
+			pub fn code(&self) -> ErrorCode {
+				match self {
+					$($ty::$variant { .. } => ErrorCode::new($domain, $index),)*
+				}
+			}
+		}
+	};
+}
+
+error_codes!(ElementError, ELEMENT_CODES, ErrorDomain::Element, {
+	1 => InvalidElementSource,
+	2 => InvalidPickerPadding,
+	3 => NotCollection,
+	4 => EnumMatterFrom,
+	5 => EnumMatterRowAt,
+	6 => PermMatterFrom,
+	7 => PermMatterRowAt,
+	8 => NoHereCollection,
+	9 => NoCustomPicker,
+	10 => NoPreviousRevision,
+	11 => StateReaderGetMatter,
+	12 => StateReaderGetSnapshot,
+	13 => CacheGet,
+	14 => RowOutOfBounds,
+	15 => ColOutOfBounds,
+	16 => InvalidMutBits,
+	17 => ResultLengthMismatch,
+	18 => InvalidElementLength,
+	19 => TooManyElements,
+	20 => NotFormula,
+	21 => Formula,
+	22 => SparseMatterFrom,
+	23 => SparseMatterRowAt,
+	24 => RangeMatterFrom,
+	25 => RangeMatterRowAt,
+	26 => Schema,
+	27 => MetaSpec,
+	28 => CustomMatterInvalid,
+	29 => ReservedFlagBits,
+	30 => UnsupportedFlagsVersion,
+	31 => SampleTooLarge,
+});
+
+error_codes!(EnumMatterError, ENUM_MATTER_CODES, ErrorDomain::EnumMatter, {
+	1 => BadHeader,
+	2 => BadMagic,
+	3 => BadVersion,
+	4 => BadAuxCount,
+	5 => BadColCount,
+	6 => BadAuxTypes,
+	7 => BadColTypes,
+	8 => BadBody,
+	9 => OobCell,
+	10 => OobAux,
+	11 => Overflow,
+});
+
+error_codes!(PermMatterError, PERM_MATTER_CODES, ErrorDomain::PermMatter, {
+	1 => BadHeader,
+	2 => BadMagic,
+	3 => BadVersion,
+	4 => BadAuxCount,
+	5 => BadColCount,
+	6 => BadAuxTypes,
+	7 => BadColTypes,
+	8 => BadEnumBitmap,
+	9 => BadHeightsBlock,
+	10 => BadColumnHeight,
+	11 => BadBody,
+	12 => OobAux,
+	13 => OobCell,
+	14 => OobRow,
+	15 => OobCol,
+	16 => EnumColIndex,
+	17 => Overflow,
+});
+
+error_codes!(#[cfg(feature = "storage")] StateError, STATE_CODES, ErrorDomain::State, {
+	1 => ProviderError,
+	2 => DataInvalid,
+	3 => UnexpectdVariant,
+	4 => DecodeFailed,
+	5 => MatterNotFound,
+	6 => ValueNotFound,
+	7 => UniqueNotFound,
+	8 => ObjectNotFound,
+	9 => SnapshotNotFound,
+	10 => TailsNotFound,
+	11 => HeadsNotFound,
+	12 => FacetsNotFound,
+	13 => FacetSelectorNotFound,
+	14 => FacetAssetNotFound,
+	15 => FacetAssetInvalid,
+	16 => GenesisNotFound,
+	17 => FrontierNotFound,
+});
+
+error_codes!(#[cfg(feature = "storage")] ProviderError, PROVIDER_CODES, ErrorDomain::Provider, {
+	1 => ItemNotFound,
+	2 => DecodeFailed,
+	3 => UnexpectdVariant,
+});
+
+#[cfg(test)]
+mod tests {
+	use crate::{ElementError, EnumMatterError, ErrorCode, ErrorDomain, UnknownErrorCode};
+
+	#[test]
+	fn codes_round_trip() {
+		let code = ElementError::TooManyElements { got: 3, max: 2 }.code();
+		assert_eq!(code, ErrorCode(0x0113));
+		assert_eq!(ErrorCode::try_from(0x0113), Ok(code));
+		assert_eq!(code.domain(), Some(ErrorDomain::Element));
+		assert_eq!(code.name(), Some("TooManyElements"));
+		assert_eq!(EnumMatterError::BadMagic(*b"PERM").code(), ErrorCode(0x0202));
+		assert_eq!(ErrorCode::try_from(0x0200), Err(UnknownErrorCode(0x0200)));
+		assert_eq!(ErrorCode::try_from(0x0900), Err(UnknownErrorCode(0x0900)));
+		// Known without the storage feature too, so decoding does not depend on the build.
+		assert_eq!(ErrorCode(0x0411).name(), Some("FrontierNotFound"));
+		#[cfg(feature = "storage")]
+		assert_eq!(crate::reader::StateError::FrontierNotFound.code(), ErrorCode(0x0411));
+	}
+}
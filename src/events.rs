@@ -0,0 +1,129 @@
+use crate::{state::Sota, Arc, Bytes32, Owner, Rev, SnapshotStreamItem, Vec, OID};
+
+#[cfg(feature = "scale")]
+use codec::{Decode, DecodeWithMemTracking, Encode};
+#[cfg(feature = "scale")]
+use scale_info::TypeInfo;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Object lifecycle events, shared by the pallet and indexers.
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "scale", derive(Encode, Decode, TypeInfo, DecodeWithMemTracking))]
+pub enum ObjectEvent {
+	Created {
+		oid: OID,
+		rev: Rev,
+		owner: Owner,
+	},
+	Updated {
+		oid: OID,
+		from: Rev,
+		to: Rev,
+	},
+	Destroyed {
+		oid: OID,
+		rev: Rev,
+	},
+	/// `hash` is `None` when the facet was cleared.
+	FacetSet {
+		oid: OID,
+		rev: Rev,
+		sel: u32,
+		#[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes_option"))]
+		hash: Option<Bytes32>,
+	},
+	Attached {
+		oid: OID,
+		rev: Rev,
+		arc: Arc,
+	},
+	Detached {
+		oid: OID,
+		rev: Rev,
+		arc: Arc,
+	},
+}
+
+impl ObjectEvent {
+	pub fn oid(&self) -> &OID {
+		match self {
+			Self::Created { oid, .. } |
+			Self::Updated { oid, .. } |
+			Self::Destroyed { oid, .. } |
+			Self::FacetSet { oid, .. } |
+			Self::Attached { oid, .. } |
+			Self::Detached { oid, .. } => oid,
+		}
+	}
+
+	/// Events for one write: `old` is `None` for a fresh object. Destruction is reported
+	/// last, against the last live revision in `changes`.
+	pub fn from_changes(old: Option<&Sota>, new: &Sota, changes: &SnapshotStreamItem) -> Vec<Self> {
+		let oid = changes.oid;
+		let rev = changes.to_rev;
+		let mut events = Vec::new();
+		match old {
+			None => events.push(Self::Created { oid, rev, owner: new.owner }),
+			Some(old) if old.desc.rev != new.desc.rev && !new.is_destroyed() =>
+				events.push(Self::Updated { oid, from: old.desc.rev, to: new.desc.rev }),
+			Some(_) => {},
+		}
+		for f in &changes.facets_diff {
+			events.push(Self::FacetSet { oid, rev, sel: f.sel, hash: f.after });
+		}
+		for arc in &changes.tails_diff.added {
+			events.push(Self::Attached { oid, rev, arc: arc.clone() });
+		}
+		for arc in &changes.tails_diff.removed {
+			events.push(Self::Detached { oid, rev, arc: arc.clone() });
+		}
+		if new.is_destroyed() && !old.is_some_and(Sota::is_destroyed) {
+			events.push(Self::Destroyed { oid, rev });
+		}
+		events
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{
+		state::Sota, Arc, ArcsDiff, Descriptor, FacetDiff, ObjectEvent, Owner, Rev,
+		SnapshotStreamItem, OID,
+	};
+
+	#[test]
+	fn events_from_changes() {
+		let oid = OID::of_set(1, 17);
+		let sota =
+			|rev| Sota { desc: Descriptor { rev, ..Default::default() }, ..Default::default() };
+		let arc = Arc { kind: 7, data: 0, rel: 2, set: 20, id: 5 };
+		let changes = SnapshotStreamItem {
+			oid,
+			from_rev: Rev(1),
+			to_rev: Rev(2),
+			elems_diff: vec![],
+			facets_diff: vec![FacetDiff { sel: 9, before: None, after: Some([1; 32]) }],
+			tails_diff: ArcsDiff { added: vec![arc.clone()], removed: vec![] },
+		};
+		assert_eq!(
+			ObjectEvent::from_changes(Some(&sota(Rev(1))), &sota(Rev(2)), &changes),
+			vec![
+				ObjectEvent::Updated { oid, from: Rev(1), to: Rev(2) },
+				ObjectEvent::FacetSet { oid, rev: Rev(2), sel: 9, hash: Some([1; 32]) },
+				ObjectEvent::Attached { oid, rev: Rev(2), arc },
+			]
+		);
+
+		let quiet =
+			SnapshotStreamItem { facets_diff: vec![], tails_diff: ArcsDiff::default(), ..changes };
+		assert_eq!(
+			ObjectEvent::from_changes(None, &sota(Rev(1)), &quiet),
+			vec![ObjectEvent::Created { oid, rev: Rev(2), owner: Owner::default() }]
+		);
+		let gone = ObjectEvent::from_changes(Some(&sota(Rev(2))), &sota(Rev::DESTROYED), &quiet);
+		assert_eq!(gone, vec![ObjectEvent::Destroyed { oid, rev: Rev(2) }]);
+		assert_eq!(gone[0].oid(), &oid);
+	}
+}
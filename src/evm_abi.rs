@@ -0,0 +1,171 @@
+#![cfg(feature = "evm")]
+
+//! Ethereum ABI encoding, the same as Solidity's `abi.encode` and `abi.decode` with the structs
+//! in [`sol`], so bridge contracts read object data in the layout defined here.
+
+use crate::{state::Snapshot, Bytes32, Descriptor, Facet, Rev, Vec, OID};
+use alloy_primitives::FixedBytes;
+use alloy_sol_types::SolValue;
+
+pub use alloy_sol_types::Error as EvmAbiError;
+
+/// Solidity mirrors of the crate types.
+pub mod sol {
+	alloy_sol_types::sol! {
+		struct OID {
+			uint64 universe;
+			uint64 set;
+			uint64 id;
+		}
+
+		struct Descriptor {
+			uint32 traits;
+			uint32 rev;
+			uint32 krev;
+			uint32 srev;
+			uint64 kind;
+			uint32 trev;
+			uint32 fsum;
+		}
+
+		struct Facet {
+			uint32 sel;
+			bytes32 hash;
+		}
+
+		struct Snapshot {
+			Descriptor desc;
+			uint128 mt;
+			bytes32[] elems;
+		}
+
+		/// Signed to hand `object` at `rev` from one owner to another.
+		struct Transfer {
+			OID object;
+			uint32 rev;
+			bytes32 from;
+			bytes32 to;
+		}
+	}
+}
+
+impl From<&OID> for sol::OID {
+	fn from(oid: &OID) -> Self {
+		sol::OID { universe: oid.universe, set: oid.set, id: oid.id }
+	}
+}
+
+impl From<sol::OID> for OID {
+	fn from(oid: sol::OID) -> Self {
+		OID { universe: oid.universe, set: oid.set, id: oid.id }
+	}
+}
+
+impl From<&Descriptor> for sol::Descriptor {
+	fn from(desc: &Descriptor) -> Self {
+		sol::Descriptor {
+			traits: desc.traits,
+			rev: desc.rev.get(),
+			krev: desc.krev.get(),
+			srev: desc.srev.get(),
+			kind: desc.kind,
+			trev: desc.trev.get(),
+			fsum: desc.fsum,
+		}
+	}
+}
+
+impl From<sol::Descriptor> for Descriptor {
+	fn from(d: sol::Descriptor) -> Self {
+		Descriptor {
+			traits: d.traits,
+			rev: Rev(d.rev),
+			krev: Rev(d.krev),
+			srev: Rev(d.srev),
+			kind: d.kind,
+			trev: Rev(d.trev),
+			fsum: d.fsum,
+		}
+	}
+}
+
+impl From<&Snapshot> for sol::Snapshot {
+	fn from(snapshot: &Snapshot) -> Self {
+		let elems = snapshot.elems.iter().map(|&e| FixedBytes(e)).collect();
+		sol::Snapshot { desc: (&snapshot.desc).into(), mt: snapshot.mt, elems }
+	}
+}
+
+pub trait EvmAbi: Sized {
+	fn abi_encode(&self) -> Vec<u8>;
+	fn abi_decode(data: &[u8]) -> Result<Self, EvmAbiError>;
+}
+
+impl EvmAbi for OID {
+	fn abi_encode(&self) -> Vec<u8> {
+		sol::OID::from(self).abi_encode()
+	}
+
+	fn abi_decode(data: &[u8]) -> Result<Self, EvmAbiError> {
+		Ok(sol::OID::abi_decode(data)?.into())
+	}
+}
+
+impl EvmAbi for Descriptor {
+	fn abi_encode(&self) -> Vec<u8> {
+		sol::Descriptor::from(self).abi_encode()
+	}
+
+	fn abi_decode(data: &[u8]) -> Result<Self, EvmAbiError> {
+		Ok(sol::Descriptor::abi_decode(data)?.into())
+	}
+}
+
+impl EvmAbi for Facet {
+	fn abi_encode(&self) -> Vec<u8> {
+		sol::Facet { sel: self.sel, hash: self.hash.into() }.abi_encode()
+	}
+
+	fn abi_decode(data: &[u8]) -> Result<Self, EvmAbiError> {
+		let sol::Facet { sel, hash } = sol::Facet::abi_decode(data)?;
+		Ok(Facet { sel, hash: hash.0 })
+	}
+}
+
+/// Snapshot elements as `bytes32[]`.
+pub fn abi_encode_elems(elems: &[Bytes32]) -> Vec<u8> {
+	elems.iter().map(|&e| FixedBytes(e)).collect::<Vec<_>>().abi_encode()
+}
+
+pub fn abi_decode_elems(data: &[u8]) -> Result<Vec<Bytes32>, EvmAbiError> {
+	Ok(Vec::<FixedBytes<32>>::abi_decode(data)?.into_iter().map(|e| e.0).collect())
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{
+		evm_abi::{abi_decode_elems, abi_encode_elems, EvmAbi},
+		Descriptor, Facet, Rev, OID,
+	};
+
+	#[test]
+	fn matches_solidity_layout() {
+		let oid = OID { universe: 1, set: 2, id: 0x0102 };
+		let data = oid.abi_encode();
+		assert_eq!(data.len(), 96);
+		assert_eq!((data[31], data[63], &data[94..]), (1, 2, &[1u8, 2][..]));
+		assert_eq!(OID::abi_decode(&data).unwrap(), oid);
+
+		let desc = Descriptor { rev: Rev(3), kind: 17, fsum: 9, ..Default::default() };
+		assert_eq!(Descriptor::abi_decode(&desc.abi_encode()).unwrap(), desc);
+		let facet = Facet { sel: 0xdeadbeef, hash: [7; 32] };
+		assert_eq!(&facet.abi_encode()[32..], &[7; 32]);
+		assert_eq!(Facet::abi_decode(&facet.abi_encode()).unwrap(), facet);
+
+		// Offset to the array, its length, then the elements.
+		let data = abi_encode_elems(&[[1; 32], [2; 32]]);
+		assert_eq!((data.len(), data[31], data[63]), (128, 0x20, 2));
+		assert_eq!(abi_decode_elems(&data).unwrap(), vec![[1; 32], [2; 32]]);
+		assert!(OID::abi_decode(&data[..64]).is_err());
+	}
+}
@@ -0,0 +1,175 @@
+use crate::{slice_from_fixed, to_fixed};
+use core::{fmt, str::FromStr};
+use thiserror::Error;
+
+#[cfg(feature = "scale")]
+use codec::{Decode, DecodeWithMemTracking, Encode, MaxEncodedLen};
+#[cfg(feature = "scale")]
+use scale_info::TypeInfo;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+#[derive(Clone, Copy, Debug, Error, PartialEq, Eq)]
+pub enum FixedStrError {
+	#[error("text is longer than {max} bytes")]
+	TooLong { max: usize },
+	#[error("text contains NUL")]
+	Nul,
+	#[error("text is not UTF-8")]
+	NotUtf8,
+	#[error("text is not printable ASCII")]
+	NotPrintable,
+}
+
+/// Text of up to `N` bytes stored zero-padded in a fixed-size field.
+///
+/// The constructors only accept UTF-8 without NUL. The raw array conversions are kept for
+/// compatibility and accept anything; `as_str` then stops at the first invalid byte.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "scale", derive(Encode, Decode, TypeInfo, DecodeWithMemTracking))]
+#[cfg_attr(feature = "scale", derive(MaxEncodedLen))]
+pub struct FixedStr<const N: usize>([u8; N]);
+
+/// Symbol of a `Value` or `Unique`.
+pub type Symbol = FixedStr<30>;
+
+/// Media type of a `Matter`.
+pub type Mime = FixedStr<31>;
+
+impl<const N: usize> FixedStr<N> {
+	pub fn new(text: &str) -> Result<Self, FixedStrError> {
+		if text.len() > N {
+			return Err(FixedStrError::TooLong { max: N });
+		}
+		if text.contains('\0') {
+			return Err(FixedStrError::Nul);
+		}
+		Ok(Self(to_fixed(text.as_bytes())))
+	}
+
+	/// `new` for constants; panics, at compile time in a `const`, on text `new` would reject.
+	pub const fn from_static(text: &'static str) -> Self {
+		let bytes = text.as_bytes();
+		assert!(bytes.len() <= N, "text is too long");
+		let mut out = [0; N];
+		let mut i = 0;
+		while i < bytes.len() {
+			assert!(bytes[i] != 0, "text contains NUL");
+			out[i] = bytes[i];
+			i += 1;
+		}
+		Self(out)
+	}
+
+	/// `new`, also requiring every character to be printable ASCII (space to `~`).
+	pub fn new_printable(text: &str) -> Result<Self, FixedStrError> {
+		if !text.bytes().all(|b| (b' '..=b'~').contains(&b)) {
+			return Err(FixedStrError::NotPrintable);
+		}
+		Self::new(text)
+	}
+
+	pub fn from_utf8(bytes: &[u8]) -> Result<Self, FixedStrError> {
+		Self::new(core::str::from_utf8(bytes).map_err(|_| FixedStrError::NotUtf8)?)
+	}
+
+	/// The text before the first NUL.
+	pub fn as_str(&self) -> &str {
+		let bytes = slice_from_fixed(&self.0);
+		match core::str::from_utf8(bytes) {
+			Ok(text) => text,
+			// Only reachable through `From<[u8; N]>`.
+			Err(err) => core::str::from_utf8(&bytes[..err.valid_up_to()]).unwrap_or_default(),
+		}
+	}
+
+	pub fn as_bytes(&self) -> &[u8; N] {
+		&self.0
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.0[0] == 0
+	}
+}
+
+impl<const N: usize> Default for FixedStr<N> {
+	fn default() -> Self {
+		Self([0; N])
+	}
+}
+
+impl<const N: usize> From<[u8; N]> for FixedStr<N> {
+	fn from(bytes: [u8; N]) -> Self {
+		Self(bytes)
+	}
+}
+
+impl<const N: usize> From<FixedStr<N>> for [u8; N] {
+	fn from(text: FixedStr<N>) -> Self {
+		text.0
+	}
+}
+
+impl<const N: usize> AsRef<[u8]> for FixedStr<N> {
+	fn as_ref(&self) -> &[u8] {
+		&self.0
+	}
+}
+
+impl<const N: usize> FromStr for FixedStr<N> {
+	type Err = FixedStrError;
+
+	fn from_str(text: &str) -> Result<Self, FixedStrError> {
+		Self::new(text)
+	}
+}
+
+impl<const N: usize> fmt::Display for FixedStr<N> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(self.as_str())
+	}
+}
+
+impl<const N: usize> fmt::Debug for FixedStr<N> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		fmt::Debug::fmt(self.as_str(), f)
+	}
+}
+
+/// A string in human-readable formats, the raw bytes otherwise.
+#[cfg(feature = "serde")]
+impl<const N: usize> Serialize for FixedStr<N> {
+	fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+		crate::serde_hex::text::serialize(&self.0, s)
+	}
+}
+
+#[cfg(feature = "serde")]
+impl<'de, const N: usize> Deserialize<'de> for FixedStr<N> {
+	fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+		crate::serde_hex::text::deserialize(d).map(Self)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{FixedStrError, Mime, Symbol};
+
+	#[test]
+	fn checks_text() {
+		let mime = Mime::new("application/vnd.every.enum").unwrap();
+		assert_eq!(mime, Mime::ENUM);
+		assert_eq!(mime.as_str(), "application/vnd.every.enum");
+		assert_eq!(mime.to_string(), "application/vnd.every.enum");
+		assert_eq!(Symbol::new(&"x".repeat(31)), Err(FixedStrError::TooLong { max: 30 }));
+		assert_eq!(Symbol::new("a\0b"), Err(FixedStrError::Nul));
+		assert_eq!(Symbol::from_utf8(&[0xff]), Err(FixedStrError::NotUtf8));
+		assert_eq!(Symbol::new_printable("€"), Err(FixedStrError::NotPrintable));
+		assert_eq!(Symbol::new("€").unwrap().as_str(), "€");
+
+		let mut raw = [0u8; 30];
+		raw[..4].copy_from_slice(b"ab\xffc");
+		assert_eq!(Symbol::from(raw).as_str(), "ab");
+		assert_eq!(<[u8; 30]>::from(Symbol::from(raw)), raw);
+	}
+}
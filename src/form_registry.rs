@@ -0,0 +1,161 @@
+use crate::{Matter, MatterForm, RowSource, Vec};
+use core::ops::RangeInclusive;
+use sp_std::boxed::Box;
+use thiserror::Error;
+
+#[derive(Clone, Copy, Debug, Error, PartialEq, Eq)]
+pub enum FormRegistryError {
+	#[error("form {0:#04x} is a built-in MatterForm")]
+	KnownForm(u8),
+	#[error("range {first:#04x}..={last:#04x} overlaps a registered form")]
+	Overlap { first: u8, last: u8 },
+	#[error("empty form range")]
+	EmptyRange,
+}
+
+/// Checks a blob of a custom form.
+pub type ValidateFn = fn(&[u8]) -> bool;
+/// Opens a blob of a custom collection form for row access.
+pub type RowSourceFn = fn(&[u8]) -> Option<Box<dyn RowSource>>;
+
+#[derive(Clone)]
+pub struct CustomForm {
+	pub forms: RangeInclusive<u8>,
+	pub name: &'static str,
+	pub validate: ValidateFn,
+	pub row_source: Option<RowSourceFn>,
+}
+
+/// Form bytes claimed by downstream crates. Built-in forms cannot be registered, and ranges may
+/// not overlap, so every form byte resolves to at most one handler.
+#[derive(Clone, Default)]
+pub struct MatterFormRegistry {
+	forms: Vec<CustomForm>,
+}
+
+impl MatterFormRegistry {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	pub fn register(
+		&mut self,
+		forms: RangeInclusive<u8>,
+		name: &'static str,
+		validate: ValidateFn,
+		row_source: Option<RowSourceFn>,
+	) -> Result<&mut Self, FormRegistryError> {
+		if forms.is_empty() {
+			return Err(FormRegistryError::EmptyRange);
+		}
+		if let Some(known) = forms.clone().find(|&f| MatterForm::try_from(f).is_ok()) {
+			return Err(FormRegistryError::KnownForm(known));
+		}
+		let (first, last) = (*forms.start(), *forms.end());
+		if self.forms.iter().any(|c| first <= *c.forms.end() && *c.forms.start() <= last) {
+			return Err(FormRegistryError::Overlap { first, last });
+		}
+		self.forms.push(CustomForm { forms, name, validate, row_source });
+		Ok(self)
+	}
+
+	pub fn lookup(&self, form: u8) -> Option<&CustomForm> {
+		self.forms.iter().find(|c| c.forms.contains(&form))
+	}
+
+	/// Whether `matter` has a built-in form or a registered form whose validator accepts it.
+	pub fn accepts(&self, matter: &Matter) -> bool {
+		MatterForm::try_from(matter.form).is_ok() ||
+			self.lookup(matter.form).is_some_and(|c| (c.validate)(&matter.blob))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{
+		test_vectors::HERE_COLLECTION_FLAGS, to_mime, Bytes32, CollectionMatter, Descriptor,
+		ElementError, ElementPicker, FormRegistryError, Matter, MatterCache, MatterFormRegistry,
+		Prefetched, Rev, RowSource, Shared, Vec, OID,
+	};
+	use sp_std::boxed::Box;
+
+	// One column; row `r` holds the blob's first byte plus `r`, repeated.
+	struct Counter(u8);
+
+	impl RowSource for Counter {
+		fn rows(&self) -> u64 {
+			4
+		}
+
+		fn cols(&self) -> usize {
+			1
+		}
+
+		fn cell_at(&self, row: u64, col: usize) -> Result<Bytes32, ElementError> {
+			if row >= 4 || col >= 1 {
+				return Err(ElementError::RowOutOfBounds);
+			}
+			Ok([self.0 + row as u8; 32])
+		}
+	}
+
+	#[test]
+	fn dispatches_registered_forms() {
+		let mut registry = MatterFormRegistry::new();
+		registry
+			.register(
+				0x80..=0x8F,
+				"counter",
+				|b| b.len() == 1,
+				Some(|b| Some(Box::new(Counter(b[0])))),
+			)
+			.unwrap();
+		assert_eq!(
+			registry.register(0x8F..=0x90, "x", |_| true, None).err(),
+			Some(FormRegistryError::Overlap { first: 0x8F, last: 0x90 })
+		);
+		assert_eq!(
+			registry.register(0xD0..=0xD0, "x", |_| true, None).err(),
+			Some(FormRegistryError::KnownForm(0xD0))
+		);
+
+		let matter = |blob: Vec<u8>| Matter { form: 0x81, mime: to_mime(b"x"), blob };
+		let coll = CollectionMatter::from_matter_in(&matter(vec![10]), &registry).unwrap();
+		assert_eq!(coll.row_at(2).unwrap(), vec![[12u8; 32]]);
+		assert!(matches!(
+			CollectionMatter::from_matter_in(&matter(vec![1, 2]), &registry),
+			Err(ElementError::CustomMatterInvalid)
+		));
+		assert!(matches!(
+			CollectionMatter::from_matter(&matter(vec![10])),
+			Err(ElementError::NotCollection)
+		));
+	}
+
+	#[test]
+	fn pickers_resolve_registered_forms() {
+		let mut registry = MatterFormRegistry::new();
+		registry
+			.register(
+				0x80..=0x80,
+				"counter",
+				|b| b.len() == 1,
+				Some(|b| Some(Box::new(Counter(b[0])))),
+			)
+			.unwrap();
+		let hash = [0xC0; 32];
+		let mut state = Prefetched::default();
+		state.insert_matter(hash, Matter { form: 0x80, mime: to_mime(b"x"), blob: vec![10] });
+		let oid = OID { universe: 1, set: 17, id: 3 };
+		let desc = Descriptor { rev: Rev(1), ..Default::default() };
+
+		let picker = ElementPicker::new(HERE_COLLECTION_FLAGS, vec![hash]).unwrap();
+		assert_eq!(picker.resolve(&state, &oid, &desc), Err(ElementError::NotCollection));
+
+		let picker = picker.with_forms(Shared::new(registry));
+		assert_eq!(picker.resolve(&state, &oid, &desc), Ok(vec![[12u8; 32]]));
+		let cache = MatterCache::<CollectionMatter>::new(4);
+		assert_eq!(picker.resolve_cached(&state, &oid, &desc, &cache), Ok(vec![[12u8; 32]]));
+		assert_eq!(picker.resolve_batch(&state, &[(oid, desc)]), vec![Ok(vec![[12u8; 32]])]);
+	}
+}
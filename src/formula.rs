@@ -0,0 +1,270 @@
+use crate::{Bytes32, MerkleHasher, Vec};
+use thiserror::Error;
+
+// FRML matter layout: magic "FRML", version byte (1), then a flat op stream. The interpreter works
+// on a stack of 32-byte values; `EMIT` appends the top of stack to the output elements.
+
+#[derive(Clone, Copy, Debug, Error, PartialEq, Eq)]
+pub enum FormulaError {
+	#[error("bad magic: expected 'FRML' (46 52 4D 4C), got {0:02X?}")]
+	BadMagic([u8; 4]),
+	#[error("unsupported version {0} (expected 1)")]
+	BadVersion(u8),
+	#[error("unknown opcode {op:#04x} at {pc}")]
+	BadOpcode { op: u8, pc: usize },
+	#[error("truncated operand at {pc}")]
+	Truncated { pc: usize },
+	#[error("input element {0} out of bounds")]
+	OobInput(u8),
+	#[error("stack underflow at {pc}")]
+	StackUnderflow { pc: usize },
+	#[error("stack overflow at {pc}")]
+	StackOverflow { pc: usize },
+	#[error("selector {index} out of range {count}")]
+	OobSelect { index: u64, count: u8 },
+	#[error("too many output elements")]
+	TooManyOutputs,
+	#[error("formula budget exhausted at {pc}")]
+	OutOfBudget { pc: usize },
+}
+
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FormulaOp {
+	/// `ELEM i`: push input element `i`.
+	Elem = 0x01,
+	/// `CONST c[32]`: push a literal.
+	Const = 0x02,
+	Dup = 0x03,
+	Swap = 0x04,
+	Drop = 0x05,
+	/// `CAT n`: pop b, a; push `a[..n] ++ b[..32 - n]`.
+	Cat = 0x10,
+	/// Pop a; push `H(a)`.
+	Hash = 0x11,
+	/// Pop b, a; push `H(a ++ b)`.
+	Hash2 = 0x12,
+	/// `RANGE n bound[n x u64 BE]`: pop a, read its last 8 bytes as u64 BE and push the index of
+	/// the first bound it is below (or `n`) as a u64 BE value.
+	Range = 0x20,
+	/// `SELECT n`: pop idx, then n values (pushed first to last); push value `idx`.
+	Select = 0x21,
+	/// Pop a and append it to the output.
+	Emit = 0xF0,
+}
+
+impl FormulaOp {
+	pub fn from_u8(v: u8) -> Option<Self> {
+		use FormulaOp::*;
+		Some(match v {
+			0x01 => Elem,
+			0x02 => Const,
+			0x03 => Dup,
+			0x04 => Swap,
+			0x05 => Drop,
+			0x10 => Cat,
+			0x11 => Hash,
+			0x12 => Hash2,
+			0x20 => Range,
+			0x21 => Select,
+			0xF0 => Emit,
+			_ => return None,
+		})
+	}
+
+	/// Budget units charged per execution.
+	pub fn cost(&self) -> u32 {
+		match self {
+			FormulaOp::Hash | FormulaOp::Hash2 => Formula::HASH_COST,
+			_ => 1,
+		}
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Formula {
+	pub code: Vec<u8>,
+}
+
+impl Formula {
+	pub const MAGIC: [u8; 4] = *b"FRML";
+	pub const HEADER_SIZE: usize = 5;
+	pub const STACK_MAX: usize = 32;
+	pub const OUTPUT_MAX: usize = 16;
+	pub const HASH_COST: u32 = 16;
+
+	pub fn from(blob: &[u8]) -> Result<Self, FormulaError> {
+		let magic: [u8; 4] =
+			blob.get(0..4).ok_or(FormulaError::Truncated { pc: 0 })?.try_into().unwrap();
+		if magic != Self::MAGIC {
+			return Err(FormulaError::BadMagic(magic));
+		}
+		let ver = *blob.get(4).ok_or(FormulaError::Truncated { pc: 4 })?;
+		if ver != 1 {
+			return Err(FormulaError::BadVersion(ver));
+		}
+		Ok(Self { code: blob[Self::HEADER_SIZE..].to_vec() })
+	}
+
+	/// Runs the formula over `inputs`, charging each op against `budget`.
+	pub fn eval<H: MerkleHasher>(
+		&self,
+		inputs: &[Bytes32],
+		budget: u32,
+	) -> Result<Vec<Bytes32>, FormulaError> {
+		let code = &self.code[..];
+		let mut stack: Vec<Bytes32> = Vec::new();
+		let mut out = Vec::new();
+		let mut left = budget;
+		let mut pc = 0;
+		while pc < code.len() {
+			let at = pc;
+			let op =
+				FormulaOp::from_u8(code[pc]).ok_or(FormulaError::BadOpcode { op: code[pc], pc })?;
+			left = left.checked_sub(op.cost()).ok_or(FormulaError::OutOfBudget { pc })?;
+			pc += 1;
+			let pop = |stack: &mut Vec<Bytes32>| {
+				stack.pop().ok_or(FormulaError::StackUnderflow { pc: at })
+			};
+			let operand =
+				|pc: usize, n: usize| code.get(pc..pc + n).ok_or(FormulaError::Truncated { pc });
+			match op {
+				FormulaOp::Elem => {
+					let i = operand(pc, 1)?[0];
+					pc += 1;
+					stack.push(*inputs.get(i as usize).ok_or(FormulaError::OobInput(i))?);
+				},
+				FormulaOp::Const => {
+					stack.push(operand(pc, 32)?.try_into().unwrap());
+					pc += 32;
+				},
+				FormulaOp::Dup => {
+					let a = *stack.last().ok_or(FormulaError::StackUnderflow { pc: at })?;
+					stack.push(a);
+				},
+				FormulaOp::Swap => {
+					let (b, a) = (pop(&mut stack)?, pop(&mut stack)?);
+					stack.push(b);
+					stack.push(a);
+				},
+				FormulaOp::Drop => {
+					pop(&mut stack)?;
+				},
+				FormulaOp::Cat => {
+					let n = (operand(pc, 1)?[0] as usize).min(32);
+					pc += 1;
+					let (b, a) = (pop(&mut stack)?, pop(&mut stack)?);
+					let mut v = [0u8; 32];
+					v[..n].copy_from_slice(&a[..n]);
+					v[n..].copy_from_slice(&b[..32 - n]);
+					stack.push(v);
+				},
+				FormulaOp::Hash => {
+					let a = pop(&mut stack)?;
+					stack.push(H::hash(&a));
+				},
+				FormulaOp::Hash2 => {
+					let (b, a) = (pop(&mut stack)?, pop(&mut stack)?);
+					let mut buf = [0u8; 64];
+					buf[..32].copy_from_slice(&a);
+					buf[32..].copy_from_slice(&b);
+					stack.push(H::hash(&buf));
+				},
+				FormulaOp::Range => {
+					let n = operand(pc, 1)?[0] as usize;
+					let bounds = operand(pc + 1, n * 8)?;
+					pc += 1 + n * 8;
+					let v = u64_of(&pop(&mut stack)?);
+					let index = bounds
+						.chunks_exact(8)
+						.position(|b| v < u64::from_be_bytes(b.try_into().unwrap()))
+						.unwrap_or(n);
+					stack.push(of_u64(index as u64));
+				},
+				FormulaOp::Select => {
+					let n = operand(pc, 1)?[0];
+					pc += 1;
+					let index = u64_of(&pop(&mut stack)?);
+					if stack.len() < n as usize {
+						return Err(FormulaError::StackUnderflow { pc: at });
+					}
+					let choices = stack.split_off(stack.len() - n as usize);
+					let v = *choices
+						.get(index as usize)
+						.filter(|_| index < n as u64)
+						.ok_or(FormulaError::OobSelect { index, count: n })?;
+					stack.push(v);
+				},
+				FormulaOp::Emit => {
+					if out.len() >= Self::OUTPUT_MAX {
+						return Err(FormulaError::TooManyOutputs);
+					}
+					out.push(pop(&mut stack)?);
+				},
+			}
+			if stack.len() > Self::STACK_MAX {
+				return Err(FormulaError::StackOverflow { pc: at });
+			}
+		}
+		Ok(out)
+	}
+}
+
+fn u64_of(v: &Bytes32) -> u64 {
+	u64::from_be_bytes(v[24..32].try_into().unwrap())
+}
+
+fn of_u64(n: u64) -> Bytes32 {
+	let mut v = [0u8; 32];
+	v[24..32].copy_from_slice(&n.to_be_bytes());
+	v
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_vectors::SumHasher;
+
+	fn formula(code: &[u8]) -> Formula {
+		let mut blob = Formula::MAGIC.to_vec();
+		blob.push(1);
+		blob.extend_from_slice(code);
+		Formula::from(&blob).unwrap()
+	}
+
+	#[test]
+	fn derives_tier_by_range() {
+		let mut level = [0u8; 32];
+		level[31] = 25;
+		let tiers: Vec<Bytes32> = (1..=3u8).map(|t| [t; 32]).collect();
+		let mut code = vec![0x01, 1, 0x01, 2, 0x01, 3, 0x01, 0, 0x20, 2];
+		code.extend_from_slice(&10u64.to_be_bytes());
+		code.extend_from_slice(&20u64.to_be_bytes());
+		code.extend_from_slice(&[0x21, 3, 0xF0, 0x01, 0, 0x11, 0xF0]);
+		let f = formula(&code);
+		let inputs = [level, tiers[0], tiers[1], tiers[2]];
+		let out = f.eval::<SumHasher>(&inputs, 64).unwrap();
+		assert_eq!(out, vec![tiers[2], SumHasher::hash(&level)]);
+		assert_eq!(
+			f.eval::<SumHasher>(&inputs, 20),
+			Err(FormulaError::OutOfBudget { pc: code.len() - 2 })
+		);
+	}
+
+	#[test]
+	fn rejects_malformed_code() {
+		assert_eq!(
+			formula(&[0x03]).eval::<SumHasher>(&[], 8),
+			Err(FormulaError::StackUnderflow { pc: 0 })
+		);
+		assert_eq!(
+			formula(&[0x02, 0]).eval::<SumHasher>(&[], 8),
+			Err(FormulaError::Truncated { pc: 1 })
+		);
+		assert_eq!(
+			formula(&[0x7F]).eval::<SumHasher>(&[], 8),
+			Err(FormulaError::BadOpcode { op: 0x7F, pc: 0 })
+		);
+		assert_eq!(Formula::from(b"FRML\x02"), Err(FormulaError::BadVersion(2)));
+	}
+}
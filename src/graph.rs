@@ -0,0 +1,170 @@
+use crate::{Arc, Rev, StateReader, Vec, OID};
+use sp_std::collections::{btree_set::BTreeSet, vec_deque::VecDeque};
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum GraphError<E> {
+	#[error("state access error")]
+	State(E),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WalkOrder {
+	BreadthFirst,
+	DepthFirst,
+}
+
+/// What the walk does after a visit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Visit {
+	Continue,
+	/// Do not descend into the tails of this node.
+	Skip,
+	Stop,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WalkStep {
+	pub head: OID,
+	pub arc: Arc,
+	pub tail: OID,
+	/// 1 for tails of the root.
+	pub depth: usize,
+}
+
+/// Breadth-first walk over the tails of `oid` at `rev`; see `walk_tails_ordered`.
+pub fn walk_tails<E, S, F, V>(
+	state: &S,
+	oid: &OID,
+	rev: Rev,
+	filter: F,
+	max_depth: usize,
+	visitor: V,
+) -> Result<usize, GraphError<E>>
+where
+	S: StateReader<E>,
+	F: Fn(&Arc) -> bool,
+	V: FnMut(&WalkStep) -> Visit,
+{
+	walk_tails_ordered(state, oid, rev, WalkOrder::BreadthFirst, filter, max_depth, visitor)
+}
+
+/// Calls `visitor` once for every object reachable from `oid` through arcs accepted by
+/// `filter`, up to `max_depth` levels deep, and returns the number of visits. Objects already
+/// visited (including the root) are not visited again, so cycles end the branch. Tails below the
+/// root are read at their latest revision; objects whose tails cannot be read are leaves.
+pub fn walk_tails_ordered<E, S, F, V>(
+	state: &S,
+	oid: &OID,
+	rev: Rev,
+	order: WalkOrder,
+	filter: F,
+	max_depth: usize,
+	mut visitor: V,
+) -> Result<usize, GraphError<E>>
+where
+	S: StateReader<E>,
+	F: Fn(&Arc) -> bool,
+	V: FnMut(&WalkStep) -> Visit,
+{
+	let key = |o: &OID| (o.universe, o.set, o.id);
+	let mut visited = BTreeSet::from([key(oid)]);
+	let mut pending: VecDeque<WalkStep> = VecDeque::new();
+	let root_tails = state.get_tails(oid, rev).map_err(GraphError::State)?;
+	enqueue(&mut pending, order, oid, root_tails, &filter, 1);
+
+	let mut visits = 0;
+	while let Some(step) = match order {
+		WalkOrder::BreadthFirst => pending.pop_front(),
+		WalkOrder::DepthFirst => pending.pop_back(),
+	} {
+		if !visited.insert(key(&step.tail)) {
+			continue;
+		}
+		visits += 1;
+		match visitor(&step) {
+			Visit::Stop => break,
+			Visit::Skip => continue,
+			Visit::Continue if step.depth >= max_depth => continue,
+			Visit::Continue => {},
+		}
+		if let Ok(tails) = state.get_tails(&step.tail, Rev::LATEST) {
+			enqueue(&mut pending, order, &step.tail, tails, &filter, step.depth + 1);
+		}
+	}
+	Ok(visits)
+}
+
+fn enqueue<F: Fn(&Arc) -> bool>(
+	pending: &mut VecDeque<WalkStep>,
+	order: WalkOrder,
+	head: &OID,
+	tails: Vec<Arc>,
+	filter: &F,
+	depth: usize,
+) {
+	let steps = tails.into_iter().filter(|a| filter(a)).map(|arc| WalkStep {
+		head: *head,
+		tail: OID { universe: head.universe, set: arc.set, id: arc.id },
+		arc,
+		depth,
+	});
+	match order {
+		WalkOrder::BreadthFirst => pending.extend(steps),
+		// Reversed so the first tail is popped first.
+		WalkOrder::DepthFirst => pending.extend(steps.collect::<Vec<_>>().into_iter().rev()),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{
+		test_state::{TestObject, TestState},
+		walk_tails, walk_tails_ordered, Arc, Descriptor, Rev, Visit, WalkOrder, OID,
+	};
+	use std::cell::RefCell;
+
+	// Objects of set 20 at revision 1, each with tails to the given ids; odd ids use relation 1.
+	fn tails(graph: &[(u64, &[u64])]) -> TestState {
+		let mut state = TestState::default();
+		for &(id, tail_ids) in graph {
+			let tails = tail_ids
+				.iter()
+				.map(|&id| Arc { kind: 1, data: 0, rel: id % 2, set: 20, id })
+				.collect();
+			let desc = Descriptor { rev: Rev(1), ..Default::default() };
+			state.insert(OID { universe: 1, set: 20, id }, TestObject::new(desc).tails(tails));
+		}
+		state
+	}
+
+	#[test]
+	fn walks_with_cycles_and_depth() {
+		// 1 -> 2, 3; 2 -> 4, 1; 3 -> 5; 4 -> 2
+		let state = tails(&[(1, &[2, 3]), (2, &[4, 1]), (3, &[5]), (4, &[2])]);
+		let root = OID { universe: 1, set: 20, id: 1 };
+		let walk = |state: &TestState, order, filter: fn(&Arc) -> bool, depth| {
+			let mut seen = Vec::new();
+			walk_tails_ordered(state, &root, Rev(1), order, filter, depth, |s| {
+				seen.push((s.tail.id, s.depth));
+				Visit::Continue
+			})
+			.unwrap();
+			seen
+		};
+
+		let bfs = walk(&state, WalkOrder::BreadthFirst, |_| true, 8);
+		assert_eq!(bfs, vec![(2, 1), (3, 1), (4, 2), (5, 2)]);
+		let dfs = walk(&state, WalkOrder::DepthFirst, |_| true, 8);
+		assert_eq!(dfs, vec![(2, 1), (4, 2), (3, 1), (5, 2)]);
+		assert_eq!(walk(&state, WalkOrder::BreadthFirst, |_| true, 1).len(), 2);
+		assert_eq!(walk(&state, WalkOrder::BreadthFirst, |a| a.rel == 0, 8), vec![(2, 1), (4, 2)]);
+
+		let n = walk_tails(&state, &root, Rev(1), |_| true, 8, |_| Visit::Stop).unwrap();
+		assert_eq!(n, 1);
+
+		// Through the `StateReaderMut` blanket impl and back via `RefCell`.
+		let shared = RefCell::new(state);
+		assert_eq!(walk_tails(&shared, &root, Rev(1), |_| true, 8, |_| Visit::Continue), Ok(4));
+	}
+}
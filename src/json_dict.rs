@@ -0,0 +1,190 @@
+use crate::{
+	to_mime, Bytes, Constants, Matter, MatterForm, MerkleHasher, Mime, StateReader, Vec, H256,
+};
+use sp_std::collections::btree_map::BTreeMap;
+use thiserror::Error;
+
+// JSONZ envelope: magic "JDZ1", dictionary matter hash, unpacked length (u32 LE), then ops:
+//   0x00 len(u16 LE) bytes[len]      literal
+//   0x01 offset(u16 LE) len(u16 LE)  copy from the dictionary
+// The dictionary is an ordinary JSON matter, so it is stored and deduplicated like any other.
+
+#[derive(Clone, Copy, Debug, Error, PartialEq, Eq)]
+pub enum JsonDictError {
+	#[error("invalid JSONZ header")]
+	BadHeader,
+	#[error("bad magic: expected 'JDZ1' (4A 44 5A 31), got {0:02X?}")]
+	BadMagic([u8; 4]),
+	#[error("truncated op at {0}")]
+	Truncated(usize),
+	#[error("unknown op {op:#04x} at {at}")]
+	BadOp { op: u8, at: usize },
+	#[error("dictionary copy out of bounds at {0}")]
+	OobCopy(usize),
+	#[error("unpacked length mismatch: expected {expect} bytes, got {got} bytes")]
+	LengthMismatch { expect: usize, got: usize },
+	#[error("dictionary hash mismatch")]
+	DictMismatch,
+	#[error("dictionary larger than 64 KiB")]
+	DictTooLarge,
+	#[error("state access error")]
+	StateReaderGetMatter,
+}
+
+pub struct JsonDict;
+
+impl JsonDict {
+	pub const MAGIC: [u8; 4] = *b"JDZ1";
+	pub const HEADER_SIZE: usize = 40;
+	pub const DICT_MAX: usize = u16::MAX as usize + 1;
+	/// Shorter matches are emitted as literals; a copy op costs 5 bytes.
+	pub const MIN_MATCH: usize = 8;
+
+	const OP_LITERAL: u8 = 0x00;
+	const OP_COPY: u8 = 0x01;
+
+	/// Dictionary matter hash referenced by a JSONZ envelope.
+	pub fn dict_hash(packed: &[u8]) -> Result<H256, JsonDictError> {
+		let header = packed.get(..Self::HEADER_SIZE).ok_or(JsonDictError::BadHeader)?;
+		let magic: [u8; 4] = header[0..4].try_into().unwrap();
+		if magic != Self::MAGIC {
+			return Err(JsonDictError::BadMagic(magic));
+		}
+		Ok(header[4..36].try_into().unwrap())
+	}
+
+	/// Compresses `json` against `dict`. Matching is greedy, longest first and lowest offset on
+	/// ties, so the same input always produces the same envelope.
+	pub fn pack<H: MerkleHasher>(json: &[u8], dict: &[u8]) -> Result<Bytes, JsonDictError> {
+		if dict.len() > Self::DICT_MAX {
+			return Err(JsonDictError::DictTooLarge);
+		}
+		let mut index: BTreeMap<&[u8], Vec<usize>> = BTreeMap::new();
+		for (offset, window) in dict.windows(Self::MIN_MATCH).enumerate() {
+			index.entry(window).or_default().push(offset);
+		}
+
+		let mut out = Vec::with_capacity(Self::HEADER_SIZE + json.len() / 2);
+		out.extend_from_slice(&Self::MAGIC);
+		out.extend_from_slice(&H::hash(dict));
+		out.extend_from_slice(&(json.len() as u32).to_le_bytes());
+
+		let mut literal_start = 0;
+		let mut pos = 0;
+		while pos < json.len() {
+			let best = json
+				.get(pos..pos + Self::MIN_MATCH)
+				.and_then(|key| index.get(key))
+				.and_then(|offsets| {
+					offsets
+						.iter()
+						.map(|&offset| {
+							let len = dict[offset..]
+								.iter()
+								.zip(&json[pos..])
+								.take(u16::MAX as usize)
+								.take_while(|(a, b)| a == b)
+								.count();
+							(len, offset)
+						})
+						.max_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)))
+				});
+			match best {
+				Some((len, offset)) => {
+					Self::push_literal(&mut out, &json[literal_start..pos]);
+					out.push(Self::OP_COPY);
+					out.extend_from_slice(&(offset as u16).to_le_bytes());
+					out.extend_from_slice(&(len as u16).to_le_bytes());
+					pos += len;
+					literal_start = pos;
+				},
+				None => pos += 1,
+			}
+		}
+		Self::push_literal(&mut out, &json[literal_start..]);
+		Ok(out)
+	}
+
+	fn push_literal(out: &mut Vec<u8>, bytes: &[u8]) {
+		for chunk in bytes.chunks(u16::MAX as usize) {
+			out.push(Self::OP_LITERAL);
+			out.extend_from_slice(&(chunk.len() as u16).to_le_bytes());
+			out.extend_from_slice(chunk);
+		}
+	}
+
+	/// Restores the original JSON. `dict` must hash to the envelope's dictionary hash.
+	pub fn unpack<H: MerkleHasher>(packed: &[u8], dict: &[u8]) -> Result<Bytes, JsonDictError> {
+		if Self::dict_hash(packed)? != H::hash(dict) {
+			return Err(JsonDictError::DictMismatch);
+		}
+		let expect = u32::from_le_bytes(packed[36..40].try_into().unwrap()) as usize;
+		if expect > Constants::MATTER_BLOB_MAX {
+			return Err(JsonDictError::BadHeader);
+		}
+		let mut out = Vec::with_capacity(expect);
+
+		let read_u16 = |at: usize| {
+			packed
+				.get(at..at + 2)
+				.map(|b| u16::from_le_bytes([b[0], b[1]]) as usize)
+				.ok_or(JsonDictError::Truncated(at))
+		};
+		let mut pc = Self::HEADER_SIZE;
+		while pc < packed.len() {
+			let at = pc;
+			let bytes = match packed[pc] {
+				Self::OP_LITERAL => {
+					let len = read_u16(pc + 1)?;
+					pc += 3 + len;
+					packed.get(at + 3..pc).ok_or(JsonDictError::Truncated(at))?
+				},
+				Self::OP_COPY => {
+					let (offset, len) = (read_u16(pc + 1)?, read_u16(pc + 3)?);
+					pc += 5;
+					dict.get(offset..offset + len).ok_or(JsonDictError::OobCopy(at))?
+				},
+				op => return Err(JsonDictError::BadOp { op, at }),
+			};
+			if out.len() + bytes.len() > expect {
+				return Err(JsonDictError::LengthMismatch { expect, got: out.len() + bytes.len() });
+			}
+			out.extend_from_slice(bytes);
+		}
+		if out.len() != expect {
+			return Err(JsonDictError::LengthMismatch { expect, got: out.len() });
+		}
+		Ok(out)
+	}
+
+	/// JSON content of a JSON-form matter, unpacking JSONZ envelopes with their dictionary
+	/// loaded from `state`. Other matters are returned as is.
+	pub fn resolve<E, S: StateReader<E>, H: MerkleHasher>(
+		state: &S,
+		matter: &Matter,
+	) -> Result<Bytes, JsonDictError> {
+		if matter.form != MatterForm::Json as u8 || matter.mime != Mime::JSONZ {
+			return Ok(matter.blob.clone());
+		}
+		let dict = state
+			.get_matter(&Self::dict_hash(&matter.blob)?)
+			.map_err(|_| JsonDictError::StateReaderGetMatter)?;
+		Self::unpack::<H>(&matter.blob, &dict.blob)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{test_vectors::SumHasher, JsonDict, JsonDictError};
+
+	#[test]
+	fn pack_roundtrip() {
+		let dict = br#"{"name":"","description":"","attributes":[{"trait_type":"Background","value":""}]}"#;
+		let json = br#"{"name":"Cat #7","description":"","attributes":[{"trait_type":"Background","value":"Blue"}]}"#;
+		let packed = JsonDict::pack::<SumHasher>(json, dict).unwrap();
+		assert!(packed.len() < json.len());
+		assert_eq!(packed, JsonDict::pack::<SumHasher>(json, dict).unwrap());
+		assert_eq!(JsonDict::unpack::<SumHasher>(&packed, dict).unwrap(), json.to_vec());
+		assert_eq!(JsonDict::unpack::<SumHasher>(&packed, b"{}"), Err(JsonDictError::DictMismatch));
+	}
+}
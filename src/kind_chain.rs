@@ -0,0 +1,166 @@
+use crate::{Bytes32, KindSpec, Matter, MatterForm, MetaSpecError, Rev, StateReader, Vec, OID};
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum KindChainError<E> {
+	#[error("state access error")]
+	State(E),
+	#[error("kind {kind}: {err}")]
+	Spec { kind: u64, err: MetaSpecError },
+	#[error("no wasm contract within {0} kinds")]
+	NoContract(usize),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct KindContract {
+	/// Kinds walked, nearest first, as (kind id, revision); the last one holds `matter`.
+	pub chain: Vec<(u64, Rev)>,
+	pub matter: Matter,
+}
+
+/// Finds the nearest Wasm contract for `oid`: the contract of its kind, else of that kind's own
+/// kind, and so on for at most `max_depth` kinds. Kinds with a zero or non-Wasm contract are
+/// passed through; the walk ends early at a kind that is its own kind.
+pub fn resolve_kind_contract<E, S: StateReader<E>>(
+	state: &S,
+	oid: &OID,
+	rev: Rev,
+	max_depth: usize,
+) -> Result<KindContract, KindChainError<E>> {
+	let desc = state.get_descriptor(oid, rev).map_err(KindChainError::State)?;
+	let (mut kind, mut krev) = (desc.kind, desc.krev);
+	let mut chain = Vec::new();
+	while chain.len() < max_depth {
+		chain.push((kind, krev));
+		let kind_oid = oid.kind_oid(kind);
+		let (kind_desc, elems) =
+			state.get_snapshot(&kind_oid, krev).map_err(KindChainError::State)?;
+		let spec =
+			KindSpec::from_elems(&elems).map_err(|err| KindChainError::Spec { kind, err })?;
+		if *spec.contract() != [0u8; 32] {
+			let matter = state.get_matter(spec.contract()).map_err(KindChainError::State)?;
+			if matter.form == MatterForm::Wasm as u8 {
+				return Ok(KindContract { chain, matter });
+			}
+		}
+		if kind_desc.kind == kind {
+			break;
+		}
+		(kind, krev) = (kind_desc.kind, kind_desc.krev);
+	}
+	Err(KindChainError::NoContract(chain.len()))
+}
+
+/// Elements of `oid` at `rev`, with slots the object does not store filled from its kind's
+/// default cells.
+pub fn materialize_elements<E, S: StateReader<E>>(
+	state: &S,
+	oid: &OID,
+	rev: Rev,
+) -> Result<Vec<Bytes32>, KindChainError<E>> {
+	let (desc, elems) = state.get_snapshot(oid, rev).map_err(KindChainError::State)?;
+	let (_, kind_elems) = state
+		.get_snapshot(&oid.kind_oid(desc.kind), desc.krev)
+		.map_err(KindChainError::State)?;
+	let spec_err = |err| KindChainError::Spec { kind: desc.kind, err };
+	KindSpec::from_elems(&kind_elems)
+		.and_then(|spec| spec.materialize(&elems))
+		.map_err(spec_err)
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{
+		materialize_elements, resolve_kind_contract,
+		test_state::{TestObject, TestState},
+		to_mime, Descriptor, KindChainError, KindSpec, Matter, MatterForm, MetaSpecError,
+		Prefetched, Rev, H256, OID,
+	};
+
+	// Kind `id` at revision `id`, of kind `parent`, with contract `code`. Objects of set 40
+	// are of the kind of the same id.
+	fn kinds(kinds: &[(u64, u64, H256)]) -> TestState {
+		let mut state = TestState::default();
+		for &(id, parent, code) in kinds {
+			let spec = KindSpec {
+				code,
+				data: [0; 32],
+				elem_types: vec![],
+				relations: vec![],
+				defaults: vec![],
+			};
+			let desc = Descriptor {
+				kind: parent,
+				krev: Rev(parent as u32),
+				rev: Rev(id as u32),
+				..Default::default()
+			};
+			let kind = TestObject::new(desc).elems(spec.to_elems().unwrap());
+			state.insert(OID { universe: 1, set: 40, id }.kind_oid(id), kind);
+			let desc =
+				Descriptor { kind: id, krev: Rev(id as u32), rev: Rev(1), ..Default::default() };
+			state.insert(OID { universe: 1, set: 40, id }, TestObject::new(desc));
+		}
+		for (hash, form) in [([1; 32], MatterForm::Wasm), ([2; 32], MatterForm::Json)] {
+			let matter = Matter { form: form as u8, mime: to_mime(b""), blob: hash.to_vec() };
+			state.insert_matter(hash, matter);
+		}
+		state
+	}
+
+	#[test]
+	fn walks_to_nearest_wasm() {
+		// 30 (no code) -> 20 (json) -> 10 (wasm) -> 2 (kind of kinds, its own kind)
+		let state =
+			kinds(&[(30, 20, [0; 32]), (20, 10, [2; 32]), (10, 2, [1; 32]), (2, 2, [0; 32])]);
+		let oid = |id| OID { universe: 1, set: 40, id };
+		let found = resolve_kind_contract(&state, &oid(30), Rev(1), 8).unwrap();
+		assert_eq!(found.chain, vec![(30, Rev(30)), (20, Rev(20)), (10, Rev(10))]);
+		assert_eq!(found.matter.blob, vec![1; 32]);
+		assert_eq!(
+			resolve_kind_contract(&state, &oid(30), Rev(1), 2),
+			Err(KindChainError::NoContract(2))
+		);
+		assert_eq!(
+			resolve_kind_contract(&state, &oid(2), Rev(1), 8),
+			Err(KindChainError::NoContract(1))
+		);
+	}
+
+	#[test]
+	fn materializes_through_kind() {
+		let oid = |id| OID { universe: 1, set: 40, id };
+		let kind = KindSpec {
+			code: [0; 32],
+			data: [0; 32],
+			elem_types: vec![1, 1, 1],
+			relations: vec![],
+			defaults: vec![[7; 32], [8; 32], [9; 32]],
+		};
+		let desc = Descriptor { kind: 5, krev: Rev(2), ..Default::default() };
+		let mut state = Prefetched::default();
+		state.insert_snapshot(
+			&oid(1).kind_oid(5),
+			Rev(2),
+			Descriptor::default(),
+			kind.to_elems().unwrap(),
+		);
+		state.insert_snapshot(&oid(1), Rev(1), desc.clone(), vec![[1; 32]]);
+		state.insert_snapshot(&oid(2), Rev(1), desc.clone(), vec![[1; 32]; 4]);
+		assert_eq!(
+			materialize_elements(&state, &oid(1), Rev(1)),
+			Ok(vec![[1; 32], [8; 32], [9; 32]])
+		);
+		assert_eq!(
+			materialize_elements(&state, &oid(2), Rev(1)),
+			Err(KindChainError::Spec {
+				kind: 5,
+				err: MetaSpecError::TooManyElements { max: 3, got: 4 }
+			})
+		);
+		assert!(matches!(
+			materialize_elements(&state, &oid(3), Rev(1)),
+			Err(KindChainError::State(_))
+		));
+	}
+}
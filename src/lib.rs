@@ -1,21 +1,117 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 #![allow(unused)]
 
+pub mod abi;
+pub mod amount;
+pub mod arc_data;
+pub mod archive;
+pub mod batch;
+pub mod bounded_state;
+pub mod canonical;
+pub mod cell;
 pub mod constants;
+pub mod dag_cbor;
+pub mod db_state;
+pub mod descriptor;
+pub mod diff;
 pub mod elem_picker;
 pub mod elem_types;
+pub mod enum_csv;
+pub mod enum_delta;
 pub mod enum_matter;
+pub mod error;
+pub mod error_code;
+pub mod events;
+pub mod evm_abi;
+pub mod fixed_str;
+pub mod form_registry;
+pub mod formula;
+pub mod graph;
+pub mod json_dict;
+pub mod kind_chain;
+pub mod matter_cache;
+pub mod matter_json;
+pub mod merkle;
+pub mod meta_spec;
+pub mod metrics;
+pub mod migrations;
+pub mod mime;
+pub mod object_view;
+pub mod oid_pattern;
+pub mod oid_range;
+pub mod owner;
 pub mod perm_matter;
+pub mod provenance;
+pub mod prune;
+pub mod query;
+pub mod range_matter;
 pub mod reader;
+pub mod router;
+pub mod row_source;
+pub mod rpc_state;
+pub mod schema;
+pub mod selector;
+pub mod serde_hex;
+pub mod shard;
+pub mod signing;
+pub mod sparse_matter;
 pub mod state;
+pub mod state_proof;
 pub mod storage;
+pub mod storage_key;
+mod test_state;
+pub mod test_vectors;
+pub mod token;
+mod trace;
 pub mod traits;
 pub mod types;
+pub mod typescript;
+pub mod writer;
 
+pub use abi::*;
+pub use amount::*;
+pub use arc_data::*;
+pub use archive::*;
+pub use batch::*;
+pub use canonical::*;
+pub use cell::*;
 pub use constants::Constants;
+pub use descriptor::*;
+pub use diff::*;
 pub use elem_picker::*;
 pub use elem_types::*;
+pub use enum_delta::*;
 pub use enum_matter::*;
+pub use error::Error;
+pub use error_code::*;
+pub use events::*;
+pub use fixed_str::*;
+pub use form_registry::*;
+pub use formula::*;
+pub use graph::*;
+pub use json_dict::*;
+pub use kind_chain::*;
+pub use matter_cache::*;
+#[cfg(feature = "storage")]
+pub use merkle::Blake2Hasher;
+pub use merkle::MerkleHasher;
+pub use meta_spec::*;
+pub use metrics::*;
+pub use mime::*;
+pub use object_view::*;
+pub use oid_pattern::*;
+pub use oid_range::*;
+pub use owner::*;
 pub use perm_matter::*;
+pub use provenance::*;
+pub use query::*;
+pub use range_matter::*;
+pub use router::*;
+pub use row_source::*;
+pub use schema::*;
+pub use selector::*;
+pub use shard::*;
+pub use sparse_matter::*;
+pub use token::*;
 pub use traits::*;
 pub use types::*;
@@ -0,0 +1,184 @@
+use crate::{
+	trace::event, CollectionMatter, CollectionSource, ElementError, MatterFormRegistry,
+	StateMetrics, StateReader, H256,
+};
+use sp_std::{
+	boxed::Box,
+	cell::RefCell,
+	collections::{btree_map::BTreeMap, vec_deque::VecDeque},
+};
+
+/// Reference-counted handle to a parsed matter: `Arc` with `std`, `Rc` without.
+#[cfg(feature = "std")]
+pub type Shared<T> = sp_std::sync::Arc<T>;
+#[cfg(not(feature = "std"))]
+pub type Shared<T> = sp_std::rc::Rc<T>;
+
+/// Decides which entry a full `MatterCache` drops.
+pub trait EvictionPolicy {
+	fn on_insert(&mut self, hash: &H256);
+
+	fn on_hit(&mut self, _hash: &H256) {}
+
+	/// Picks the entry to drop and forgets it; `None` lets the cache grow.
+	fn evict(&mut self) -> Option<H256>;
+}
+
+/// Drops the least recently used entry.
+#[derive(Debug, Default)]
+pub struct Lru {
+	tick: u64,
+	last_used: BTreeMap<H256, u64>,
+	by_tick: BTreeMap<u64, H256>,
+}
+
+impl Lru {
+	fn touch(&mut self, hash: &H256) {
+		self.tick += 1;
+		if let Some(old) = self.last_used.insert(*hash, self.tick) {
+			self.by_tick.remove(&old);
+		}
+		self.by_tick.insert(self.tick, *hash);
+	}
+}
+
+impl EvictionPolicy for Lru {
+	fn on_insert(&mut self, hash: &H256) {
+		self.touch(hash);
+	}
+
+	fn on_hit(&mut self, hash: &H256) {
+		self.touch(hash);
+	}
+
+	fn evict(&mut self) -> Option<H256> {
+		let (_, hash) = self.by_tick.pop_first()?;
+		self.last_used.remove(&hash);
+		Some(hash)
+	}
+}
+
+/// Drops entries in the order they were added.
+#[derive(Debug, Default)]
+pub struct Fifo(VecDeque<H256>);
+
+impl EvictionPolicy for Fifo {
+	fn on_insert(&mut self, hash: &H256) {
+		self.0.push_back(*hash);
+	}
+
+	fn evict(&mut self) -> Option<H256> {
+		self.0.pop_front()
+	}
+}
+
+/// Parsed collection matters by hash, so pickers resolving against the same collections parse
+/// each one once. Every read of a hash returns the same shared parse.
+///
+/// Reads take `&self`, so one cache can be passed to any number of `ElementPicker`s; it is
+/// not `Sync`.
+pub struct MatterCache<C = CollectionMatter> {
+	capacity: usize,
+	entries: RefCell<BTreeMap<H256, Shared<C>>>,
+	policy: RefCell<Box<dyn EvictionPolicy>>,
+	metrics: Option<Shared<dyn StateMetrics>>,
+}
+
+impl<C: CollectionSource> MatterCache<C> {
+	/// Keeps up to `capacity` matters, dropping the least recently used.
+	pub fn new(capacity: usize) -> Self {
+		Self::with_policy(capacity, Lru::default())
+	}
+
+	pub fn with_policy(capacity: usize, policy: impl EvictionPolicy + 'static) -> Self {
+		Self {
+			capacity,
+			entries: RefCell::new(BTreeMap::new()),
+			policy: RefCell::new(Box::new(policy)),
+			metrics: None,
+		}
+	}
+
+	/// Reports hits and misses to `metrics`.
+	pub fn with_metrics(mut self, metrics: Shared<dyn StateMetrics>) -> Self {
+		self.metrics = Some(metrics);
+		self
+	}
+
+	pub fn len(&self) -> usize {
+		self.entries.borrow().len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.entries.borrow().is_empty()
+	}
+
+	pub fn contains(&self, hash: &H256) -> bool {
+		self.entries.borrow().contains_key(hash)
+	}
+
+	/// The cached parse of `hash`, reading and parsing the matter on a miss.
+	pub fn get_or_parse<E, S: StateReader<E>>(
+		&self,
+		state: &S,
+		hash: &H256,
+	) -> Result<Shared<C>, ElementError> {
+		self.get_or_parse_in(state, hash, None)
+	}
+
+	/// `get_or_parse`, opening a missed matter with the custom forms in `registry`. Hits are
+	/// returned as cached, so one cache should not be shared between registries.
+	pub fn get_or_parse_in<E, S: StateReader<E>>(
+		&self,
+		state: &S,
+		hash: &H256,
+		registry: Option<&MatterFormRegistry>,
+	) -> Result<Shared<C>, ElementError> {
+		if let Some(hit) = self.entries.borrow().get(hash).cloned() {
+			self.policy.borrow_mut().on_hit(hash);
+			event!("matter cache hit");
+			self.metrics.iter().for_each(|m| m.cache_hit());
+			return Ok(hit);
+		}
+		event!("matter cache miss");
+		self.metrics.iter().for_each(|m| m.cache_miss());
+		let matter = state
+			.get_matter(hash)
+			.map_err(|err| ElementError::StateReaderGetMatter(state.error_code(&err)))?;
+		let parsed = Shared::new(C::open(&matter, registry)?);
+		if self.capacity == 0 {
+			return Ok(parsed);
+		}
+		let mut entries = self.entries.borrow_mut();
+		let mut policy = self.policy.borrow_mut();
+		while entries.len() >= self.capacity {
+			let Some(old) = policy.evict() else { break };
+			entries.remove(&old);
+		}
+		entries.insert(*hash, parsed.clone());
+		policy.on_insert(hash);
+		Ok(parsed)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{EvictionPolicy, Fifo, Lru};
+
+	#[test]
+	fn policies_pick_victims() {
+		let mut lru = Lru::default();
+		lru.on_insert(&[1; 32]);
+		lru.on_insert(&[2; 32]);
+		lru.on_hit(&[1; 32]);
+		assert_eq!(lru.evict(), Some([2; 32]));
+		assert_eq!(lru.evict(), Some([1; 32]));
+		assert_eq!(lru.evict(), None);
+
+		let mut fifo = Fifo::default();
+		fifo.on_insert(&[1; 32]);
+		fifo.on_insert(&[2; 32]);
+		fifo.on_hit(&[1; 32]);
+		assert_eq!(fifo.evict(), Some([1; 32]));
+	}
+}
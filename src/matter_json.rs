@@ -0,0 +1,196 @@
+#![cfg(feature = "serde")]
+
+use crate::{
+	Bytes32, EnumMatter, EnumMatterError, EnumMatterHeader, PermColumn, PermHeader, PermMatter,
+	PermMatterError, Vec,
+};
+use serde::{Deserialize, Serialize};
+
+fn cells(data: &[u8]) -> Vec<Bytes32> {
+	data.chunks_exact(32).map(|c| c.try_into().unwrap()).collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EnumMatterJson {
+	pub aux_types: Vec<u8>,
+	pub col_types: Vec<u8>,
+	#[serde(with = "crate::serde_hex::bytes_vec")]
+	pub aux: Vec<Bytes32>,
+	#[serde(with = "crate::serde_hex::bytes_rows")]
+	pub rows: Vec<Vec<Bytes32>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PermColumnJson {
+	pub col_type: u8,
+	pub perm: bool,
+	#[serde(with = "crate::serde_hex::bytes_vec")]
+	pub cells: Vec<Bytes32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PermMatterJson {
+	pub version: u8,
+	pub aux_types: Vec<u8>,
+	#[serde(with = "crate::serde_hex::bytes_vec")]
+	pub aux: Vec<Bytes32>,
+	pub cols: Vec<PermColumnJson>,
+}
+
+impl EnumMatter {
+	pub fn to_json(&self) -> EnumMatterJson {
+		let row_size = self.cols() * EnumMatterHeader::CELL_SIZE;
+		EnumMatterJson {
+			aux_types: self.header.aux_types[..self.aux()].to_vec(),
+			col_types: self.header.col_types[..self.cols()].to_vec(),
+			aux: cells(&self.aux_data),
+			rows: match row_size {
+				0 => Vec::new(),
+				n => self.row_data.chunks_exact(n).map(cells).collect(),
+			},
+		}
+	}
+
+	/// Rebuilds the binary matter; the result goes through `EnumMatter::from`, so the usual
+	/// header and layout checks apply.
+	pub fn from_json(json: &EnumMatterJson) -> Result<Self, EnumMatterError> {
+		if json.aux_types.len() > 8 {
+			return Err(EnumMatterError::BadAuxCount(json.aux_types.len().min(0x0F) as u8));
+		}
+		if json.col_types.len() > 16 {
+			return Err(EnumMatterError::BadColCount(json.col_types.len().min(0xFF) as u8));
+		}
+		let rows = u16::try_from(json.rows.len()).map_err(|_| EnumMatterError::Overflow)?;
+		let mut header = EnumMatterHeader {
+			magic: EnumMatterHeader::MAGIC,
+			ver_aux: 0x10 | json.aux_types.len() as u8,
+			cols: json.col_types.len() as u8,
+			rows,
+			aux_types: [0; 8],
+			col_types: [0; 16],
+		};
+		header.aux_types[..json.aux_types.len()].copy_from_slice(&json.aux_types);
+		header.col_types[..json.col_types.len()].copy_from_slice(&json.col_types);
+
+		let mut blob = header.encode().to_vec();
+		blob.extend(json.aux.iter().flatten());
+		for (row, cells) in json.rows.iter().enumerate() {
+			if cells.len() != json.col_types.len() {
+				return Err(EnumMatterError::OobCell { row, col: cells.len() });
+			}
+			blob.extend(cells.iter().flatten());
+		}
+		EnumMatter::from(&blob)
+	}
+}
+
+impl PermMatter {
+	pub fn to_json(&self) -> PermMatterJson {
+		let h = &self.header;
+		PermMatterJson {
+			version: h.version,
+			aux_types: h.aux.clone(),
+			aux: cells(&self.aux_data),
+			cols: h
+				.cols
+				.iter()
+				.map(|c| PermColumnJson {
+					col_type: c.col_type,
+					perm: c.perm_col,
+					cells: cells(
+						&self.col_data[c.col_offset * PermHeader::CELL_SIZE..]
+							[..c.col_height * PermHeader::CELL_SIZE],
+					),
+				})
+				.collect(),
+		}
+	}
+
+	/// Rebuilds the binary matter and parses it strictly.
+	pub fn from_json(json: &PermMatterJson) -> Result<Self, PermMatterError> {
+		if json.cols.len() > 16 {
+			return Err(PermMatterError::BadColCount(json.cols.len().min(0xFF) as u8));
+		}
+		if json.aux_types.len() > 8 {
+			return Err(PermMatterError::BadAuxCount(json.aux_types.len().min(0x0F) as u8));
+		}
+		if json.version != 1 && json.version != 2 {
+			return Err(PermMatterError::BadVersion(json.version));
+		}
+		let max_height = if json.version == 1 { u16::MAX as usize } else { u32::MAX as usize };
+		let (mut offset, mut perm_idx) = (0, 0);
+		let mut cols = Vec::with_capacity(json.cols.len());
+		for (i, c) in json.cols.iter().enumerate() {
+			if c.cells.len() > max_height {
+				return Err(PermMatterError::Overflow);
+			}
+			cols.push(PermColumn {
+				col_idx: i as u8,
+				col_type: c.col_type,
+				perm_col: c.perm,
+				perm_idx,
+				col_offset: offset,
+				col_height: c.cells.len(),
+			});
+			perm_idx += c.perm as u8;
+			offset += c.cells.len();
+		}
+		let enum_cols = cols
+			.iter()
+			.filter(|c| !c.perm_col)
+			.fold(0u16, |bits, c| bits | 1 << (15 - c.col_idx));
+		// `rows` is left for the strict parse below to derive; `encode` does not read it.
+		let header = PermHeader {
+			version: json.version,
+			aux: json.aux_types.clone(),
+			enum_cols,
+			perm_cols: cols.iter().filter(|c| c.perm_col).cloned().collect(),
+			cols,
+			rows: 0,
+			sum_heights: offset,
+		};
+		let mut blob = header.encode();
+		blob.extend(json.aux.iter().flatten());
+		for c in &json.cols {
+			blob.extend(c.cells.iter().flatten());
+		}
+		PermMatter::from_strict(&blob)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{EnumMatterJson, PermMatterJson};
+	use crate::{EnumMatter, PermMatter};
+
+	#[test]
+	fn json_roundtrip() {
+		let cell = |b: u8| format!("\"0x{}\"", format!("{b:02x}").repeat(32));
+		let json = format!(
+			r#"{{"aux_types":[],"col_types":[1,2],"aux":[],"rows":[[{},{}],[{},{}]]}}"#,
+			cell(1),
+			cell(2),
+			cell(3),
+			cell(4)
+		);
+		let parsed: EnumMatterJson = serde_json::from_str(&json).unwrap();
+		let m = EnumMatter::from_json(&parsed).unwrap();
+		assert_eq!(m.cell_at(1, 0).unwrap(), &[3u8; 32]);
+		assert_eq!(serde_json::to_string(&m.to_json()).unwrap(), json);
+
+		let json = format!(
+			r#"{{"version":1,"aux_types":[7],"aux":[{}],"cols":[{{"col_type":1,"perm":true,"cells":[{},{}]}},{{"col_type":1,"perm":false,"cells":[{},{}]}}]}}"#,
+			cell(9),
+			cell(1),
+			cell(2),
+			cell(3),
+			cell(4)
+		);
+		let parsed: PermMatterJson = serde_json::from_str(&json).unwrap();
+		let p = PermMatter::from_json(&parsed).unwrap();
+		assert_eq!(p.rows(), 2);
+		assert_eq!(p.cell_at(1, 1).unwrap(), &[4u8; 32]);
+		assert_eq!(PermMatter::from_strict(&p.to_blob()).unwrap().to_json(), parsed);
+		assert_eq!(serde_json::to_string(&p.to_json()).unwrap(), json);
+	}
+}
@@ -0,0 +1,477 @@
+use crate::{Arc, Bytes32, Constants, ElementType, Vec};
+use thiserror::Error;
+
+#[cfg(feature = "scale")]
+use codec::{Decode, DecodeWithMemTracking, Encode};
+#[cfg(feature = "scale")]
+use scale_info::TypeInfo;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, Error, PartialEq, Eq)]
+pub enum MetaSpecError {
+	#[error("expected {expect} snapshot elements, got {got}")]
+	BadLength { expect: usize, got: usize },
+	#[error("invalid element spec")]
+	BadElemSpec,
+	#[error("invalid relation spec")]
+	BadRelSpec,
+	#[error("more default cells than elements")]
+	TooManyDefaults,
+	#[error("expected at most {max} elements, got {got}")]
+	TooManyElements { max: usize, got: usize },
+	#[error("no default for element {0}")]
+	NoDefault(usize),
+	#[error("invalid adjacency spec")]
+	BadAdjSpec,
+	#[error("kind {0} is not allowed")]
+	KindNotAllowed(u64),
+	#[error("degree limit exceeded for kind {0}")]
+	DegreeExceeded(u64),
+	#[error("too many tails")]
+	TooManyTails,
+}
+
+/// Set meta object snapshot: `[code, data]`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "scale", derive(Encode, Decode, TypeInfo, DecodeWithMemTracking))]
+pub struct SetSpec {
+	#[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes"))]
+	pub code: Bytes32,
+	#[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes"))]
+	pub data: Bytes32,
+}
+
+impl SetSpec {
+	pub const ELEMS: usize = 2;
+
+	pub fn from_elems(elems: &[Bytes32]) -> Result<Self, MetaSpecError> {
+		match elems {
+			[code, data] => Ok(Self { code: *code, data: *data }),
+			_ => Err(MetaSpecError::BadLength { expect: Self::ELEMS, got: elems.len() }),
+		}
+	}
+
+	pub fn to_elems(&self) -> Vec<Bytes32> {
+		sp_std::vec![self.code, self.data]
+	}
+
+	/// Hash of the set contract matter.
+	pub fn contract(&self) -> &Bytes32 {
+		&self.code
+	}
+
+	/// Hash of the collection matter objects of the set pick rows from.
+	pub fn collection(&self) -> &Bytes32 {
+		&self.data
+	}
+}
+
+/// Kind meta object snapshot: `[code, data, elem_spec, rel_spec, defaults..]`.
+///
+/// `elem_spec` holds one element type byte per element, zero terminated, in its first
+/// `ELEM_SPEC_CAPACITY` bytes. `rel_spec` holds up to `REL_SPEC_CAPACITY` accepted relation ids
+/// as big-endian u32s, zero terminated. The optional trailing `defaults` are the cells of the
+/// first elements, used for objects that store fewer elements than the spec lists.
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "scale", derive(Encode, Decode, TypeInfo, DecodeWithMemTracking))]
+pub struct KindSpec {
+	#[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes"))]
+	pub code: Bytes32,
+	#[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes"))]
+	pub data: Bytes32,
+	pub elem_types: Vec<u8>,
+	pub relations: Vec<u64>,
+	#[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes_vec"))]
+	pub defaults: Vec<Bytes32>,
+}
+
+impl KindSpec {
+	pub const ELEMS: usize = 4;
+
+	pub fn from_elems(elems: &[Bytes32]) -> Result<Self, MetaSpecError> {
+		let [code, data, elem_spec, rel_spec, defaults @ ..] = elems else {
+			return Err(MetaSpecError::BadLength { expect: Self::ELEMS, got: elems.len() });
+		};
+		let elem_types = zero_terminated(elem_spec, 1, Constants::ELEM_SPEC_CAPACITY)
+			.ok_or(MetaSpecError::BadElemSpec)?;
+		if elem_types.iter().any(|&t| ElementType::try_from(t).is_err()) {
+			return Err(MetaSpecError::BadElemSpec);
+		}
+		let relations = zero_terminated(rel_spec, 4, Constants::REL_SPEC_CAPACITY)
+			.ok_or(MetaSpecError::BadRelSpec)?
+			.chunks_exact(4)
+			.map(|id| u32::from_be_bytes(id.try_into().unwrap()) as u64)
+			.collect();
+		if defaults.len() > elem_types.len() {
+			return Err(MetaSpecError::TooManyDefaults);
+		}
+		Ok(Self {
+			code: *code,
+			data: *data,
+			elem_types: elem_types.to_vec(),
+			relations,
+			defaults: defaults.to_vec(),
+		})
+	}
+
+	pub fn to_elems(&self) -> Result<Vec<Bytes32>, MetaSpecError> {
+		self.validate()?;
+		let mut elem_spec = [0u8; 32];
+		elem_spec[..self.elem_types.len()].copy_from_slice(&self.elem_types);
+		let mut rel_spec = [0u8; 32];
+		for (i, rel) in self.relations.iter().enumerate() {
+			rel_spec[i * 4..i * 4 + 4].copy_from_slice(&(*rel as u32).to_be_bytes());
+		}
+		let mut elems = sp_std::vec![self.code, self.data, elem_spec, rel_spec];
+		elems.extend_from_slice(&self.defaults);
+		Ok(elems)
+	}
+
+	/// Checks the fields fit their packing; `from_elems(to_elems())` round-trips once this passes.
+	pub fn validate(&self) -> Result<(), MetaSpecError> {
+		let elems_ok = self.elem_types.len() <= Constants::ELEM_SPEC_CAPACITY &&
+			self.elem_types.iter().all(|&t| ElementType::try_from(t).is_ok());
+		if !elems_ok {
+			return Err(MetaSpecError::BadElemSpec);
+		}
+		let rels_ok = self.relations.len() <= Constants::REL_SPEC_CAPACITY &&
+			self.relations.iter().all(|&r| r != 0 && r <= u32::MAX as u64);
+		if !rels_ok {
+			return Err(MetaSpecError::BadRelSpec);
+		}
+		if self.defaults.len() > self.elem_types.len() {
+			return Err(MetaSpecError::TooManyDefaults);
+		}
+		Ok(())
+	}
+
+	/// Hash of the kind contract matter.
+	pub fn contract(&self) -> &Bytes32 {
+		&self.code
+	}
+
+	/// Hash of the collection matter objects of the kind pick rows from.
+	pub fn collection(&self) -> &Bytes32 {
+		&self.data
+	}
+
+	pub fn elem_count(&self) -> usize {
+		self.elem_types.len()
+	}
+
+	pub fn elem_type(&self, index: usize) -> Option<ElementType> {
+		self.elem_types.get(index).and_then(|&t| ElementType::try_from(t).ok())
+	}
+
+	pub fn accepts_relation(&self, rel: u64) -> bool {
+		self.relations.contains(&rel)
+	}
+
+	/// `elems` extended to `elem_count` elements with the kind's default cells.
+	pub fn materialize(&self, elems: &[Bytes32]) -> Result<Vec<Bytes32>, MetaSpecError> {
+		let max = self.elem_count();
+		if elems.len() > max {
+			return Err(MetaSpecError::TooManyElements { max, got: elems.len() });
+		}
+		let mut out = elems.to_vec();
+		for i in elems.len()..max {
+			out.push(*self.defaults.get(i).ok_or(MetaSpecError::NoDefault(i))?);
+		}
+		Ok(out)
+	}
+}
+
+/// Packed spec of a relation meta object: version (1), flags, max tails per head (u16 BE, 0
+/// means `TAIL_CAPACITY`), then up to `REL_SPEC_CAPACITY` head kind ids as u24 BE, zero
+/// terminated, and 4 zero bytes. No head kinds means any head kind.
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "scale", derive(Encode, Decode, TypeInfo, DecodeWithMemTracking))]
+pub struct RelationSpec {
+	pub flags: u8,
+	pub max_tails: u16,
+	pub head_kinds: Vec<u64>,
+}
+
+impl RelationSpec {
+	pub const VERSION: u8 = 1;
+	/// A head may be linked to itself.
+	pub const ALLOW_SELF: u8 = 0x01;
+	/// Heads must be owned by the same account as their tails.
+	pub const SAME_OWNER: u8 = 0x02;
+	const KNOWN_FLAGS: u8 = Self::ALLOW_SELF | Self::SAME_OWNER;
+
+	pub fn decode(cell: &Bytes32) -> Result<Self, MetaSpecError> {
+		if cell[0] != Self::VERSION || cell[1] & !Self::KNOWN_FLAGS != 0 {
+			return Err(MetaSpecError::BadRelSpec);
+		}
+		let max_tails = u16::from_be_bytes([cell[2], cell[3]]);
+		if max_tails as usize > Constants::TAIL_CAPACITY {
+			return Err(MetaSpecError::BadRelSpec);
+		}
+		let mut kinds = [0u8; 32];
+		kinds[..28].copy_from_slice(&cell[4..]);
+		let head_kinds = zero_terminated(&kinds, 3, Constants::REL_SPEC_CAPACITY)
+			.ok_or(MetaSpecError::BadRelSpec)?
+			.chunks_exact(3)
+			.map(u24_of)
+			.collect();
+		Ok(Self { flags: cell[1], max_tails, head_kinds })
+	}
+
+	pub fn encode(&self) -> Result<Bytes32, MetaSpecError> {
+		self.validate()?;
+		let mut cell = [0u8; 32];
+		cell[0] = Self::VERSION;
+		cell[1] = self.flags;
+		cell[2..4].copy_from_slice(&self.max_tails.to_be_bytes());
+		for (i, kind) in self.head_kinds.iter().enumerate() {
+			cell[4 + i * 3..7 + i * 3].copy_from_slice(&of_u24(*kind));
+		}
+		Ok(cell)
+	}
+
+	/// Checks the fields fit their packing; `decode(encode())` round-trips once this passes.
+	pub fn validate(&self) -> Result<(), MetaSpecError> {
+		let kinds_ok = self.head_kinds.len() <= Constants::REL_SPEC_CAPACITY &&
+			self.head_kinds.iter().all(|&k| k != 0 && k <= U24_MAX);
+		let ok = self.flags & !Self::KNOWN_FLAGS == 0 &&
+			self.max_tails as usize <= Constants::TAIL_CAPACITY &&
+			kinds_ok;
+		ok.then_some(()).ok_or(MetaSpecError::BadRelSpec)
+	}
+
+	pub fn max_tails(&self) -> usize {
+		match self.max_tails {
+			0 => Constants::TAIL_CAPACITY,
+			n => n as usize,
+		}
+	}
+
+	pub fn allows_head(&self, kind: u64) -> bool {
+		self.head_kinds.is_empty() || self.head_kinds.contains(&kind)
+	}
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "scale", derive(Encode, Decode, TypeInfo, DecodeWithMemTracking))]
+pub struct Adjacency {
+	/// Tail kind id; `AdjacencySpec::ANY_KIND` matches every kind not listed elsewhere.
+	pub kind: u64,
+	/// Most tails of this kind per head; 0 means no limit.
+	pub degree: u8,
+}
+
+/// Tail kinds a relation accepts with their degree limits, packed as up to `ADJ_SPEC_CAPACITY`
+/// entries of kind (u24 BE) and degree (u8), zero terminated.
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "scale", derive(Encode, Decode, TypeInfo, DecodeWithMemTracking))]
+pub struct AdjacencySpec {
+	pub adjs: Vec<Adjacency>,
+}
+
+impl AdjacencySpec {
+	pub const ANY_KIND: u64 = U24_MAX;
+
+	pub fn decode(cell: &Bytes32) -> Result<Self, MetaSpecError> {
+		let adjs: Vec<Adjacency> = zero_terminated(cell, 4, Constants::ADJ_SPEC_CAPACITY)
+			.ok_or(MetaSpecError::BadAdjSpec)?
+			.chunks_exact(4)
+			.map(|e| Adjacency { kind: u24_of(&e[..3]), degree: e[3] })
+			.collect();
+		let spec = Self { adjs };
+		spec.validate()?;
+		Ok(spec)
+	}
+
+	pub fn encode(&self) -> Result<Bytes32, MetaSpecError> {
+		self.validate()?;
+		let mut cell = [0u8; 32];
+		for (i, adj) in self.adjs.iter().enumerate() {
+			cell[i * 4..i * 4 + 3].copy_from_slice(&of_u24(adj.kind));
+			cell[i * 4 + 3] = adj.degree;
+		}
+		Ok(cell)
+	}
+
+	/// Kinds must be non-zero, fit in 24 bits and appear at most once.
+	pub fn validate(&self) -> Result<(), MetaSpecError> {
+		let ok = self.adjs.len() <= Constants::ADJ_SPEC_CAPACITY &&
+			self.adjs.iter().enumerate().all(|(i, a)| {
+				a.kind != 0 && a.kind <= U24_MAX && self.adjs[..i].iter().all(|b| b.kind != a.kind)
+			});
+		ok.then_some(()).ok_or(MetaSpecError::BadAdjSpec)
+	}
+
+	pub fn adjacency(&self, kind: u64) -> Option<&Adjacency> {
+		let find = |k| self.adjs.iter().find(|a| a.kind == k);
+		find(kind).or_else(|| find(Self::ANY_KIND))
+	}
+
+	/// Checks the tails of one head against `rel`'s tail limit and the per-kind degrees.
+	pub fn check_tails(&self, rel: &RelationSpec, tails: &[Arc]) -> Result<(), MetaSpecError> {
+		if tails.len() > rel.max_tails() {
+			return Err(MetaSpecError::TooManyTails);
+		}
+		let mut counts: Vec<(u64, usize)> = Vec::new();
+		for arc in tails {
+			let adj = self.adjacency(arc.kind).ok_or(MetaSpecError::KindNotAllowed(arc.kind))?;
+			let n = match counts.iter_mut().find(|(k, _)| *k == adj.kind) {
+				Some((_, n)) => {
+					*n += 1;
+					*n
+				},
+				None => {
+					counts.push((adj.kind, 1));
+					1
+				},
+			};
+			if adj.degree != 0 && n > adj.degree as usize {
+				return Err(MetaSpecError::DegreeExceeded(arc.kind));
+			}
+		}
+		Ok(())
+	}
+}
+
+const U24_MAX: u64 = 0xFF_FFFF;
+
+fn u24_of(b: &[u8]) -> u64 {
+	(b[0] as u64) << 16 | (b[1] as u64) << 8 | b[2] as u64
+}
+
+fn of_u24(n: u64) -> [u8; 3] {
+	[(n >> 16) as u8, (n >> 8) as u8, n as u8]
+}
+
+/// Leading non-zero items of `cell` (of `width` bytes each, at most `cap` of them); everything
+/// after the first zero item must be zero too.
+fn zero_terminated(cell: &Bytes32, width: usize, cap: usize) -> Option<&[u8]> {
+	let n = cell
+		.chunks_exact(width)
+		.take(cap)
+		.take_while(|c| c.iter().any(|&b| b != 0))
+		.count();
+	let used = n * width;
+	cell[used..].iter().all(|&b| b == 0).then_some(&cell[..used])
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{
+		Adjacency, AdjacencySpec, Arc, ElementType, KindSpec, MetaSpecError, RelationSpec, SetSpec,
+	};
+
+	#[test]
+	fn kind_spec_roundtrip() {
+		let kind = KindSpec {
+			code: [1; 32],
+			data: [2; 32],
+			elem_types: vec![ElementType::Image as u8, ElementType::Info as u8],
+			relations: vec![7, 0x1_0000],
+			defaults: vec![[3; 32], [4; 32]],
+		};
+		let elems = kind.to_elems().unwrap();
+		assert_eq!(elems[2][..3], [0x02, 0xFF, 0]);
+		let parsed = KindSpec::from_elems(&elems).unwrap();
+		assert_eq!(parsed, kind);
+		assert_eq!(parsed.elem_type(1), Some(ElementType::Info));
+		assert!(parsed.accepts_relation(7) && !parsed.accepts_relation(8));
+
+		assert_eq!(parsed.materialize(&[[9; 32]]).unwrap(), vec![[9; 32], [4; 32]]);
+		let bare = KindSpec { defaults: vec![], ..parsed.clone() };
+		assert_eq!(bare.materialize(&[[9; 32]]), Err(MetaSpecError::NoDefault(1)));
+
+		let mut extra = elems.clone();
+		extra.push([5; 32]);
+		assert_eq!(KindSpec::from_elems(&extra), Err(MetaSpecError::TooManyDefaults));
+		assert_eq!(KindSpec::from_elems(&elems[..5]).unwrap().defaults, vec![[3; 32]]);
+
+		let mut gap = elems.clone();
+		gap[2][5] = 0x01;
+		assert_eq!(KindSpec::from_elems(&gap), Err(MetaSpecError::BadElemSpec));
+		assert_eq!(
+			SetSpec::from_elems(&elems),
+			Err(MetaSpecError::BadLength { expect: 2, got: 6 })
+		);
+		assert_eq!(SetSpec::from_elems(&elems[..2]).unwrap().collection(), &[2; 32]);
+	}
+
+	#[test]
+	fn kind_spec_capacity() {
+		let kind = |elem_types: Vec<u8>, relations: Vec<u64>| KindSpec {
+			code: [1; 32],
+			data: [2; 32],
+			elem_types,
+			relations,
+			defaults: vec![],
+		};
+		let full = kind(vec![ElementType::Json as u8; 16], (1..=8).collect());
+		assert_eq!(KindSpec::from_elems(&full.to_elems().unwrap()), Ok(full));
+		let rel_max = kind(vec![], vec![u32::MAX as u64]);
+		assert_eq!(KindSpec::from_elems(&rel_max.to_elems().unwrap()), Ok(rel_max));
+
+		let bad = |k: KindSpec| k.to_elems().unwrap_err();
+		assert_eq!(
+			bad(kind(vec![ElementType::Json as u8; 17], vec![])),
+			MetaSpecError::BadElemSpec
+		);
+		assert_eq!(
+			bad(kind(vec![ElementType::Json as u8; 33], vec![])),
+			MetaSpecError::BadElemSpec
+		);
+		assert_eq!(bad(kind(vec![0], vec![])), MetaSpecError::BadElemSpec);
+		assert_eq!(bad(kind(vec![], (1..=9).collect())), MetaSpecError::BadRelSpec);
+		assert_eq!(bad(kind(vec![], vec![0])), MetaSpecError::BadRelSpec);
+		assert_eq!(bad(kind(vec![], vec![u32::MAX as u64 + 1])), MetaSpecError::BadRelSpec);
+		let defaults = KindSpec { defaults: vec![[3; 32]], ..kind(vec![], vec![]) };
+		assert_eq!(defaults.validate(), Err(MetaSpecError::TooManyDefaults));
+	}
+
+	#[test]
+	fn relation_limits() {
+		let rel =
+			RelationSpec { flags: RelationSpec::ALLOW_SELF, max_tails: 3, head_kinds: vec![9] };
+		assert_eq!(RelationSpec::decode(&rel.encode().unwrap()), Ok(rel.clone()));
+		assert!(rel.allows_head(9) && !rel.allows_head(10));
+
+		let adj = AdjacencySpec {
+			adjs: vec![
+				Adjacency { kind: 5, degree: 1 },
+				Adjacency { kind: AdjacencySpec::ANY_KIND, degree: 0 },
+			],
+		};
+		assert_eq!(AdjacencySpec::decode(&adj.encode().unwrap()), Ok(adj.clone()));
+		let arc = |kind, id| Arc { kind, data: 0, rel: 1, set: 2, id };
+		assert_eq!(adj.check_tails(&rel, &[arc(5, 1), arc(6, 2), arc(7, 3)]), Ok(()));
+		assert_eq!(
+			adj.check_tails(&rel, &[arc(5, 1), arc(5, 2)]),
+			Err(MetaSpecError::DegreeExceeded(5))
+		);
+		assert_eq!(
+			adj.check_tails(&rel, &[arc(6, 1), arc(6, 2), arc(6, 3), arc(6, 4)]),
+			Err(MetaSpecError::TooManyTails)
+		);
+		let dup = AdjacencySpec { adjs: vec![adj.adjs[0], adj.adjs[0]] };
+		assert_eq!(dup.encode(), Err(MetaSpecError::BadAdjSpec));
+	}
+
+	#[test]
+	fn spec_capacity() {
+		let rel = |n: u64| RelationSpec { flags: 0, max_tails: 0, head_kinds: (1..=n).collect() };
+		assert_eq!(RelationSpec::decode(&rel(8).encode().unwrap()), Ok(rel(8)));
+		assert_eq!(rel(9).encode(), Err(MetaSpecError::BadRelSpec));
+		assert_eq!(rel(10).encode(), Err(MetaSpecError::BadRelSpec));
+
+		let adj = |n: u64| AdjacencySpec {
+			adjs: (1..=n).map(|kind| Adjacency { kind, degree: 1 }).collect(),
+		};
+		assert_eq!(AdjacencySpec::decode(&adj(8).encode().unwrap()), Ok(adj(8)));
+		assert_eq!(adj(9).encode(), Err(MetaSpecError::BadAdjSpec));
+	}
+}
@@ -0,0 +1,239 @@
+use crate::{
+	Arc, Bytes32, Descriptor, ErrorCode, Facet, Matter, ObjectView, Rev, StateReader, Time, Unique,
+	Value, Vec, H256, OID,
+};
+use core::sync::atomic::{AtomicU64, Ordering};
+
+#[cfg(feature = "storage")]
+use crate::reader::StateProvider;
+
+/// Receives counts of state access from `MeteredReader`, `MeteredProvider` and `MatterCache`.
+/// Every method defaults to doing nothing; implement the ones to export.
+pub trait StateMetrics {
+	fn matter_read(&self) {}
+
+	/// A read of a snapshot or a current state, including its descriptor alone.
+	fn snapshot_read(&self) {}
+
+	fn cache_hit(&self) {}
+
+	fn cache_miss(&self) {}
+
+	/// `bytes` of stored value fetched to be decoded.
+	fn bytes_decoded(&self, _bytes: usize) {}
+}
+
+impl<M: StateMetrics + ?Sized> StateMetrics for &M {
+	fn matter_read(&self) {
+		(**self).matter_read()
+	}
+
+	fn snapshot_read(&self) {
+		(**self).snapshot_read()
+	}
+
+	fn cache_hit(&self) {
+		(**self).cache_hit()
+	}
+
+	fn cache_miss(&self) {
+		(**self).cache_miss()
+	}
+
+	fn bytes_decoded(&self, bytes: usize) {
+		(**self).bytes_decoded(bytes)
+	}
+}
+
+/// `StateMetrics` as plain atomic counters, for exporters that scrape totals.
+#[derive(Debug, Default)]
+pub struct StateCounters {
+	matter_reads: AtomicU64,
+	snapshot_reads: AtomicU64,
+	cache_hits: AtomicU64,
+	cache_misses: AtomicU64,
+	bytes_decoded: AtomicU64,
+}
+
+impl StateCounters {
+	pub fn matter_reads(&self) -> u64 {
+		self.matter_reads.load(Ordering::Relaxed)
+	}
+
+	pub fn snapshot_reads(&self) -> u64 {
+		self.snapshot_reads.load(Ordering::Relaxed)
+	}
+
+	pub fn cache_hits(&self) -> u64 {
+		self.cache_hits.load(Ordering::Relaxed)
+	}
+
+	pub fn cache_misses(&self) -> u64 {
+		self.cache_misses.load(Ordering::Relaxed)
+	}
+
+	pub fn bytes_decoded(&self) -> u64 {
+		self.bytes_decoded.load(Ordering::Relaxed)
+	}
+}
+
+impl StateMetrics for StateCounters {
+	fn matter_read(&self) {
+		self.matter_reads.fetch_add(1, Ordering::Relaxed);
+	}
+
+	fn snapshot_read(&self) {
+		self.snapshot_reads.fetch_add(1, Ordering::Relaxed);
+	}
+
+	fn cache_hit(&self) {
+		self.cache_hits.fetch_add(1, Ordering::Relaxed);
+	}
+
+	fn cache_miss(&self) {
+		self.cache_misses.fetch_add(1, Ordering::Relaxed);
+	}
+
+	fn bytes_decoded(&self, bytes: usize) {
+		self.bytes_decoded.fetch_add(bytes as u64, Ordering::Relaxed);
+	}
+}
+
+/// A `StateReader` that reports matter and snapshot reads to `M`, failed ones included.
+pub struct MeteredReader<R, M> {
+	inner: R,
+	metrics: M,
+}
+
+impl<R, M: StateMetrics> MeteredReader<R, M> {
+	pub fn new(inner: R, metrics: M) -> Self {
+		Self { inner, metrics }
+	}
+
+	pub fn inner(&self) -> &R {
+		&self.inner
+	}
+
+	pub fn metrics(&self) -> &M {
+		&self.metrics
+	}
+
+	pub fn into_inner(self) -> R {
+		self.inner
+	}
+}
+
+impl<E, R: StateReader<E>, M: StateMetrics> StateReader<E> for MeteredReader<R, M> {
+	fn get_matter(&self, hash: &H256) -> Result<Matter, E> {
+		self.metrics.matter_read();
+		self.inner.get_matter(hash)
+	}
+
+	fn get_value(&self, tid: &OID, rev: Rev) -> Result<Value, E> {
+		self.inner.get_value(tid, rev)
+	}
+
+	fn get_unique(&self, tid: &OID, rev: Rev) -> Result<Unique, E> {
+		self.inner.get_unique(tid, rev)
+	}
+
+	fn get_descriptor(&self, oid: &OID, rev: Rev) -> Result<Descriptor, E> {
+		self.metrics.snapshot_read();
+		self.inner.get_descriptor(oid, rev)
+	}
+
+	fn get_snapshot(&self, oid: &OID, rev: Rev) -> Result<(Descriptor, Vec<Bytes32>), E> {
+		self.metrics.snapshot_read();
+		self.inner.get_snapshot(oid, rev)
+	}
+
+	fn get_snapshot_at(&self, oid: &OID, time: Time) -> Result<(Descriptor, Vec<Bytes32>), E> {
+		self.metrics.snapshot_read();
+		self.inner.get_snapshot_at(oid, time)
+	}
+
+	fn get_tails(&self, oid: &OID, rev: Rev) -> Result<Vec<Arc>, E> {
+		self.inner.get_tails(oid, rev)
+	}
+
+	fn get_heads(&self, oid: &OID, rev: Rev) -> Result<Vec<Arc>, E> {
+		self.inner.get_heads(oid, rev)
+	}
+
+	fn get_facets(&self, oid: &OID, rev: Rev) -> Result<Vec<Facet>, E> {
+		self.inner.get_facets(oid, rev)
+	}
+
+	fn get_facet(&self, oid: &OID, rev: Rev, sel: u32) -> Result<Matter, E> {
+		self.metrics.matter_read();
+		self.inner.get_facet(oid, rev, sel)
+	}
+
+	fn get_kind_contract(&self, oid: &OID, rev: Rev) -> Result<Matter, E> {
+		self.metrics.matter_read();
+		self.inner.get_kind_contract(oid, rev)
+	}
+
+	fn error_code(&self, err: &E) -> Option<ErrorCode> {
+		self.inner.error_code(err)
+	}
+}
+
+/// A `StateProvider` that reports the size of every value it fetches to `M`. Wrap it in a
+/// `MeteredReader` to count reads as well.
+#[cfg(feature = "storage")]
+pub struct MeteredProvider<P, M> {
+	inner: P,
+	metrics: M,
+}
+
+#[cfg(feature = "storage")]
+impl<P, M: StateMetrics> MeteredProvider<P, M> {
+	pub fn new(inner: P, metrics: M) -> Self {
+		Self { inner, metrics }
+	}
+
+	pub fn into_inner(self) -> P {
+		self.inner
+	}
+}
+
+#[cfg(feature = "storage")]
+impl<P: StateProvider, M: StateMetrics> StateProvider for MeteredProvider<P, M> {
+	fn _get(&self, key: &[u8]) -> Option<Vec<u8>> {
+		let value = self.inner._get(key)?;
+		self.metrics.bytes_decoded(value.len());
+		Some(value)
+	}
+
+	fn _next_key(&self, key: &[u8]) -> Option<Vec<u8>> {
+		self.inner._next_key(key)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{
+		test_vectors::{ENUM_BLOB, ENUM_MATTER_HASH},
+		to_mime, CollectionMatter, Descriptor, Matter, MatterCache, MeteredReader, Mime,
+		Prefetched, Rev, Shared, StateCounters, StateReader, OID,
+	};
+
+	#[test]
+	fn counts_reads_and_cache_use() {
+		let mut state = Prefetched::default();
+		let blob = ENUM_BLOB.to_vec();
+		state.insert_matter(ENUM_MATTER_HASH, Matter { form: 208, mime: Mime::ENUM, blob });
+		let counters = Shared::new(StateCounters::default());
+		let reader = MeteredReader::new(state, &*counters);
+
+		let oid = OID { universe: 1, set: 2, id: 3 };
+		assert!(reader.get_snapshot(&oid, Rev(1)).is_err());
+		let cache = MatterCache::<CollectionMatter>::new(2).with_metrics(counters.clone());
+		for _ in 0..3 {
+			cache.get_or_parse(&reader, &ENUM_MATTER_HASH).unwrap();
+		}
+		assert_eq!((counters.matter_reads(), counters.snapshot_reads()), (1, 1));
+		assert_eq!((counters.cache_hits(), counters.cache_misses()), (2, 1));
+	}
+}
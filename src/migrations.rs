@@ -0,0 +1,95 @@
+#![cfg(feature = "scale")]
+
+//! Conversions from the storage layouts of earlier `Constants::STORAGE_VERSION`s.
+//!
+//! Each migration decodes a value stored by the previous version and returns it in the current
+//! layout, so a runtime upgrade can translate its storage one entry at a time.
+
+use crate::state::ObjectValue;
+use codec::DecodeAll;
+
+/// Layouts of storage version 0, the first released one. Version 1 only added the
+/// `ObjectValue::Heads` and `ObjectValue::Facet` variants, so every version 0 value is also a
+/// valid version 1 value and migrates unchanged.
+pub mod v0 {
+	use crate::state::{Arcs, Facets, Snapshot, Sota};
+	use codec::{Decode, Encode};
+
+	#[derive(Debug, PartialEq, Clone, Encode, Decode)]
+	pub enum ObjectValue {
+		#[codec(index = 0)]
+		Sota(Sota),
+		#[codec(index = 1)]
+		Snapshot(Snapshot),
+		#[codec(index = 2)]
+		Tails(Arcs),
+		#[codec(index = 3)]
+		Facets(Facets),
+	}
+}
+
+impl From<v0::ObjectValue> for ObjectValue {
+	fn from(old: v0::ObjectValue) -> Self {
+		match old {
+			v0::ObjectValue::Sota(sota) => ObjectValue::Sota(sota),
+			v0::ObjectValue::Snapshot(snap) => ObjectValue::Snapshot(snap),
+			v0::ObjectValue::Tails(arcs) => ObjectValue::Tails(arcs),
+			v0::ObjectValue::Facets(facets) => ObjectValue::Facets(facets),
+		}
+	}
+}
+
+/// Decodes an object value stored by the previous storage version into the current layout.
+/// The whole input must be consumed.
+pub fn migrate_object_value(old_bytes: &[u8]) -> Result<ObjectValue, codec::Error> {
+	v0::ObjectValue::decode_all(&mut &old_bytes[..]).map(Into::into)
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{
+		migrations::migrate_object_value,
+		state::{Arcs, ObjectValue, Snapshot, Sota},
+		Constants, Descriptor, Owner, Rev,
+	};
+	use codec::Encode;
+
+	#[test]
+	fn migrates_v0_object_values() {
+		assert_eq!(Constants::STORAGE_VERSION, 1);
+		let desc = Descriptor {
+			traits: 1,
+			rev: Rev(3),
+			krev: Rev(2),
+			srev: Rev(2),
+			kind: 17,
+			..Default::default()
+		};
+
+		// Encoded by the baseline release, before any migration existed.
+		let desc_hex = concat!(
+			"01000000",
+			"03000000",
+			"02000000",
+			"02000000",
+			"1100000000000000",
+			"00000000",
+			"00000000",
+		);
+		let (pos, mt) = ("09000000000000000000000000000000", "05000000000000000000000000000000");
+		let old = hex::decode(["00", desc_hex, &"07".repeat(32), pos, mt].concat()).unwrap();
+		let sota = Sota { desc: desc.clone(), owner: Owner([7; 32]), pos: 9, mt: 5 };
+		assert_eq!(migrate_object_value(&old), Ok(ObjectValue::Sota(sota)));
+
+		let old = hex::decode(["01", desc_hex, mt, "04", &"01".repeat(32)].concat()).unwrap();
+		let snap = Snapshot { desc, mt: 5, elems: vec![[1; 32]] };
+		assert_eq!(migrate_object_value(&old), Ok(ObjectValue::Snapshot(snap)));
+
+		// Version 1 variants and trailing bytes are not version 0 values.
+		assert!(migrate_object_value(&ObjectValue::Heads(Arcs::default()).encode()).is_err());
+		let mut long = ObjectValue::Tails(Arcs::default()).encode();
+		assert!(migrate_object_value(&long).is_ok());
+		long.push(0);
+		assert!(migrate_object_value(&long).is_err());
+	}
+}
@@ -0,0 +1,81 @@
+use crate::{MatterForm, Mime};
+
+impl Mime {
+	// Simple
+	pub const JSON: Mime = Mime::from_static("application/json");
+	pub const JSONZ: Mime = Mime::from_static("application/vnd.every.jsonz");
+	pub const PNG: Mime = Mime::from_static("image/png");
+	pub const JPEG: Mime = Mime::from_static("image/jpeg");
+	pub const GIF: Mime = Mime::from_static("image/gif");
+	pub const SVG: Mime = Mime::from_static("image/svg+xml");
+	pub const WEBP: Mime = Mime::from_static("image/webp");
+	pub const SCHEMA: Mime = Mime::from_static("application/vnd.every.schema");
+	// Code
+	pub const WASM: Mime = Mime::from_static("application/wasm");
+	pub const FORMULA: Mime = Mime::from_static("application/vnd.every.formula");
+	// Data Collection
+	pub const ENUM: Mime = Mime::from_static("application/vnd.every.enum");
+	pub const PERM: Mime = Mime::from_static("application/vnd.every.perm");
+	pub const SPARSE: Mime = Mime::from_static("application/vnd.every.sparse");
+	pub const RANGE: Mime = Mime::from_static("application/vnd.every.range");
+}
+
+impl MatterForm {
+	pub fn canonical_mime(&self) -> Mime {
+		self.accepted_mimes()[0]
+	}
+
+	/// Mimes accepted for this form; the first one is canonical.
+	pub fn accepted_mimes(&self) -> &'static [Mime] {
+		match self {
+			MatterForm::Json => &[Mime::JSON, Mime::JSONZ],
+			MatterForm::Image => &[Mime::PNG, Mime::JPEG, Mime::GIF, Mime::SVG, Mime::WEBP],
+			MatterForm::Schema => &[Mime::SCHEMA],
+			MatterForm::Wasm => &[Mime::WASM],
+			MatterForm::Formula => &[Mime::FORMULA],
+			MatterForm::Enum => &[Mime::ENUM],
+			MatterForm::Perm => &[Mime::PERM],
+			MatterForm::Sparse => &[Mime::SPARSE],
+			MatterForm::Range => &[Mime::RANGE],
+		}
+	}
+}
+
+/// Whether `mime` is accepted for `form`. Type and subtype compare case-insensitively and
+/// parameters such as `; charset=utf-8` are ignored.
+pub fn is_valid_mime(form: MatterForm, mime: &str) -> bool {
+	let essence = mime.split(';').next().unwrap_or_default().trim();
+	form.accepted_mimes()
+		.iter()
+		.any(|accepted| accepted.as_str().eq_ignore_ascii_case(essence))
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{is_valid_mime, MatterForm, Mime};
+
+	#[test]
+	fn canonical_mime_is_first_accepted() {
+		assert_eq!(MatterForm::Enum.canonical_mime(), Mime::ENUM);
+		assert_eq!(MatterForm::Json.canonical_mime(), Mime::JSON);
+		assert_eq!(MatterForm::Image.canonical_mime(), Mime::PNG);
+		assert_eq!(MatterForm::Json.accepted_mimes(), &[Mime::JSON, Mime::JSONZ]);
+		assert_eq!(MatterForm::Image.accepted_mimes().len(), 5);
+		assert_eq!(Mime::ENUM.as_str(), "application/vnd.every.enum");
+	}
+
+	#[test]
+	fn validates_mimes() {
+		assert!(is_valid_mime(MatterForm::Enum, "application/vnd.every.enum"));
+		assert!(is_valid_mime(MatterForm::Json, "application/vnd.every.jsonz"));
+		assert!(is_valid_mime(MatterForm::Image, "image/webp"));
+		assert!(!is_valid_mime(MatterForm::Perm, "application/vnd.every.enum"));
+		assert!(!is_valid_mime(MatterForm::Wasm, "application/vnd.every.wasm"));
+		assert!(!is_valid_mime(MatterForm::Json, ""));
+
+		assert!(is_valid_mime(MatterForm::Json, "Application/JSON"));
+		assert!(is_valid_mime(MatterForm::Json, "application/json; charset=utf-8"));
+		assert!(is_valid_mime(MatterForm::Image, "image/svg+xml ;charset=UTF-8"));
+		assert!(!is_valid_mime(MatterForm::Json, "application/json+x; charset=utf-8"));
+	}
+}
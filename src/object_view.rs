@@ -0,0 +1,58 @@
+use crate::{short_hex, Arc, Bytes32, Descriptor, Facet, Vec, OID};
+use core::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Everything stored for one object revision, as returned by `StateReader::get_object`.
+/// `tails` and `facets` are `None` when they could not be read.
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ObjectView {
+	pub oid: OID,
+	pub desc: Descriptor,
+	#[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes_vec"))]
+	pub elems: Vec<Bytes32>,
+	pub tails: Option<Vec<Arc>>,
+	pub facets: Option<Vec<Facet>>,
+}
+
+impl fmt::Display for ObjectView {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		writeln!(f, "{} {}", self.oid, self.desc)?;
+		for (i, elem) in self.elems.iter().enumerate() {
+			writeln!(f, "  [{i}] {}", short_hex(elem))?;
+		}
+		match &self.tails {
+			Some(tails) => tails.iter().try_for_each(|arc| writeln!(f, "  {arc}"))?,
+			None => writeln!(f, "  <- (unavailable)")?,
+		}
+		match &self.facets {
+			Some(facets) => facets.iter().try_for_each(|facet| writeln!(f, "  {facet}")),
+			None => writeln!(f, "  <> (unavailable)"),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{Arc, Descriptor, ObjectView, Rev, OID};
+
+	#[test]
+	fn display_lists_parts() {
+		let view = ObjectView {
+			oid: OID { universe: 1, set: 20, id: 3 },
+			desc: Descriptor { rev: Rev(2), kind: 17, ..Default::default() },
+			elems: vec![[0xAB; 32]],
+			tails: Some(vec![Arc { kind: 5, data: 0, rel: 9, set: 21, id: 4 }]),
+			facets: None,
+		};
+		let text = view.to_string();
+		let lines: Vec<&str> = text.lines().collect();
+		assert_eq!(lines.len(), 4);
+		assert!(lines[0].starts_with("1.20.3 {"));
+		assert_eq!(lines[1], "  [0] ababab...ababab");
+		assert_eq!(lines[2], "  <- 9 [0] -- [5] 21.4");
+		assert_eq!(lines[3], "  <> (unavailable)");
+	}
+}
@@ -0,0 +1,127 @@
+use crate::{Constants, OID};
+use core::fmt;
+
+#[cfg(feature = "scale")]
+use codec::{Decode, DecodeWithMemTracking, Encode, MaxEncodedLen};
+#[cfg(feature = "scale")]
+use scale_info::TypeInfo;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+const ANY: u64 = Constants::ID_WILDCARD;
+
+/// A set of objects: each part of an `OID` either fixed or `Constants::ID_WILDCARD`, which
+/// matches anything. Laid out like an `OID`, so rules store it in the same place.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "scale", derive(Encode, Decode, TypeInfo, DecodeWithMemTracking))]
+#[cfg_attr(feature = "scale", derive(MaxEncodedLen))]
+pub struct OidPattern {
+	pub universe: u64,
+	pub set: u64,
+	pub id: u64,
+}
+
+impl OidPattern {
+	pub const ANY: OidPattern = OidPattern { universe: ANY, set: ANY, id: ANY };
+
+	pub fn exact(oid: &OID) -> Self {
+		Self { universe: oid.universe, set: oid.set, id: oid.id }
+	}
+
+	/// Every object of `set` in `universe`.
+	pub fn in_set(universe: u64, set: u64) -> Self {
+		Self { universe, set, id: ANY }
+	}
+
+	pub fn in_universe(universe: u64) -> Self {
+		Self { universe, set: ANY, id: ANY }
+	}
+
+	pub fn matches(&self, oid: &OID) -> bool {
+		part_matches(self.universe, oid.universe) &&
+			part_matches(self.set, oid.set) &&
+			part_matches(self.id, oid.id)
+	}
+
+	/// Whether every object `other` matches is also matched by this pattern.
+	pub fn covers(&self, other: &OidPattern) -> bool {
+		part_matches(self.universe, other.universe) &&
+			part_matches(self.set, other.set) &&
+			part_matches(self.id, other.id)
+	}
+
+	/// The objects both patterns match, `None` when there are none.
+	pub fn intersect(&self, other: &OidPattern) -> Option<OidPattern> {
+		Some(OidPattern {
+			universe: part_intersect(self.universe, other.universe)?,
+			set: part_intersect(self.set, other.set)?,
+			id: part_intersect(self.id, other.id)?,
+		})
+	}
+
+	/// The one object matched, when no part is a wildcard.
+	pub fn as_oid(&self) -> Option<OID> {
+		let parts = [self.universe, self.set, self.id];
+		(!parts.contains(&ANY)).then_some(OID {
+			universe: self.universe,
+			set: self.set,
+			id: self.id,
+		})
+	}
+}
+
+fn part_matches(pattern: u64, value: u64) -> bool {
+	pattern == ANY || pattern == value
+}
+
+fn part_intersect(a: u64, b: u64) -> Option<u64> {
+	match (a, b) {
+		(ANY, b) => Some(b),
+		(a, ANY) => Some(a),
+		(a, b) => (a == b).then_some(a),
+	}
+}
+
+impl From<OID> for OidPattern {
+	fn from(oid: OID) -> Self {
+		Self::exact(&oid)
+	}
+}
+
+/// Like `OID`, with `*` for wildcards: `1.17.*`.
+impl fmt::Display for OidPattern {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let part = |f: &mut fmt::Formatter<'_>, v: u64| match v {
+			ANY => f.write_str("*"),
+			v => write!(f, "{v}"),
+		};
+		part(f, self.universe)?;
+		f.write_str(".")?;
+		part(f, self.set)?;
+		f.write_str(".")?;
+		part(f, self.id)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{OidPattern, OID};
+
+	#[test]
+	fn matches_and_intersects() {
+		let oid = OID { universe: 1, set: 17, id: 42 };
+		let set = OidPattern::in_set(1, 17);
+		assert!(set.matches(&oid));
+		assert!(!set.matches(&OID { set: 18, ..oid }));
+		assert!(OidPattern::ANY.covers(&set));
+		assert!(!set.covers(&OidPattern::in_universe(1)));
+		assert_eq!(set.to_string(), "1.17.*");
+
+		let exact = OidPattern::from(oid);
+		assert_eq!(set.intersect(&OidPattern::in_universe(1)), Some(set));
+		assert_eq!(set.intersect(&exact).and_then(|p| p.as_oid()), Some(oid));
+		assert_eq!(set.intersect(&OidPattern::in_set(1, 18)), None);
+		assert_eq!(set.as_oid(), None);
+	}
+}
@@ -0,0 +1,97 @@
+use crate::{Constants, OID};
+
+/// Objects `start..end` of one set, yielded in id order. `set_oid` is the set's own object, as
+/// `OID::set_oid` returns it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OidRange {
+	pub set_oid: OID,
+	pub start: u64,
+	pub end: u64,
+}
+
+impl OidRange {
+	pub fn new(set_oid: OID, start: u64, end: u64) -> Self {
+		Self { set_oid, start, end }
+	}
+
+	/// `count` new objects from `next_id`, the set's first unused id. `None` when that would
+	/// start below `Constants::ID_MIN` or pass `Constants::ID_MAX`.
+	pub fn mint(set_oid: OID, next_id: u64, count: u64) -> Option<Self> {
+		let end = next_id.checked_add(count)?;
+		if next_id < Constants::ID_MIN || end - 1 > Constants::ID_MAX {
+			return None;
+		}
+		Some(Self::new(set_oid, next_id, end))
+	}
+
+	pub fn oid(&self, id: u64) -> OID {
+		OID { universe: self.set_oid.universe, set: self.set_oid.id, id }
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.start >= self.end
+	}
+
+	pub fn matches(&self, oid: &OID) -> bool {
+		oid.universe == self.set_oid.universe &&
+			oid.set == self.set_oid.id &&
+			(self.start..self.end).contains(&oid.id)
+	}
+
+	/// The next range to mint from once this one is used up.
+	pub fn following(&self, count: u64) -> Option<Self> {
+		Self::mint(self.set_oid, self.end, count)
+	}
+
+	/// Consecutive ranges of at most `size` objects covering this one.
+	pub fn chunks(&self, size: u64) -> impl Iterator<Item = OidRange> + '_ {
+		let size = size.max(1);
+		let starts = (self.start..self.end).step_by(size.min(usize::MAX as u64) as usize);
+		starts.map(move |start| {
+			Self::new(self.set_oid, start, start.saturating_add(size).min(self.end))
+		})
+	}
+}
+
+impl Iterator for OidRange {
+	type Item = OID;
+
+	fn next(&mut self) -> Option<OID> {
+		if self.is_empty() {
+			return None;
+		}
+		self.start += 1;
+		Some(self.oid(self.start - 1))
+	}
+
+	fn size_hint(&self) -> (usize, Option<usize>) {
+		let len = self.end.saturating_sub(self.start);
+		(usize::try_from(len).unwrap_or(usize::MAX), usize::try_from(len).ok())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{Constants, OidRange, OID};
+
+	#[test]
+	fn mints_and_iterates() {
+		let set = OID::of_set(1, 17);
+		let range = OidRange::mint(set, 1, 5).unwrap();
+		let ids: Vec<u64> = range.clone().map(|oid| oid.id).collect();
+		assert_eq!(ids, vec![1, 2, 3, 4, 5]);
+		assert_eq!(range.clone().next(), Some(OID { universe: 1, set: 17, id: 1 }));
+		assert_eq!(range.following(2), Some(OidRange::new(set, 6, 8)));
+		let chunks: Vec<_> = range.chunks(2).map(|c| (c.start, c.end)).collect();
+		assert_eq!(chunks, vec![(1, 3), (3, 5), (5, 6)]);
+		let whole: Vec<_> = range.chunks(u64::MAX).map(|c| (c.start, c.end)).collect();
+		assert_eq!(whole, vec![(1, 6)]);
+
+		assert_eq!(OidRange::mint(set, 0, 1), None);
+		assert!(OidRange::mint(set, Constants::ID_MAX, 1).is_some());
+		assert_eq!(OidRange::mint(set, Constants::ID_MAX, 2), None);
+		let last = OID { universe: 1, set: 17, id: Constants::ID_MAX };
+		assert_eq!(last.next_id(), None);
+		assert_eq!(OID { id: 7, ..last }.next_id().map(|oid| oid.id), Some(8));
+	}
+}
@@ -0,0 +1,124 @@
+use crate::Bytes32;
+use core::fmt;
+
+#[cfg(feature = "scale")]
+use codec::{Decode, DecodeWithMemTracking, Encode, MaxEncodedLen};
+#[cfg(feature = "scale")]
+use scale_info::TypeInfo;
+#[cfg(feature = "schemars")]
+use schemars::JsonSchema;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "ss58")]
+use sp_core::crypto::{AccountId32, Ss58Codec};
+
+/// Owner of an object: a 32-byte account id, or a 20-byte address left-padded with zeros.
+/// All zeros means unowned.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "scale", derive(Encode, Decode, TypeInfo, DecodeWithMemTracking))]
+#[cfg_attr(feature = "scale", derive(MaxEncodedLen))]
+pub struct Owner(
+	#[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes"))]
+	#[cfg_attr(feature = "schemars", schemars(schema_with = "crate::serde_hex::bytes::schema"))]
+	pub Bytes32,
+);
+
+impl Owner {
+	pub const NONE: Owner = Owner([0; 32]);
+
+	#[inline]
+	pub fn is_zero(&self) -> bool {
+		self.0 == [0; 32]
+	}
+
+	#[inline]
+	pub fn as_bytes(&self) -> &Bytes32 {
+		&self.0
+	}
+
+	/// The 20-byte address, if the leading 12 bytes are zero padding.
+	pub fn to_h160(&self) -> Option<[u8; 20]> {
+		if self.0[..12] != [0; 12] {
+			return None;
+		}
+		let mut addr = [0u8; 20];
+		addr.copy_from_slice(&self.0[12..]);
+		Some(addr)
+	}
+}
+
+impl From<Bytes32> for Owner {
+	fn from(bytes: Bytes32) -> Self {
+		Self(bytes)
+	}
+}
+
+impl From<Owner> for Bytes32 {
+	fn from(owner: Owner) -> Self {
+		owner.0
+	}
+}
+
+impl From<[u8; 20]> for Owner {
+	fn from(addr: [u8; 20]) -> Self {
+		let mut bytes = [0u8; 32];
+		bytes[12..].copy_from_slice(&addr);
+		Self(bytes)
+	}
+}
+
+#[cfg(feature = "ss58")]
+impl From<AccountId32> for Owner {
+	fn from(account: AccountId32) -> Self {
+		Self(account.into())
+	}
+}
+
+#[cfg(feature = "ss58")]
+impl From<Owner> for AccountId32 {
+	fn from(owner: Owner) -> Self {
+		AccountId32::new(owner.0)
+	}
+}
+
+#[cfg(feature = "ss58")]
+impl From<sp_core::H160> for Owner {
+	fn from(addr: sp_core::H160) -> Self {
+		addr.to_fixed_bytes().into()
+	}
+}
+
+/// SS58 with the generic Substrate prefix when the `ss58` feature is on, `0x`-prefixed hex
+/// otherwise.
+impl fmt::Display for Owner {
+	#[cfg(feature = "ss58")]
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(&AccountId32::new(self.0).to_ss58check())
+	}
+
+	#[cfg(not(feature = "ss58"))]
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}", crate::Hex(&self.0))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::Owner;
+
+	#[test]
+	fn pads_and_displays() {
+		assert!(Owner::default().is_zero());
+		let owner = Owner::from([0xAB; 20]);
+		assert!(!owner.is_zero());
+		assert_eq!(owner.0[..12], [0; 12]);
+		assert_eq!(owner.to_h160(), Some([0xAB; 20]));
+		assert_eq!(Owner([1; 32]).to_h160(), None);
+		#[cfg(not(feature = "ss58"))]
+		assert_eq!(owner.to_string(), format!("0x{}{}", "00".repeat(12), "ab".repeat(20)));
+		#[cfg(feature = "ss58")]
+		assert_eq!(Owner::NONE.to_string(), "5C4hrfjw9DjXZTzV3MwzrrAr9P1MJhSrvWGWqi1eSuyUpnhM");
+	}
+}
@@ -13,10 +13,12 @@ pub struct PermColumn {
 
 #[derive(Debug, Clone)]
 pub struct PermHeader {
+	pub version: u8,                // 1 = u16 heights, 2 = u32 heights
 	pub aux: Vec<u8>,               // aux types
+	pub enum_cols: u16,             // enum-column bitmap, bit (15 - i) marks column i
 	pub cols: Vec<PermColumn>,      // all columns, natural order
 	pub perm_cols: Vec<PermColumn>, // only permutation columns, order reserved
-	pub rows: usize,                // product of perm column heights
+	pub rows: u64,                  // product of perm column heights (or enum height if none)
 	pub sum_heights: usize,         // sum of all column heights
 }
 
@@ -25,6 +27,12 @@ impl PermHeader {
 	pub const CELL_SIZE: usize = 32;
 	pub const HEADER_SIZE_MIN: usize = 32;
 	pub const HEADER_SIZE_MAX: usize = 64;
+	pub const HEADER_SIZE_V2_MAX: usize = 96;
+
+	#[inline]
+	pub fn version(&self) -> u8 {
+		self.version
+	}
 
 	#[inline]
 	pub fn aux(&self) -> usize {
@@ -37,10 +45,20 @@ impl PermHeader {
 	}
 
 	#[inline]
-	pub fn rows(&self) -> usize {
+	pub fn rows(&self) -> u64 {
 		self.rows
 	}
 
+	#[inline]
+	pub fn enum_cols(&self) -> u16 {
+		self.enum_cols
+	}
+
+	#[inline]
+	pub fn is_enum_col(&self, col: usize) -> bool {
+		self.cols.get(col).is_some_and(|c| !c.perm_col)
+	}
+
 	#[inline]
 	pub fn col_info(&self, col: usize) -> Option<&PermColumn> {
 		self.cols.get(col)
@@ -48,10 +66,15 @@ impl PermHeader {
 
 	#[inline]
 	pub fn header_end(&self) -> usize {
-		if self.cols.is_empty() {
-			Self::HEADER_SIZE_MIN
-		} else {
-			Self::HEADER_SIZE_MAX
+		Self::header_size(self.version, self.cols.len())
+	}
+
+	#[inline]
+	fn header_size(version: u8, cols: usize) -> usize {
+		match (cols, version) {
+			(0, _) => Self::HEADER_SIZE_MIN,
+			(_, 1) => Self::HEADER_SIZE_MAX,
+			_ => Self::HEADER_SIZE_V2_MAX,
 		}
 	}
 
@@ -67,49 +90,126 @@ impl PermHeader {
 
 	#[inline]
 	pub fn col_begin(&self) -> usize {
-		self.aux_begin()
+		self.aux_end()
 	}
 
 	#[inline]
 	pub fn col_end(&self) -> usize {
-		self.aux_begin() + self.sum_heights * Self::CELL_SIZE
+		self.col_begin() + self.sum_heights * Self::CELL_SIZE
 	}
 
-	pub fn row_to_indexes(&self, row: usize) -> Result<Vec<usize>, PermMatterError> {
+	// Perm columns form a mixed-radix number with the last perm column varying fastest; enum
+	// columns are indexed by the row itself.
+	pub fn row_indices(&self, row: u64) -> Result<Vec<usize>, PermMatterError> {
 		if row >= self.rows {
-			return Err(PermMatterError::Overflow);
+			return Err(PermMatterError::OobRow { row });
 		}
-		let mut idxs = Vec::with_capacity(self.cols.len());
+		let row_idx = usize::try_from(row).map_err(|_| PermMatterError::Overflow)?;
+		let mut idxs = Vec::new();
+		idxs.try_reserve_exact(self.cols.len()).map_err(|_| PermMatterError::Overflow)?;
+		idxs.resize(self.cols.len(), 0);
 		let mut r = row;
 		for (c, ci) in self.cols.iter().enumerate().rev() {
 			if ci.perm_col {
-				let h = ci.col_height;
-				idxs[c] = r % h;
+				let h = ci.col_height as u64;
+				idxs[c] = (r % h) as usize;
 				r /= h;
 			} else {
-				idxs[c] = row;
+				idxs[c] = row_idx;
 			}
 		}
 		Ok(idxs)
 	}
 
-	pub fn row_to_index(&self, row: usize, col: usize) -> Result<usize, PermMatterError> {
-		if row >= self.rows || col >= self.cols.len() {
-			return Err(PermMatterError::Overflow);
+	pub fn col_index(&self, row: u64, col: usize) -> Result<usize, PermMatterError> {
+		if row >= self.rows {
+			return Err(PermMatterError::OobRow { row });
+		}
+		let ci = self.cols.get(col).ok_or(PermMatterError::OobCol { col })?;
+		if !ci.perm_col {
+			return usize::try_from(row).map_err(|_| PermMatterError::Overflow);
 		}
-
 		let mut r = row;
-		let mut idx = 0;
-		for (c, ci) in self.cols.iter().enumerate().rev() {
-			if ci.perm_col {
-				let h = ci.col_height;
-				idx = r % h;
-				r /= h;
-			} else {
-				idx = row;
+		for cj in self.cols[col + 1..].iter().filter(|c| c.perm_col) {
+			r /= cj.col_height as u64;
+		}
+		Ok((r % ci.col_height as u64) as usize)
+	}
+
+	/// Inverse of `row_indices`: maps one index per column back to its row. Enum column entries
+	/// must equal the resulting row.
+	pub fn rank(&self, indices: &[usize]) -> Result<u64, PermMatterError> {
+		if indices.len() != self.cols.len() {
+			return Err(PermMatterError::OobCol { col: indices.len() });
+		}
+		let mut row = 0u64;
+		for ci in self.perm_cols.iter() {
+			let col = ci.col_idx as usize;
+			let index = indices[col];
+			if index >= ci.col_height {
+				return Err(PermMatterError::OobCell { col, index });
 			}
+			row = row
+				.checked_mul(ci.col_height as u64)
+				.and_then(|r| r.checked_add(index as u64))
+				.ok_or(PermMatterError::Overflow)?;
+		}
+		if self.perm_cols.is_empty() {
+			row = indices.first().map_or(0, |&i| i as u64);
+		}
+		if row >= self.rows {
+			return Err(PermMatterError::OobRow { row });
 		}
-		Ok(idx)
+		if let Some(ci) = self
+			.cols
+			.iter()
+			.find(|c| !c.perm_col && indices[c.col_idx as usize] as u64 != row)
+		{
+			return Err(PermMatterError::EnumColIndex { col: ci.col_idx as usize });
+		}
+		Ok(row)
+	}
+
+	#[inline]
+	pub fn unrank(&self, row: u64) -> Result<Vec<usize>, PermMatterError> {
+		self.row_indices(row)
+	}
+
+	/// Header bytes as laid out in a blob: 32 bytes with no columns, else 64 (v1) or 96 (v2).
+	pub fn encode(&self) -> Vec<u8> {
+		let mut out = Vec::with_capacity(self.header_end());
+		out.extend_from_slice(&Self::MAGIC);
+		out.push(self.version << 4 | self.aux.len() as u8);
+		out.push(self.cols.len() as u8);
+		out.extend_from_slice(&self.enum_cols.to_le_bytes());
+		let mut aux_types = [0u8; 8];
+		aux_types[..self.aux.len()].copy_from_slice(&self.aux);
+		out.extend_from_slice(&aux_types);
+		let mut col_types = [0u8; 16];
+		for c in &self.cols {
+			col_types[c.col_idx as usize] = c.col_type;
+		}
+		out.extend_from_slice(&col_types);
+		if !self.cols.is_empty() {
+			for i in 0..16 {
+				let h = self.cols.get(i).map_or(0, |c| c.col_height);
+				match self.version {
+					1 => out.extend_from_slice(&(h as u16).to_le_bytes()),
+					_ => out.extend_from_slice(&(h as u32).to_le_bytes()),
+				}
+			}
+		}
+		out
+	}
+
+	#[deprecated(note = "use `row_indices`")]
+	pub fn row_to_indexes(&self, row: usize) -> Result<Vec<usize>, PermMatterError> {
+		self.row_indices(row as u64)
+	}
+
+	#[deprecated(note = "use `col_index`")]
+	pub fn row_to_index(&self, row: usize, col: usize) -> Result<usize, PermMatterError> {
+		self.col_index(row as u64, col)
 	}
 
 	pub fn from(blob: &[u8]) -> Result<Self, PermMatterError> {
@@ -125,7 +225,7 @@ impl PermHeader {
 		let ver_aux = blob[4];
 		let ver = ver_aux >> 4;
 		let aux_cnt = ver_aux & 0x0F;
-		if ver != 1 {
+		if ver != 1 && ver != 2 {
 			return Err(PermMatterError::BadVersion(ver));
 		}
 		if aux_cnt > 8 {
@@ -138,11 +238,15 @@ impl PermHeader {
 		}
 
 		let enum_cols = u16::from_le_bytes(blob[6..8].try_into().unwrap());
+		let declared = if cols_cnt == 0 { 0 } else { !0u16 << (16 - cols_cnt as u32) };
+		if enum_cols & !declared != 0 {
+			return Err(PermMatterError::BadEnumBitmap { bitmap: enum_cols, cols: cols_cnt });
+		}
 
 		let aux_types: [u8; 8] = blob[8..16].try_into().unwrap();
 		{
 			let (active, pad) = aux_types.split_at(aux_cnt as usize);
-			if active.iter().any(|&t| t == 0) || pad.iter().any(|&t| t != 0) {
+			if active.contains(&0) || pad.iter().any(|&t| t != 0) {
 				return Err(PermMatterError::BadAuxTypes);
 			}
 		}
@@ -150,24 +254,28 @@ impl PermHeader {
 		let col_types: [u8; 16] = blob[16..32].try_into().unwrap();
 		{
 			let (active, pad) = col_types.split_at(cols_cnt as usize);
-			if active.iter().any(|&t| t == 0) || pad.iter().any(|&t| t != 0) {
+			if active.contains(&0) || pad.iter().any(|&t| t != 0) {
 				return Err(PermMatterError::BadColTypes);
 			}
 		}
 
+		// Heights block: 16 x u16 (v1) or 16 x u32 (v2), present only if cols > 0.
 		let mut col_heights = Vec::new();
 		if cols_cnt > 0 {
-			if blob.len() < Self::HEADER_SIZE_MAX {
-				return Err(PermMatterError::BadHeader);
+			let need = Self::header_size(ver, cols_cnt as usize);
+			if blob.len() < need {
+				return Err(PermMatterError::BadHeightsBlock { need, got: blob.len() });
 			}
 			let cols_cnt = cols_cnt as usize;
-			for i in 0..cols_cnt {
-				let h = u16::from_le_bytes(blob[32 + i * 2..34 + i * 2].try_into().unwrap());
-				col_heights.push(h);
-			}
-			for i in cols_cnt..16 {
-				let h = u16::from_le_bytes(blob[32 + i * 2..34 + i * 2].try_into().unwrap());
-				if h != 0 {
+			for i in 0..16 {
+				let h = match ver {
+					1 =>
+						u16::from_le_bytes(blob[32 + i * 2..34 + i * 2].try_into().unwrap()) as u32,
+					_ => u32::from_le_bytes(blob[32 + i * 4..36 + i * 4].try_into().unwrap()),
+				};
+				if i < cols_cnt {
+					col_heights.push(h);
+				} else if h != 0 {
 					return Err(PermMatterError::BadColumnHeight { col: i });
 				}
 			}
@@ -177,7 +285,7 @@ impl PermHeader {
 
 		let mut col_offset = 0usize;
 		let mut perm_idx = 0u8;
-		let mut rows = 1usize;
+		let mut perm_rows = 1u64;
 		let mut sum_heights = 0usize;
 		let mut cols = Vec::new();
 		for i in 0..(cols_cnt as usize) {
@@ -192,27 +300,67 @@ impl PermHeader {
 				col_height,
 			};
 			perm_idx += if perm_col { 1 } else { 0 };
-			col_offset += col_height;
-			rows.checked_mul(col_height).ok_or(PermMatterError::Overflow)?;
-			sum_heights.checked_add(col_height).ok_or(PermMatterError::Overflow)?;
+			col_offset = col_offset.checked_add(col_height).ok_or(PermMatterError::Overflow)?;
+			if perm_col {
+				perm_rows =
+					perm_rows.checked_mul(col_height as u64).ok_or(PermMatterError::Overflow)?;
+			}
+			sum_heights = sum_heights.checked_add(col_height).ok_or(PermMatterError::Overflow)?;
 			cols.push(col);
 		}
-		let perm_cols: Vec<PermColumn> =
-			cols.iter().filter(|c| c.perm_col).map(|c| c.clone()).collect();
-		Ok(Self { aux, cols, perm_cols, rows, sum_heights })
+		let perm_cols: Vec<PermColumn> = cols.iter().filter(|c| c.perm_col).cloned().collect();
+
+		// Enum columns hold one cell per row, so they must all be exactly `rows` tall.
+		let rows = match (perm_cols.is_empty(), cols.iter().find(|c| !c.perm_col)) {
+			(false, _) => perm_rows,
+			(true, Some(first_enum)) => first_enum.col_height as u64,
+			(true, None) => 0,
+		};
+		if let Some(bad) = cols.iter().find(|c| !c.perm_col && c.col_height as u64 != rows) {
+			return Err(PermMatterError::BadColumnHeight { col: bad.col_idx as usize });
+		}
+		Ok(Self { version: ver, aux, enum_cols, cols, perm_cols, rows, sum_heights })
 	}
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ParseMode {
+	/// Blob length must match the declared layout exactly.
+	Strict,
+	/// Trailing bytes after the declared layout are ignored.
+	#[default]
+	Lenient,
+}
+
 #[derive(Debug, Clone)]
 pub struct PermMatter {
-	pub header: PermHeader, // size = 32 or 64
+	pub header: PermHeader, // size = 32, 64 (v1) or 96 (v2)
 	pub aux_data: Vec<u8>,  // len = aux * 32
 	pub col_data: Vec<u8>,  // len = sum_heights * 32 (columns laid out back-to-back)
 }
 
 impl PermMatter {
 	pub fn from(blob: &[u8]) -> Result<Self, PermMatterError> {
+		Self::from_with(blob, ParseMode::Lenient)
+	}
+
+	pub fn from_strict(blob: &[u8]) -> Result<Self, PermMatterError> {
+		Self::from_with(blob, ParseMode::Strict)
+	}
+
+	pub fn from_with(blob: &[u8], mode: ParseMode) -> Result<Self, PermMatterError> {
 		let header = PermHeader::from(blob)?;
+		if mode == ParseMode::Strict {
+			let expect = header
+				.sum_heights
+				.checked_add(header.aux())
+				.and_then(|cells| cells.checked_mul(PermHeader::CELL_SIZE))
+				.and_then(|body| body.checked_add(header.header_end()))
+				.ok_or(PermMatterError::Overflow)?;
+			if blob.len() != expect {
+				return Err(PermMatterError::BadBody { expect, got: blob.len() });
+			}
+		}
 		let aux_data = blob
 			.get(header.aux_begin()..header.aux_end())
 			.ok_or(PermMatterError::Overflow)?
@@ -224,6 +372,13 @@ impl PermMatter {
 		Ok(Self { header, aux_data, col_data })
 	}
 
+	pub fn to_blob(&self) -> Vec<u8> {
+		let mut blob = self.header.encode();
+		blob.extend_from_slice(&self.aux_data);
+		blob.extend_from_slice(&self.col_data);
+		blob
+	}
+
 	#[inline]
 	pub fn aux(&self) -> usize {
 		self.header.aux.len()
@@ -235,10 +390,20 @@ impl PermMatter {
 	}
 
 	#[inline]
-	pub fn rows(&self) -> usize {
+	pub fn rows(&self) -> u64 {
 		self.header.rows
 	}
 
+	#[inline]
+	pub fn rank(&self, indices: &[usize]) -> Result<u64, PermMatterError> {
+		self.header.rank(indices)
+	}
+
+	#[inline]
+	pub fn unrank(&self, row: u64) -> Result<Vec<usize>, PermMatterError> {
+		self.header.unrank(row)
+	}
+
 	pub fn aux_at(&self, index: usize) -> Result<&[u8; 32], PermMatterError> {
 		if index >= self.header.aux() {
 			return Err(PermMatterError::OobAux { index });
@@ -254,36 +419,35 @@ impl PermMatter {
 		Ok(s)
 	}
 
-	pub fn cell_at(&self, row: usize, col: usize) -> Result<&[u8; 32], PermMatterError> {
-		let index = self.header.row_to_index(row, col)?;
-		let offset = (self.header.cols[col].col_offset + index) * PermHeader::CELL_SIZE;
-		let end = offset + PermHeader::CELL_SIZE;
-		let s: &[u8; 32] = self
-			.col_data
-			.get(offset..end)
-			.ok_or(PermMatterError::OobCell { col, index })?
-			.try_into()
-			.unwrap();
-		Ok(s)
+	pub fn cell_at(&self, row: u64, col: usize) -> Result<&[u8; 32], PermMatterError> {
+		let index = self.header.col_index(row, col)?;
+		self.col_cell(col, index)
 	}
 
-	pub fn row_at(&self, row: usize) -> Result<Vec<&[u8; 32]>, PermMatterError> {
-		let idxs = self.header.row_to_indexes(row)?;
+	pub fn row_at(&self, row: u64) -> Result<Vec<&[u8; 32]>, PermMatterError> {
+		let idxs = self.header.row_indices(row)?;
 		let mut out = Vec::with_capacity(idxs.len());
 		for (col, index) in idxs.into_iter().enumerate() {
-			let ci = self.header.col_info(col).unwrap();
-			let offset = (ci.col_offset + index) * PermHeader::CELL_SIZE;
-			let end = offset + PermHeader::CELL_SIZE;
-			let cell: &[u8; 32] = self
-				.col_data
-				.get(offset..end)
-				.ok_or(PermMatterError::OobCell { col, index })?
-				.try_into()
-				.unwrap();
-			out.push(cell);
+			out.push(self.col_cell(col, index)?);
 		}
 		Ok(out)
 	}
+
+	fn col_cell(&self, col: usize, index: usize) -> Result<&[u8; 32], PermMatterError> {
+		let offset = self.header.cols[col]
+			.col_offset
+			.checked_add(index)
+			.and_then(|cell| cell.checked_mul(PermHeader::CELL_SIZE))
+			.ok_or(PermMatterError::Overflow)?;
+		let s: &[u8; 32] = self
+			.col_data
+			.get(offset..)
+			.and_then(|rest| rest.get(..PermHeader::CELL_SIZE))
+			.ok_or(PermMatterError::OobCell { col, index })?
+			.try_into()
+			.unwrap();
+		Ok(s)
+	}
 }
 
 #[derive(Clone, Copy, Debug, Error, PartialEq, Eq)]
@@ -293,7 +457,7 @@ pub enum PermMatterError {
 	BadHeader,
 	#[error("bad magic: expected 'PERM' (50 45 52 4D), got {0:02X?}")]
 	BadMagic([u8; 4]),
-	#[error("unsupported version {0} (expected 1)")]
+	#[error("unsupported version {0} (expected 1 or 2)")]
 	BadVersion(u8),
 
 	// Field ranges
@@ -327,8 +491,140 @@ pub enum PermMatterError {
 	OobAux { index: usize },
 	#[error("cell out of bounds at (col={col}, index={index})")]
 	OobCell { col: usize, index: usize },
+	#[error("row out of bounds: {row}")]
+	OobRow { row: u64 },
+	#[error("column out of bounds: {col}")]
+	OobCol { col: usize },
+	#[error("enum column {col} index does not match the row")]
+	EnumColIndex { col: usize },
 
 	// Arithmetic / indexing
 	#[error("arithmetic overflow")]
 	Overflow,
 }
+
+#[cfg(test)]
+mod tests {
+	use crate::{PermHeader, PermMatter, PermMatterError, Vec};
+
+	// Builds a PERM blob whose cells hold (col, index) so lookups can be checked directly.
+	fn perm_blob_v(ver: u8, heights: &[u32], enum_cols: u16, cells: bool) -> Vec<u8> {
+		let mut blob = Vec::new();
+		blob.extend_from_slice(b"PERM");
+		blob.push(ver << 4);
+		blob.push(heights.len() as u8);
+		blob.extend_from_slice(&enum_cols.to_le_bytes());
+		blob.extend_from_slice(&[0u8; 8]);
+		let mut col_types = [0u8; 16];
+		col_types[..heights.len()].fill(1);
+		blob.extend_from_slice(&col_types);
+		for i in 0..16 {
+			let h = heights.get(i).copied().unwrap_or(0);
+			match ver {
+				1 => blob.extend_from_slice(&(h as u16).to_le_bytes()),
+				_ => blob.extend_from_slice(&h.to_le_bytes()),
+			}
+		}
+		for (c, h) in heights.iter().enumerate().filter(|_| cells) {
+			for i in 0..*h {
+				let mut cell = [0u8; 32];
+				cell[0] = c as u8;
+				cell[1] = i as u8;
+				blob.extend_from_slice(&cell);
+			}
+		}
+		blob
+	}
+
+	fn perm_blob(heights: &[u32], enum_cols: u16) -> Vec<u8> {
+		perm_blob_v(1, heights, enum_cols, true)
+	}
+
+	#[test]
+	fn mixed_layout_rows() {
+		// col0 perm(2), col1 enum(6), col2 perm(3)
+		let blob = perm_blob(&[2, 6, 3], 0b0100_0000_0000_0000);
+		let m = PermMatter::from_strict(&blob).unwrap();
+		assert_eq!(m.rows(), 6);
+		assert_eq!(m.header.enum_cols(), 0b0100_0000_0000_0000);
+		assert!(m.header.is_enum_col(1));
+		for row in 0..6u64 {
+			let r = row as usize;
+			let expect = [r / 3, r, r % 3];
+			assert_eq!(m.header.row_indices(row).unwrap(), expect.to_vec());
+			let cells = m.row_at(row).unwrap();
+			for (col, idx) in expect.iter().enumerate() {
+				assert_eq!(m.header.col_index(row, col).unwrap(), *idx);
+				assert_eq!(cells[col][..2], [col as u8, *idx as u8]);
+			}
+		}
+		assert_eq!(m.row_at(6).unwrap_err(), PermMatterError::OobRow { row: 6 });
+		assert_eq!(m.header.col_index(0, 3).unwrap_err(), PermMatterError::OobCol { col: 3 });
+	}
+
+	#[test]
+	fn enum_only_layout() {
+		let blob = perm_blob(&[4, 4], 0b1100_0000_0000_0000);
+		let m = PermMatter::from_strict(&blob).unwrap();
+		assert_eq!(m.rows(), 4);
+		assert_eq!(m.cell_at(3, 1).unwrap()[..2], [1, 3]);
+	}
+
+	#[test]
+	fn rejects_bad_enum_bitmap() {
+		let blob = perm_blob(&[2, 3], 0b0010_0000_0000_0000);
+		assert_eq!(
+			PermHeader::from(&blob).unwrap_err(),
+			PermMatterError::BadEnumBitmap { bitmap: 0b0010_0000_0000_0000, cols: 2 }
+		);
+	}
+
+	#[test]
+	fn rejects_enum_height_mismatch() {
+		let blob = perm_blob(&[2, 5, 3], 0b0100_0000_0000_0000);
+		assert_eq!(
+			PermHeader::from(&blob).unwrap_err(),
+			PermMatterError::BadColumnHeight { col: 1 }
+		);
+	}
+
+	#[test]
+	fn strict_rejects_trailing_bytes() {
+		let mut blob = perm_blob(&[2, 3], 0);
+		blob.push(0);
+		assert!(PermMatter::from(&blob).is_ok());
+		assert!(matches!(PermMatter::from_strict(&blob), Err(PermMatterError::BadBody { .. })));
+	}
+
+	#[test]
+	fn v2_header_sizes() {
+		let blob = perm_blob_v(2, &[2, 3], 0, true);
+		let m = PermMatter::from_strict(&blob).unwrap();
+		assert_eq!(m.header.version(), 2);
+		assert_eq!(m.header.header_end(), PermHeader::HEADER_SIZE_V2_MAX);
+		assert_eq!(m.rows(), 6);
+		assert_eq!(m.cell_at(5, 1).unwrap()[..2], [1, 2]);
+	}
+
+	#[test]
+	fn v2_large_row_counts() {
+		let blob = perm_blob_v(2, &[100_000, 100_000, 100_000], 0, false);
+		let h = PermHeader::from(&blob).unwrap();
+		assert_eq!(h.rows(), 1_000_000_000_000_000);
+		assert_eq!(h.row_indices(h.rows() - 1).unwrap(), [99_999, 99_999, 99_999].to_vec());
+
+		let blob = perm_blob_v(2, &[100_000, 100_000, 100_000, 100_000], 0, false);
+		assert_eq!(PermHeader::from(&blob).unwrap_err(), PermMatterError::Overflow);
+	}
+
+	#[test]
+	fn rank_unrank_roundtrip() {
+		let blob = perm_blob(&[2, 6, 3], 0b0100_0000_0000_0000);
+		let m = PermMatter::from_strict(&blob).unwrap();
+		for row in 0..m.rows() {
+			assert_eq!(m.rank(&m.unrank(row).unwrap()).unwrap(), row);
+		}
+		assert_eq!(m.rank(&[1, 0, 2]).unwrap_err(), PermMatterError::EnumColIndex { col: 1 });
+		assert_eq!(m.rank(&[2, 0, 0]).unwrap_err(), PermMatterError::OobCell { col: 0, index: 2 });
+	}
+}
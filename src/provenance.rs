@@ -0,0 +1,91 @@
+use crate::{Bytes32, ElementOrigin, PickFrom, Rev, Vec, OID};
+
+#[cfg(feature = "scale")]
+use codec::{Decode, DecodeWithMemTracking, Encode};
+#[cfg(feature = "scale")]
+use scale_info::TypeInfo;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "scale", derive(Encode, Decode, TypeInfo, DecodeWithMemTracking))]
+pub struct ProvenanceNode {
+	pub rev: Rev,
+	pub index: u8,
+	#[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes"))]
+	pub value: Bytes32,
+	/// `None` if the element was carried over unchanged from the previous revision.
+	pub origin: Option<ElementOrigin>,
+}
+
+/// Per-revision element origins of one object, as produced by `ElementPicker::resolve_traced`
+/// and `ElementPicker::patch_origins`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "scale", derive(Encode, Decode, TypeInfo, DecodeWithMemTracking))]
+pub struct ProvenanceGraph {
+	pub oid: OID,
+	pub nodes: Vec<ProvenanceNode>,
+}
+
+impl ProvenanceGraph {
+	pub fn new(oid: OID) -> Self {
+		Self { oid, nodes: Vec::new() }
+	}
+
+	/// Adds the elements of revision `rev`, replacing any earlier record for it.
+	pub fn record(&mut self, rev: Rev, elems: &[Bytes32], origins: &[Option<ElementOrigin>]) {
+		self.nodes.retain(|n| n.rev != rev);
+		for (i, value) in elems.iter().enumerate() {
+			let origin = origins.get(i).copied().flatten();
+			self.nodes.push(ProvenanceNode { rev, index: i as u8, value: *value, origin });
+		}
+		self.nodes.sort_by_key(|n| (n.rev, n.index));
+	}
+
+	pub fn node(&self, rev: Rev, index: u8) -> Option<&ProvenanceNode> {
+		self.nodes
+			.binary_search_by_key(&(rev, index), |n| (n.rev, n.index))
+			.ok()
+			.map(|i| &self.nodes[i])
+	}
+
+	/// Nodes an element was derived through, newest first. Elements carried over or picked from
+	/// the object's own previous revision are followed back until an outside source is reached
+	/// or the history runs out.
+	pub fn lineage(&self, rev: Rev, index: u8) -> Vec<&ProvenanceNode> {
+		let mut out = Vec::new();
+		let mut at = self.node(rev, index);
+		while let Some(node) = at {
+			out.push(node);
+			let prev_index = match node.origin {
+				None => node.index,
+				Some(ElementOrigin { src: PickFrom::ObjectData, col, .. }) => col,
+				Some(_) => break,
+			};
+			at = node.rev.prev().and_then(|prev| self.node(prev, prev_index));
+		}
+		out
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{ElementOrigin, PickFrom, ProvenanceGraph, Rev, OID};
+
+	#[test]
+	fn lineage_follows_object_data() {
+		let mut g = ProvenanceGraph::new(OID { universe: 1, set: 2, id: 3 });
+		let coll = ElementOrigin { src: PickFrom::HereCollection, row: 2, col: 1 };
+		let prev = |col| ElementOrigin { src: PickFrom::ObjectData, row: 0, col };
+		g.record(Rev(1), &[[1; 32], [2; 32]], &[Some(coll), Some(coll)]);
+		g.record(Rev(2), &[[2; 32], [3; 32]], &[Some(prev(1)), None]);
+		g.record(Rev(3), &[[2; 32], [3; 32]], &[None, None]);
+
+		let revs: Vec<_> = g.lineage(Rev(3), 0).iter().map(|n| (n.rev, n.index)).collect();
+		assert_eq!(revs, vec![(Rev(3), 0), (Rev(2), 0), (Rev(1), 1)]);
+		assert_eq!(g.lineage(Rev(3), 0).last().unwrap().origin, Some(coll));
+		assert_eq!(g.lineage(Rev(3), 1).len(), 3);
+	}
+}
@@ -0,0 +1,121 @@
+#![cfg(feature = "storage")]
+
+use crate::{
+	reader::{StateError, StateProvider},
+	state::{ObjectKey, OidRev, OidRevSel},
+	storage_key::object_storage_key,
+	writer::WriteOp,
+	Rev, StateReader, Vec, OID,
+};
+
+/// What pruning one object's history removes.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PruneReport {
+	/// Revisions removed entirely.
+	pub revisions: Vec<Rev>,
+	/// Revisions whose snapshot stays because the next, kept revision reads it through
+	/// `PickFrom::ObjectData`; their other entries are removed.
+	pub snapshot_only: Vec<Rev>,
+	/// Stored entries to remove; applying the removals releases the matters they name.
+	pub keys: Vec<ObjectKey>,
+}
+
+impl PruneReport {
+	pub fn is_empty(&self) -> bool {
+		self.keys.is_empty()
+	}
+
+	/// The removals as one write batch.
+	pub fn into_ops(self) -> Vec<WriteOp> {
+		self.keys.into_iter().map(WriteOp::RemoveObject).collect()
+	}
+}
+
+/// Finds the history of `oid` that can be deleted while keeping the newest `keep_last_n`
+/// revisions (at least the current one) and every revision divisible by `keep_every_k` (none
+/// when 0). Revisions are found with `StateProvider::iter_revisions`, so providers that cannot
+/// enumerate keys report nothing.
+pub fn prunable_revisions<P: StateProvider>(
+	state: &P,
+	oid: &OID,
+	keep_last_n: u32,
+	keep_every_k: u32,
+) -> Result<PruneReport, StateError> {
+	let mut revs: Vec<u32> = state.iter_revisions(oid).map(Rev::get).collect();
+	revs.sort_unstable();
+	let mut report = PruneReport::default();
+	let Some(&latest) = revs.last() else {
+		return Ok(report);
+	};
+	let keep = |rev: u32| {
+		latest - rev < keep_last_n.max(1) || (keep_every_k != 0 && rev.is_multiple_of(keep_every_k))
+	};
+
+	for &r in revs.iter().filter(|&&r| !keep(r)) {
+		let rev = Rev(r);
+		let read_by_next = revs.binary_search(&(r + 1)).is_ok() && keep(r + 1);
+		if read_by_next {
+			report.snapshot_only.push(rev);
+		} else {
+			report.revisions.push(rev);
+			report.keys.push(ObjectKey::Snapshot(OidRev::new(oid, rev)));
+		}
+		for key in
+			[ObjectKey::Tails(OidRev::new(oid, rev)), ObjectKey::Heads(OidRev::new(oid, rev))]
+		{
+			if state._get(&object_storage_key(key.clone())).is_some() {
+				report.keys.push(key);
+			}
+		}
+		let Ok(facets) = StateReader::<StateError>::get_facets(state, oid, rev) else {
+			continue;
+		};
+		report.keys.push(ObjectKey::Facets(OidRev::new(oid, rev)));
+		for facet in facets {
+			let key = ObjectKey::Facet(OidRevSel::new(oid, rev, facet.sel));
+			if state._get(&object_storage_key(key.clone())).is_some() {
+				report.keys.push(key);
+			}
+		}
+	}
+	Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{
+		db_state::{DbState, MemKv},
+		prune::prunable_revisions,
+		reader::StateProvider,
+		state::{Facets, ObjectKey, ObjectValue, OidRev, Snapshot},
+		writer::{StateWriter, WriteOp},
+		Facet, Rev, OID,
+	};
+
+	#[test]
+	fn keeps_recent_periodic_and_read_revisions() {
+		let oid = OID { universe: 1, set: 2, id: 3 };
+		let mut ops = Vec::new();
+		for rev in 1..=8 {
+			let key = ObjectKey::Snapshot(OidRev::new(&oid, Rev(rev)));
+			ops.push(WriteOp::PutObject(key, ObjectValue::Snapshot(Snapshot::default())));
+		}
+		let facets = Facets { facets: vec![Facet { sel: 1, hash: [9; 32] }] };
+		let key = ObjectKey::Facets(OidRev::new(&oid, Rev(2)));
+		ops.push(WriteOp::PutObject(key, ObjectValue::Facets(facets)));
+		let mut state = DbState::new(MemKv::default());
+		state.apply_batch(ops).unwrap();
+
+		// Keeps 7, 8 (last two) and 4 (every 4th); 3 and 6 stay as snapshots read by 4 and 7.
+		let report = prunable_revisions(&state, &oid, 2, 4).unwrap();
+		assert_eq!(report.revisions, vec![Rev(1), Rev(2), Rev(5)]);
+		assert_eq!(report.snapshot_only, vec![Rev(3), Rev(6)]);
+		assert_eq!(report.keys.len(), 4);
+
+		assert_eq!(state._get_matter_refs(&[9; 32]), 1);
+		let receipt = state.apply_batch(report.into_ops()).unwrap();
+		assert_eq!(receipt.removed, 4);
+		assert_eq!(state._get_matter_refs(&[9; 32]), 0);
+		assert!(prunable_revisions(&state, &oid, 2, 4).unwrap().is_empty());
+	}
+}
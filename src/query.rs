@@ -0,0 +1,276 @@
+use crate::{Bytes32, Descriptor, Facet, OidPattern, Rev, StateReader, Vec, OID};
+use thiserror::Error;
+
+#[cfg(feature = "storage")]
+use crate::{
+	reader::{StateError, StateProvider},
+	Constants,
+};
+
+#[derive(Clone, Copy, Debug, Error, PartialEq, Eq)]
+pub enum QueryError {
+	#[error("query budget exhausted after {0} reads")]
+	BudgetExhausted(u32),
+	#[error("pattern {0} does not name one set to enumerate")]
+	Unbounded(OidPattern),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Predicate {
+	// Descriptor
+	Kind(u64),
+	MinRev(Rev),
+	TraitsAll(u32),
+	TraitsAny(u32),
+	// Elements
+	ElemEq { index: u8, value: Bytes32 },
+	// Facets
+	HasFacet(u32),
+	FacetEq { sel: u32, hash: Bytes32 },
+}
+
+impl Predicate {
+	fn needs_elems(&self) -> bool {
+		matches!(self, Predicate::ElemEq { .. })
+	}
+
+	fn needs_facets(&self) -> bool {
+		matches!(self, Predicate::HasFacet(_) | Predicate::FacetEq { .. })
+	}
+
+	fn eval(&self, desc: &Descriptor, elems: &[Bytes32], facets: &[Facet]) -> bool {
+		match self {
+			Predicate::Kind(kind) => desc.kind == *kind,
+			Predicate::MinRev(rev) => desc.rev >= *rev,
+			Predicate::TraitsAll(bits) => desc.traits & bits == *bits,
+			Predicate::TraitsAny(bits) => desc.traits & bits != 0,
+			Predicate::ElemEq { index, value } => elems.get(*index as usize) == Some(value),
+			Predicate::HasFacet(sel) => facets.iter().any(|f| f.sel == *sel),
+			Predicate::FacetEq { sel, hash } =>
+				facets.iter().any(|f| f.sel == *sel && f.hash == *hash),
+		}
+	}
+}
+
+/// Upper bound on state reads one evaluation may perform.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Budget {
+	pub max_reads: u32,
+	pub reads: u32,
+}
+
+impl Budget {
+	pub fn new(max_reads: u32) -> Self {
+		Self { max_reads, reads: 0 }
+	}
+
+	fn charge(&mut self) -> Result<(), QueryError> {
+		if self.reads >= self.max_reads {
+			return Err(QueryError::BudgetExhausted(self.reads));
+		}
+		self.reads += 1;
+		Ok(())
+	}
+}
+
+/// `SELECT oid FROM from WHERE filters[0] AND filters[1] ... LIMIT limit`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Select {
+	pub from: OidPattern,
+	pub filters: Vec<Predicate>,
+	pub limit: usize,
+}
+
+impl Select {
+	/// Returns the matching objects among `candidates`, in the order given. Candidates `from`
+	/// does not match are skipped without a read. Elements and facets are read only if a
+	/// predicate needs them; an object whose descriptor, elements or facets cannot be read is
+	/// treated as absent and skipped. Failed reads still count against `budget`.
+	pub fn eval<E, S: StateReader<E>>(
+		&self,
+		state: &S,
+		candidates: impl IntoIterator<Item = OID>,
+		budget: &mut Budget,
+	) -> Result<Vec<OID>, QueryError> {
+		let needs_elems = self.filters.iter().any(Predicate::needs_elems);
+		let needs_facets = self.filters.iter().any(Predicate::needs_facets);
+		let mut out = Vec::new();
+		for oid in candidates.into_iter().filter(|oid| self.from.matches(oid)) {
+			if out.len() >= self.limit {
+				break;
+			}
+			budget.charge()?;
+			let Ok(desc) = state.get_descriptor(&oid, Rev::LATEST) else {
+				continue;
+			};
+			let elems = if needs_elems {
+				budget.charge()?;
+				let Ok((_, elems)) = state.get_snapshot(&oid, desc.rev) else {
+					continue;
+				};
+				elems
+			} else {
+				Vec::new()
+			};
+			let facets = if needs_facets {
+				budget.charge()?;
+				let Ok(facets) = state.get_facets(&oid, desc.rev) else {
+					continue;
+				};
+				facets
+			} else {
+				Vec::new()
+			};
+			if self.filters.iter().all(|p| p.eval(&desc, &elems, &facets)) {
+				out.push(oid);
+			}
+		}
+		Ok(out)
+	}
+
+	/// `eval` over the objects `state` stores for `from`, in id order: the one object of an
+	/// exact pattern, or the current objects of a set. Patterns with a wildcard universe or set
+	/// fail with `QueryError::Unbounded`.
+	#[cfg(feature = "storage")]
+	pub fn eval_stored<P: StateProvider>(
+		&self,
+		state: &P,
+		budget: &mut Budget,
+	) -> Result<Vec<OID>, QueryError> {
+		if let Some(oid) = self.from.as_oid() {
+			return self.eval::<StateError, P>(state, [oid], budget);
+		}
+		let any = Constants::ID_WILDCARD;
+		if self.from.universe == any || self.from.set == any {
+			return Err(QueryError::Unbounded(self.from));
+		}
+		let mut oids: Vec<OID> =
+			state.iter_objects_in_set(self.from.universe, self.from.set).collect();
+		oids.sort_unstable_by_key(|oid| oid.id);
+		self.eval::<StateError, P>(state, oids, budget)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{
+		test_state::{TestObject, TestState},
+		Budget, Bytes32, Descriptor, Facet, OidPattern, Predicate, QueryError, Rev, Select, OID,
+	};
+
+	fn state() -> TestState {
+		let desc =
+			|kind, rev| Descriptor { kind, rev: Rev(rev), traits: 0b01, ..Default::default() };
+		let facet = Facet { sel: 9, hash: [9; 32] };
+		let object = |kind, rev, elem: Option<Bytes32>, facets: Option<Vec<Facet>>| TestObject {
+			elems: elem.map(|e| vec![e]),
+			facets,
+			..TestObject::new(desc(kind, rev))
+		};
+		// Id 4 is absent; 5 has no readable elements, 6 no readable facets.
+		let mut state = TestState::default();
+		let objects = [
+			(1, object(7, 1, Some([1; 32]), Some(vec![]))),
+			(2, object(7, 3, Some([2; 32]), Some(vec![facet.clone()]))),
+			(3, object(8, 2, Some([1; 32]), Some(vec![facet.clone()]))),
+			(5, object(7, 1, None, Some(vec![facet.clone()]))),
+			(6, object(7, 1, Some([1; 32]), None)),
+		];
+		for (id, object) in objects {
+			state.insert(OID { universe: 1, set: 17, id }, object);
+		}
+		state
+	}
+
+	fn select(filters: Vec<Predicate>, limit: usize) -> Select {
+		Select { from: OidPattern::in_set(1, 17), filters, limit }
+	}
+
+	// Ids 1 to 6 of the selected set, and one object of another set that is never read.
+	fn candidates() -> Vec<OID> {
+		let mut oids: Vec<OID> = (1..=6).map(|id| OID { universe: 1, set: 17, id }).collect();
+		oids.insert(1, OID { universe: 1, set: 18, id: 2 });
+		oids
+	}
+
+	fn ids(out: Result<Vec<OID>, QueryError>) -> Vec<u64> {
+		out.unwrap().into_iter().map(|oid| oid.id).collect()
+	}
+
+	#[test]
+	fn filters_objects() {
+		let state = state();
+		let eval = |filters, limit| {
+			select(filters, limit).eval(&state, candidates(), &mut Budget::new(100))
+		};
+		assert_eq!(ids(eval(vec![], 10)), [1, 2, 3, 5, 6]);
+		assert_eq!(ids(eval(vec![Predicate::Kind(7)], 10)), [1, 2, 5, 6]);
+		assert_eq!(ids(eval(vec![Predicate::Kind(7), Predicate::MinRev(Rev(2))], 10)), [2]);
+		assert_eq!(ids(eval(vec![Predicate::TraitsAll(0b11)], 10)), Vec::<u64>::new());
+		assert_eq!(ids(eval(vec![Predicate::TraitsAny(0b11)], 10)), [1, 2, 3, 5, 6]);
+		let elem = Predicate::ElemEq { index: 0, value: [1; 32] };
+		assert_eq!(ids(eval(vec![elem], 10)), [1, 3, 6]);
+		assert_eq!(ids(eval(vec![Predicate::HasFacet(9)], 10)), [2, 3, 5]);
+		assert_eq!(
+			ids(eval(vec![Predicate::FacetEq { sel: 9, hash: [1; 32] }], 10)),
+			Vec::<u64>::new()
+		);
+	}
+
+	#[test]
+	fn stops_at_limit() {
+		let state = state();
+		let mut budget = Budget::new(100);
+		let out = select(vec![Predicate::Kind(7)], 2).eval(&state, candidates(), &mut budget);
+		assert_eq!(ids(out), [1, 2]);
+		assert_eq!(budget.reads, 2);
+		let out = select(vec![], 0).eval(&state, candidates(), &mut Budget::new(0));
+		assert_eq!(ids(out), Vec::<u64>::new());
+	}
+
+	#[test]
+	fn exhausts_budget() {
+		let state = state();
+		// Two reads per object once elements are needed.
+		let elem = Predicate::ElemEq { index: 0, value: [1; 32] };
+		let mut budget = Budget::new(5);
+		assert_eq!(
+			select(vec![elem.clone()], 10).eval(&state, candidates(), &mut budget),
+			Err(QueryError::BudgetExhausted(5))
+		);
+		let mut budget = Budget::new(12);
+		let out = select(vec![elem], 10).eval(&state, candidates(), &mut budget);
+		assert_eq!(ids(out), [1, 3, 6]);
+		assert_eq!(budget.reads, 11);
+	}
+
+	#[cfg(all(feature = "std", feature = "storage"))]
+	#[test]
+	fn evaluates_stored_set() {
+		use crate::{
+			db_state::{DbState, MemKv},
+			state::{ObjectKey, ObjectValue, OidRev, Sota},
+			writer::{StateWriter, WriteOp},
+		};
+
+		let put = |set, id, kind| {
+			let desc = Descriptor { kind, rev: Rev(1), ..Default::default() };
+			let key = ObjectKey::Sota(OidRev::new(&OID { universe: 1, set, id }, Rev::LATEST));
+			WriteOp::PutObject(key, ObjectValue::Sota(Sota { desc, ..Default::default() }))
+		};
+		let mut db = DbState::new(MemKv::default());
+		db.apply_batch(vec![put(17, 300, 7), put(17, 2, 7), put(17, 5, 8), put(18, 1, 7)])
+			.unwrap();
+
+		let kind7 = select(vec![Predicate::Kind(7)], 10);
+		assert_eq!(ids(kind7.eval_stored(&db, &mut Budget::new(10))), [2, 300]);
+		let exact =
+			Select { from: OidPattern::exact(&OID { universe: 1, set: 18, id: 1 }), ..kind7 };
+		assert_eq!(ids(exact.eval_stored(&db, &mut Budget::new(10))), [1]);
+		let wide = Select { from: OidPattern::in_universe(1), ..exact };
+		assert_eq!(
+			wide.eval_stored(&db, &mut Budget::new(10)),
+			Err(QueryError::Unbounded(OidPattern::in_universe(1)))
+		);
+	}
+}
@@ -0,0 +1,209 @@
+use crate::{Bytes32, ElementError, RowSource, Vec};
+use thiserror::Error;
+
+#[derive(Clone, Copy, Debug, Error, PartialEq, Eq)]
+pub enum RangeMatterError {
+	#[error("invalid RangeMatter header")]
+	BadHeader,
+
+	#[error("bad magic: expected 'RANG' (52 41 4E 47), got {0:02X?}")]
+	BadMagic([u8; 4]),
+
+	#[error("unsupported version {0} (expected 1)")]
+	BadVersion(u8),
+
+	#[error("column count {0} exceeds maximum of 16")]
+	BadColCount(u8),
+
+	#[error("invalid body length: expected {expect} bytes, got {got} bytes")]
+	BadBody { expect: usize, got: usize },
+
+	#[error("column {0}: unknown sequence kind or zero run length")]
+	BadColumn(usize),
+
+	#[error("cell out of bounds at (row={row}, col={col})")]
+	OobCell { row: u64, col: usize },
+
+	#[error("arithmetic overflow")]
+	Overflow,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum SeqKind {
+	Constant = 0,
+	Arithmetic = 1,
+	Geometric = 2,
+}
+
+/// One column as a sequence of u64 values. Every `count` consecutive rows share a term, so
+/// `count = 100` with an arithmetic step of 1 gives tiers of 100 rows each.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SeqColumn {
+	pub kind: SeqKind,
+	pub start: u64,
+	pub step: u64,
+	pub count: u64,
+}
+
+impl SeqColumn {
+	/// Value at `row`, or `None` if it does not fit in a u64 or `count` is 0.
+	pub fn value_at(&self, row: u64) -> Option<u64> {
+		let term = row.checked_div(self.count)?;
+		match self.kind {
+			SeqKind::Constant => Some(self.start),
+			SeqKind::Arithmetic => self.step.checked_mul(term)?.checked_add(self.start),
+			SeqKind::Geometric => {
+				let exp = u32::try_from(term).ok()?;
+				self.step.checked_pow(exp)?.checked_mul(self.start)
+			},
+		}
+	}
+}
+
+/// A collection whose cells are computed, not stored. Values are encoded like formula numbers:
+/// big-endian in the last 8 bytes of the cell.
+///
+/// Layout: magic "RANG", version (1), cols, 2 zero bytes, rows (u64 LE), then per column: kind,
+/// 7 zero bytes, start, step and count (each u64 LE).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RangeMatter {
+	pub rows: u64,
+	pub cols: Vec<SeqColumn>,
+}
+
+impl RangeMatter {
+	pub const MAGIC: [u8; 4] = *b"RANG";
+	pub const HEADER_SIZE: usize = 16;
+	pub const COLUMN_SIZE: usize = 32;
+
+	pub fn from(blob: &[u8]) -> Result<Self, RangeMatterError> {
+		if blob.len() < Self::HEADER_SIZE {
+			return Err(RangeMatterError::BadHeader);
+		}
+		let magic: [u8; 4] = blob[0..4].try_into().unwrap();
+		if magic != Self::MAGIC {
+			return Err(RangeMatterError::BadMagic(magic));
+		}
+		if blob[4] != 1 {
+			return Err(RangeMatterError::BadVersion(blob[4]));
+		}
+		let ncols = blob[5];
+		if ncols > 16 {
+			return Err(RangeMatterError::BadColCount(ncols));
+		}
+		if blob[6..8] != [0, 0] {
+			return Err(RangeMatterError::BadHeader);
+		}
+		let rows = u64::from_le_bytes(blob[8..16].try_into().unwrap());
+		let expect = Self::HEADER_SIZE + ncols as usize * Self::COLUMN_SIZE;
+		if blob.len() != expect {
+			return Err(RangeMatterError::BadBody { expect, got: blob.len() });
+		}
+
+		let word = |c: &[u8], at: usize| u64::from_le_bytes(c[at..at + 8].try_into().unwrap());
+		let mut cols = Vec::with_capacity(ncols as usize);
+		for (i, c) in blob[Self::HEADER_SIZE..].chunks_exact(Self::COLUMN_SIZE).enumerate() {
+			let kind = match c[0] {
+				0 => SeqKind::Constant,
+				1 => SeqKind::Arithmetic,
+				2 => SeqKind::Geometric,
+				_ => return Err(RangeMatterError::BadColumn(i)),
+			};
+			let count = word(c, 24);
+			if c[1..8].iter().any(|&b| b != 0) || count == 0 {
+				return Err(RangeMatterError::BadColumn(i));
+			}
+			cols.push(SeqColumn { kind, start: word(c, 8), step: word(c, 16), count });
+		}
+		Ok(Self { rows, cols })
+	}
+
+	pub fn to_blob(&self) -> Vec<u8> {
+		let mut blob = Vec::with_capacity(Self::HEADER_SIZE + self.cols.len() * Self::COLUMN_SIZE);
+		blob.extend_from_slice(&Self::MAGIC);
+		blob.push(1);
+		blob.push(self.cols.len() as u8);
+		blob.extend_from_slice(&[0, 0]);
+		blob.extend_from_slice(&self.rows.to_le_bytes());
+		for c in &self.cols {
+			blob.push(c.kind as u8);
+			blob.extend_from_slice(&[0u8; 7]);
+			blob.extend_from_slice(&c.start.to_le_bytes());
+			blob.extend_from_slice(&c.step.to_le_bytes());
+			blob.extend_from_slice(&c.count.to_le_bytes());
+		}
+		blob
+	}
+
+	pub fn cell_at(&self, row: u64, col: usize) -> Result<Bytes32, RangeMatterError> {
+		let seq = self.cols.get(col).filter(|_| row < self.rows);
+		let seq = seq.ok_or(RangeMatterError::OobCell { row, col })?;
+		if seq.count == 0 {
+			return Err(RangeMatterError::BadColumn(col));
+		}
+		let value = seq.value_at(row).ok_or(RangeMatterError::Overflow)?;
+		let mut cell = [0u8; 32];
+		cell[24..32].copy_from_slice(&value.to_be_bytes());
+		Ok(cell)
+	}
+}
+
+impl RowSource for RangeMatter {
+	fn rows(&self) -> u64 {
+		self.rows
+	}
+
+	fn cols(&self) -> usize {
+		self.cols.len()
+	}
+
+	fn cell_at(&self, row: u64, col: usize) -> Result<Bytes32, ElementError> {
+		RangeMatter::cell_at(self, row, col).map_err(|_| ElementError::RangeMatterRowAt)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{RangeMatter, RangeMatterError, RowSource, SeqColumn, SeqKind};
+
+	#[test]
+	fn sequences_resolve_without_cells() {
+		let col = |kind, start, step, count| SeqColumn { kind, start, step, count };
+		let m = RangeMatter {
+			rows: 1000,
+			cols: vec![
+				col(SeqKind::Arithmetic, 1, 1, 1),
+				col(SeqKind::Arithmetic, 1, 1, 100),
+				col(SeqKind::Geometric, 3, 2, 1),
+				col(SeqKind::Constant, 7, 0, 1),
+			],
+		};
+		let m = RangeMatter::from(&m.to_blob()).unwrap();
+		let n = |v: u64| {
+			let mut c = [0u8; 32];
+			c[24..].copy_from_slice(&v.to_be_bytes());
+			c
+		};
+		assert_eq!(RowSource::row_at(&m, 4).unwrap(), vec![n(5), n(1), n(48), n(7)]);
+		assert_eq!(m.cell_at(999, 1), Ok(n(10)));
+		assert_eq!(m.cell_at(70, 2), Err(RangeMatterError::Overflow));
+		assert_eq!(m.cell_at(1000, 0), Err(RangeMatterError::OobCell { row: 1000, col: 0 }));
+
+		let mut blob = m.to_blob();
+		blob[16] = 3;
+		assert_eq!(RangeMatter::from(&blob), Err(RangeMatterError::BadColumn(0)));
+
+		let mut old = vec![b'R', b'N', b'G', 1, 0, 0, 0, 0];
+		old.extend_from_slice(&1u64.to_le_bytes());
+		assert_eq!(RangeMatter::from(&old), Err(RangeMatterError::BadMagic(*b"RNG\x01")));
+	}
+
+	#[test]
+	fn zero_run_length_does_not_panic() {
+		let seq = SeqColumn { kind: SeqKind::Arithmetic, start: 1, step: 1, count: 0 };
+		assert_eq!(seq.value_at(5), None);
+		let m = RangeMatter { rows: 10, cols: vec![seq] };
+		assert_eq!(m.cell_at(5, 0), Err(RangeMatterError::BadColumn(0)));
+	}
+}
@@ -1,15 +1,23 @@
 #![cfg(feature = "storage")]
 
 use crate::{
-	state::{Arcs, Facets, MatterKey, MatterValue, ObjectKey, ObjectValue, OidRev, Snapshot, Sota},
-	storage::{MatterMap, ObjectMap},
-	Bytes32, Descriptor, Facet, Matter, StateReader, Unique, Value, Vec, H256, OID,
+	state::{
+		Arcs, Facets, Frontier, Genesis, MatterKey, MatterValue, ObjectKey, ObjectValue, OidRev,
+		OidRevSel, Snapshot, Sota, UniverseHerald, UniverseId, UniverseKey, UniverseValue,
+	},
+	storage_key::{
+		matter_prefix, matter_refs_storage_key, matter_storage_key, object_prefix,
+		object_storage_key, universe_storage_key,
+	},
+	trace::event,
+	Arc, Bytes32, Descriptor, ErrorCode, Facet, KindSpec, Matter, Rev, StateReader, Time, Unique,
+	UniverseReader, Value, Vec, H256, OID,
 };
 use codec::{Decode, Encode};
 use derive_more::Display;
 use thiserror::Error;
 
-#[derive(Error, Debug, Display)]
+#[derive(Error, Debug, Display, Clone, Copy, PartialEq, Eq)]
 pub enum ProviderError {
 	ItemNotFound,
 	DecodeFailed,
@@ -18,7 +26,7 @@ pub enum ProviderError {
 
 #[derive(Error, Debug, Display)]
 pub enum StateError {
-	ProviderError(ProviderError),
+	ProviderError(#[source] ProviderError),
 	DataInvalid,
 	UnexpectdVariant,
 	DecodeFailed,
@@ -28,10 +36,31 @@ pub enum StateError {
 	ObjectNotFound,
 	SnapshotNotFound,
 	TailsNotFound,
+	HeadsNotFound,
 	FacetsNotFound,
 	FacetSelectorNotFound,
 	FacetAssetNotFound,
 	FacetAssetInvalid,
+	GenesisNotFound,
+	FrontierNotFound,
+}
+
+/// A block on the chain, by hash or by number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockRef {
+	Hash(H256),
+	Number(u64),
+}
+
+/// Sources that can serve reads from the state at a given block. Every read through one view
+/// sees the same state, so a whole resolution can be run "as of block N".
+pub trait StateReaderAt {
+	type Error;
+	type View<'a>: StateReader<StateError>
+	where
+		Self: 'a;
+
+	fn at(&mut self, block: BlockRef) -> Result<Self::View<'_>, Self::Error>;
 }
 
 impl From<ProviderError> for StateError {
@@ -41,11 +70,43 @@ impl From<ProviderError> for StateError {
 }
 
 pub trait StateProvider {
-	fn _get(&mut self, key: &[u8]) -> Option<Vec<u8>>;
+	fn _get(&self, key: &[u8]) -> Option<Vec<u8>>;
+
+	/// The smallest stored key strictly after `key`, like `sp_io::storage::next_key`. Backends
+	/// that cannot enumerate keys keep the default, and every iteration over them is empty.
+	fn _next_key(&self, _key: &[u8]) -> Option<Vec<u8>> {
+		None
+	}
+
+	/// Objects of `set` in `universe` that have a current state, in storage key order.
+	fn iter_objects_in_set(&self, universe: u64, set: u64) -> impl Iterator<Item = OID> + '_
+	where
+		Self: Sized,
+	{
+		let probe = ObjectKey::Sota(OidRev::new(&OID { universe, set, id: 0 }, Rev::LATEST));
+		ObjectKeys::new(self, &probe, 1 + 8 + 8).filter_map(|key| match key {
+			ObjectKey::Sota(oid_rev) => Some(oid_rev.oid()),
+			_ => None,
+		})
+	}
 
-	fn _get_sota(&mut self, oid: &OID) -> Result<Sota, ProviderError> {
-		let key = ObjectKey::Sota(OidRev::new(oid, 0));
-		let raw = self._get(&ObjectMap::hashed_key_for(key)).ok_or(ProviderError::ItemNotFound)?;
+	/// Revisions of `oid` that have a snapshot, in storage key order (the revision is encoded
+	/// little-endian, so this is not numeric order).
+	fn iter_revisions(&self, oid: &OID) -> impl Iterator<Item = Rev> + '_
+	where
+		Self: Sized,
+	{
+		let probe = ObjectKey::Snapshot(OidRev::new(oid, Rev::LATEST));
+		ObjectKeys::new(self, &probe, 1 + 8 + 8 + 8).filter_map(|key| match key {
+			ObjectKey::Snapshot(oid_rev) => Some(oid_rev.rev()),
+			_ => None,
+		})
+	}
+
+	fn _get_sota(&self, oid: &OID) -> Result<Sota, ProviderError> {
+		let key = ObjectKey::Sota(OidRev::new(oid, Rev::LATEST));
+		let raw = self._get(&object_storage_key(key)).ok_or(ProviderError::ItemNotFound)?;
+		event!(%oid, bytes = raw.len(), "read sota");
 		let val = ObjectValue::decode(&mut &raw[..]).map_err(|_| ProviderError::DecodeFailed)?;
 		match val {
 			ObjectValue::Sota(sota) => Ok(sota),
@@ -53,9 +114,10 @@ pub trait StateProvider {
 		}
 	}
 
-	fn _get_snapshot(&mut self, oid: &OID, rev: u32) -> Result<Snapshot, ProviderError> {
+	fn _get_snapshot(&self, oid: &OID, rev: Rev) -> Result<Snapshot, ProviderError> {
 		let key = ObjectKey::Snapshot(OidRev::new(oid, rev));
-		let raw = self._get(&ObjectMap::hashed_key_for(key)).ok_or(ProviderError::ItemNotFound)?;
+		let raw = self._get(&object_storage_key(key)).ok_or(ProviderError::ItemNotFound)?;
+		event!(%oid, %rev, bytes = raw.len(), "read snapshot");
 		let val = ObjectValue::decode(&mut &raw[..]).map_err(|_| ProviderError::DecodeFailed)?;
 		match val {
 			ObjectValue::Snapshot(snap) => Ok(snap),
@@ -63,36 +125,105 @@ pub trait StateProvider {
 		}
 	}
 
-	fn _get_matter(&mut self, hash: &H256) -> Result<Matter, ProviderError> {
-		let key = MatterKey::Matter(*hash);
-		let raw = self._get(&MatterMap::hashed_key_for(key)).ok_or(ProviderError::ItemNotFound)?;
+	fn _get_matter(&self, hash: &H256) -> Result<Matter, ProviderError> {
+		let raw = self._get(&matter_storage_key(hash)).ok_or(ProviderError::ItemNotFound)?;
+		event!(bytes = raw.len(), "read matter");
 		let val = MatterValue::decode(&mut &raw[..]).map_err(|_| ProviderError::DecodeFailed)?;
 		match val {
 			MatterValue::Matter(mat) => Ok(mat),
 		}
 	}
 
-	fn _resolve_rev(&mut self, oid: &OID, rev0: u32) -> Result<u32, ProviderError> {
-		let rev = if rev0 == 0 { self._get_sota(oid)?.desc.rev } else { rev0 };
+	/// Reference count of a matter, 0 when none is stored.
+	fn _get_matter_refs(&self, hash: &H256) -> u32 {
+		self._get(&matter_refs_storage_key(hash))
+			.and_then(|raw| u32::decode(&mut &raw[..]).ok())
+			.unwrap_or_default()
+	}
+
+	/// Stored matters with no references, in storage key order.
+	fn find_orphans(&self) -> impl Iterator<Item = H256> + '_
+	where
+		Self: Sized,
+	{
+		let mut prefix = matter_prefix().to_vec();
+		let skip = prefix.len();
+		prefix.extend_from_slice(&MatterKey::Matter([0; 32]).encode()[..1]);
+		let mut cursor = prefix.clone();
+		core::iter::from_fn(move || loop {
+			let key = self._next_key(&cursor)?;
+			if !key.starts_with(&prefix) {
+				return None;
+			}
+			cursor = key;
+			let Ok(MatterKey::Matter(hash)) = MatterKey::decode(&mut &cursor[skip..]) else {
+				continue;
+			};
+			if self._get_matter_refs(&hash) == 0 {
+				return Some(hash);
+			}
+		})
+	}
+
+	fn _resolve_rev(&self, oid: &OID, rev0: Rev) -> Result<Rev, ProviderError> {
+		let rev = if rev0.is_latest() { self._get_sota(oid)?.desc.rev } else { rev0 };
 		Ok(rev)
 	}
 
-	fn _resolve_desc(&mut self, oid: &OID, rev0: u32) -> Result<Descriptor, ProviderError> {
-		let desc =
-			if rev0 == 0 { self._get_sota(oid)?.desc } else { self._get_snapshot(oid, rev0)?.desc };
+	fn _resolve_desc(&self, oid: &OID, rev0: Rev) -> Result<Descriptor, ProviderError> {
+		let desc = if rev0.is_latest() {
+			self._get_sota(oid)?.desc
+		} else {
+			self._get_snapshot(oid, rev0)?.desc
+		};
 		Ok(desc)
 	}
 }
 
+/// Object keys stored under a common prefix of their encoding, read through
+/// `StateProvider::_next_key`. Keys that do not decode are skipped.
+pub struct ObjectKeys<'a, P> {
+	provider: &'a P,
+	prefix: Vec<u8>,
+	cursor: Vec<u8>,
+}
+
+impl<'a, P: StateProvider> ObjectKeys<'a, P> {
+	/// Keys sharing the first `len` encoded bytes of `probe`.
+	pub fn new(provider: &'a P, probe: &ObjectKey, len: usize) -> Self {
+		let mut prefix = object_prefix().to_vec();
+		prefix.extend_from_slice(&probe.encode()[..len]);
+		Self { provider, cursor: prefix.clone(), prefix }
+	}
+}
+
+impl<P: StateProvider> Iterator for ObjectKeys<'_, P> {
+	type Item = ObjectKey;
+
+	fn next(&mut self) -> Option<ObjectKey> {
+		let skip = object_prefix().len();
+		loop {
+			let key = self.provider._next_key(&self.cursor)?;
+			if !key.starts_with(&self.prefix) {
+				return None;
+			}
+			self.cursor = key;
+			if let Ok(key) = ObjectKey::decode(&mut &self.cursor[skip..]) {
+				return Some(key);
+			}
+		}
+	}
+}
+
 impl<T> StateReader<StateError> for T
 where
 	T: StateProvider,
 {
-	fn get_matter(&mut self, hash: &H256) -> Result<Matter, StateError> {
+	fn get_matter(&self, hash: &H256) -> Result<Matter, StateError> {
 		self._get_matter(hash).map_err(StateError::from)
 	}
 
-	fn get_value(&mut self, tid: &OID, rev: u32) -> Result<Value, StateError> {
+	fn get_value(&self, tid: &OID, rev: Rev) -> Result<Value, StateError> {
 		let rev = self._resolve_rev(tid, rev)?;
 		let snap = self._get_snapshot(tid, rev)?;
 		if snap.elems.len() != 3 {
@@ -105,10 +236,10 @@ where
 		let decimals = snap.elems[2][1];
 		let mut symbol = [0u8; 30];
 		symbol.copy_from_slice(&snap.elems[2][2..32]);
-		Ok(Value { std, decimals, symbol, code, data })
+		Ok(Value { std, decimals, symbol: symbol.into(), code, data })
 	}
 
-	fn get_unique(&mut self, tid: &OID, rev: u32) -> Result<Unique, StateError> {
+	fn get_unique(&self, tid: &OID, rev: Rev) -> Result<Unique, StateError> {
 		let rev = self._resolve_rev(tid, rev)?;
 		let snap = self._get_snapshot(tid, rev)?;
 		if snap.elems.len() != 3 {
@@ -121,27 +252,46 @@ where
 		let decimals = snap.elems[2][1];
 		let mut symbol = [0u8; 30];
 		symbol.copy_from_slice(&snap.elems[2][2..32]);
-		Ok(Unique { std, decimals, symbol, code, data })
+		Ok(Unique { std, decimals, symbol: symbol.into(), code, data })
 	}
 
-	fn get_descriptor(&mut self, oid: &OID, rev: u32) -> Result<Descriptor, StateError> {
+	fn get_descriptor(&self, oid: &OID, rev: Rev) -> Result<Descriptor, StateError> {
 		self._resolve_desc(oid, rev).map_err(StateError::from)
 	}
 
-	fn get_snapshot(
-		&mut self,
-		oid: &OID,
-		rev: u32,
-	) -> Result<(Descriptor, Vec<Bytes32>), StateError> {
+	fn get_snapshot(&self, oid: &OID, rev: Rev) -> Result<(Descriptor, Vec<Bytes32>), StateError> {
 		let rev = self._resolve_rev(oid, rev)?;
 		let snap = self._get_snapshot(oid, rev)?;
 		Ok((snap.desc, snap.elems))
 	}
 
-	fn get_tails(&mut self, oid: &OID, rev: u32) -> Result<crate::Vec<crate::Arc>, StateError> {
+	fn get_snapshot_at(
+		&self,
+		oid: &OID,
+		time: Time,
+	) -> Result<(Descriptor, Vec<Bytes32>), StateError> {
+		// Revisions may have been pruned, so walk the stored ones instead of searching a dense
+		// range, and keep the highest one modified at or before `time`.
+		let time = u128::from(time);
+		let mut found: Option<(Rev, Snapshot)> = None;
+		for rev in self.iter_revisions(oid) {
+			if found.as_ref().is_some_and(|(best, _)| *best > rev) {
+				continue;
+			}
+			let snap = self._get_snapshot(oid, rev)?;
+			if snap.mt <= time {
+				found = Some((rev, snap));
+			}
+		}
+		found
+			.map(|(_, snap)| (snap.desc, snap.elems))
+			.ok_or(StateError::SnapshotNotFound)
+	}
+
+	fn get_tails(&self, oid: &OID, rev: Rev) -> Result<crate::Vec<crate::Arc>, StateError> {
 		let rev = self._resolve_rev(oid, rev)?;
 		let key = ObjectKey::Tails(OidRev::new(oid, rev));
-		let raw = self._get(&ObjectMap::hashed_key_for(key)).ok_or(StateError::TailsNotFound)?;
+		let raw = self._get(&object_storage_key(key)).ok_or(StateError::TailsNotFound)?;
 		let val = ObjectValue::decode(&mut &raw[..]).map_err(|_| StateError::DecodeFailed)?;
 		match val {
 			ObjectValue::Tails(Arcs { arcs }) => Ok(arcs),
@@ -149,10 +299,21 @@ where
 		}
 	}
 
-	fn get_facets(&mut self, oid: &OID, rev: u32) -> Result<Vec<Facet>, StateError> {
+	fn get_heads(&self, oid: &OID, rev: Rev) -> Result<Vec<Arc>, StateError> {
+		let rev = self._resolve_rev(oid, rev)?;
+		let key = ObjectKey::Heads(OidRev::new(oid, rev));
+		let raw = self._get(&object_storage_key(key)).ok_or(StateError::HeadsNotFound)?;
+		let val = ObjectValue::decode(&mut &raw[..]).map_err(|_| StateError::DecodeFailed)?;
+		match val {
+			ObjectValue::Heads(Arcs { arcs }) => Ok(arcs),
+			_ => Err(StateError::DataInvalid),
+		}
+	}
+
+	fn get_facets(&self, oid: &OID, rev: Rev) -> Result<Vec<Facet>, StateError> {
 		let rev = self._resolve_rev(oid, rev)?;
 		let key = ObjectKey::Facets(OidRev::new(oid, rev));
-		let raw = self._get(&ObjectMap::hashed_key_for(key)).ok_or(StateError::FacetsNotFound)?;
+		let raw = self._get(&object_storage_key(key)).ok_or(StateError::FacetsNotFound)?;
 		let val = ObjectValue::decode(&mut &raw[..]).map_err(|_| StateError::DecodeFailed)?;
 		match val {
 			ObjectValue::Facets(Facets { facets }) => Ok(facets),
@@ -160,10 +321,20 @@ where
 		}
 	}
 
-	fn get_facet(&mut self, oid: &OID, rev: u32, sel: u32) -> Result<Matter, StateError> {
+	fn get_facet(&self, oid: &OID, rev: Rev, sel: u32) -> Result<Matter, StateError> {
 		let rev = self._resolve_rev(oid, rev)?;
+		let key = ObjectKey::Facet(OidRevSel::new(oid, rev, sel));
+		if let Some(raw) = self._get(&object_storage_key(key)) {
+			let val = ObjectValue::decode(&mut &raw[..]).map_err(|_| StateError::DecodeFailed)?;
+			return match val {
+				ObjectValue::Facet(facet) =>
+					self._get_matter(&facet.hash).map_err(StateError::from),
+				_ => Err(StateError::DataInvalid),
+			};
+		}
+		// Objects written before per-facet entries existed only have the full list.
 		let key = ObjectKey::Facets(OidRev::new(oid, rev));
-		let raw = self._get(&ObjectMap::hashed_key_for(key)).ok_or(StateError::FacetsNotFound)?;
+		let raw = self._get(&object_storage_key(key)).ok_or(StateError::FacetsNotFound)?;
 		let val = ObjectValue::decode(&mut &raw[..]).map_err(|_| StateError::DecodeFailed)?;
 		let facets = match val {
 			ObjectValue::Facets(Facets { facets }) => facets,
@@ -174,55 +345,280 @@ where
 		self._get_matter(&facet.hash).map_err(StateError::from)
 	}
 
-	fn get_kind_contract(&mut self, oid: &OID, rev: u32) -> Result<Matter, StateError> {
+	fn get_kind_contract(&self, oid: &OID, rev: Rev) -> Result<Matter, StateError> {
 		let desc = self._resolve_desc(oid, rev)?;
 		let snap = self._get_snapshot(&oid.kind_oid(desc.kind), desc.krev)?;
-		self._get_matter(&snap.elems[0]).map_err(StateError::from)
+		let kind = KindSpec::from_elems(&snap.elems).map_err(|_| StateError::DataInvalid)?;
+		self._get_matter(kind.contract()).map_err(StateError::from)
+	}
+
+	fn error_code(&self, err: &StateError) -> Option<ErrorCode> {
+		Some(match err {
+			StateError::ProviderError(cause) => cause.code(),
+			err => err.code(),
+		})
+	}
+}
+
+impl<T> UniverseReader<StateError> for T
+where
+	T: StateProvider,
+{
+	fn get_genesis(&self, universe: UniverseId) -> Result<Genesis, StateError> {
+		let key = UniverseKey::Genesis(universe);
+		let raw = self._get(&universe_storage_key(key)).ok_or(StateError::GenesisNotFound)?;
+		let val = UniverseValue::decode(&mut &raw[..]).map_err(|_| StateError::DecodeFailed)?;
+		match val {
+			UniverseValue::Genesis(genesis) => Ok(genesis),
+			_ => Err(StateError::DataInvalid),
+		}
+	}
+
+	fn get_frontier(&self, universe: UniverseId) -> Result<Frontier, StateError> {
+		let key = UniverseKey::Frontier(universe);
+		let raw = self._get(&universe_storage_key(key)).ok_or(StateError::FrontierNotFound)?;
+		let val = UniverseValue::decode(&mut &raw[..]).map_err(|_| StateError::DecodeFailed)?;
+		match val {
+			UniverseValue::Frontier(frontier) => Ok(frontier),
+			_ => Err(StateError::DataInvalid),
+		}
+	}
+
+	fn is_herald(&self, universe: UniverseId, account: &Bytes32) -> Result<bool, StateError> {
+		let key = UniverseKey::Herald(UniverseHerald { universe, herald: *account });
+		match self._get(&universe_storage_key(key)) {
+			None => Ok(false),
+			Some(raw) => match UniverseValue::decode(&mut &raw[..]) {
+				Ok(UniverseValue::Herald) => Ok(true),
+				Ok(_) => Err(StateError::DataInvalid),
+				Err(_) => Err(StateError::DecodeFailed),
+			},
+		}
 	}
 }
 
 #[cfg(all(feature = "anyhow", feature = "storage"))]
 impl<T> StateReader<anyhow::Error> for T
 where
-	T: StateReader<StateError>,
+	T: StateProvider,
 {
-	fn get_matter(&mut self, hash: &H256) -> Result<Matter, anyhow::Error> {
+	fn get_matter(&self, hash: &H256) -> Result<Matter, anyhow::Error> {
 		<T as StateReader<StateError>>::get_matter(self, hash).map_err(Into::into)
 	}
 
-	fn get_value(&mut self, tid: &OID, rev: u32) -> Result<Value, anyhow::Error> {
+	fn get_value(&self, tid: &OID, rev: Rev) -> Result<Value, anyhow::Error> {
 		<T as StateReader<StateError>>::get_value(self, tid, rev).map_err(Into::into)
 	}
 
-	fn get_unique(&mut self, tid: &OID, rev: u32) -> Result<Unique, anyhow::Error> {
+	fn get_unique(&self, tid: &OID, rev: Rev) -> Result<Unique, anyhow::Error> {
 		<T as StateReader<StateError>>::get_unique(self, tid, rev).map_err(Into::into)
 	}
 
-	fn get_descriptor(&mut self, oid: &OID, rev: u32) -> Result<Descriptor, anyhow::Error> {
+	fn get_descriptor(&self, oid: &OID, rev: Rev) -> Result<Descriptor, anyhow::Error> {
 		<T as StateReader<StateError>>::get_descriptor(self, oid, rev).map_err(Into::into)
 	}
 
 	fn get_snapshot(
-		&mut self,
+		&self,
 		oid: &OID,
-		rev: u32,
+		rev: Rev,
 	) -> Result<(Descriptor, Vec<Bytes32>), anyhow::Error> {
 		<T as StateReader<StateError>>::get_snapshot(self, oid, rev).map_err(Into::into)
 	}
 
-	fn get_tails(&mut self, oid: &OID, rev: u32) -> Result<crate::Vec<crate::Arc>, anyhow::Error> {
+	fn get_snapshot_at(
+		&self,
+		oid: &OID,
+		time: Time,
+	) -> Result<(Descriptor, Vec<Bytes32>), anyhow::Error> {
+		<T as StateReader<StateError>>::get_snapshot_at(self, oid, time).map_err(Into::into)
+	}
+
+	fn get_tails(&self, oid: &OID, rev: Rev) -> Result<crate::Vec<crate::Arc>, anyhow::Error> {
 		<T as StateReader<StateError>>::get_tails(self, oid, rev).map_err(Into::into)
 	}
 
-	fn get_facets(&mut self, oid: &OID, rev: u32) -> Result<Vec<Facet>, anyhow::Error> {
+	fn get_heads(&self, oid: &OID, rev: Rev) -> Result<Vec<Arc>, anyhow::Error> {
+		<T as StateReader<StateError>>::get_heads(self, oid, rev).map_err(Into::into)
+	}
+
+	fn get_facets(&self, oid: &OID, rev: Rev) -> Result<Vec<Facet>, anyhow::Error> {
 		<T as StateReader<StateError>>::get_facets(self, oid, rev).map_err(Into::into)
 	}
 
-	fn get_facet(&mut self, oid: &OID, rev: u32, sel: u32) -> Result<Matter, anyhow::Error> {
+	fn get_facet(&self, oid: &OID, rev: Rev, sel: u32) -> Result<Matter, anyhow::Error> {
 		<T as StateReader<StateError>>::get_facet(self, oid, rev, sel).map_err(Into::into)
 	}
 
-	fn get_kind_contract(&mut self, oid: &OID, rev: u32) -> Result<Matter, anyhow::Error> {
+	fn get_kind_contract(&self, oid: &OID, rev: Rev) -> Result<Matter, anyhow::Error> {
 		<T as StateReader<StateError>>::get_kind_contract(self, oid, rev).map_err(Into::into)
 	}
+
+	fn error_code(&self, err: &anyhow::Error) -> Option<ErrorCode> {
+		let err = err.downcast_ref::<StateError>()?;
+		<T as StateReader<StateError>>::error_code(self, err)
+	}
+}
+
+#[cfg(all(feature = "anyhow", feature = "storage"))]
+impl<T> UniverseReader<anyhow::Error> for T
+where
+	T: StateProvider,
+{
+	fn get_genesis(&self, universe: UniverseId) -> Result<Genesis, anyhow::Error> {
+		<T as UniverseReader<StateError>>::get_genesis(self, universe).map_err(Into::into)
+	}
+
+	fn get_frontier(&self, universe: UniverseId) -> Result<Frontier, anyhow::Error> {
+		<T as UniverseReader<StateError>>::get_frontier(self, universe).map_err(Into::into)
+	}
+
+	fn is_herald(&self, universe: UniverseId, account: &Bytes32) -> Result<bool, anyhow::Error> {
+		<T as UniverseReader<StateError>>::is_herald(self, universe, account).map_err(Into::into)
+	}
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+	use crate::{
+		db_state::{DbState, MemKv},
+		reader::{StateError, StateProvider},
+		state::{
+			Arcs, Genesis, ObjectKey, ObjectValue, OidRev, Snapshot, Sota, UniverseHerald,
+			UniverseKey, UniverseValue,
+		},
+		storage_key::universe_storage_key,
+		writer::{Overlay, StateWriter, WriteOp},
+		Arc, Descriptor, Rev, StateReader, Time, UniverseReader, OID,
+	};
+	use codec::Encode;
+
+	const OID3: OID = OID { universe: 1, set: 2, id: 3 };
+
+	fn at(block: u64) -> Time {
+		Time { block, slot: 0, tick: 0 }
+	}
+
+	/// A store holding `OID3` at `latest` with a snapshot of each of `revs` at block `rev * 10`.
+	fn history(latest: Rev, revs: &[u32]) -> DbState<MemKv> {
+		let desc = |rev| Descriptor { rev, ..Default::default() };
+		let mut ops = vec![WriteOp::PutObject(
+			ObjectKey::Sota(OidRev::new(&OID3, Rev::LATEST)),
+			ObjectValue::Sota(Sota { desc: desc(latest), ..Default::default() }),
+		)];
+		for &rev in revs {
+			let snap =
+				Snapshot { desc: desc(Rev(rev)), mt: at(rev as u64 * 10).into(), elems: vec![] };
+			let key = ObjectKey::Snapshot(OidRev::new(&OID3, Rev(rev)));
+			ops.push(WriteOp::PutObject(key, ObjectValue::Snapshot(snap)));
+		}
+		let mut db = DbState::new(MemKv::default());
+		db.apply_batch(ops).unwrap();
+		db
+	}
+
+	fn rev_at(db: &DbState<MemKv>, block: u64) -> Result<Rev, StateError> {
+		StateReader::<StateError>::get_snapshot_at(db, &OID3, at(block)).map(|(d, _)| d.rev)
+	}
+
+	#[test]
+	fn finds_snapshot_by_time() {
+		let db = history(Rev(3), &[1, 2, 3]);
+		assert_eq!(rev_at(&db, 25).unwrap(), Rev(2));
+		assert_eq!(rev_at(&db, 10).unwrap(), Rev(1));
+		assert_eq!(rev_at(&db, 99).unwrap(), Rev(3));
+		assert!(matches!(rev_at(&db, 9), Err(StateError::SnapshotNotFound)));
+	}
+
+	#[test]
+	fn finds_snapshot_across_gaps() {
+		// Revisions 2, 3 and 5 were pruned; 256 encodes before 1 in key order.
+		let db = history(Rev(256), &[1, 4, 6, 256]);
+		assert_eq!(rev_at(&db, 35).unwrap(), Rev(1));
+		assert_eq!(rev_at(&db, 40).unwrap(), Rev(4));
+		assert_eq!(rev_at(&db, 59).unwrap(), Rev(4));
+		assert_eq!(rev_at(&db, 2559).unwrap(), Rev(6));
+		assert_eq!(rev_at(&db, 5000).unwrap(), Rev(256));
+		assert!(matches!(rev_at(&db, 5), Err(StateError::SnapshotNotFound)));
+	}
+
+	#[test]
+	fn finds_snapshot_of_destroyed_object() {
+		let db = history(Rev::DESTROYED, &[1, 2]);
+		assert_eq!(rev_at(&db, 15).unwrap(), Rev(1));
+		assert_eq!(rev_at(&db, 1000).unwrap(), Rev(2));
+		assert!(matches!(
+			rev_at(&history(Rev::DESTROYED, &[]), 1000),
+			Err(StateError::SnapshotNotFound)
+		));
+	}
+
+	#[test]
+	fn iterates_objects_and_revisions() {
+		let oid = |set, id| OID { universe: 1, set, id };
+		let sota_key = |set, id| ObjectKey::Sota(OidRev::new(&oid(set, id), Rev::LATEST));
+		let sota = || ObjectValue::Sota(Sota::default());
+		let mut ops: Vec<WriteOp> = [(2, 5), (2, 300), (3, 5)]
+			.into_iter()
+			.map(|(set, id)| WriteOp::PutObject(sota_key(set, id), sota()))
+			.collect();
+		for rev in 1..=3 {
+			let key = ObjectKey::Snapshot(OidRev::new(&oid(2, 5), Rev(rev)));
+			ops.push(WriteOp::PutObject(key, ObjectValue::Snapshot(Snapshot::default())));
+		}
+		let mut store = DbState::new(MemKv::default());
+		store.apply_batch(ops).unwrap();
+
+		let mut ids: Vec<u64> = store.iter_objects_in_set(1, 2).map(|o| o.id).collect();
+		ids.sort();
+		assert_eq!(ids, vec![5, 300]);
+		assert_eq!(store.iter_objects_in_set(1, 4).count(), 0);
+		assert_eq!(store.iter_revisions(&oid(2, 5)).count(), 3);
+
+		// Staged writes and removals show through an overlay.
+		let mut overlay = Overlay::new(&mut store);
+		overlay.stage(0, WriteOp::RemoveObject(sota_key(2, 5))).unwrap();
+		overlay.stage(1, WriteOp::PutObject(sota_key(2, 7), sota())).unwrap();
+		let mut ids: Vec<u64> = overlay.iter_objects_in_set(1, 2).map(|o| o.id).collect();
+		ids.sort();
+		assert_eq!(ids, vec![7, 300]);
+	}
+
+	#[test]
+	fn reads_heads_of_exact_revision() {
+		let arc = |id| Arc { kind: 0, data: 0, rel: 1, set: 2, id };
+		let heads = ObjectValue::Heads(Arcs { arcs: vec![arc(9)] });
+		let mut db = history(Rev(2), &[1, 2]);
+		let op = WriteOp::PutObject(ObjectKey::Heads(OidRev::new(&OID3, Rev(1))), heads);
+		db.apply_batch(vec![op]).unwrap();
+
+		let heads = |oid, rev| StateReader::<StateError>::get_heads(&db, oid, rev);
+		assert_eq!(heads(&OID3, Rev(1)).unwrap(), vec![arc(9)]);
+		// Not carried over to revision 2, the current one.
+		assert!(matches!(heads(&OID3, Rev(2)), Err(StateError::HeadsNotFound)));
+		assert!(matches!(heads(&OID3, Rev::LATEST), Err(StateError::HeadsNotFound)));
+		let missing = OID { id: 4, ..OID3 };
+		assert!(matches!(heads(&missing, Rev::LATEST), Err(StateError::ProviderError(_))));
+		assert!(matches!(heads(&missing, Rev(1)), Err(StateError::HeadsNotFound)));
+	}
+
+	#[test]
+	fn reads_universe_entries() {
+		let mut store = DbState::new(MemKv::default());
+		let genesis = Genesis { horizon: 100, otime: 1, originator: [7; 32] };
+		let herald = UniverseKey::Herald(UniverseHerald { universe: 1, herald: [9; 32] });
+		store._put(
+			&universe_storage_key(UniverseKey::Genesis(1)),
+			UniverseValue::Genesis(genesis.clone()).encode(),
+		);
+		store._put(&universe_storage_key(herald), UniverseValue::Herald.encode());
+
+		assert_eq!(UniverseReader::<StateError>::get_genesis(&store, 1).unwrap(), genesis);
+		assert!(matches!(
+			UniverseReader::<StateError>::get_frontier(&store, 1),
+			Err(StateError::FrontierNotFound)
+		));
+		assert!(UniverseReader::<StateError>::is_herald(&store, 1, &[9; 32]).unwrap());
+		assert!(!UniverseReader::<StateError>::is_herald(&store, 2, &[9; 32]).unwrap());
+	}
 }
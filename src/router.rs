@@ -0,0 +1,186 @@
+use crate::{
+	Arc, Bytes32, Descriptor, Facet, Matter, Rev, StateReader, Time, Unique, Value, Vec, H256, OID,
+};
+use sp_std::{boxed::Box, collections::btree_map::BTreeMap};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum RouterError<E> {
+	#[error("no backend for universe {0}")]
+	NoRoute(u64),
+	#[error("no backend holds the matter")]
+	NoMatter,
+	#[error("backend error")]
+	Backend(E),
+}
+
+/// Routes object reads to a backend by `OID.universe`.
+///
+/// Matters are content-addressed and carry no universe, so `get_matter` tries the fallback first
+/// and then every routed backend in universe order until one succeeds.
+pub struct RouterReader<'a, E> {
+	routes: BTreeMap<u64, Box<dyn StateReader<E> + 'a>>,
+	fallback: Option<Box<dyn StateReader<E> + 'a>>,
+}
+
+impl<'a, E> Default for RouterReader<'a, E> {
+	fn default() -> Self {
+		Self { routes: BTreeMap::new(), fallback: None }
+	}
+}
+
+impl<'a, E> RouterReader<'a, E> {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	pub fn with_route(mut self, universe: u64, reader: impl StateReader<E> + 'a) -> Self {
+		self.routes.insert(universe, Box::new(reader));
+		self
+	}
+
+	pub fn with_fallback(mut self, reader: impl StateReader<E> + 'a) -> Self {
+		self.fallback = Some(Box::new(reader));
+		self
+	}
+
+	pub fn universes(&self) -> impl Iterator<Item = u64> + '_ {
+		self.routes.keys().copied()
+	}
+
+	fn route(&self, universe: u64) -> Result<&(dyn StateReader<E> + 'a), RouterError<E>> {
+		match self.routes.get(&universe) {
+			Some(reader) => Ok(reader.as_ref()),
+			None => self.fallback.as_deref().ok_or(RouterError::NoRoute(universe)),
+		}
+	}
+}
+
+impl<'a, E> StateReader<RouterError<E>> for RouterReader<'a, E> {
+	fn get_matter(&self, hash: &H256) -> Result<Matter, RouterError<E>> {
+		let mut last = RouterError::NoMatter;
+		for reader in self.fallback.iter().chain(self.routes.values()) {
+			match reader.get_matter(hash) {
+				Ok(matter) => return Ok(matter),
+				Err(e) => last = RouterError::Backend(e),
+			}
+		}
+		Err(last)
+	}
+
+	fn get_value(&self, tid: &OID, rev: Rev) -> Result<Value, RouterError<E>> {
+		self.route(tid.universe)?.get_value(tid, rev).map_err(RouterError::Backend)
+	}
+
+	fn get_unique(&self, tid: &OID, rev: Rev) -> Result<Unique, RouterError<E>> {
+		self.route(tid.universe)?.get_unique(tid, rev).map_err(RouterError::Backend)
+	}
+
+	fn get_descriptor(&self, oid: &OID, rev: Rev) -> Result<Descriptor, RouterError<E>> {
+		self.route(oid.universe)?.get_descriptor(oid, rev).map_err(RouterError::Backend)
+	}
+
+	fn get_snapshot(
+		&self,
+		oid: &OID,
+		rev: Rev,
+	) -> Result<(Descriptor, Vec<Bytes32>), RouterError<E>> {
+		self.route(oid.universe)?.get_snapshot(oid, rev).map_err(RouterError::Backend)
+	}
+
+	fn get_snapshot_at(
+		&self,
+		oid: &OID,
+		time: Time,
+	) -> Result<(Descriptor, Vec<Bytes32>), RouterError<E>> {
+		self.route(oid.universe)?
+			.get_snapshot_at(oid, time)
+			.map_err(RouterError::Backend)
+	}
+
+	fn get_tails(&self, oid: &OID, rev: Rev) -> Result<Vec<Arc>, RouterError<E>> {
+		self.route(oid.universe)?.get_tails(oid, rev).map_err(RouterError::Backend)
+	}
+
+	fn get_heads(&self, oid: &OID, rev: Rev) -> Result<Vec<Arc>, RouterError<E>> {
+		self.route(oid.universe)?.get_heads(oid, rev).map_err(RouterError::Backend)
+	}
+
+	fn get_facets(&self, oid: &OID, rev: Rev) -> Result<Vec<Facet>, RouterError<E>> {
+		self.route(oid.universe)?.get_facets(oid, rev).map_err(RouterError::Backend)
+	}
+
+	fn get_facet(&self, oid: &OID, rev: Rev, sel: u32) -> Result<Matter, RouterError<E>> {
+		self.route(oid.universe)?.get_facet(oid, rev, sel).map_err(RouterError::Backend)
+	}
+
+	fn get_kind_contract(&self, oid: &OID, rev: Rev) -> Result<Matter, RouterError<E>> {
+		self.route(oid.universe)?
+			.get_kind_contract(oid, rev)
+			.map_err(RouterError::Backend)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{
+		test_state::{Missing, TestObject, TestState},
+		to_mime, Descriptor, Matter, Rev, RouterError, RouterReader, StateReader, OID,
+	};
+
+	// Object 5 of set 17 in universes 1 to 3, of kind `id` and without readable tails, and a
+	// matter of form `id` under a hash starting with `id`. Fails with `Missing(id)`.
+	fn backend(id: u64) -> TestState {
+		let mut state = TestState::with_id(id);
+		for universe in 1..=3 {
+			let desc = Descriptor { kind: id, rev: Rev(1), ..Default::default() };
+			let object = TestObject { tails: None, ..TestObject::new(desc) };
+			state.insert(OID { universe, set: 17, id: 5 }, object);
+		}
+		let matter = Matter { form: id as u8, mime: to_mime(b"x"), blob: vec![] };
+		state.insert_matter([id as u8; 32], matter);
+		state
+	}
+
+	fn kind_of<E>(router: &RouterReader<E>, universe: u64, id: u64) -> Result<u64, RouterError<E>> {
+		router
+			.get_descriptor(&OID { universe, set: 17, id }, Rev::LATEST)
+			.map(|d| d.kind)
+	}
+
+	#[test]
+	fn routes_by_universe() {
+		let router = RouterReader::new().with_route(1, backend(1)).with_route(2, backend(2));
+		assert_eq!(router.universes().collect::<Vec<_>>(), [1, 2]);
+		assert_eq!(kind_of(&router, 1, 5).unwrap(), 1);
+		assert_eq!(kind_of(&router, 2, 5).unwrap(), 2);
+		assert!(matches!(kind_of(&router, 3, 5), Err(RouterError::NoRoute(3))));
+		assert!(matches!(kind_of(&router, 2, 0), Err(RouterError::Backend(Missing(2)))));
+		let oid = OID { universe: 1, set: 17, id: 5 };
+		assert!(matches!(router.get_tails(&oid, Rev(1)), Err(RouterError::Backend(Missing(1)))));
+	}
+
+	#[test]
+	fn falls_back_for_unrouted_universes() {
+		let router = RouterReader::new().with_route(1, backend(1)).with_fallback(backend(9));
+		assert_eq!(kind_of(&router, 1, 5).unwrap(), 1);
+		assert_eq!(kind_of(&router, 3, 5).unwrap(), 9);
+		assert!(matches!(kind_of(&router, 3, 0), Err(RouterError::Backend(Missing(9)))));
+		assert!(matches!(kind_of(&router, 1, 0), Err(RouterError::Backend(Missing(1)))));
+	}
+
+	#[test]
+	fn matters_try_every_backend() {
+		let router = RouterReader::new().with_route(1, backend(1)).with_route(2, backend(2));
+		assert_eq!(router.get_matter(&[2; 32]).unwrap().form, 2);
+		// The last backend's error is reported.
+		assert!(matches!(router.get_matter(&[7; 32]), Err(RouterError::Backend(Missing(2)))));
+		let router = router.with_fallback(backend(7));
+		assert_eq!(router.get_matter(&[7; 32]).unwrap().form, 7);
+		assert_eq!(router.get_matter(&[1; 32]).unwrap().form, 1);
+		assert!(matches!(
+			RouterReader::<Missing>::new().get_matter(&[1; 32]),
+			Err(RouterError::NoMatter)
+		));
+	}
+}
@@ -0,0 +1,85 @@
+use crate::{Bytes32, ElementError, EnumMatter, Matter, MatterFormRegistry, PermMatter, Vec};
+
+/// Row access over a collection matter, independent of its binary form.
+pub trait RowSource {
+	fn rows(&self) -> u64;
+	fn cols(&self) -> usize;
+	fn cell_at(&self, row: u64, col: usize) -> Result<Bytes32, ElementError>;
+
+	fn row_at(&self, row: u64) -> Result<Vec<Bytes32>, ElementError> {
+		if row >= self.rows() {
+			return Err(ElementError::RowOutOfBounds);
+		}
+		(0..self.cols()).map(|col| self.cell_at(row, col)).collect()
+	}
+}
+
+/// A `RowSource` that can be opened from a stored matter; what `ElementPicker` resolves
+/// collection picks through.
+pub trait CollectionSource: RowSource + Sized {
+	fn from_matter(matter: &Matter) -> Result<Self, ElementError>;
+
+	/// Like `from_matter`, also opening forms registered in `registry`. Sources with no notion of
+	/// custom forms keep the default, which ignores it.
+	fn from_matter_in(
+		matter: &Matter,
+		registry: &MatterFormRegistry,
+	) -> Result<Self, ElementError> {
+		let _ = registry;
+		Self::from_matter(matter)
+	}
+
+	/// `from_matter_in` when a registry is given, else `from_matter`.
+	fn open(matter: &Matter, registry: Option<&MatterFormRegistry>) -> Result<Self, ElementError> {
+		match registry {
+			Some(registry) => Self::from_matter_in(matter, registry),
+			None => Self::from_matter(matter),
+		}
+	}
+}
+
+impl RowSource for EnumMatter {
+	fn rows(&self) -> u64 {
+		EnumMatter::rows(self) as u64
+	}
+
+	fn cols(&self) -> usize {
+		EnumMatter::cols(self)
+	}
+
+	fn cell_at(&self, row: u64, col: usize) -> Result<Bytes32, ElementError> {
+		let row = usize::try_from(row).map_err(|_| ElementError::RowOutOfBounds)?;
+		EnumMatter::cell_at(self, row, col)
+			.copied()
+			.map_err(|_| ElementError::EnumMatterRowAt)
+	}
+
+	fn row_at(&self, row: u64) -> Result<Vec<Bytes32>, ElementError> {
+		let row = usize::try_from(row).map_err(|_| ElementError::RowOutOfBounds)?;
+		EnumMatter::row_at(self, row)
+			.map_err(|_| ElementError::EnumMatterRowAt)
+			.map(|v| v.into_iter().copied().collect())
+	}
+}
+
+impl RowSource for PermMatter {
+	fn rows(&self) -> u64 {
+		PermMatter::rows(self)
+	}
+
+	fn cols(&self) -> usize {
+		PermMatter::cols(self)
+	}
+
+	fn cell_at(&self, row: u64, col: usize) -> Result<Bytes32, ElementError> {
+		PermMatter::cell_at(self, row, col)
+			.copied()
+			.map_err(|_| ElementError::PermMatterRowAt)
+	}
+
+	fn row_at(&self, row: u64) -> Result<Vec<Bytes32>, ElementError> {
+		PermMatter::row_at(self, row)
+			.map_err(|_| ElementError::PermMatterRowAt)
+			.map(|v| v.into_iter().copied().collect())
+	}
+}
@@ -0,0 +1,256 @@
+#![cfg(feature = "rpc")]
+
+use crate::{
+	reader::{BlockRef, StateProvider, StateReaderAt},
+	H256,
+};
+use serde_json::{json, Value};
+use std::cell::RefCell;
+use thiserror::Error;
+
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+pub enum RpcError {
+	#[error("transport error: {0}")]
+	Transport(String),
+	#[error("rpc error {code}: {message}")]
+	Rpc { code: i64, message: String },
+	#[error("unexpected response: {0}")]
+	BadResponse(String),
+}
+
+/// Sends one JSON-RPC request and returns its `result`.
+pub trait RpcTransport {
+	fn call(&mut self, method: &str, params: Value) -> Result<Value, RpcError>;
+}
+
+impl<T: RpcTransport + ?Sized> RpcTransport for &mut T {
+	fn call(&mut self, method: &str, params: Value) -> Result<Value, RpcError> {
+		(**self).call(method, params)
+	}
+}
+
+/// JSON-RPC over HTTP. Plain `http://` only unless ureq's TLS support is enabled.
+pub struct HttpTransport {
+	url: String,
+	next_id: u64,
+}
+
+impl HttpTransport {
+	pub fn new(url: impl Into<String>) -> Self {
+		Self { url: url.into(), next_id: 1 }
+	}
+}
+
+impl RpcTransport for HttpTransport {
+	fn call(&mut self, method: &str, params: Value) -> Result<Value, RpcError> {
+		let id = self.next_id;
+		self.next_id += 1;
+		let body = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+		let reply: Value = ureq::post(&self.url)
+			.send_json(body)
+			.map_err(|e| RpcError::Transport(e.to_string()))?
+			.into_json()
+			.map_err(|e| RpcError::Transport(e.to_string()))?;
+		if let Some(err) = reply.get("error") {
+			let code = err["code"].as_i64().unwrap_or_default();
+			let message = err["message"].as_str().unwrap_or_default().into();
+			return Err(RpcError::Rpc { code, message });
+		}
+		reply
+			.get("result")
+			.cloned()
+			.ok_or_else(|| RpcError::BadResponse(reply.to_string()))
+	}
+}
+
+/// Every state read from a node through `state_getStorage`, so the readers and pickers in this
+/// crate can run against a live chain.
+///
+/// Reads follow the node's best block unless the state is pinned with `StateReaderAt::at`.
+/// Like `DbState`, the first failed call is kept for `take_error` and the read fails as missing.
+pub struct RpcState<T> {
+	transport: RefCell<T>,
+	at: Option<H256>,
+	error: RefCell<Option<RpcError>>,
+}
+
+impl<T: RpcTransport> RpcState<T> {
+	pub fn new(transport: T) -> Self {
+		Self { transport: RefCell::new(transport), at: None, error: RefCell::new(None) }
+	}
+
+	/// Hash of the block reads are pinned to.
+	pub fn pinned(&self) -> Option<H256> {
+		self.at
+	}
+
+	pub fn take_error(&mut self) -> Option<RpcError> {
+		self.error.get_mut().take()
+	}
+
+	/// Values of several keys in one `state_queryStorageAt` call, in the order of `keys`.
+	pub fn get_many(&self, keys: &[Vec<u8>]) -> Result<Vec<Option<Vec<u8>>>, RpcError> {
+		let hex_keys: Vec<String> = keys.iter().map(|k| to_hex(k)).collect();
+		let sets = self.call_at("state_queryStorageAt", vec![json!(hex_keys)])?;
+		let mut found = std::collections::BTreeMap::new();
+		for set in sets.as_array().into_iter().flatten() {
+			for change in set["changes"].as_array().into_iter().flatten() {
+				let key = from_hex(&change[0])?.unwrap_or_default();
+				found.insert(key, from_hex(&change[1])?);
+			}
+		}
+		Ok(keys.iter().map(|k| found.get(k).cloned().flatten()).collect())
+	}
+
+	fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, RpcError> {
+		from_hex(&self.call_at("state_getStorage", vec![to_hex(key).into()])?)
+	}
+
+	fn next_key(&self, key: &[u8]) -> Result<Option<Vec<u8>>, RpcError> {
+		let keys =
+			self.call_at("state_getKeysPaged", vec!["0x".into(), 1.into(), to_hex(key).into()])?;
+		match keys.as_array().and_then(|keys| keys.first()) {
+			Some(key) => from_hex(key),
+			None => Ok(None),
+		}
+	}
+
+	/// Calls `method`, adding the pinned block hash as the last parameter.
+	fn call_at(&self, method: &str, mut params: Vec<Value>) -> Result<Value, RpcError> {
+		if let Some(at) = self.at {
+			params.push(to_hex(&at).into());
+		}
+		self.transport.borrow_mut().call(method, params.into())
+	}
+
+	fn check(&self, result: Result<Option<Vec<u8>>, RpcError>) -> Option<Vec<u8>> {
+		match result {
+			Ok(value) => value,
+			Err(err) => {
+				self.error.borrow_mut().get_or_insert(err);
+				None
+			},
+		}
+	}
+}
+
+impl<T: RpcTransport> StateProvider for RpcState<T> {
+	fn _get(&self, key: &[u8]) -> Option<Vec<u8>> {
+		self.check(self.get(key))
+	}
+
+	fn _next_key(&self, key: &[u8]) -> Option<Vec<u8>> {
+		self.check(self.next_key(key))
+	}
+}
+
+impl<T: RpcTransport> StateReaderAt for RpcState<T> {
+	type Error = RpcError;
+	type View<'a>
+		= RpcState<&'a mut T>
+	where
+		T: 'a;
+
+	/// Pins to `block`, looking up the hash of a block number with `chain_getBlockHash`.
+	fn at(&mut self, block: BlockRef) -> Result<RpcState<&mut T>, RpcError> {
+		let transport = self.transport.get_mut();
+		let hash = match block {
+			BlockRef::Hash(hash) => hash,
+			BlockRef::Number(number) => {
+				let hash = transport.call("chain_getBlockHash", json!([number]))?;
+				from_hex(&hash)?
+					.and_then(|bytes| bytes.try_into().ok())
+					.ok_or_else(|| RpcError::BadResponse(hash.to_string()))?
+			},
+		};
+		Ok(RpcState { at: Some(hash), ..RpcState::new(transport) })
+	}
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+	format!("0x{}", hex::encode(bytes))
+}
+
+/// Decodes a `0x`-prefixed hex string; `null` is `None`.
+fn from_hex(value: &Value) -> Result<Option<Vec<u8>>, RpcError> {
+	if value.is_null() {
+		return Ok(None);
+	}
+	let bad = || RpcError::BadResponse(value.to_string());
+	let text = value.as_str().and_then(|s| s.strip_prefix("0x")).ok_or_else(bad)?;
+	hex::decode(text).map(Some).map_err(|_| bad())
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{
+		reader::{BlockRef, StateError, StateProvider, StateReaderAt},
+		rpc_state::{from_hex, to_hex, RpcError, RpcState, RpcTransport},
+		state::{ObjectKey, ObjectValue, OidRev, Sota},
+		storage_key::object_storage_key,
+		Descriptor, Rev, StateReader, OID,
+	};
+	use codec::Encode;
+	use serde_json::{json, Value};
+	use std::collections::BTreeMap;
+
+	/// Answers the storage calls from in-memory states, one per block; block `n` has hash
+	/// `[n; 32]` and the last block is the best one.
+	struct Node(Vec<BTreeMap<Vec<u8>, Vec<u8>>>);
+
+	impl RpcTransport for Node {
+		fn call(&mut self, method: &str, params: Value) -> Result<Value, RpcError> {
+			let hex_of = |v: Option<&Vec<u8>>| v.map_or(Value::Null, |v| to_hex(v).into());
+			let state = |at: &Value| match from_hex(at).unwrap() {
+				Some(hash) => &self.0[hash[0] as usize],
+				None => self.0.last().unwrap(),
+			};
+			match method {
+				"chain_getBlockHash" => Ok(to_hex(&[params[0].as_u64().unwrap() as u8; 32]).into()),
+				"state_getStorage" => {
+					let key = from_hex(&params[0])?.unwrap_or_default();
+					Ok(hex_of(state(&params[1]).get(&key)))
+				},
+				"state_queryStorageAt" => {
+					let state = state(&params[1]);
+					let changes: Vec<Value> = params[0]
+						.as_array()
+						.unwrap()
+						.iter()
+						.map(|k| json!([k, hex_of(state.get(&from_hex(k).unwrap().unwrap()))]))
+						.collect();
+					Ok(json!([{ "block": "0x00", "changes": changes }]))
+				},
+				_ => Err(RpcError::Rpc { code: -32601, message: "Method not found".into() }),
+			}
+		}
+	}
+
+	#[test]
+	fn reads_through_rpc() {
+		let oid = OID { universe: 1, set: 2, id: 3 };
+		let key = ObjectKey::Sota(OidRev::new(&oid, Rev::LATEST));
+		let desc = Descriptor { rev: Rev(4), ..Default::default() };
+		let value = ObjectValue::Sota(Sota { desc: desc.clone(), ..Default::default() });
+		let node = Node(vec![
+			BTreeMap::new(),
+			BTreeMap::from([(object_storage_key(key.clone()), value.encode())]),
+		]);
+		let mut state = RpcState::new(node);
+
+		let read = StateReader::<StateError>::get_descriptor(&state, &oid, Rev::LATEST);
+		assert_eq!(read.unwrap(), desc);
+		let many = state.get_many(&[object_storage_key(key), vec![1, 2]]).unwrap();
+		assert_eq!(many, vec![Some(value.encode()), None]);
+
+		assert_eq!(state._next_key(&[]), None);
+		assert!(matches!(state.take_error(), Some(RpcError::Rpc { code: -32601, .. })));
+
+		let old = state.at(BlockRef::Number(0)).unwrap();
+		assert_eq!(old.pinned(), Some([0; 32]));
+		let read = StateReader::<StateError>::get_descriptor(&old, &oid, Rev::LATEST);
+		assert!(read.is_err());
+		let head = state.at(BlockRef::Hash([1; 32])).unwrap();
+		assert!(StateReader::<StateError>::get_descriptor(&head, &oid, Rev::LATEST).is_ok());
+	}
+}
@@ -0,0 +1,313 @@
+use crate::{Bytes32, ElementType, KindSpec, MatterForm, StateReader, Vec};
+use thiserror::Error;
+
+#[derive(Clone, Copy, Debug, Error, PartialEq, Eq)]
+pub enum SchemaError {
+	#[error("invalid Schema header")]
+	BadHeader,
+	#[error("bad magic: expected 'SCHM' (53 43 48 4D), got {0:02X?}")]
+	BadMagic([u8; 4]),
+	#[error("unsupported version {0} (expected 1)")]
+	BadVersion(u8),
+	#[error("slot count {0} exceeds maximum of 16")]
+	BadSlotCount(u8),
+	#[error("invalid body length: expected {expect} bytes, got {got} bytes")]
+	BadBody { expect: usize, got: usize },
+	#[error("slot {0}: unknown element type, flags or empty range")]
+	BadSlot(usize),
+	#[error("expected {expect} elements, got {got}")]
+	CountMismatch { expect: usize, got: usize },
+	#[error("slot {0}: required element is zero")]
+	Missing(usize),
+	#[error("slot {0}: value does not fit the declared range")]
+	OutOfRange(usize),
+	#[error("slot {0}: referenced matter has the wrong form")]
+	WrongForm(usize),
+	#[error("slot {0}: referenced matter not found")]
+	NoMatter(usize),
+}
+
+/// One element of a snapshot that does not fit its kind's element spec.
+#[derive(Clone, Copy, Debug, Error, PartialEq, Eq)]
+pub enum SlotError {
+	#[error("slot {0}: not declared by the kind")]
+	Extra(usize),
+	#[error("slot {0}: missing and the kind has no default")]
+	Missing(usize),
+	#[error("slot {index}: unknown element type {elem_type:#04x}")]
+	UnknownType { index: usize, elem_type: u8 },
+	#[error("slot {0}: referenced matter not found")]
+	NoMatter(usize),
+	#[error("slot {index}: expected {expect} matter, got form {got:#04x}")]
+	WrongForm { index: usize, expect: MatterForm, got: u8 },
+}
+
+/// Declared element slot of a kind. `elem_type` 0 accepts any cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SchemaSlot {
+	pub elem_type: u8,
+	pub flags: u8,
+	pub name_hash: Bytes32,
+	pub min: u64,
+	pub max: u64,
+}
+
+impl SchemaSlot {
+	/// The cell must not be all zeros.
+	pub const REQUIRED: u8 = 0x01;
+	/// The cell is a number (as in formulas: big-endian in the last 8 bytes) in `min..=max`.
+	pub const NUMERIC: u8 = 0x02;
+	const KNOWN_FLAGS: u8 = Self::REQUIRED | Self::NUMERIC;
+
+	/// Form of the matter the cell must point at, if the slot's type is a matter form.
+	pub fn matter_form(&self) -> Option<MatterForm> {
+		MatterForm::try_from(self.elem_type).ok()
+	}
+
+	fn check(&self, index: usize, cell: &Bytes32) -> Result<(), SchemaError> {
+		if self.flags & Self::REQUIRED != 0 && cell.iter().all(|&b| b == 0) {
+			return Err(SchemaError::Missing(index));
+		}
+		if self.flags & Self::NUMERIC != 0 {
+			let fits = cell[..24].iter().all(|&b| b == 0);
+			let value = u64::from_be_bytes(cell[24..32].try_into().unwrap());
+			if !fits || value < self.min || value > self.max {
+				return Err(SchemaError::OutOfRange(index));
+			}
+		}
+		Ok(())
+	}
+}
+
+/// Element spec of a kind.
+///
+/// Layout: magic "SCHM", version (1), slots, 2 zero bytes, then per slot: element type, flags,
+/// 6 zero bytes, name hash[32], min and max (each u64 LE).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schema {
+	pub slots: Vec<SchemaSlot>,
+}
+
+impl Schema {
+	pub const MAGIC: [u8; 4] = *b"SCHM";
+	pub const HEADER_SIZE: usize = 8;
+	pub const SLOT_SIZE: usize = 56;
+
+	pub fn from(blob: &[u8]) -> Result<Self, SchemaError> {
+		if blob.len() < Self::HEADER_SIZE {
+			return Err(SchemaError::BadHeader);
+		}
+		let magic: [u8; 4] = blob[0..4].try_into().unwrap();
+		if magic != Self::MAGIC {
+			return Err(SchemaError::BadMagic(magic));
+		}
+		if blob[4] != 1 {
+			return Err(SchemaError::BadVersion(blob[4]));
+		}
+		let n = blob[5];
+		if n > 16 {
+			return Err(SchemaError::BadSlotCount(n));
+		}
+		if blob[6..8] != [0, 0] {
+			return Err(SchemaError::BadHeader);
+		}
+		let expect = Self::HEADER_SIZE + n as usize * Self::SLOT_SIZE;
+		if blob.len() != expect {
+			return Err(SchemaError::BadBody { expect, got: blob.len() });
+		}
+
+		let word = |s: &[u8], at: usize| u64::from_le_bytes(s[at..at + 8].try_into().unwrap());
+		let mut slots = Vec::with_capacity(n as usize);
+		for (i, s) in blob[Self::HEADER_SIZE..].chunks_exact(Self::SLOT_SIZE).enumerate() {
+			let slot = SchemaSlot {
+				elem_type: s[0],
+				flags: s[1],
+				name_hash: s[8..40].try_into().unwrap(),
+				min: word(s, 40),
+				max: word(s, 48),
+			};
+			let known_type = slot.elem_type == 0 || ElementType::try_from(slot.elem_type).is_ok();
+			if !known_type ||
+				slot.flags & !SchemaSlot::KNOWN_FLAGS != 0 ||
+				s[2..8].iter().any(|&b| b != 0) ||
+				slot.min > slot.max
+			{
+				return Err(SchemaError::BadSlot(i));
+			}
+			slots.push(slot);
+		}
+		Ok(Self { slots })
+	}
+
+	pub fn to_blob(&self) -> Vec<u8> {
+		let mut blob = Vec::with_capacity(Self::HEADER_SIZE + self.slots.len() * Self::SLOT_SIZE);
+		blob.extend_from_slice(&Self::MAGIC);
+		blob.push(1);
+		blob.push(self.slots.len() as u8);
+		blob.extend_from_slice(&[0, 0]);
+		for s in &self.slots {
+			blob.push(s.elem_type);
+			blob.push(s.flags);
+			blob.extend_from_slice(&[0u8; 6]);
+			blob.extend_from_slice(&s.name_hash);
+			blob.extend_from_slice(&s.min.to_le_bytes());
+			blob.extend_from_slice(&s.max.to_le_bytes());
+		}
+		blob
+	}
+
+	/// Checks that referenced matters have the form their slot declares. Only slots typed with a
+	/// matter form are looked up, and zero cells are left to the `REQUIRED` flag.
+	pub fn check_matters<E, S: StateReader<E>>(
+		&self,
+		state: &S,
+		elems: &[Bytes32],
+	) -> Result<(), SchemaError> {
+		for (i, (slot, cell)) in self.slots.iter().zip(elems).enumerate() {
+			let Some(form) = slot.matter_form() else { continue };
+			if cell.iter().all(|&b| b == 0) {
+				continue;
+			}
+			let matter = state.get_matter(cell).map_err(|_| SchemaError::NoMatter(i))?;
+			if matter.form != form as u8 {
+				return Err(SchemaError::WrongForm(i));
+			}
+		}
+		Ok(())
+	}
+}
+
+/// Checks element count and per-slot constraints. Matter forms need state; see
+/// `Schema::check_matters`.
+pub fn validate_elements(schema: &Schema, elems: &[Bytes32]) -> Result<(), SchemaError> {
+	if elems.len() != schema.slots.len() {
+		return Err(SchemaError::CountMismatch { expect: schema.slots.len(), got: elems.len() });
+	}
+	for (i, (slot, cell)) in schema.slots.iter().zip(elems).enumerate() {
+		slot.check(i, cell)?;
+	}
+	Ok(())
+}
+
+/// Checks `elems` against the element types of `kind`, reporting every failing slot in order.
+///
+/// Elements past the spec are extra; missing trailing ones must have a kind default, which is
+/// then checked in their place. Slots typed with a matter form must point at a matter of that
+/// form, or be zero; other types accept any cell.
+pub fn check_kind_elements<E, S: StateReader<E>>(
+	state: &S,
+	kind: &KindSpec,
+	elems: &[Bytes32],
+) -> Vec<SlotError> {
+	let mut errors = Vec::new();
+	for index in 0..elems.len().max(kind.elem_count()) {
+		let Some(&elem_type) = kind.elem_types.get(index) else {
+			errors.push(SlotError::Extra(index));
+			continue;
+		};
+		let Ok(ty) = ElementType::try_from(elem_type) else {
+			errors.push(SlotError::UnknownType { index, elem_type });
+			continue;
+		};
+		let Some(cell) = elems.get(index).or(kind.defaults.get(index)) else {
+			errors.push(SlotError::Missing(index));
+			continue;
+		};
+		let Ok(expect) = MatterForm::try_from(ty) else { continue };
+		if cell.iter().all(|&b| b == 0) {
+			continue;
+		}
+		match state.get_matter(cell) {
+			Err(_) => errors.push(SlotError::NoMatter(index)),
+			Ok(m) if m.form != expect as u8 =>
+				errors.push(SlotError::WrongForm { index, expect, got: m.form }),
+			Ok(_) => {},
+		}
+	}
+	errors
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{
+		check_kind_elements, validate_elements, ElementType, KindSpec, Matter, MatterForm, Mime,
+		Prefetched, Schema, SchemaError, SchemaSlot, SlotError,
+	};
+
+	#[test]
+	fn validates_slots() {
+		let slot = |elem_type, flags, min, max| SchemaSlot {
+			elem_type,
+			flags,
+			name_hash: [elem_type; 32],
+			min,
+			max,
+		};
+		let schema = Schema {
+			slots: vec![
+				slot(ElementType::Info as u8, SchemaSlot::REQUIRED, 0, 0),
+				slot(0, SchemaSlot::NUMERIC, 1, 5),
+			],
+		};
+		let schema = Schema::from(&schema.to_blob()).unwrap();
+		let num = |v: u8| {
+			let mut c = [0u8; 32];
+			c[31] = v;
+			c
+		};
+
+		assert_eq!(validate_elements(&schema, &[[7; 32], num(5)]), Ok(()));
+		assert_eq!(validate_elements(&schema, &[[0; 32], num(5)]), Err(SchemaError::Missing(0)));
+		assert_eq!(validate_elements(&schema, &[[7; 32], num(6)]), Err(SchemaError::OutOfRange(1)));
+		assert_eq!(
+			validate_elements(&schema, &[[7; 32]]),
+			Err(SchemaError::CountMismatch { expect: 2, got: 1 })
+		);
+
+		let mut blob = schema.to_blob();
+		blob[8] = 0x7F;
+		assert_eq!(Schema::from(&blob), Err(SchemaError::BadSlot(0)));
+	}
+
+	#[test]
+	fn checks_kind_elements() {
+		let mut state = Prefetched::default();
+		let matter =
+			|form: MatterForm| Matter { form: form as u8, mime: Mime::default(), blob: vec![] };
+		state.insert_matter([1; 32], matter(MatterForm::Image));
+		let kind = KindSpec {
+			code: [0; 32],
+			data: [0; 32],
+			elem_types: vec![ElementType::Image as u8, ElementType::Info as u8, 0x7F],
+			relations: vec![],
+			defaults: vec![[0; 32], [9; 32]],
+		};
+
+		assert_eq!(
+			check_kind_elements(&state, &kind, &[[1; 32]]),
+			vec![SlotError::UnknownType { index: 2, elem_type: 0x7F }]
+		);
+		let short = KindSpec { defaults: vec![], ..kind.clone() };
+		assert_eq!(
+			check_kind_elements(&state, &short, &[[2; 32]]),
+			vec![
+				SlotError::NoMatter(0),
+				SlotError::Missing(1),
+				SlotError::UnknownType { index: 2, elem_type: 0x7F },
+			]
+		);
+		state.insert_matter([2; 32], matter(MatterForm::Json));
+		assert_eq!(
+			check_kind_elements(&state, &kind, &[[2; 32], [0; 32], [0; 32], [0; 32]]),
+			vec![
+				SlotError::WrongForm {
+					index: 0,
+					expect: MatterForm::Image,
+					got: MatterForm::Json as u8
+				},
+				SlotError::UnknownType { index: 2, elem_type: 0x7F },
+				SlotError::Extra(3),
+			]
+		);
+	}
+}
@@ -0,0 +1,41 @@
+/// Facet selector for a facet name or mime type: FNV-1a (32-bit) over `"every.facet:"` followed
+/// by the ASCII-lowercased name. Usable in constants.
+pub const fn facet_selector(name_or_mime: &str) -> u32 {
+	const PRIME: u32 = 0x0100_0193;
+	let mut hash: u32 = 0x811C_9DC5;
+	let key = b"every.facet:";
+	let mut i = 0;
+	while i < key.len() {
+		hash = (hash ^ key[i] as u32).wrapping_mul(PRIME);
+		i += 1;
+	}
+	let name = name_or_mime.as_bytes();
+	let mut i = 0;
+	while i < name.len() {
+		hash = (hash ^ name[i].to_ascii_lowercase() as u32).wrapping_mul(PRIME);
+		i += 1;
+	}
+	hash
+}
+
+pub struct FacetSelector;
+
+impl FacetSelector {
+	pub const THUMBNAIL: u32 = facet_selector("thumbnail");
+	pub const ANIMATION: u32 = facet_selector("animation");
+	pub const METADATA: u32 = facet_selector("metadata");
+	pub const PREVIEW: u32 = facet_selector("preview");
+	pub const MODEL: u32 = facet_selector("model");
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{facet_selector, FacetSelector};
+
+	#[test]
+	fn selectors_are_stable() {
+		assert_eq!(facet_selector(""), 0x3E11_1CC9);
+		assert_eq!(FacetSelector::THUMBNAIL, facet_selector("Thumbnail"));
+		assert_ne!(facet_selector("image/png"), facet_selector("image/gif"));
+	}
+}
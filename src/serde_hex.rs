@@ -0,0 +1,271 @@
+#![cfg(feature = "serde")]
+
+//! `#[serde(with = "...")]` helpers for byte fields. Human-readable formats such as JSON get
+//! `0x`-prefixed hex for hashes and blobs and plain text for fixed-size strings; binary formats
+//! keep the layout serde derives for arrays and vectors. With `schemars`, each helper also has
+//! a `schema` function for `#[schemars(schema_with = "...")]` describing the readable form.
+
+use crate::{parse_hex_into, slice_from_fixed, to_fixed, Hex, Vec};
+use core::{fmt, marker::PhantomData};
+#[cfg(feature = "schemars")]
+use schemars::{json_schema, Schema, SchemaGenerator};
+use serde::{
+	de::{self, Deserializer, SeqAccess, Visitor},
+	ser::{SerializeTuple, Serializer},
+	Deserialize, Serialize,
+};
+
+fn decode_hex<E: de::Error>(s: &str, out: &mut [u8]) -> Result<(), E> {
+	match s.starts_with("0x") && parse_hex_into(s, out).is_ok() {
+		true => Ok(()),
+		false => Err(E::invalid_value(de::Unexpected::Str(s), &"0x-prefixed hex")),
+	}
+}
+
+fn serialize_tuple<S: Serializer, const N: usize>(v: &[u8; N], s: S) -> Result<S::Ok, S::Error> {
+	let mut tuple = s.serialize_tuple(N)?;
+	v.iter().try_for_each(|b| tuple.serialize_element(b))?;
+	tuple.end()
+}
+
+/// Reads the array layout, or a string handed to `from_str` in human-readable formats.
+struct FixedVisitor<const N: usize>(fn(&str) -> Option<[u8; N]>, &'static str);
+
+impl<'de, const N: usize> Visitor<'de> for FixedVisitor<N> {
+	type Value = [u8; N];
+
+	fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str(self.1)
+	}
+
+	fn visit_str<E: de::Error>(self, v: &str) -> Result<[u8; N], E> {
+		self.0(v).ok_or_else(|| E::invalid_value(de::Unexpected::Str(v), &self))
+	}
+
+	fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<[u8; N], A::Error> {
+		let mut out = [0u8; N];
+		for (i, b) in out.iter_mut().enumerate() {
+			*b = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(i, &self))?;
+		}
+		Ok(out)
+	}
+}
+
+/// Hashes and other fixed-size byte arrays (`Bytes32`, `H256`) as hex.
+pub mod bytes {
+	use super::*;
+
+	pub fn serialize<S: Serializer, const N: usize>(v: &[u8; N], s: S) -> Result<S::Ok, S::Error> {
+		if s.is_human_readable() {
+			s.collect_str(&Hex(v))
+		} else {
+			serialize_tuple(v, s)
+		}
+	}
+
+	pub fn deserialize<'de, D: Deserializer<'de>, const N: usize>(
+		d: D,
+	) -> Result<[u8; N], D::Error> {
+		fn from_hex<const N: usize>(s: &str) -> Option<[u8; N]> {
+			let mut out = [0u8; N];
+			decode_hex::<de::value::Error>(s, &mut out).ok().map(|_| out)
+		}
+		if d.is_human_readable() {
+			d.deserialize_str(FixedVisitor(from_hex::<N>, "0x-prefixed hex"))
+		} else {
+			d.deserialize_tuple(N, FixedVisitor(from_hex::<N>, "a byte array"))
+		}
+	}
+
+	/// Schema of a 32-byte value, the only size the crate's types use.
+	#[cfg(feature = "schemars")]
+	pub fn schema(_: &mut SchemaGenerator) -> Schema {
+		json_schema!({ "type": "string", "pattern": "^0x[0-9a-fA-F]{64}$" })
+	}
+
+	#[derive(Serialize, Deserialize)]
+	#[serde(transparent)]
+	pub(super) struct Wrap<const N: usize>(#[serde(with = "self")] pub [u8; N]);
+}
+
+/// `Option` of a fixed-size byte array as hex.
+pub mod bytes_option {
+	use super::{bytes::Wrap, *};
+
+	pub fn serialize<S: Serializer, const N: usize>(
+		v: &Option<[u8; N]>,
+		s: S,
+	) -> Result<S::Ok, S::Error> {
+		v.map(Wrap).serialize(s)
+	}
+
+	pub fn deserialize<'de, D: Deserializer<'de>, const N: usize>(
+		d: D,
+	) -> Result<Option<[u8; N]>, D::Error> {
+		Ok(Option::<Wrap<N>>::deserialize(d)?.map(|w| w.0))
+	}
+}
+
+/// A list of fixed-size byte arrays, each as hex.
+pub mod bytes_vec {
+	use super::{bytes::Wrap, *};
+
+	pub fn serialize<S: Serializer, const N: usize>(
+		v: &[[u8; N]],
+		s: S,
+	) -> Result<S::Ok, S::Error> {
+		s.collect_seq(v.iter().map(|b| Wrap(*b)))
+	}
+
+	pub fn deserialize<'de, D: Deserializer<'de>, const N: usize>(
+		d: D,
+	) -> Result<Vec<[u8; N]>, D::Error> {
+		Ok(Vec::<Wrap<N>>::deserialize(d)?.into_iter().map(|w| w.0).collect())
+	}
+
+	#[cfg(feature = "schemars")]
+	pub fn schema(generator: &mut SchemaGenerator) -> Schema {
+		json_schema!({ "type": "array", "items": super::bytes::schema(generator) })
+	}
+}
+
+/// Rows of fixed-size byte arrays, such as matter cells, each as hex.
+pub mod bytes_rows {
+	use super::*;
+
+	struct RowRef<'a, const N: usize>(&'a [[u8; N]]);
+
+	impl<const N: usize> Serialize for RowRef<'_, N> {
+		fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+			super::bytes_vec::serialize(self.0, s)
+		}
+	}
+
+	#[derive(Deserialize)]
+	#[serde(transparent)]
+	struct Row<const N: usize>(#[serde(with = "super::bytes_vec")] Vec<[u8; N]>);
+
+	pub fn serialize<S: Serializer, const N: usize>(
+		v: &[Vec<[u8; N]>],
+		s: S,
+	) -> Result<S::Ok, S::Error> {
+		s.collect_seq(v.iter().map(|row| RowRef(row)))
+	}
+
+	pub fn deserialize<'de, D: Deserializer<'de>, const N: usize>(
+		d: D,
+	) -> Result<Vec<Vec<[u8; N]>>, D::Error> {
+		Ok(Vec::<Row<N>>::deserialize(d)?.into_iter().map(|r| r.0).collect())
+	}
+}
+
+/// Variable-length bytes, such as matter blobs, as hex.
+pub mod blob {
+	use super::*;
+
+	pub fn serialize<S: Serializer>(v: &[u8], s: S) -> Result<S::Ok, S::Error> {
+		if s.is_human_readable() {
+			s.collect_str(&Hex(v))
+		} else {
+			s.collect_seq(v)
+		}
+	}
+
+	pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<u8>, D::Error> {
+		struct BlobVisitor;
+
+		impl Visitor<'_> for BlobVisitor {
+			type Value = Vec<u8>;
+
+			fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+				f.write_str("0x-prefixed hex")
+			}
+
+			fn visit_str<E: de::Error>(self, v: &str) -> Result<Vec<u8>, E> {
+				let mut out = sp_std::vec![0u8; v.len().saturating_sub(2) / 2];
+				decode_hex(v, &mut out)?;
+				Ok(out)
+			}
+		}
+
+		if d.is_human_readable() {
+			d.deserialize_str(BlobVisitor)
+		} else {
+			Vec::deserialize(d)
+		}
+	}
+
+	#[cfg(feature = "schemars")]
+	pub fn schema(_: &mut SchemaGenerator) -> Schema {
+		json_schema!({ "type": "string", "pattern": "^0x([0-9a-fA-F]{2})*$" })
+	}
+}
+
+/// Zero-padded fixed-size strings (`FixedStr`) as UTF-8 text.
+pub mod text {
+	use super::*;
+
+	pub fn serialize<S: Serializer, const N: usize>(v: &[u8; N], s: S) -> Result<S::Ok, S::Error> {
+		if !s.is_human_readable() {
+			return serialize_tuple(v, s);
+		}
+		match core::str::from_utf8(slice_from_fixed(v)) {
+			Ok(text) => s.serialize_str(text),
+			Err(_) => Err(serde::ser::Error::custom("fixed string is not UTF-8")),
+		}
+	}
+
+	pub fn deserialize<'de, D: Deserializer<'de>, const N: usize>(
+		d: D,
+	) -> Result<[u8; N], D::Error> {
+		fn from_text<const N: usize>(s: &str) -> Option<[u8; N]> {
+			(s.len() <= N && !s.contains('\0')).then(|| to_fixed(s.as_bytes()))
+		}
+		if d.is_human_readable() {
+			d.deserialize_str(FixedVisitor(from_text::<N>, "a string that fits the field"))
+		} else {
+			d.deserialize_tuple(N, FixedVisitor(from_text::<N>, "a byte array"))
+		}
+	}
+
+	/// `maxLength` counts characters, so multi-byte text can pass it and still not fit.
+	#[cfg(feature = "schemars")]
+	pub fn schema<const N: usize>(_: &mut SchemaGenerator) -> Schema {
+		json_schema!({ "type": "string", "maxLength": N })
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{to_mime, Facet, Matter};
+	use serde_json::json;
+
+	#[test]
+	fn json_uses_hex_and_text() {
+		let matter = Matter { form: 1, mime: to_mime(b"text/plain"), blob: b"hi".to_vec() };
+		let value = serde_json::to_value(&matter).unwrap();
+		assert_eq!(value, json!({ "form": 1, "mime": "text/plain", "blob": "0x6869" }));
+		assert_eq!(serde_json::from_value::<Matter>(value).unwrap(), matter);
+
+		let facet = Facet { sel: 7, hash: [0xab; 32] };
+		let value = serde_json::to_value(&facet).unwrap();
+		assert_eq!(value["hash"], format!("0x{}", "ab".repeat(32)));
+		assert_eq!(serde_json::from_value::<Facet>(value).unwrap(), facet);
+
+		let short = json!({ "sel": 7, "hash": "0xabcd" });
+		assert!(serde_json::from_value::<Facet>(short).is_err());
+		let long_mime = json!({ "form": 1, "mime": "x".repeat(32), "blob": "0x" });
+		assert!(serde_json::from_value::<Matter>(long_mime).is_err());
+	}
+
+	#[cfg(feature = "schemars")]
+	#[test]
+	fn schemas_match_readable_forms() {
+		let matter = serde_json::to_value(schemars::schema_for!(Matter)).unwrap();
+		assert_eq!(matter["properties"]["mime"]["maxLength"], 31);
+		assert_eq!(matter["properties"]["blob"]["pattern"], "^0x([0-9a-fA-F]{2})*$");
+		let snapshot = serde_json::to_value(schemars::schema_for!(crate::state::Snapshot)).unwrap();
+		assert_eq!(snapshot["properties"]["elems"]["items"]["pattern"], "^0x[0-9a-fA-F]{64}$");
+		assert!(snapshot["$defs"]["Descriptor"].is_object());
+	}
+}
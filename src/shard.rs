@@ -0,0 +1,105 @@
+use crate::{Vec, H256, OID};
+
+/// A split of state into `count` shards, so indexer workers can each take one. Assignment only
+/// depends on the key and the count: every worker computes the same split, and growing the
+/// count from `n` to `n + 1` only moves keys into the new shard (jump consistent hashing).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Shards {
+	count: u32,
+}
+
+impl Shards {
+	/// `None` for zero shards.
+	pub fn new(count: u32) -> Option<Self> {
+		(count > 0).then_some(Self { count })
+	}
+
+	pub fn count(&self) -> u32 {
+		self.count
+	}
+
+	pub fn of_oid(&self, oid: &OID) -> u32 {
+		jump(oid_key(oid), self.count)
+	}
+
+	/// Matter hashes are already uniform, so their first eight bytes are the key.
+	pub fn of_matter(&self, hash: &H256) -> u32 {
+		jump(u64::from_be_bytes(hash[..8].try_into().unwrap()), self.count)
+	}
+
+	/// The objects of `oids` in `shard`, in input order.
+	pub fn oids_in<'a>(
+		&self,
+		shard: u32,
+		oids: impl IntoIterator<Item = &'a OID>,
+	) -> impl Iterator<Item = &'a OID> {
+		let shards = *self;
+		oids.into_iter().filter(move |oid| shards.of_oid(oid) == shard)
+	}
+
+	/// The matters of `hashes` in `shard`, in input order.
+	pub fn matters_in<'a>(
+		&self,
+		shard: u32,
+		hashes: impl IntoIterator<Item = &'a H256>,
+	) -> impl Iterator<Item = &'a H256> {
+		let shards = *self;
+		hashes.into_iter().filter(move |hash| shards.of_matter(hash) == shard)
+	}
+
+	/// `oids` grouped by shard, indexed by shard number.
+	pub fn split_oids<'a>(&self, oids: impl IntoIterator<Item = &'a OID>) -> Vec<Vec<&'a OID>> {
+		let mut parts: Vec<Vec<&OID>> = (0..self.count).map(|_| Vec::new()).collect();
+		for oid in oids {
+			parts[self.of_oid(oid) as usize].push(oid);
+		}
+		parts
+	}
+}
+
+// SplitMix64 finalizer.
+fn mix(mut x: u64) -> u64 {
+	x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+	x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+	x ^ (x >> 31)
+}
+
+/// Stable 64-bit key of an object, mixing universe, set and id.
+pub fn oid_key(oid: &OID) -> u64 {
+	mix(mix(mix(oid.universe) ^ oid.set) ^ oid.id)
+}
+
+// Jump consistent hash (Lamping & Veach).
+fn jump(mut key: u64, buckets: u32) -> u32 {
+	let (mut b, mut j) = (-1i64, 0i64);
+	while j < buckets as i64 {
+		b = j;
+		key = key.wrapping_mul(2862933555777941757).wrapping_add(1);
+		j = ((b + 1) as f64 * ((1u64 << 31) as f64 / ((key >> 33) + 1) as f64)) as i64;
+	}
+	b as u32
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{shard::Shards, OID};
+
+	#[test]
+	fn splits_stably() {
+		assert_eq!(Shards::new(0), None);
+		let oids: Vec<OID> = (1..=1000).map(|id| OID { universe: 1, set: 17, id }).collect();
+		let four = Shards::new(4).unwrap();
+		let parts = four.split_oids(&oids);
+		assert_eq!(parts.iter().map(Vec::len).sum::<usize>(), 1000);
+		assert!(parts.iter().all(|p| p.len() > 200 && p.len() < 300));
+		assert_eq!(four.oids_in(2, &oids).count(), parts[2].len());
+
+		let five = Shards::new(5).unwrap();
+		for oid in &oids {
+			let (from, to) = (four.of_oid(oid), five.of_oid(oid));
+			assert!(from == to || to == 4);
+		}
+		assert_eq!(Shards::new(1).unwrap().of_matter(&[0xFF; 32]), 0);
+		assert_eq!(four.matters_in(four.of_matter(&[7; 32]), &[[7; 32]]).count(), 1);
+	}
+}
@@ -0,0 +1,109 @@
+#![cfg(feature = "evm")]
+
+//! EIP-712 typed-data hashes, so a wallet signing an object mutation and the runtime checking
+//! the signature hash the same bytes. Structs are encoded as their [`crate::evm_abi::sol`]
+//! mirrors, and signatures bind to a domain made by [`domain`].
+
+use crate::{evm_abi::sol, state::Snapshot, Descriptor, Owner, Rev, H256, OID};
+use alloy_primitives::{Address, FixedBytes, U256};
+use alloy_sol_types::SolStruct;
+
+pub use alloy_sol_types::Eip712Domain;
+
+pub const DOMAIN_NAME: &str = "Every";
+pub const DOMAIN_VERSION: &str = "1";
+
+/// Handing an object at revision `rev` from one owner to another.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransferPayload {
+	pub object: OID,
+	pub rev: Rev,
+	pub from: Owner,
+	pub to: Owner,
+}
+
+/// The domain of the contract or runtime at `verifying_contract` on `chain_id`.
+pub fn domain(chain_id: u64, verifying_contract: [u8; 20]) -> Eip712Domain {
+	Eip712Domain::new(
+		Some(DOMAIN_NAME.into()),
+		Some(DOMAIN_VERSION.into()),
+		Some(U256::from(chain_id)),
+		Some(Address::from(verifying_contract)),
+		None,
+	)
+}
+
+pub trait TypedData {
+	/// `keccak256` of the EIP-712 type string.
+	fn type_hash() -> H256;
+
+	/// `hashStruct` of the value.
+	fn struct_hash(&self) -> H256;
+
+	/// The digest to sign: `keccak256(0x1901 ‖ domainSeparator ‖ hashStruct)`.
+	fn signing_hash(&self, domain: &Eip712Domain) -> H256;
+}
+
+macro_rules! typed_data {
+	($ty:ty => $sol:ty, |$v:ident| $to_sol:expr) => {
+		impl TypedData for $ty {
+			fn type_hash() -> H256 {
+				alloy_primitives::keccak256(<$sol as SolStruct>::eip712_encode_type().as_bytes()).0
+			}
+
+			fn struct_hash(&self) -> H256 {
+				let $v = self;
+				$to_sol.eip712_hash_struct().0
+			}
+
+			fn signing_hash(&self, domain: &Eip712Domain) -> H256 {
+				let $v = self;
+				$to_sol.eip712_signing_hash(domain).0
+			}
+		}
+	};
+}
+
+typed_data!(OID => sol::OID, |v| sol::OID::from(v));
+typed_data!(Descriptor => sol::Descriptor, |v| sol::Descriptor::from(v));
+typed_data!(Snapshot => sol::Snapshot, |v| sol::Snapshot::from(v));
+typed_data!(TransferPayload => sol::Transfer, |v| sol::Transfer {
+	object: (&v.object).into(),
+	rev: v.rev.get(),
+	from: FixedBytes(v.from.0),
+	to: FixedBytes(v.to.0),
+});
+
+#[cfg(test)]
+mod tests {
+	use crate::{
+		signing::{domain, TransferPayload, TypedData},
+		Descriptor, Owner, Rev, OID,
+	};
+	use alloy_primitives::keccak256;
+
+	#[test]
+	fn hashes_typed_data() {
+		let encode_type = "Transfer(OID object,uint32 rev,bytes32 from,bytes32 to)\
+			OID(uint64 universe,uint64 set,uint64 id)";
+		assert_eq!(TransferPayload::type_hash(), keccak256(encode_type).0);
+		assert_eq!(OID::type_hash(), keccak256("OID(uint64 universe,uint64 set,uint64 id)").0);
+
+		// hashStruct(OID) = keccak256(typeHash ‖ universe ‖ set ‖ id), each a 32-byte word.
+		let oid = OID { universe: 1, set: 2, id: 3 };
+		let mut data = OID::type_hash().to_vec();
+		for n in [1u8, 2, 3] {
+			data.extend_from_slice(&[0; 31]);
+			data.push(n);
+		}
+		assert_eq!(oid.struct_hash(), keccak256(&data).0);
+
+		let transfer =
+			TransferPayload { object: oid, rev: Rev(4), from: Owner([1; 32]), to: Owner([2; 32]) };
+		let here = domain(1, [9; 20]);
+		assert_eq!(transfer.signing_hash(&here), transfer.signing_hash(&domain(1, [9; 20])));
+		assert_ne!(transfer.signing_hash(&here), transfer.signing_hash(&domain(2, [9; 20])));
+		let desc = Descriptor { rev: Rev(4), ..Default::default() };
+		assert_ne!(desc.struct_hash(), Descriptor::default().struct_hash());
+	}
+}
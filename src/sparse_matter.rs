@@ -0,0 +1,253 @@
+use crate::{Bytes32, ElementError, RowSource, Vec};
+use sp_std::collections::btree_map::BTreeMap;
+use thiserror::Error;
+
+#[derive(Clone, Copy, Debug, Error, PartialEq, Eq)]
+pub enum SparseMatterError {
+	#[error("invalid SparseMatter header")]
+	BadHeader,
+
+	#[error("bad magic: expected 'SPRS' (53 50 52 53), got {0:02X?}")]
+	BadMagic([u8; 4]),
+
+	#[error("unsupported version {0} (expected 1)")]
+	BadVersion(u8),
+
+	#[error("column count {0} exceeds maximum of 16")]
+	BadColCount(u8),
+
+	#[error("invalid body length: expected {expect} bytes, got {got} bytes")]
+	BadBody { expect: usize, got: usize },
+
+	#[error("overrides must be strictly ordered by (row, col) and differ from the default")]
+	BadOverrides,
+
+	#[error("cell out of bounds at (row={row}, col={col})")]
+	OobCell { row: u64, col: usize },
+
+	#[error("arithmetic overflow")]
+	Overflow,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SparseCell {
+	pub row: u64,
+	pub col: u8,
+	pub cell: Bytes32,
+}
+
+/// A collection where every row equals `default` except for a sorted list of overridden cells.
+///
+/// Layout: magic "SPRS", version (1), cols, 0u16, rows (u64 LE), overrides (u32 LE), 0u32, the
+/// default row (cols x 32), then each override as row (u64 LE), col, 7 zero bytes, cell[32].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SparseMatter {
+	pub rows: u64,
+	pub default: Vec<Bytes32>,
+	pub overrides: Vec<SparseCell>,
+}
+
+impl SparseMatter {
+	pub const MAGIC: [u8; 4] = *b"SPRS";
+	pub const HEADER_SIZE: usize = 24;
+	pub const OVERRIDE_SIZE: usize = 48;
+	pub const CELL_SIZE: usize = 32;
+
+	pub fn from(blob: &[u8]) -> Result<Self, SparseMatterError> {
+		if blob.len() < Self::HEADER_SIZE {
+			return Err(SparseMatterError::BadHeader);
+		}
+		let magic: [u8; 4] = blob[0..4].try_into().unwrap();
+		if magic != Self::MAGIC {
+			return Err(SparseMatterError::BadMagic(magic));
+		}
+		if blob[4] != 1 {
+			return Err(SparseMatterError::BadVersion(blob[4]));
+		}
+		let cols = blob[5];
+		if cols > 16 {
+			return Err(SparseMatterError::BadColCount(cols));
+		}
+		if blob[6..8] != [0, 0] || blob[20..24] != [0, 0, 0, 0] {
+			return Err(SparseMatterError::BadHeader);
+		}
+		let rows = u64::from_le_bytes(blob[8..16].try_into().unwrap());
+		let count = u32::from_le_bytes(blob[16..20].try_into().unwrap()) as usize;
+
+		let default_end = Self::HEADER_SIZE + cols as usize * Self::CELL_SIZE;
+		let expect = count
+			.checked_mul(Self::OVERRIDE_SIZE)
+			.and_then(|n| n.checked_add(default_end))
+			.ok_or(SparseMatterError::Overflow)?;
+		if blob.len() != expect {
+			return Err(SparseMatterError::BadBody { expect, got: blob.len() });
+		}
+
+		let default = blob[Self::HEADER_SIZE..default_end]
+			.chunks_exact(Self::CELL_SIZE)
+			.map(|c| c.try_into().unwrap())
+			.collect();
+		let mut overrides = Vec::with_capacity(count);
+		for o in blob[default_end..].chunks_exact(Self::OVERRIDE_SIZE) {
+			if o[9..16].iter().any(|&b| b != 0) {
+				return Err(SparseMatterError::BadBody { expect, got: blob.len() });
+			}
+			overrides.push(SparseCell {
+				row: u64::from_le_bytes(o[0..8].try_into().unwrap()),
+				col: o[8],
+				cell: o[16..48].try_into().unwrap(),
+			});
+		}
+		let matter = Self { rows, default, overrides };
+		matter.validate()?;
+		Ok(matter)
+	}
+
+	/// Overrides must lie inside the table, be strictly ordered by (row, col) and differ from the
+	/// default cell, so each table has exactly one encoding.
+	pub fn validate(&self) -> Result<(), SparseMatterError> {
+		if self.default.len() > 16 {
+			return Err(SparseMatterError::BadColCount(self.default.len().min(0xFF) as u8));
+		}
+		for o in &self.overrides {
+			if o.row >= self.rows || o.col as usize >= self.default.len() {
+				return Err(SparseMatterError::OobCell { row: o.row, col: o.col as usize });
+			}
+			if o.cell == self.default[o.col as usize] {
+				return Err(SparseMatterError::BadOverrides);
+			}
+		}
+		if self.overrides.windows(2).any(|w| (w[0].row, w[0].col) >= (w[1].row, w[1].col)) {
+			return Err(SparseMatterError::BadOverrides);
+		}
+		Ok(())
+	}
+
+	pub fn to_blob(&self) -> Vec<u8> {
+		let mut blob = Vec::with_capacity(
+			Self::HEADER_SIZE +
+				self.default.len() * Self::CELL_SIZE +
+				self.overrides.len() * Self::OVERRIDE_SIZE,
+		);
+		blob.extend_from_slice(&Self::MAGIC);
+		blob.push(1);
+		blob.push(self.default.len() as u8);
+		blob.extend_from_slice(&[0, 0]);
+		blob.extend_from_slice(&self.rows.to_le_bytes());
+		blob.extend_from_slice(&(self.overrides.len() as u32).to_le_bytes());
+		blob.extend_from_slice(&[0, 0, 0, 0]);
+		for cell in &self.default {
+			blob.extend_from_slice(cell);
+		}
+		for o in &self.overrides {
+			blob.extend_from_slice(&o.row.to_le_bytes());
+			blob.push(o.col);
+			blob.extend_from_slice(&[0u8; 7]);
+			blob.extend_from_slice(&o.cell);
+		}
+		blob
+	}
+
+	#[inline]
+	pub fn rows(&self) -> u64 {
+		self.rows
+	}
+
+	#[inline]
+	pub fn cols(&self) -> usize {
+		self.default.len()
+	}
+
+	pub fn cell_at(&self, row: u64, col: usize) -> Result<&Bytes32, SparseMatterError> {
+		if row >= self.rows || col >= self.cols() {
+			return Err(SparseMatterError::OobCell { row, col });
+		}
+		Ok(self
+			.overrides
+			.binary_search_by_key(&(row, col as u8), |o| (o.row, o.col))
+			.map_or(&self.default[col], |i| &self.overrides[i].cell))
+	}
+}
+
+/// Builds a `SparseMatter` cell by cell; setting a cell back to its default drops the override.
+#[derive(Debug, Clone)]
+pub struct SparseMatterBuilder {
+	rows: u64,
+	default: Vec<Bytes32>,
+	overrides: BTreeMap<(u64, u8), Bytes32>,
+}
+
+impl SparseMatterBuilder {
+	pub fn new(rows: u64, default: Vec<Bytes32>) -> Self {
+		Self { rows, default, overrides: BTreeMap::new() }
+	}
+
+	pub fn set(
+		&mut self,
+		row: u64,
+		col: usize,
+		cell: Bytes32,
+	) -> Result<&mut Self, SparseMatterError> {
+		let default = self.default.get(col).filter(|_| row < self.rows);
+		let default = default.ok_or(SparseMatterError::OobCell { row, col })?;
+		if *default == cell {
+			self.overrides.remove(&(row, col as u8));
+		} else {
+			self.overrides.insert((row, col as u8), cell);
+		}
+		Ok(self)
+	}
+
+	pub fn build(&self) -> Result<SparseMatter, SparseMatterError> {
+		let overrides = self
+			.overrides
+			.iter()
+			.map(|(&(row, col), &cell)| SparseCell { row, col, cell })
+			.collect();
+		let matter = SparseMatter { rows: self.rows, default: self.default.clone(), overrides };
+		matter.validate()?;
+		Ok(matter)
+	}
+}
+
+impl RowSource for SparseMatter {
+	fn rows(&self) -> u64 {
+		self.rows
+	}
+
+	fn cols(&self) -> usize {
+		self.default.len()
+	}
+
+	fn cell_at(&self, row: u64, col: usize) -> Result<Bytes32, ElementError> {
+		SparseMatter::cell_at(self, row, col)
+			.copied()
+			.map_err(|_| ElementError::SparseMatterRowAt)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{RowSource, SparseMatter, SparseMatterBuilder, SparseMatterError};
+
+	#[test]
+	fn builder_roundtrip() {
+		let mut b = SparseMatterBuilder::new(1 << 40, vec![[0u8; 32], [1u8; 32]]);
+		b.set(5, 1, [9u8; 32]).unwrap().set(3, 0, [7u8; 32]).unwrap();
+		b.set(4, 0, [2u8; 32]).unwrap().set(4, 0, [0u8; 32]).unwrap();
+		assert_eq!(
+			b.set(1 << 40, 0, [1u8; 32]).err(),
+			Some(SparseMatterError::OobCell { row: 1 << 40, col: 0 })
+		);
+
+		let m = SparseMatter::from(&b.build().unwrap().to_blob()).unwrap();
+		assert_eq!(m.overrides.len(), 2);
+		assert_eq!(RowSource::row_at(&m, 5).unwrap(), vec![[0u8; 32], [9u8; 32]]);
+		assert_eq!(RowSource::row_at(&m, (1 << 40) - 1).unwrap(), vec![[0u8; 32], [1u8; 32]]);
+		assert_eq!(m.cell_at(3, 0).unwrap(), &[7u8; 32]);
+
+		let mut bad = m.clone();
+		bad.overrides.swap(0, 1);
+		assert_eq!(SparseMatter::from(&bad.to_blob()), Err(SparseMatterError::BadOverrides));
+	}
+}
@@ -1,24 +1,69 @@
-use crate::{Arc, Bytes32, Constants, Descriptor, Facet, Matter, Vec, H256, OID};
+use crate::{
+	Arc, Bytes32, Constants, Descriptor, Facet, Matter, Owner, Position, Result, Rev, Time, Vec,
+	H256, OID,
+};
+use derive_more::Display;
+use thiserror::Error;
 
 #[cfg(feature = "scale")]
 use codec::{Decode, DecodeWithMemTracking, Encode, MaxEncodedLen};
 #[cfg(feature = "scale")]
 use scale_info::TypeInfo;
+#[cfg(feature = "schemars")]
+use schemars::JsonSchema;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
+#[cfg(feature = "ts-rs")]
+use ts_rs::TS;
 
 #[derive(Debug, PartialEq, Clone, Default)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 #[cfg_attr(feature = "scale", derive(Encode, Decode, TypeInfo, DecodeWithMemTracking))]
 #[cfg_attr(feature = "scale", derive(MaxEncodedLen))]
 pub struct Sota {
 	pub desc: Descriptor,
-	pub owner: Bytes32,
+	pub owner: Owner,
 	pub pos: u128,
 	pub mt: u128,
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Default)]
+impl Sota {
+	#[inline]
+	pub fn is_destroyed(&self) -> bool {
+		self.desc.rev.is_destroyed()
+	}
+
+	/// Time of the last modification.
+	#[inline]
+	pub fn mtime(&self) -> Time {
+		Time::from(self.mt)
+	}
+
+	#[inline]
+	pub fn pos(&self) -> Position {
+		Position::unpack(self.pos)
+	}
+
+	#[inline]
+	pub fn set_pos(&mut self, pos: Position) {
+		self.pos = pos.pack();
+	}
+
+	#[inline]
+	pub fn owner_account(&self) -> &Owner {
+		&self.owner
+	}
+
+	/// Records a modification at `now`.
+	#[inline]
+	pub fn touch(&mut self, now: Time) {
+		self.mt = now.into();
+	}
+}
+
+#[derive(Debug, Display, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Default)]
+#[display("{universe}.{set}.{id}@{rev}")]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "scale", derive(Encode, Decode, TypeInfo, DecodeWithMemTracking))]
 #[cfg_attr(feature = "scale", derive(MaxEncodedLen))]
@@ -26,21 +71,77 @@ pub struct OidRev {
 	universe: u64,
 	set: u64,
 	id: u64,
-	rev: u32,
+	rev: Rev,
 }
 
 impl OidRev {
-	pub fn new(oid: &OID, rev: u32) -> Self {
+	pub fn new(oid: &OID, rev: Rev) -> Self {
 		Self { universe: oid.universe, set: oid.set, id: oid.id, rev }
 	}
+
+	pub fn oid(&self) -> OID {
+		OID { universe: self.universe, set: self.set, id: self.id }
+	}
+
+	pub fn rev(&self) -> Rev {
+		self.rev
+	}
+}
+
+impl From<(OID, Rev)> for OidRev {
+	fn from((oid, rev): (OID, Rev)) -> Self {
+		Self::new(&oid, rev)
+	}
+}
+
+impl From<(OID, u32)> for OidRev {
+	fn from((oid, rev): (OID, u32)) -> Self {
+		Self::new(&oid, Rev(rev))
+	}
+}
+
+impl From<OidRev> for (OID, Rev) {
+	fn from(key: OidRev) -> Self {
+		(key.oid(), key.rev)
+	}
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "scale", derive(Encode, Decode, TypeInfo, DecodeWithMemTracking))]
+#[cfg_attr(feature = "scale", derive(MaxEncodedLen))]
+pub struct OidRevSel {
+	universe: u64,
+	set: u64,
+	id: u64,
+	rev: Rev,
+	sel: u32,
+}
+
+impl OidRevSel {
+	pub fn new(oid: &OID, rev: Rev, sel: u32) -> Self {
+		Self { universe: oid.universe, set: oid.set, id: oid.id, rev, sel }
+	}
+
+	pub fn sel(&self) -> u32 {
+		self.sel
+	}
 }
 
 #[derive(Debug, PartialEq, Clone, Default)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "ts-rs", derive(TS))]
 #[cfg_attr(feature = "scale", derive(Encode, Decode, TypeInfo, DecodeWithMemTracking))]
 pub struct Snapshot {
 	pub desc: Descriptor,
 	pub mt: u128,
+	#[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes_vec"))]
+	#[cfg_attr(
+		feature = "schemars",
+		schemars(schema_with = "crate::serde_hex::bytes_vec::schema")
+	)]
+	#[cfg_attr(feature = "ts-rs", ts(type = "string[]"))]
 	pub elems: Vec<H256>,
 }
 
@@ -51,6 +152,7 @@ impl MaxEncodedLen for Snapshot {
 			.saturating_mul(Constants::ELEM_SPEC_CAPACITY)
 			.saturating_add(codec::Compact(Constants::ELEM_SPEC_CAPACITY as u32).encoded_size())
 			.saturating_add(Descriptor::max_encoded_len())
+			.saturating_add(u128::max_encoded_len())
 	}
 }
 #[derive(Debug, PartialEq, Clone, Default)]
@@ -60,6 +162,83 @@ pub struct Facets {
 	pub facets: Vec<Facet>,
 }
 
+// Stored facets are sorted by selector and stored arcs by (rel, set, id), each key unique, so a
+// given set of values has exactly one encoding.
+impl Facets {
+	/// Sorts `facets` by selector. Of several facets with one selector the last wins, as if each
+	/// were `upsert` in turn.
+	pub fn canonical(mut facets: Vec<Facet>) -> Self {
+		// The sort is stable, so reversing first puts the last duplicate ahead for `dedup`.
+		facets.reverse();
+		facets.sort_by_key(|f| f.sel);
+		facets.dedup_by_key(|f| f.sel);
+		Self { facets }
+	}
+
+	pub fn is_canonical(&self) -> bool {
+		self.facets.windows(2).all(|w| w[0].sel < w[1].sel)
+	}
+
+	pub fn get(&self, sel: u32) -> Option<&Facet> {
+		self.facets.binary_search_by_key(&sel, |f| f.sel).ok().map(|i| &self.facets[i])
+	}
+
+	/// Inserts `facet` in selector order, replacing any facet with the same selector, and
+	/// returns the replaced one. Fails without changes if a new selector would exceed
+	/// `FACET_CAPACITY`.
+	pub fn upsert(&mut self, facet: Facet) -> Result<Option<Facet>, FacetsFull> {
+		match self.facets.binary_search_by_key(&facet.sel, |f| f.sel) {
+			Ok(i) => Ok(Some(core::mem::replace(&mut self.facets[i], facet))),
+			Err(_) if self.facets.len() >= Constants::FACET_CAPACITY => Err(FacetsFull),
+			Err(i) => {
+				self.facets.insert(i, facet);
+				Ok(None)
+			},
+		}
+	}
+
+	pub fn remove(&mut self, sel: u32) -> Option<Facet> {
+		let i = self.facets.binary_search_by_key(&sel, |f| f.sel).ok()?;
+		Some(self.facets.remove(i))
+	}
+
+	/// Checksum kept in `Descriptor::fsum`; see `facets_checksum`.
+	pub fn fsum(&self) -> u32 {
+		facets_checksum(&self.facets)
+	}
+
+	pub fn sync_fsum(&self, desc: &mut Descriptor) {
+		desc.fsum = self.fsum();
+	}
+
+	/// Whether `desc.fsum` is the checksum of these facets.
+	pub fn matches_fsum(&self, desc: &Descriptor) -> bool {
+		desc.fsum == self.fsum()
+	}
+}
+
+fn facet_term(facet: &Facet) -> u32 {
+	facet.sel ^ u32::from_be_bytes(facet.hash[..4].try_into().unwrap())
+}
+
+/// Checksum kept in `Descriptor::fsum`: the wrapping sum over all facets of the selector xor
+/// the first four hash bytes (big-endian). It does not depend on facet order.
+pub fn facets_checksum(facets: &[Facet]) -> u32 {
+	facets.iter().fold(0u32, |sum, f| sum.wrapping_add(facet_term(f)))
+}
+
+/// `sum` updated for `old` being replaced by `new`, without rereading the other facets. Pass `None`
+/// as `old` for an inserted facet and as `new` for a removed one, as returned by `Facets::upsert`
+/// and `Facets::remove`.
+pub fn update_checksum(sum: u32, old: Option<&Facet>, new: Option<&Facet>) -> u32 {
+	let sum = old.map_or(sum, |f| sum.wrapping_sub(facet_term(f)));
+	new.map_or(sum, |f| sum.wrapping_add(facet_term(f)))
+}
+
+#[derive(Clone, Copy, Debug, Error, PartialEq, Eq)]
+#[error("facet capacity exceeded")]
+pub struct FacetsFull;
+
 #[cfg(feature = "scale")]
 impl MaxEncodedLen for Facets {
 	fn max_encoded_len() -> usize {
@@ -76,6 +255,71 @@ pub struct Arcs {
 	pub arcs: Vec<Arc>,
 }
 
+impl Arcs {
+	#[inline]
+	pub fn sort_key(arc: &Arc) -> (u64, u64, u64) {
+		(arc.rel, arc.set, arc.id)
+	}
+
+	/// Sorts `arcs` by (rel, set, id). Of several arcs with one key the last wins, like
+	/// `Facets::canonical`.
+	pub fn canonical(mut arcs: Vec<Arc>) -> Self {
+		arcs.reverse();
+		arcs.sort_by_key(Self::sort_key);
+		arcs.dedup_by_key(|a| Self::sort_key(a));
+		Self { arcs }
+	}
+
+	pub fn is_canonical(&self) -> bool {
+		self.arcs.windows(2).all(|w| Self::sort_key(&w[0]) < Self::sort_key(&w[1]))
+	}
+
+	// The lookups below rely on canonical order, as stored, and never look past
+	// `TAIL_CAPACITY` arcs.
+
+	fn capped(&self) -> &[Arc] {
+		&self.arcs[..self.arcs.len().min(Constants::TAIL_CAPACITY)]
+	}
+
+	pub fn filter_rel(&self, rel: u64) -> &[Arc] {
+		let arcs = self.capped();
+		let start = arcs.partition_point(|a| a.rel < rel);
+		let end = start + arcs[start..].partition_point(|a| a.rel == rel);
+		&arcs[start..end]
+	}
+
+	pub fn filter_kind(&self, kind: u64) -> impl Iterator<Item = &Arc> + '_ {
+		self.capped().iter().filter(move |a| a.kind == kind)
+	}
+
+	/// The arc with the lowest id among those of relation `rel` into set `set`.
+	pub fn find_first(&self, rel: u64, set: u64) -> Option<&Arc> {
+		let arcs = self.capped();
+		let i = arcs.partition_point(|a| (a.rel, a.set) < (rel, set));
+		arcs.get(i).filter(|a| a.rel == rel && a.set == set)
+	}
+
+	pub fn contains(&self, rel: u64, set: u64, id: u64) -> bool {
+		self.capped().binary_search_by_key(&(rel, set, id), Self::sort_key).is_ok()
+	}
+
+	pub fn count_rel(&self, rel: u64) -> usize {
+		self.filter_rel(rel).len()
+	}
+
+	pub fn count_kind(&self, kind: u64) -> usize {
+		self.filter_kind(kind).count()
+	}
+
+	pub fn remaining_capacity(&self) -> usize {
+		Constants::TAIL_CAPACITY.saturating_sub(self.arcs.len())
+	}
+
+	pub fn is_full(&self) -> bool {
+		self.remaining_capacity() == 0
+	}
+}
+
 #[cfg(feature = "scale")]
 impl MaxEncodedLen for Arcs {
 	fn max_encoded_len() -> usize {
@@ -94,17 +338,50 @@ pub enum ObjectKey {
 	Snapshot(OidRev),
 	Tails(OidRev),
 	Facets(OidRev),
+	Heads(OidRev),
+	/// One facet of `Facets`, stored on its own so it can be read without the whole list.
+	Facet(OidRevSel),
 }
 
+/// Variant indices are part of the storage layout: a changed value layout gets a new
+/// `Constants::STORAGE_VERSION` and a conversion in `migrations`, never a reused index.
 #[derive(Debug, PartialEq, Clone)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "scale", derive(Encode, Decode, TypeInfo, DecodeWithMemTracking))]
 #[cfg_attr(feature = "scale", derive(MaxEncodedLen))]
 pub enum ObjectValue {
+	#[cfg_attr(feature = "scale", codec(index = 0))]
 	Sota(Sota),
+	#[cfg_attr(feature = "scale", codec(index = 1))]
 	Snapshot(Snapshot),
+	#[cfg_attr(feature = "scale", codec(index = 2))]
 	Tails(Arcs),
+	#[cfg_attr(feature = "scale", codec(index = 3))]
 	Facets(Facets),
+	#[cfg_attr(feature = "scale", codec(index = 4))]
+	Heads(Arcs),
+	#[cfg_attr(feature = "scale", codec(index = 5))]
+	Facet(Facet),
+}
+
+impl ObjectValue {
+	pub fn is_canonical(&self) -> bool {
+		match self {
+			ObjectValue::Tails(arcs) | ObjectValue::Heads(arcs) => arcs.is_canonical(),
+			ObjectValue::Facets(facets) => facets.is_canonical(),
+			_ => true,
+		}
+	}
+
+	/// Decodes a value and rejects facets or tails that are not in canonical order.
+	#[cfg(feature = "scale")]
+	pub fn decode_strict(input: &mut &[u8]) -> Result<Self, codec::Error> {
+		let value = Self::decode(input)?;
+		if !value.is_canonical() {
+			return Err("non-canonical object value".into());
+		}
+		Ok(value)
+	}
 }
 
 pub type UniverseId = u64;
@@ -115,6 +392,7 @@ pub type UniverseId = u64;
 #[cfg_attr(feature = "scale", derive(MaxEncodedLen))]
 pub struct UniverseHerald {
 	pub universe: u64,
+	#[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes"))]
 	pub herald: Bytes32,
 }
 
@@ -125,9 +403,18 @@ pub struct UniverseHerald {
 pub struct Genesis {
 	pub horizon: u128,
 	pub otime: u128,
+	#[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes"))]
 	pub originator: Bytes32,
 }
 
+impl Genesis {
+	/// Whether `time` lies between the universe's origin time and its horizon, both inclusive.
+	pub fn within_horizon(&self, time: Time) -> bool {
+		let time = u128::from(time);
+		self.otime <= time && time <= self.horizon
+	}
+}
+
 #[derive(Debug, PartialEq, Clone)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "scale", derive(Encode, Decode, TypeInfo, DecodeWithMemTracking))]
@@ -137,6 +424,24 @@ pub struct Frontier {
 	pub frontier: u128,
 }
 
+#[derive(Clone, Copy, Debug, Error, PartialEq, Eq)]
+pub enum FrontierError {
+	#[error("frontier cannot move back from {current} to {got}")]
+	Regress { current: Time, got: Time },
+}
+
+impl Frontier {
+	/// The frontier moved to `time`, raising `furthest` along with it. The frontier never moves
+	/// back; advancing to the current frontier is a no-op.
+	pub fn advance_to(&self, time: Time) -> Result<Frontier, FrontierError> {
+		let to = u128::from(time);
+		if to < self.frontier {
+			return Err(FrontierError::Regress { current: self.frontier.into(), got: time });
+		}
+		Ok(Frontier { furthest: self.furthest.max(to), frontier: to })
+	}
+}
+
 #[derive(Debug, PartialEq, Clone)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "scale", derive(Encode, Decode, TypeInfo, DecodeWithMemTracking))]
@@ -152,8 +457,11 @@ pub enum UniverseKey {
 #[cfg_attr(feature = "scale", derive(Encode, Decode, TypeInfo, DecodeWithMemTracking))]
 #[cfg_attr(feature = "scale", derive(MaxEncodedLen))]
 pub enum UniverseValue {
+	#[cfg_attr(feature = "scale", codec(index = 0))]
 	Genesis(Genesis),
+	#[cfg_attr(feature = "scale", codec(index = 1))]
 	Frontier(Frontier),
+	#[cfg_attr(feature = "scale", codec(index = 2))]
 	Herald,
 }
 
@@ -162,7 +470,7 @@ pub enum UniverseValue {
 #[cfg_attr(feature = "scale", derive(Encode, Decode, TypeInfo, DecodeWithMemTracking))]
 #[cfg_attr(feature = "scale", derive(MaxEncodedLen))]
 pub enum MatterKey {
-	Matter(H256),
+	Matter(#[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes"))] H256),
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -170,5 +478,126 @@ pub enum MatterKey {
 #[cfg_attr(feature = "scale", derive(Encode, Decode, TypeInfo, DecodeWithMemTracking))]
 #[cfg_attr(feature = "scale", derive(MaxEncodedLen))]
 pub enum MatterValue {
+	#[cfg_attr(feature = "scale", codec(index = 0))]
 	Matter(Matter),
 }
+
+#[cfg(test)]
+mod tests {
+	use crate::{
+		state::{
+			facets_checksum, update_checksum, Arcs, Facets, FacetsFull, Frontier, FrontierError,
+			Genesis, OidRev, Sota,
+		},
+		Arc, Constants, Descriptor, Facet, Position, Rev, Time, OID,
+	};
+
+	#[test]
+	fn arc_lookups() {
+		let arc = |rel, set, id, kind| Arc { kind, data: 0, rel, set, id };
+		let arcs = Arcs::canonical(vec![
+			arc(2, 20, 5, 7),
+			arc(1, 20, 3, 7),
+			arc(2, 21, 1, 8),
+			arc(2, 20, 4, 8),
+			arc(3, 20, 1, 7),
+		]);
+		assert_eq!(arcs.count_rel(2), 3);
+		assert_eq!(arcs.count_rel(4), 0);
+		assert_eq!(arcs.filter_rel(2)[0], arc(2, 20, 4, 8));
+		assert_eq!(arcs.count_kind(7), 3);
+		assert_eq!(arcs.find_first(2, 21), Some(&arc(2, 21, 1, 8)));
+		assert_eq!(arcs.find_first(1, 21), None);
+		assert!(arcs.contains(3, 20, 1) && !arcs.contains(3, 20, 2));
+		assert_eq!(arcs.remaining_capacity(), 1019);
+	}
+
+	#[test]
+	fn canonical_keeps_last_duplicate() {
+		let arc = |id, kind| Arc { kind, data: 0, rel: 1, set: 20, id };
+		let arcs = Arcs::canonical(vec![arc(2, 7), arc(1, 7), arc(2, 8), arc(2, 9)]);
+		assert_eq!(arcs.arcs, vec![arc(1, 7), arc(2, 9)]);
+		assert!(arcs.is_canonical());
+
+		let facet = |sel, byte| Facet { sel, hash: [byte; 32] };
+		let facets = Facets::canonical(vec![facet(5, 1), facet(3, 1), facet(5, 2)]);
+		assert_eq!(facets.facets, vec![facet(3, 1), facet(5, 2)]);
+		let mut upserted = Facets::default();
+		[facet(5, 1), facet(3, 1), facet(5, 2)].into_iter().for_each(|f| {
+			upserted.upsert(f).unwrap();
+		});
+		assert_eq!(upserted, facets);
+	}
+
+	#[test]
+	fn frontier_and_horizon() {
+		let t = |block| Time { block, slot: 0, tick: 0 };
+		let genesis = Genesis { horizon: t(100).into(), otime: t(10).into(), originator: [0; 32] };
+		assert!(genesis.within_horizon(t(10)) && genesis.within_horizon(t(100)));
+		assert!(!genesis.within_horizon(t(9)) && !genesis.within_horizon(t(101)));
+
+		let frontier = Frontier { furthest: t(50).into(), frontier: t(20).into() };
+		let next = frontier.advance_to(t(30)).unwrap();
+		assert_eq!(next, Frontier { furthest: t(50).into(), frontier: t(30).into() });
+		assert_eq!(next.advance_to(t(60)).unwrap().furthest, t(60).into());
+		assert_eq!(next.advance_to(t(30)), Ok(next.clone()));
+		assert_eq!(
+			next.advance_to(t(29)),
+			Err(FrontierError::Regress { current: t(30), got: t(29) })
+		);
+	}
+
+	#[test]
+	fn sota_accessors() {
+		let mut sota = Sota { pos: 3 << 64 | 9, ..Default::default() };
+		assert_eq!(sota.pos(), Position { block: 3, coord: 9 });
+		sota.touch(Time { block: 7, slot: 1, tick: 2 });
+		assert_eq!(sota.mt, 7 << 64 | 1 << 32 | 2);
+		assert_eq!(sota.mtime().block, 7);
+		assert!(!sota.is_destroyed());
+		sota.desc.rev = Rev::DESTROYED;
+		assert!(sota.is_destroyed());
+	}
+
+	#[test]
+	fn facet_upsert_and_fsum() {
+		let facet = |sel, b| Facet { sel, hash: [b; 32] };
+		let mut facets = Facets::default();
+		assert_eq!(facets.upsert(facet(9, 1)), Ok(None));
+		assert_eq!(facets.upsert(facet(3, 2)), Ok(None));
+		assert_eq!(facets.upsert(facet(9, 4)), Ok(Some(facet(9, 1))));
+		assert!(facets.is_canonical());
+		assert_eq!(facets.get(9), Some(&facet(9, 4)));
+
+		let mut desc = Descriptor::default();
+		facets.sync_fsum(&mut desc);
+		assert_eq!(desc.fsum, (3 ^ 0x0202_0202u32).wrapping_add(9 ^ 0x0404_0404));
+		assert!(facets.matches_fsum(&desc));
+		let replaced = facets.upsert(facet(9, 7)).unwrap();
+		desc.fsum = update_checksum(desc.fsum, replaced.as_ref(), Some(&facet(9, 7)));
+		assert_eq!(desc.fsum, facets_checksum(&[facet(9, 7), facet(3, 2)]));
+		let removed = facets.remove(3);
+		assert_eq!(removed, Some(facet(3, 2)));
+		assert_eq!(facets.remove(3), None);
+		desc.fsum = update_checksum(desc.fsum, removed.as_ref(), None);
+		assert!(facets.matches_fsum(&desc));
+
+		for sel in 100..100 + Constants::FACET_CAPACITY as u32 - 1 {
+			facets.upsert(facet(sel, 0)).unwrap();
+		}
+		assert_eq!(facets.upsert(facet(1, 0)), Err(FacetsFull));
+		assert!(facets.upsert(facet(9, 0)).is_ok());
+	}
+
+	#[test]
+	fn oid_rev_keys() {
+		let oid = OID::of_set(1, 17);
+		let key = OidRev::from((oid, 3));
+		assert_eq!(key, OidRev::new(&oid, Rev(3)));
+		assert_eq!((key.oid(), key.rev()), (oid, Rev(3)));
+		assert_eq!(<(OID, Rev)>::from(key), (oid, Rev(3)));
+		assert_eq!(key.to_string(), "1.1.17@3");
+		assert!(key < OidRev::from((oid, 4)));
+		assert!(OidRev::from((oid, 9)) < OidRev::from((oid.next_id().unwrap(), 1)));
+	}
+}
@@ -0,0 +1,99 @@
+#![cfg(feature = "proofs")]
+
+use crate::{
+	state::{ObjectKey, ObjectValue, OidRev, Snapshot},
+	storage_key::object_storage_key,
+	Rev, Vec, H256, OID,
+};
+use codec::DecodeAll;
+use sp_core::Blake2Hasher;
+use sp_trie::{LayoutV1, StorageProof};
+use thiserror::Error;
+
+#[derive(Clone, Copy, Debug, Error, PartialEq, Eq)]
+pub enum ProofError {
+	#[error("proof does not match the state root")]
+	InvalidProof,
+	#[error("key is absent from the proven state")]
+	NotFound,
+	#[error("decode failed")]
+	DecodeFailed,
+	#[error("unexpected variant")]
+	UnexpectedVariant,
+}
+
+/// Value of `key` in the state committed to by `root`, read from the trie nodes in `proof`.
+/// `None` means the proof shows the key is absent.
+pub fn verify_storage_proof(
+	root: H256,
+	proof: StorageProof,
+	key: &[u8],
+) -> Result<Option<Vec<u8>>, ProofError> {
+	let db = proof.into_memory_db::<Blake2Hasher>();
+	sp_trie::read_trie_value::<LayoutV1<Blake2Hasher>, _>(&db, &root.into(), key, None, None)
+		.map_err(|_| ProofError::InvalidProof)
+}
+
+/// Snapshot `rev` of `oid` as proven against a block's state root, so a light client can
+/// check what an RPC node serves. `rev` must be a stored revision, not `Rev::LATEST`.
+pub fn verify_snapshot_proof(
+	root: H256,
+	key_proof: StorageProof,
+	oid: &OID,
+	rev: Rev,
+) -> Result<Snapshot, ProofError> {
+	let key = object_storage_key(ObjectKey::Snapshot(OidRev::new(oid, rev)));
+	let raw = verify_storage_proof(root, key_proof, &key)?.ok_or(ProofError::NotFound)?;
+	match ObjectValue::decode_all(&mut &raw[..]).map_err(|_| ProofError::DecodeFailed)? {
+		ObjectValue::Snapshot(snap) => Ok(snap),
+		_ => Err(ProofError::UnexpectedVariant),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{
+		state::{ObjectKey, ObjectValue, OidRev, Snapshot},
+		state_proof::{verify_snapshot_proof, ProofError},
+		storage_key::object_storage_key,
+		Rev, H256, OID,
+	};
+	use codec::Encode;
+	use sp_core::Blake2Hasher;
+	use sp_trie::{LayoutV1, MemoryDB, StorageProof, TrieDBMutBuilder, TrieMut};
+
+	/// Root of a trie holding only `value` at `key`, and a proof of all its nodes.
+	fn prove(key: &[u8], value: &[u8]) -> (H256, StorageProof) {
+		let mut db = MemoryDB::<Blake2Hasher>::default();
+		let mut root = Default::default();
+		{
+			let mut trie =
+				TrieDBMutBuilder::<LayoutV1<Blake2Hasher>>::new(&mut db, &mut root).build();
+			trie.insert(key, value).unwrap();
+		}
+		(root.into(), StorageProof::new(db.drain().into_values().map(|(node, _)| node)))
+	}
+
+	#[test]
+	fn verifies_snapshot_against_root() {
+		let oid = OID::of_set(1, 17);
+		let snap = Snapshot { mt: 5, elems: vec![[7; 32]], ..Default::default() };
+		let key = object_storage_key(ObjectKey::Snapshot(OidRev::new(&oid, Rev(1))));
+		let (root, proof) = prove(&key, &ObjectValue::Snapshot(snap.clone()).encode());
+
+		assert_eq!(verify_snapshot_proof(root, proof.clone(), &oid, Rev(1)), Ok(snap.clone()));
+		assert_eq!(
+			verify_snapshot_proof(root, proof.clone(), &oid, Rev(2)),
+			Err(ProofError::NotFound)
+		);
+		assert_eq!(
+			verify_snapshot_proof([0; 32], proof, &oid, Rev(1)),
+			Err(ProofError::InvalidProof)
+		);
+
+		let mut padded = ObjectValue::Snapshot(snap).encode();
+		padded.push(0);
+		let (root, proof) = prove(&key, &padded);
+		assert_eq!(verify_snapshot_proof(root, proof, &oid, Rev(1)), Err(ProofError::DecodeFailed));
+	}
+}
@@ -1,29 +1,60 @@
-#![cfg(feature = "storage")]
-
-use crate::state::{MatterKey, MatterValue, ObjectKey, ObjectValue};
+#![cfg(feature = "frame")]
+
+use crate::{
+	state::{MatterKey, MatterValue, ObjectKey, ObjectValue, UniverseKey, UniverseValue},
+	storage_key::{
+		MATTER_PREFIX, MATTER_REFS_PREFIX, OBJECT_PREFIX, PALLET_PREFIX, UNIVERSE_PREFIX,
+	},
+	Constants, H256,
+};
 use frame_support::{
 	pallet_prelude::{OptionQuery, StorageMap},
+	traits::StorageVersion,
 	Identity,
 };
 
 pub use frame_support::traits::StorageInstance;
 
+pub const STORAGE_VERSION: StorageVersion = StorageVersion::new(Constants::STORAGE_VERSION);
+
 pub struct MatterMapPrefix;
 
 impl StorageInstance for MatterMapPrefix {
 	#[rustfmt::skip]
-	fn pallet_prefix() -> &'static str { "Every" }
-	const STORAGE_PREFIX: &'static str = "Matter";
+	fn pallet_prefix() -> &'static str { PALLET_PREFIX }
+	const STORAGE_PREFIX: &'static str = MATTER_PREFIX;
 }
 
 pub struct ObjectMapPrefix;
 
 impl StorageInstance for ObjectMapPrefix {
 	#[rustfmt::skip]
-	fn pallet_prefix() -> &'static str { "Every" }
-	const STORAGE_PREFIX: &'static str = "Object";
+	fn pallet_prefix() -> &'static str { PALLET_PREFIX }
+	const STORAGE_PREFIX: &'static str = OBJECT_PREFIX;
+}
+
+pub struct UniverseMapPrefix;
+
+impl StorageInstance for UniverseMapPrefix {
+	#[rustfmt::skip]
+	fn pallet_prefix() -> &'static str { PALLET_PREFIX }
+	const STORAGE_PREFIX: &'static str = UNIVERSE_PREFIX;
+}
+
+pub struct MatterRefsMapPrefix;
+
+impl StorageInstance for MatterRefsMapPrefix {
+	#[rustfmt::skip]
+	fn pallet_prefix() -> &'static str { PALLET_PREFIX }
+	const STORAGE_PREFIX: &'static str = MATTER_REFS_PREFIX;
 }
 
 pub type MatterMap = StorageMap<MatterMapPrefix, Identity, MatterKey, MatterValue, OptionQuery>;
 
+/// Number of stored references to each matter; absent means none.
+pub type MatterRefsMap = StorageMap<MatterRefsMapPrefix, Identity, H256, u32, OptionQuery>;
+
 pub type ObjectMap = StorageMap<ObjectMapPrefix, Identity, ObjectKey, ObjectValue, OptionQuery>;
+
+pub type UniverseMap =
+	StorageMap<UniverseMapPrefix, Identity, UniverseKey, UniverseValue, OptionQuery>;
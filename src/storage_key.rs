@@ -0,0 +1,96 @@
+#![cfg(feature = "storage")]
+
+use crate::{
+	state::{MatterKey, ObjectKey, UniverseKey},
+	Vec, H256,
+};
+use codec::Encode;
+use sp_crypto_hashing::twox_128;
+
+pub const PALLET_PREFIX: &str = "Every";
+pub const MATTER_PREFIX: &str = "Matter";
+pub const OBJECT_PREFIX: &str = "Object";
+pub const UNIVERSE_PREFIX: &str = "Universe";
+pub const MATTER_REFS_PREFIX: &str = "MatterRefs";
+
+/// `twox128(pallet) ++ twox128(storage)`, the prefix FRAME puts in front of every key of a
+/// storage map.
+pub fn map_prefix(pallet: &str, storage: &str) -> [u8; 32] {
+	let mut prefix = [0u8; 32];
+	prefix[..16].copy_from_slice(&twox_128(pallet.as_bytes()));
+	prefix[16..].copy_from_slice(&twox_128(storage.as_bytes()));
+	prefix
+}
+
+pub fn matter_prefix() -> [u8; 32] {
+	map_prefix(PALLET_PREFIX, MATTER_PREFIX)
+}
+
+pub fn object_prefix() -> [u8; 32] {
+	map_prefix(PALLET_PREFIX, OBJECT_PREFIX)
+}
+
+pub fn universe_prefix() -> [u8; 32] {
+	map_prefix(PALLET_PREFIX, UNIVERSE_PREFIX)
+}
+
+pub fn matter_refs_prefix() -> [u8; 32] {
+	map_prefix(PALLET_PREFIX, MATTER_REFS_PREFIX)
+}
+
+/// Full storage key of `key` in the map under `prefix`. The Every maps use the `Identity`
+/// hasher, so this is the prefix followed by the encoded key.
+pub fn hashed_key<K: Encode>(prefix: &[u8; 32], key: &K) -> Vec<u8> {
+	let mut out = prefix.to_vec();
+	key.encode_to(&mut out);
+	out
+}
+
+/// Key of `key` in the pallet's object map, as passed to `state_getStorage`.
+pub fn object_storage_key(key: ObjectKey) -> Vec<u8> {
+	hashed_key(&object_prefix(), &key)
+}
+
+/// Key of the matter with content hash `hash` in the pallet's matter map.
+pub fn matter_storage_key(hash: &H256) -> Vec<u8> {
+	hashed_key(&matter_prefix(), &MatterKey::Matter(*hash))
+}
+
+/// Key of `key` in the pallet's universe map, which holds each universe's genesis, frontier
+/// and heralds.
+pub fn universe_storage_key(key: UniverseKey) -> Vec<u8> {
+	hashed_key(&universe_prefix(), &key)
+}
+
+/// Key of the reference count of the matter with content hash `hash`.
+pub fn matter_refs_storage_key(hash: &H256) -> Vec<u8> {
+	hashed_key(&matter_refs_prefix(), hash)
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{
+		state::{ObjectKey, OidRev},
+		storage_key::{map_prefix, matter_storage_key, object_storage_key},
+		Rev, OID,
+	};
+
+	#[test]
+	fn matches_frame_keys() {
+		assert_eq!(
+			hex::encode(&map_prefix("System", "Account")[..]),
+			"26aa394eea5630e07c48ae0c9558cef7b99d880ec681799c0cf30e8886371da9"
+		);
+		let key = ObjectKey::Sota(OidRev::new(&OID { universe: 1, set: 2, id: 3 }, Rev::LATEST));
+		#[cfg(feature = "frame")]
+		{
+			use crate::storage::{MatterMap, ObjectMap};
+			assert_eq!(object_storage_key(key.clone()), ObjectMap::hashed_key_for(key.clone()));
+			let matter = crate::state::MatterKey::Matter([7; 32]);
+			assert_eq!(matter_storage_key(&[7; 32]), MatterMap::hashed_key_for(matter));
+		}
+		let raw = object_storage_key(key);
+		assert_eq!(raw.len(), 32 + 1 + 28);
+		assert_eq!(raw[32], 0);
+	}
+}
@@ -0,0 +1,117 @@
+#![cfg(test)]
+
+//! In-memory `StateReader` shared by the tests of modules that only need reads.
+
+use crate::{
+	Arc, Bytes32, Descriptor, Facet, Matter, Rev, StateReader, Time, Unique, Value, Vec, H256, OID,
+};
+use std::collections::BTreeMap;
+
+/// What a `TestState` read fails with: the id of the state, so tests reading through several
+/// can tell which one failed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Missing(pub u64);
+
+/// One revision of an object. `None` parts fail to read.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct TestObject {
+	pub desc: Descriptor,
+	pub elems: Option<Vec<Bytes32>>,
+	pub tails: Option<Vec<Arc>>,
+	pub facets: Option<Vec<Facet>>,
+}
+
+impl TestObject {
+	/// A revision with `desc` and every part readable and empty.
+	pub fn new(desc: Descriptor) -> Self {
+		Self { desc, elems: Some(Vec::new()), tails: Some(Vec::new()), facets: Some(Vec::new()) }
+	}
+
+	pub fn elems(self, elems: Vec<Bytes32>) -> Self {
+		Self { elems: Some(elems), ..self }
+	}
+
+	pub fn tails(self, tails: Vec<Arc>) -> Self {
+		Self { tails: Some(tails), ..self }
+	}
+
+	pub fn facets(self, facets: Vec<Facet>) -> Self {
+		Self { facets: Some(facets), ..self }
+	}
+}
+
+/// Objects by revision and matters by hash. `Rev::LATEST` reads the highest stored revision;
+/// values, uniques, timed snapshots, heads and kind contracts are never found.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct TestState {
+	id: u64,
+	matters: BTreeMap<H256, Matter>,
+	objects: BTreeMap<(OID, Rev), TestObject>,
+}
+
+impl TestState {
+	pub fn with_id(id: u64) -> Self {
+		Self { id, ..Default::default() }
+	}
+
+	pub fn insert_matter(&mut self, hash: H256, matter: Matter) {
+		self.matters.insert(hash, matter);
+	}
+
+	/// Stores `object` at the revision of its descriptor.
+	pub fn insert(&mut self, oid: OID, object: TestObject) {
+		self.objects.insert((oid, object.desc.rev), object);
+	}
+
+	fn object(&self, oid: &OID, rev: Rev) -> Result<&TestObject, Missing> {
+		let found = if rev.is_latest() {
+			self.objects.range((*oid, Rev::LATEST)..=(*oid, Rev::DESTROYED)).next_back()
+		} else {
+			self.objects.get_key_value(&(*oid, rev))
+		};
+		found.map(|(_, object)| object).ok_or(Missing(self.id))
+	}
+
+	fn part<T: Clone>(&self, part: &Option<T>) -> Result<T, Missing> {
+		part.clone().ok_or(Missing(self.id))
+	}
+}
+
+impl StateReader<Missing> for TestState {
+	fn get_matter(&self, hash: &H256) -> Result<Matter, Missing> {
+		self.matters.get(hash).cloned().ok_or(Missing(self.id))
+	}
+	fn get_value(&self, _: &OID, _: Rev) -> Result<Value, Missing> {
+		Err(Missing(self.id))
+	}
+	fn get_unique(&self, _: &OID, _: Rev) -> Result<Unique, Missing> {
+		Err(Missing(self.id))
+	}
+	fn get_descriptor(&self, oid: &OID, rev: Rev) -> Result<Descriptor, Missing> {
+		Ok(self.object(oid, rev)?.desc.clone())
+	}
+	fn get_snapshot(&self, oid: &OID, rev: Rev) -> Result<(Descriptor, Vec<Bytes32>), Missing> {
+		let object = self.object(oid, rev)?;
+		Ok((object.desc.clone(), self.part(&object.elems)?))
+	}
+	fn get_snapshot_at(&self, _: &OID, _: Time) -> Result<(Descriptor, Vec<Bytes32>), Missing> {
+		Err(Missing(self.id))
+	}
+	fn get_tails(&self, oid: &OID, rev: Rev) -> Result<Vec<Arc>, Missing> {
+		self.part(&self.object(oid, rev)?.tails)
+	}
+	fn get_heads(&self, _: &OID, _: Rev) -> Result<Vec<Arc>, Missing> {
+		Err(Missing(self.id))
+	}
+	fn get_facets(&self, oid: &OID, rev: Rev) -> Result<Vec<Facet>, Missing> {
+		self.part(&self.object(oid, rev)?.facets)
+	}
+	fn get_facet(&self, oid: &OID, rev: Rev, sel: u32) -> Result<Matter, Missing> {
+		let facets = self.get_facets(oid, rev)?;
+		let facet = facets.iter().find(|f| f.sel == sel).ok_or(Missing(self.id))?;
+		self.get_matter(&facet.hash)
+	}
+	fn get_kind_contract(&self, _: &OID, _: Rev) -> Result<Matter, Missing> {
+		Err(Missing(self.id))
+	}
+}
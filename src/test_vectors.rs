@@ -0,0 +1,164 @@
+#![cfg(any(test, feature = "fixtures"))]
+
+//! Known-good blobs, picker specs and the resolutions they must produce, shared so that crates
+//! building or reading matters check themselves against the same bytes.
+
+use crate::{parse_hex, Bytes32, PickFrom, Vec};
+
+/// Decodes hex in const context; bad input fails the build.
+const fn hex<const N: usize>(s: &str) -> [u8; N] {
+	match parse_hex(s) {
+		Ok(bytes) => bytes,
+		Err(_) => panic!("bad hex"),
+	}
+}
+
+/// ENUM v1 with 2 columns and 2 rows.
+pub const ENUM_BLOB: [u8; 160] = hex(concat!(
+	"454e554d10020200000000000000000002ff0000000000000000000000000000",
+	"cb8dd44f076c2a2bc61da2fe9bd5be9201357571a98fcea73737779070cafa78",
+	"0000000000000000000000000000000000000000000000000000000000123456",
+	"561592b3c5d66e46c470f2b9ac36a855c4d91531239d61f0ec3e571ca51059e8",
+	"0000000000000000000000000000000000000000000000000000000000123456",
+));
+
+/// blake2-256 of `ENUM_BLOB`, the key the chain stores it under.
+pub const ENUM_MATTER_HASH: Bytes32 =
+	hex("f12b63c27656b3cd624d39bd641c36e0be3d1c4f5c0e580e8071d9fdcd04e634");
+
+/// Picker flags reading the row from the collection in the object's first element.
+pub const HERE_COLLECTION_FLAGS: u32 = PickFrom::HereCollection as u32;
+
+/// What `HERE_COLLECTION_FLAGS` resolves to for object id 1 (row 0) over `ENUM_BLOB`.
+pub const ENUM_ROW_0: [Bytes32; 2] = [
+	hex("cb8dd44f076c2a2bc61da2fe9bd5be9201357571a98fcea73737779070cafa78"),
+	hex("0000000000000000000000000000000000000000000000000000000000123456"),
+];
+
+/// PERM v1 with columns of heights 2 and 3 and no enum columns; each cell starts with its
+/// column and index.
+pub const PERM_BLOB: [u8; 224] = hex(concat!(
+	"5045524d10020000000000000000000001010000000000000000000000000000",
+	"0200030000000000000000000000000000000000000000000000000000000000",
+	"0000000000000000000000000000000000000000000000000000000000000000",
+	"0001000000000000000000000000000000000000000000000000000000000000",
+	"0100000000000000000000000000000000000000000000000000000000000000",
+	"0101000000000000000000000000000000000000000000000000000000000000",
+	"0102000000000000000000000000000000000000000000000000000000000000",
+));
+
+pub const PERM_ROWS: u64 = 6;
+
+/// Row 4 of `PERM_BLOB`: index 1 of column 0 and index 1 of column 1.
+pub const PERM_ROW_4: [Bytes32; 2] = [
+	hex("0001000000000000000000000000000000000000000000000000000000000000"),
+	hex("0101000000000000000000000000000000000000000000000000000000000000"),
+];
+
+/// Cell `(row, col)` of `enum_blob`: the row as a little-endian u16, then the column.
+pub fn enum_cell(row: u16, col: u8) -> Bytes32 {
+	let mut cell = [0u8; 32];
+	cell[..2].copy_from_slice(&row.to_le_bytes());
+	cell[2] = col;
+	cell
+}
+
+/// ENUM v1 of any size, for tests and benches that need more than `ENUM_BLOB`. Every column
+/// has type 1 and cells are `enum_cell`.
+pub fn enum_blob(rows: u16, cols: u8) -> Vec<u8> {
+	let mut blob = b"ENUM".to_vec();
+	blob.extend_from_slice(&[0x10, cols]);
+	blob.extend_from_slice(&rows.to_le_bytes());
+	blob.extend_from_slice(&[0; 8]);
+	let mut col_types = [0u8; 16];
+	col_types[..cols as usize].fill(1);
+	blob.extend_from_slice(&col_types);
+	for row in 0..rows {
+		(0..cols).for_each(|col| blob.extend_from_slice(&enum_cell(row, col)));
+	}
+	blob
+}
+
+/// Cell `index` of column `col` in `perm_blob`: the column, then the index as a little-endian
+/// u16, as in `PERM_BLOB`.
+pub fn perm_cell(col: u8, index: u16) -> Bytes32 {
+	let mut cell = [0u8; 32];
+	cell[0] = col;
+	cell[1..3].copy_from_slice(&index.to_le_bytes());
+	cell
+}
+
+/// PERM v1 whose columns, all permuted and of type 1, have `heights`; cells are `perm_cell`.
+pub fn perm_blob(heights: &[u16]) -> Vec<u8> {
+	let mut blob = b"PERM".to_vec();
+	blob.extend_from_slice(&[0x10, heights.len() as u8, 0, 0]);
+	blob.extend_from_slice(&[0; 8]);
+	let mut col_types = [0u8; 16];
+	col_types[..heights.len()].fill(1);
+	blob.extend_from_slice(&col_types);
+	let mut padded = [0u16; 16];
+	padded[..heights.len()].copy_from_slice(heights);
+	padded.iter().for_each(|h| blob.extend_from_slice(&h.to_le_bytes()));
+	for (col, &height) in heights.iter().enumerate() {
+		(0..height).for_each(|i| blob.extend_from_slice(&perm_cell(col as u8, i)));
+	}
+	blob
+}
+
+/// Cheap deterministic `MerkleHasher` for tests that only need stable, input-sensitive output.
+/// Not collision resistant.
+#[cfg(test)]
+pub(crate) struct SumHasher;
+
+#[cfg(test)]
+impl crate::MerkleHasher for SumHasher {
+	fn hash(data: &[u8]) -> crate::H256 {
+		let mut out = [0u8; 32];
+		for (i, b) in data.iter().enumerate() {
+			out[i % 32] = out[i % 32].wrapping_mul(31).wrapping_add(*b);
+		}
+		out
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{test_vectors::*, EnumMatter, PermMatter};
+
+	#[test]
+	fn vectors_parse() {
+		let m = EnumMatter::from(&ENUM_BLOB).unwrap();
+		assert_eq!((m.rows(), m.cols()), (2, 2));
+		assert_eq!(m.row_at(0).unwrap(), ENUM_ROW_0.iter().collect::<Vec<_>>());
+
+		let p = PermMatter::from_strict(&PERM_BLOB).unwrap();
+		assert_eq!(p.rows(), PERM_ROWS);
+		assert_eq!(p.row_at(4).unwrap(), PERM_ROW_4.iter().collect::<Vec<_>>());
+		assert_eq!(perm_blob(&[2, 3]), PERM_BLOB);
+	}
+
+	#[test]
+	fn built_blobs_parse() {
+		let m = EnumMatter::from(&enum_blob(300, 3)).unwrap();
+		assert_eq!(m.row_at(299).unwrap()[2], &enum_cell(299, 2));
+		let p = PermMatter::from_strict(&perm_blob(&[300, 2])).unwrap();
+		assert_eq!(p.rows(), 600);
+		assert_eq!(p.cell_at(599, 0).unwrap(), &perm_cell(0, 299));
+	}
+
+	#[test]
+	fn unchecked_parse_matches() {
+		let blob = enum_blob(4, 2);
+		let m = EnumMatter::from_unchecked(&blob).unwrap();
+		assert_eq!(m.row_data, EnumMatter::from(&blob).unwrap().row_data);
+		assert!(EnumMatter::from_unchecked(&blob[..blob.len() - 1]).is_err());
+	}
+
+	#[cfg(feature = "storage")]
+	#[test]
+	fn matter_hash_is_blob_digest() {
+		use crate::{Blake2Hasher, MerkleHasher};
+
+		assert_eq!(Blake2Hasher::hash(&ENUM_BLOB), ENUM_MATTER_HASH);
+	}
+}
@@ -0,0 +1,195 @@
+use crate::{Bytes32, Unique, Value, OID};
+use thiserror::Error;
+
+#[cfg(feature = "scale")]
+use codec::{Decode, DecodeWithMemTracking, Encode, MaxEncodedLen};
+#[cfg(feature = "scale")]
+use scale_info::TypeInfo;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, Error, PartialEq, Eq)]
+pub enum TokenError {
+	#[error("unknown token standard {0}")]
+	UnknownStandard(u8),
+	#[error("code does not match its token standard")]
+	BadCode,
+}
+
+/// What the `std` byte of a `Value` or `Unique` names, and so how its `code` is packed.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "scale", derive(Encode, Decode, TypeInfo, DecodeWithMemTracking))]
+#[cfg_attr(feature = "scale", derive(MaxEncodedLen))]
+pub enum TokenStandard {
+	/// No backing asset; `code` is zero.
+	#[default]
+	None = 0,
+	/// The coin of a chain.
+	Native = 1,
+	Erc20 = 2,
+	Erc721 = 3,
+	Erc1155 = 4,
+	/// An object in an Every universe.
+	Internal = 5,
+}
+
+impl TryFrom<u8> for TokenStandard {
+	type Error = TokenError;
+
+	fn try_from(std: u8) -> Result<Self, TokenError> {
+		use TokenStandard::*;
+		Ok(match std {
+			0 => None,
+			1 => Native,
+			2 => Erc20,
+			3 => Erc721,
+			4 => Erc1155,
+			5 => Internal,
+			_ => return Err(TokenError::UnknownStandard(std)),
+		})
+	}
+}
+
+/// The asset a `Value` or `Unique` stands for: its `std` byte with `code` decoded.
+///
+/// `code` is packed big-endian: the chain id in bytes 0..8, then for contract tokens the
+/// address in bytes 12..32; an internal object as universe, set and id in bytes 0..24. Bytes
+/// not used are zero.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Token {
+	None,
+	Native { chain_id: u64 },
+	Erc20 { chain_id: u64, address: [u8; 20] },
+	Erc721 { chain_id: u64, address: [u8; 20] },
+	Erc1155 { chain_id: u64, address: [u8; 20] },
+	Internal(OID),
+}
+
+impl Token {
+	pub fn native(chain_id: u64) -> Self {
+		Token::Native { chain_id }
+	}
+
+	pub fn erc20(chain_id: u64, address: [u8; 20]) -> Self {
+		Token::Erc20 { chain_id, address }
+	}
+
+	pub fn erc721(chain_id: u64, address: [u8; 20]) -> Self {
+		Token::Erc721 { chain_id, address }
+	}
+
+	pub fn erc1155(chain_id: u64, address: [u8; 20]) -> Self {
+		Token::Erc1155 { chain_id, address }
+	}
+
+	pub fn internal(oid: OID) -> Self {
+		Token::Internal(oid)
+	}
+
+	pub fn decode(std: u8, code: &Bytes32) -> Result<Self, TokenError> {
+		let word = |i: usize| u64::from_be_bytes(code[i * 8..i * 8 + 8].try_into().unwrap());
+		let zero_from = |i: usize| code[i..].iter().all(|&b| b == 0);
+		let contract = || {
+			if code[8..12] != [0; 4] {
+				return Err(TokenError::BadCode);
+			}
+			let mut address = [0u8; 20];
+			address.copy_from_slice(&code[12..]);
+			Ok((word(0), address))
+		};
+		let token = match TokenStandard::try_from(std)? {
+			TokenStandard::None if zero_from(0) => Token::None,
+			TokenStandard::Native if zero_from(8) => Token::Native { chain_id: word(0) },
+			TokenStandard::Erc20 =>
+				contract().map(|(chain_id, address)| Token::erc20(chain_id, address))?,
+			TokenStandard::Erc721 =>
+				contract().map(|(chain_id, address)| Token::erc721(chain_id, address))?,
+			TokenStandard::Erc1155 =>
+				contract().map(|(chain_id, address)| Token::erc1155(chain_id, address))?,
+			TokenStandard::Internal if zero_from(24) =>
+				Token::Internal(OID { universe: word(0), set: word(1), id: word(2) }),
+			_ => return Err(TokenError::BadCode),
+		};
+		Ok(token)
+	}
+
+	pub fn standard(&self) -> TokenStandard {
+		match self {
+			Token::None => TokenStandard::None,
+			Token::Native { .. } => TokenStandard::Native,
+			Token::Erc20 { .. } => TokenStandard::Erc20,
+			Token::Erc721 { .. } => TokenStandard::Erc721,
+			Token::Erc1155 { .. } => TokenStandard::Erc1155,
+			Token::Internal(_) => TokenStandard::Internal,
+		}
+	}
+
+	pub fn code(&self) -> Bytes32 {
+		let mut code = [0u8; 32];
+		match self {
+			Token::None => {},
+			Token::Native { chain_id } => code[..8].copy_from_slice(&chain_id.to_be_bytes()),
+			Token::Erc20 { chain_id, address } |
+			Token::Erc721 { chain_id, address } |
+			Token::Erc1155 { chain_id, address } => {
+				code[..8].copy_from_slice(&chain_id.to_be_bytes());
+				code[12..].copy_from_slice(address);
+			},
+			Token::Internal(oid) => {
+				code[..8].copy_from_slice(&oid.universe.to_be_bytes());
+				code[8..16].copy_from_slice(&oid.set.to_be_bytes());
+				code[16..24].copy_from_slice(&oid.id.to_be_bytes());
+			},
+		}
+		code
+	}
+}
+
+impl Value {
+	pub fn token(&self) -> Result<Token, TokenError> {
+		Token::decode(self.std, &self.code)
+	}
+
+	pub fn set_token(&mut self, token: &Token) {
+		self.std = token.standard() as u8;
+		self.code = token.code();
+	}
+}
+
+impl Unique {
+	pub fn token(&self) -> Result<Token, TokenError> {
+		Token::decode(self.std, &self.code)
+	}
+
+	pub fn set_token(&mut self, token: &Token) {
+		self.std = token.standard() as u8;
+		self.code = token.code();
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{Token, TokenError, TokenStandard, OID};
+
+	#[test]
+	fn round_trips_codes() {
+		let tokens = [
+			Token::None,
+			Token::native(1),
+			Token::erc20(8453, [0xAB; 20]),
+			Token::erc1155(10, [1; 20]),
+			Token::internal(OID { universe: 1, set: 17, id: 42 }),
+		];
+		for token in tokens {
+			let std = token.standard() as u8;
+			assert_eq!(Token::decode(std, &token.code()), Ok(token));
+		}
+
+		let code = Token::erc20(1, [0xAB; 20]).code();
+		assert_eq!(code[7], 1);
+		assert_eq!(Token::decode(TokenStandard::Native as u8, &code), Err(TokenError::BadCode));
+		assert_eq!(Token::decode(9, &code), Err(TokenError::UnknownStandard(9)));
+	}
+}
@@ -0,0 +1,21 @@
+//! Instrumentation through `tracing` when the `tracing` feature is on; without it the macros
+//! expand to nothing and their arguments are not evaluated.
+
+/// Enters a `DEBUG` span that lasts until the end of the enclosing block.
+macro_rules! span {
+	($($arg:tt)*) => {
+		#[cfg(feature = "tracing")]
+		let _span = tracing::debug_span!($($arg)*).entered();
+	};
+}
+
+/// Emits a `TRACE` event.
+macro_rules! event {
+	($($arg:tt)*) => {
+		#[cfg(feature = "tracing")]
+		tracing::trace!($($arg)*);
+	};
+}
+
+pub(crate) use event;
+pub(crate) use span;
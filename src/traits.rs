@@ -1,18 +1,130 @@
-use crate::types::{Arc, Bytes32, Descriptor, Facet, Matter, Unique, Value, Vec, H256, OID};
+use crate::{
+	state::{Frontier, Genesis, UniverseId},
+	types::{Arc, Bytes32, Descriptor, Facet, Matter, Rev, Time, Unique, Value, Vec, H256, OID},
+	ErrorCode, ObjectView,
+};
+use core::cell::RefCell;
 
+/// Read access to object state. Readers that need `&mut self`, such as caches, implement
+/// `StateReaderMut` instead and are shared through a `RefCell`.
 pub trait StateReader<E> {
 	// elements
-	fn get_matter(&mut self, hash: &H256) -> Result<Matter, E>;
-	fn get_value(&mut self, tid: &OID, rev: u32) -> Result<Value, E>;
-	fn get_unique(&mut self, tid: &OID, rev: u32) -> Result<Unique, E>;
+	fn get_matter(&self, hash: &H256) -> Result<Matter, E>;
+	fn get_value(&self, tid: &OID, rev: Rev) -> Result<Value, E>;
+	fn get_unique(&self, tid: &OID, rev: Rev) -> Result<Unique, E>;
 
 	// objects
-	fn get_descriptor(&mut self, oid: &OID, rev: u32) -> Result<Descriptor, E>;
-	fn get_snapshot(&mut self, oid: &OID, rev: u32) -> Result<(Descriptor, Vec<Bytes32>), E>;
-	fn get_tails(&mut self, oid: &OID, rev: u32) -> Result<Vec<Arc>, E>;
-	fn get_facets(&mut self, oid: &OID, rev: u32) -> Result<Vec<Facet>, E>;
-	fn get_facet(&mut self, oid: &OID, rev: u32, sel: u32) -> Result<Matter, E>;
+	fn get_descriptor(&self, oid: &OID, rev: Rev) -> Result<Descriptor, E>;
+	fn get_snapshot(&self, oid: &OID, rev: Rev) -> Result<(Descriptor, Vec<Bytes32>), E>;
+	/// Snapshot of the revision that was current at `time`: the last one modified at or before it.
+	fn get_snapshot_at(&self, oid: &OID, time: Time) -> Result<(Descriptor, Vec<Bytes32>), E>;
+	fn get_tails(&self, oid: &OID, rev: Rev) -> Result<Vec<Arc>, E>;
+	/// Arcs from the objects `oid` is attached to as a tail; each arc names the head.
+	///
+	/// Reads the list stored for exactly `rev`, or for the current revision with `Rev::LATEST`;
+	/// lists are not carried over from earlier revisions. Fails when `oid` does not exist or no
+	/// list is stored for that revision.
+	fn get_heads(&self, oid: &OID, rev: Rev) -> Result<Vec<Arc>, E>;
+	fn get_facets(&self, oid: &OID, rev: Rev) -> Result<Vec<Facet>, E>;
+	fn get_facet(&self, oid: &OID, rev: Rev, sel: u32) -> Result<Matter, E>;
 
 	// helpers
-	fn get_kind_contract(&mut self, oid: &OID, rev: u32) -> Result<Matter, E>;
+	fn get_kind_contract(&self, oid: &OID, rev: Rev) -> Result<Matter, E>;
+
+	/// Snapshot, tails and facets of `oid` in one call. Only a failed snapshot read is an error;
+	/// tails or facets that cannot be read are left as `None`.
+	fn get_object(&self, oid: &OID, rev: Rev) -> Result<ObjectView, E> {
+		let (desc, elems) = self.get_snapshot(oid, rev)?;
+		let tails = self.get_tails(oid, desc.rev).ok();
+		let facets = self.get_facets(oid, desc.rev).ok();
+		Ok(ObjectView { oid: *oid, desc, elems, tails, facets })
+	}
+
+	/// Code of the root cause of `err`, which pickers keep so callers can tell missing data from
+	/// corrupt data. Readers whose errors have no code keep the default.
+	fn error_code(&self, _err: &E) -> Option<ErrorCode> {
+		None
+	}
+}
+
+/// `StateReader` for readers that change as they read, such as caching adapters. Every
+/// `StateReader` is also one.
+pub trait StateReaderMut<E> {
+	fn get_matter(&mut self, hash: &H256) -> Result<Matter, E>;
+	fn get_value(&mut self, tid: &OID, rev: Rev) -> Result<Value, E>;
+	fn get_unique(&mut self, tid: &OID, rev: Rev) -> Result<Unique, E>;
+	fn get_descriptor(&mut self, oid: &OID, rev: Rev) -> Result<Descriptor, E>;
+	fn get_snapshot(&mut self, oid: &OID, rev: Rev) -> Result<(Descriptor, Vec<Bytes32>), E>;
+	fn get_snapshot_at(&mut self, oid: &OID, time: Time) -> Result<(Descriptor, Vec<Bytes32>), E>;
+	fn get_tails(&mut self, oid: &OID, rev: Rev) -> Result<Vec<Arc>, E>;
+	/// As `StateReader::get_heads`: exactly `rev`, failing for a missing object or list.
+	fn get_heads(&mut self, oid: &OID, rev: Rev) -> Result<Vec<Arc>, E>;
+	fn get_facets(&mut self, oid: &OID, rev: Rev) -> Result<Vec<Facet>, E>;
+	fn get_facet(&mut self, oid: &OID, rev: Rev, sel: u32) -> Result<Matter, E>;
+	fn get_kind_contract(&mut self, oid: &OID, rev: Rev) -> Result<Matter, E>;
+	fn error_code(&mut self, _err: &E) -> Option<ErrorCode> {
+		None
+	}
+}
+
+// Forwards every `StateReaderMut` method to `$inner`, calling it through `$tr`.
+macro_rules! forward_reader {
+	($tr:ident, [$($recv:tt)*] => $inner:expr) => {
+		fn get_matter($($recv)*, hash: &H256) -> Result<Matter, E> {
+			$tr::get_matter($inner, hash)
+		}
+		fn get_value($($recv)*, tid: &OID, rev: Rev) -> Result<Value, E> {
+			$tr::get_value($inner, tid, rev)
+		}
+		fn get_unique($($recv)*, tid: &OID, rev: Rev) -> Result<Unique, E> {
+			$tr::get_unique($inner, tid, rev)
+		}
+		fn get_descriptor($($recv)*, oid: &OID, rev: Rev) -> Result<Descriptor, E> {
+			$tr::get_descriptor($inner, oid, rev)
+		}
+		fn get_snapshot($($recv)*, oid: &OID, rev: Rev) -> Result<(Descriptor, Vec<Bytes32>), E> {
+			$tr::get_snapshot($inner, oid, rev)
+		}
+		fn get_snapshot_at(
+			$($recv)*,
+			oid: &OID,
+			time: Time,
+		) -> Result<(Descriptor, Vec<Bytes32>), E> {
+			$tr::get_snapshot_at($inner, oid, time)
+		}
+		fn get_tails($($recv)*, oid: &OID, rev: Rev) -> Result<Vec<Arc>, E> {
+			$tr::get_tails($inner, oid, rev)
+		}
+		fn get_heads($($recv)*, oid: &OID, rev: Rev) -> Result<Vec<Arc>, E> {
+			$tr::get_heads($inner, oid, rev)
+		}
+		fn get_facets($($recv)*, oid: &OID, rev: Rev) -> Result<Vec<Facet>, E> {
+			$tr::get_facets($inner, oid, rev)
+		}
+		fn get_facet($($recv)*, oid: &OID, rev: Rev, sel: u32) -> Result<Matter, E> {
+			$tr::get_facet($inner, oid, rev, sel)
+		}
+		fn get_kind_contract($($recv)*, oid: &OID, rev: Rev) -> Result<Matter, E> {
+			$tr::get_kind_contract($inner, oid, rev)
+		}
+		fn error_code($($recv)*, err: &E) -> Option<ErrorCode> {
+			$tr::error_code($inner, err)
+		}
+	};
+}
+
+impl<E, T: StateReader<E> + ?Sized> StateReaderMut<E> for T {
+	forward_reader!(StateReader, [&mut self] => self);
+}
+
+/// Shares a `StateReaderMut`, borrowing it for each read. A read made while another is in
+/// progress on the same cell panics.
+impl<E, T: StateReaderMut<E>> StateReader<E> for RefCell<T> {
+	forward_reader!(StateReaderMut, [&self] => &mut *self.borrow_mut());
+}
+
+pub trait UniverseReader<E> {
+	fn get_genesis(&self, universe: UniverseId) -> Result<Genesis, E>;
+	fn get_frontier(&self, universe: UniverseId) -> Result<Frontier, E>;
+	fn is_herald(&self, universe: UniverseId, account: &Bytes32) -> Result<bool, E>;
 }
@@ -1,4 +1,4 @@
-use crate::Constants;
+use crate::{Constants, MatterForm, Mime, Symbol};
 
 pub type H256 = [u8; 32];
 pub type Bytes32 = [u8; 32];
@@ -16,10 +16,14 @@ pub use codec::{Decode, DecodeWithMemTracking, Encode, FullCodec, FullEncode, Ma
 use derive_more::Display;
 #[cfg(feature = "scale")]
 use scale_info::TypeInfo;
+#[cfg(feature = "schemars")]
+use schemars::JsonSchema;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
+#[cfg(feature = "ts-rs")]
+use ts_rs::TS;
 
-#[derive(Debug, Display, PartialEq, Eq, Clone, Default)]
+#[derive(Debug, Display, PartialEq, Eq, Clone, Copy, Default)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "scale", derive(Encode, Decode, TypeInfo, DecodeWithMemTracking))]
 #[cfg_attr(feature = "scale", derive(MaxEncodedLen))]
@@ -45,6 +49,78 @@ impl From<Time> for u128 {
 	}
 }
 
+/// Object revision. `Rev::LATEST` (0) is a read-side sentinel for "current revision"; stored
+/// revisions start at `Rev::NEW` and `Rev::DESTROYED` marks a destroyed object.
+#[derive(Debug, Display, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Default)]
+#[display("{_0}")]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "ts-rs", derive(TS))]
+#[cfg_attr(feature = "scale", derive(Encode, Decode, TypeInfo, DecodeWithMemTracking))]
+#[cfg_attr(feature = "scale", derive(MaxEncodedLen))]
+pub struct Rev(pub u32);
+
+impl Rev {
+	pub const LATEST: Rev = Rev(0);
+	pub const NEW: Rev = Rev(Constants::REV_NEW);
+	pub const DESTROYED: Rev = Rev(Constants::REV_DESTROYED);
+
+	#[inline]
+	pub const fn get(self) -> u32 {
+		self.0
+	}
+
+	#[inline]
+	pub const fn is_latest(self) -> bool {
+		self.0 == Self::LATEST.0
+	}
+
+	#[inline]
+	pub const fn is_new(self) -> bool {
+		self.0 == Self::NEW.0
+	}
+
+	#[inline]
+	pub const fn is_destroyed(self) -> bool {
+		self.0 == Self::DESTROYED.0
+	}
+
+	/// Next live revision, or `None` if it would reach `DESTROYED`.
+	#[inline]
+	pub fn next(self) -> Option<Rev> {
+		let next = self.0.checked_add(1)?;
+		(next < Self::DESTROYED.0).then_some(Rev(next))
+	}
+
+	/// Previous stored revision, or `None` if there is none before this one.
+	#[inline]
+	pub fn prev(self) -> Option<Rev> {
+		(self.0 > Self::NEW.0 && !self.is_destroyed()).then(|| Rev(self.0 - 1))
+	}
+
+	#[inline]
+	pub fn saturating_next(self) -> Rev {
+		self.next().unwrap_or(Rev(Self::DESTROYED.0 - 1))
+	}
+
+	#[inline]
+	pub fn saturating_prev(self) -> Rev {
+		self.prev().unwrap_or(Self::NEW)
+	}
+}
+
+impl From<u32> for Rev {
+	fn from(value: u32) -> Self {
+		Rev(value)
+	}
+}
+
+impl From<Rev> for u32 {
+	fn from(rev: Rev) -> Self {
+		rev.0
+	}
+}
+
 #[derive(Debug, Display, PartialEq, Eq, Clone, Default)]
 #[display("({block}, {coord})")]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -52,12 +128,76 @@ impl From<Time> for u128 {
 #[cfg_attr(feature = "scale", derive(MaxEncodedLen))]
 pub struct Position {
 	pub block: u64,
+	/// Grid cell: `x` in the high 32 bits, `y` in the low 32 bits.
 	pub coord: u64,
 }
 
-#[derive(Debug, Display, PartialEq, Eq, Clone, Default)]
+impl Position {
+	pub const fn new(block: u64, x: u32, y: u32) -> Self {
+		Self { block, coord: ((x as u64) << 32) | (y as u64) }
+	}
+
+	/// Packs into the `u128` stored in `Sota.pos`: block in the high 64 bits, coord in the low.
+	pub const fn pack(&self) -> u128 {
+		((self.block as u128) << 64) | (self.coord as u128)
+	}
+
+	pub const fn unpack(value: u128) -> Self {
+		Self { block: (value >> 64) as u64, coord: value as u64 }
+	}
+
+	#[inline]
+	pub const fn x(&self) -> u32 {
+		(self.coord >> 32) as u32
+	}
+
+	#[inline]
+	pub const fn y(&self) -> u32 {
+		self.coord as u32
+	}
+
+	/// Cells sharing an edge with this one, in the same block; cells past the grid edge are
+	/// left out.
+	pub fn neighbors(&self) -> impl Iterator<Item = Position> + '_ {
+		let (x, y) = (self.x(), self.y());
+		[
+			x.checked_sub(1).map(|x| (x, y)),
+			x.checked_add(1).map(|x| (x, y)),
+			y.checked_sub(1).map(|y| (x, y)),
+			y.checked_add(1).map(|y| (x, y)),
+		]
+		.into_iter()
+		.flatten()
+		.map(|(x, y)| Position::new(self.block, x, y))
+	}
+
+	/// Manhattan distance between the cells, ignoring the block.
+	pub fn distance(&self, other: &Position) -> u64 {
+		self.x().abs_diff(other.x()) as u64 + self.y().abs_diff(other.y()) as u64
+	}
+
+	pub fn is_adjacent(&self, other: &Position) -> bool {
+		self.distance(other) == 1
+	}
+}
+
+impl From<u128> for Position {
+	fn from(value: u128) -> Self {
+		Self::unpack(value)
+	}
+}
+
+impl From<Position> for u128 {
+	fn from(p: Position) -> Self {
+		p.pack()
+	}
+}
+
+#[derive(Debug, Display, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Default)]
 #[display("{universe}.{set}.{id}")]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "ts-rs", derive(TS))]
 #[cfg_attr(feature = "scale", derive(Encode, Decode, TypeInfo, DecodeWithMemTracking))]
 #[cfg_attr(feature = "scale", derive(MaxEncodedLen))]
 pub struct OID {
@@ -107,6 +247,12 @@ impl OID {
 		OID { universe, set: Constants::ID_SET_OF_UNIQUE, id: tok }
 	}
 
+	/// The object after this one in its set, `None` past `Constants::ID_MAX`.
+	pub fn next_id(&self) -> Option<OID> {
+		let id = self.id.checked_add(1).filter(|&id| id <= Constants::ID_MAX)?;
+		Some(OID { id, ..*self })
+	}
+
 	pub fn is_meta_object(&self) -> bool {
 		self.set <= Constants::META_OBJECT_ID_MAX
 	}
@@ -124,7 +270,7 @@ impl OID {
 	}
 }
 
-#[derive(Debug, Display, PartialEq, Eq, Clone, Default)]
+#[derive(Debug, Display, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Default)]
 #[display("{set}.{id}")]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "scale", derive(Encode, Decode, TypeInfo, DecodeWithMemTracking))]
@@ -137,28 +283,130 @@ pub struct SID {
 #[derive(Debug, Display, PartialEq, Eq, Clone, Default)]
 #[display("{{traits={traits:0x}, rev={rev}, krev={krev}, srev={srev}, kind={kind}, trev={trev}, fsum={fsum}}}")]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "ts-rs", derive(TS))]
 #[cfg_attr(feature = "scale", derive(Encode, Decode, TypeInfo, DecodeWithMemTracking))]
 #[cfg_attr(feature = "scale", derive(MaxEncodedLen))]
 pub struct Descriptor {
 	pub traits: u32,
-	pub rev: u32,
-	pub krev: u32,
-	pub srev: u32,
+	pub rev: Rev,
+	pub krev: Rev,
+	pub srev: Rev,
 	pub kind: u64,
-	pub trev: u32,
+	pub trev: Rev,
 	pub fsum: u32,
 }
 
+impl Descriptor {
+	#[inline]
+	pub fn trait_flags(&self) -> TraitFlags {
+		TraitFlags(self.traits)
+	}
+}
+
+/// Named bits of `Descriptor::traits`.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
+#[cfg_attr(feature = "scale", derive(Encode, Decode, TypeInfo, DecodeWithMemTracking))]
+#[cfg_attr(feature = "scale", derive(MaxEncodedLen))]
+pub struct TraitFlags(pub u32);
+
+impl TraitFlags {
+	pub const TRANSFERABLE: TraitFlags = TraitFlags(1 << 0);
+	pub const DESTRUCTIBLE: TraitFlags = TraitFlags(1 << 1);
+	pub const UPGRADABLE: TraitFlags = TraitFlags(1 << 2);
+	pub const FACET_LOCKED: TraitFlags = TraitFlags(1 << 3);
+	pub const ELEMS_LOCKED: TraitFlags = TraitFlags(1 << 4);
+
+	const NAMES: [(TraitFlags, &'static str); 5] = [
+		(Self::TRANSFERABLE, "transferable"),
+		(Self::DESTRUCTIBLE, "destructible"),
+		(Self::UPGRADABLE, "upgradable"),
+		(Self::FACET_LOCKED, "facet-locked"),
+		(Self::ELEMS_LOCKED, "elems-locked"),
+	];
+
+	/// True if every bit of `flags` is set.
+	#[inline]
+	pub const fn has(self, flags: TraitFlags) -> bool {
+		self.0 & flags.0 == flags.0
+	}
+
+	#[inline]
+	pub fn set(&mut self, flags: TraitFlags, on: bool) {
+		if on {
+			self.0 |= flags.0;
+		} else {
+			self.0 &= !flags.0;
+		}
+	}
+
+	#[inline]
+	pub const fn with(self, flags: TraitFlags) -> Self {
+		TraitFlags(self.0 | flags.0)
+	}
+}
+
+impl From<u32> for TraitFlags {
+	fn from(value: u32) -> Self {
+		TraitFlags(value)
+	}
+}
+
+impl From<TraitFlags> for u32 {
+	fn from(flags: TraitFlags) -> Self {
+		flags.0
+	}
+}
+
+/// Named flags joined by `|`, with unnamed bits as one trailing hex value; `-` if empty.
+impl fmt::Display for TraitFlags {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let mut rest = self.0;
+		let mut sep = "";
+		for (flag, name) in Self::NAMES {
+			if self.has(flag) {
+				write!(f, "{sep}{name}")?;
+				rest &= !flag.0;
+				sep = "|";
+			}
+		}
+		match (rest, sep) {
+			(0, "") => f.write_str("-"),
+			(0, _) => Ok(()),
+			_ => write!(f, "{sep}{rest:#x}"),
+		}
+	}
+}
+
 #[derive(Debug, Display, PartialEq, Clone)]
-#[display("@ {}, form={form}, blob={}B", str_from_fixed_unchecked(&mime), blob.len())]
+#[display("@ {mime}, form={form}, blob={}B", blob.len())]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "ts-rs", derive(TS))]
 #[cfg_attr(feature = "scale", derive(Encode, Decode, TypeInfo, DecodeWithMemTracking))]
 pub struct Matter {
 	pub form: u8,
-	pub mime: String31,
+	#[cfg_attr(
+		feature = "schemars",
+		schemars(schema_with = "crate::serde_hex::text::schema::<31>")
+	)]
+	#[cfg_attr(feature = "ts-rs", ts(type = "string"))]
+	pub mime: Mime,
+	#[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::blob"))]
+	#[cfg_attr(feature = "schemars", schemars(schema_with = "crate::serde_hex::blob::schema"))]
+	#[cfg_attr(feature = "ts-rs", ts(type = "string"))]
 	pub blob: Bytes,
 }
 
+impl Matter {
+	/// Builds a matter with form and mime detected from the blob content.
+	pub fn sniffed(blob: Bytes) -> Option<Self> {
+		let (form, mime) = MatterForm::sniff(&blob)?;
+		Some(Self { form: form.into(), mime, blob })
+	}
+}
+
 #[cfg(feature = "scale")]
 impl MaxEncodedLen for Matter {
 	fn max_encoded_len() -> usize {
@@ -174,43 +422,61 @@ impl MaxEncodedLen for Matter {
 
 #[derive(Debug, Display, PartialEq, Clone)]
 #[display(
-	"# {} std={std}, dec={decimals}, code={}, data={}",
-	str_from_fixed_unchecked(symbol),
+	"# {symbol} std={std}, dec={decimals}, code={}, data={}",
 	short_hex(code),
 	short_hex(data)
 )]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 #[cfg_attr(feature = "scale", derive(Encode, Decode, TypeInfo, DecodeWithMemTracking))]
 #[cfg_attr(feature = "scale", derive(MaxEncodedLen))]
 pub struct Unique {
 	pub std: u8,
 	pub decimals: u8,
-	pub symbol: String30,
+	#[cfg_attr(
+		feature = "schemars",
+		schemars(schema_with = "crate::serde_hex::text::schema::<30>")
+	)]
+	pub symbol: Symbol,
+	#[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes"))]
+	#[cfg_attr(feature = "schemars", schemars(schema_with = "crate::serde_hex::bytes::schema"))]
 	pub code: Bytes32,
+	#[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes"))]
+	#[cfg_attr(feature = "schemars", schemars(schema_with = "crate::serde_hex::bytes::schema"))]
 	pub data: Bytes32,
 }
 
 #[derive(Debug, Display, PartialEq, Clone)]
 #[display(
-	"$ {} std={std}, dec={decimals}, code={}, data={}",
-	str_from_fixed_unchecked(symbol),
+	"$ {symbol} std={std}, dec={decimals}, code={}, data={}",
 	short_hex(code),
 	short_hex(data)
 )]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 #[cfg_attr(feature = "scale", derive(Encode, Decode, TypeInfo, DecodeWithMemTracking))]
 #[cfg_attr(feature = "scale", derive(MaxEncodedLen))]
 pub struct Value {
 	pub std: u8,
 	pub decimals: u8,
-	pub symbol: String30,
+	#[cfg_attr(
+		feature = "schemars",
+		schemars(schema_with = "crate::serde_hex::text::schema::<30>")
+	)]
+	pub symbol: Symbol,
+	#[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes"))]
+	#[cfg_attr(feature = "schemars", schemars(schema_with = "crate::serde_hex::bytes::schema"))]
 	pub code: Bytes32,
+	#[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes"))]
+	#[cfg_attr(feature = "schemars", schemars(schema_with = "crate::serde_hex::bytes::schema"))]
 	pub data: Bytes32,
 }
 
 #[derive(Debug, Display, PartialEq, Clone)]
 #[display("<- {rel} [{data}] -- [{kind}] {set}.{id}")]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "ts-rs", derive(TS))]
 #[cfg_attr(feature = "scale", derive(Encode, Decode, TypeInfo, DecodeWithMemTracking))]
 #[cfg_attr(feature = "scale", derive(MaxEncodedLen))]
 pub struct Arc {
@@ -224,10 +490,15 @@ pub struct Arc {
 #[derive(Debug, Display, PartialEq, Clone)]
 #[display("<> {sel:08x} => 0x{}", short_hex(hash))]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "ts-rs", derive(TS))]
 #[cfg_attr(feature = "scale", derive(Encode, Decode, TypeInfo, DecodeWithMemTracking))]
 #[cfg_attr(feature = "scale", derive(MaxEncodedLen))]
 pub struct Facet {
 	pub sel: u32,
+	#[cfg_attr(feature = "serde", serde(with = "crate::serde_hex::bytes"))]
+	#[cfg_attr(feature = "schemars", schemars(schema_with = "crate::serde_hex::bytes::schema"))]
+	#[cfg_attr(feature = "ts-rs", ts(type = "string"))]
 	pub hash: Bytes32,
 }
 
@@ -251,12 +522,14 @@ pub fn str_from_fixed_unchecked<const N: usize>(buf: &[u8; N]) -> &str {
 	unsafe { sp_std::str::from_utf8_unchecked(slice_from_fixed(buf)) }
 }
 
-pub fn to_mime(input: &[u8]) -> String31 {
-	to_fixed(input)
+/// Truncates to fit; see `Mime::new` for a checked conversion.
+pub fn to_mime(input: &[u8]) -> Mime {
+	to_fixed(input).into()
 }
 
-pub fn to_symbol(input: &[u8]) -> String30 {
-	to_fixed(input)
+/// Truncates to fit; see `Symbol::new` for a checked conversion.
+pub fn to_symbol(input: &[u8]) -> Symbol {
+	to_fixed(input).into()
 }
 
 pub struct ShortHex<'a>(pub &'a [u8; 32]);
@@ -274,3 +547,69 @@ impl<'a> fmt::Display for ShortHex<'a> {
 pub fn short_hex(h: &[u8; 32]) -> ShortHex<'_> {
 	ShortHex(h)
 }
+
+#[cfg(test)]
+mod tests {
+	use crate::{Descriptor, Position, Rev, TraitFlags, OID};
+	use sp_std::collections::btree_set::BTreeSet;
+
+	#[test]
+	fn position_pack_and_neighbors() {
+		let pos = Position::new(7, 3, 0);
+		assert_eq!(Position::unpack(pos.pack()), pos);
+		assert_eq!(pos.pack(), 7 << 64 | 3 << 32);
+		let near: Vec<Position> = pos.neighbors().collect();
+		assert_eq!(
+			near,
+			vec![Position::new(7, 2, 0), Position::new(7, 4, 0), Position::new(7, 3, 1)]
+		);
+		assert!(near.iter().all(|n| pos.is_adjacent(n)));
+		assert_eq!(pos.distance(&Position::new(1, 0, u32::MAX)), 3 + u32::MAX as u64);
+	}
+
+	#[test]
+	fn rev_order_and_sentinels() {
+		assert!(Rev::LATEST < Rev::NEW && Rev::NEW < Rev(2) && Rev(2) < Rev::DESTROYED);
+		assert_eq!(Rev::default(), Rev::LATEST);
+		assert!(Rev(0).is_latest() && !Rev::NEW.is_latest());
+		assert!(Rev(1).is_new() && Rev(u32::MAX).is_destroyed());
+
+		assert_eq!(Rev(7).get(), 7);
+		assert_eq!(Rev::from(7), Rev(7));
+		assert_eq!(u32::from(Rev(7)), 7);
+		assert_eq!(Rev(7).to_string(), "7");
+
+		assert_eq!(Rev::NEW.next(), Some(Rev(2)));
+		assert_eq!(Rev(u32::MAX - 1).next(), None);
+		assert_eq!(Rev(u32::MAX - 2).saturating_next(), Rev(u32::MAX - 1));
+		assert_eq!(Rev(u32::MAX - 1).saturating_next(), Rev(u32::MAX - 1));
+		assert_eq!(Rev(2).prev(), Some(Rev::NEW));
+		assert_eq!(Rev::NEW.prev(), None);
+		assert_eq!(Rev::DESTROYED.prev(), None);
+		assert_eq!(Rev::LATEST.saturating_prev(), Rev::NEW);
+	}
+
+	#[test]
+	fn trait_flags() {
+		let desc = Descriptor { traits: 0b1_0000_0101, ..Default::default() };
+		let mut flags = desc.trait_flags();
+		assert!(flags.has(TraitFlags::TRANSFERABLE.with(TraitFlags::UPGRADABLE)));
+		assert!(!flags.has(TraitFlags::DESTRUCTIBLE));
+		assert_eq!(flags.to_string(), "transferable|upgradable|0x100");
+		flags.set(TraitFlags::TRANSFERABLE, false);
+		flags.set(TraitFlags(0x100), false);
+		assert_eq!(u32::from(flags), 0b100);
+		assert_eq!(TraitFlags::default().to_string(), "-");
+	}
+
+	#[test]
+	fn oids_order_by_universe_set_id() {
+		let oids: BTreeSet<OID> =
+			[OID::of_set(2, 1), OID::of_kind(1, 9), OID::of_set(1, 3), OID::of_set(1, 2)].into();
+		let sorted: Vec<OID> = oids.into_iter().collect();
+		assert_eq!(
+			sorted,
+			vec![OID::of_set(1, 2), OID::of_set(1, 3), OID::of_kind(1, 9), OID::of_set(2, 1)]
+		);
+	}
+}
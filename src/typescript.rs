@@ -0,0 +1,33 @@
+#![cfg(feature = "ts-rs")]
+
+//! TypeScript definitions of the wire-facing types, matching their serde JSON form.
+
+use crate::{state::Snapshot, Arc, Descriptor, Facet, Matter, OID};
+use std::path::Path;
+use ts_rs::{ExportError, TS};
+
+/// Writes one `.ts` file per type, and per type they use, into `dir`.
+pub fn export_typescript(dir: impl AsRef<Path>) -> Result<(), ExportError> {
+	let dir = dir.as_ref();
+	OID::export_all_to(dir)?;
+	Descriptor::export_all_to(dir)?;
+	Snapshot::export_all_to(dir)?;
+	Matter::export_all_to(dir)?;
+	Facet::export_all_to(dir)?;
+	Arc::export_all_to(dir)
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{state::Snapshot, Facet, Matter, Rev};
+	use ts_rs::TS;
+
+	#[test]
+	fn declares_readable_forms() {
+		assert_eq!(Rev::decl(), "type Rev = number;");
+		assert_eq!(Matter::decl(), "type Matter = { form: number, mime: string, blob: string, };");
+		assert_eq!(Facet::decl(), "type Facet = { sel: number, hash: string, };");
+		let snapshot = Snapshot::decl();
+		assert!(snapshot.contains("desc: Descriptor") && snapshot.contains("elems: string[]"));
+	}
+}
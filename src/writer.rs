@@ -0,0 +1,520 @@
+#![cfg(feature = "storage")]
+
+use crate::{
+	reader::StateProvider,
+	state::{MatterKey, MatterValue, ObjectKey, ObjectValue},
+	storage_key::{matter_refs_storage_key, matter_storage_key, object_storage_key},
+	Blake2Hasher, Constants, Matter, MerkleHasher, Vec, H256,
+};
+use codec::{Decode, Encode};
+use core::ops::Bound;
+use sp_std::collections::btree_map::BTreeMap;
+use thiserror::Error;
+
+#[derive(Clone, Copy, Debug, Error, PartialEq, Eq)]
+pub enum WriteError {
+	#[error("op {index}: value variant does not match key variant")]
+	KeyValueMismatch { index: usize },
+	#[error("op {index}: facets or tails not in canonical order")]
+	NonCanonical { index: usize },
+	#[error("op {index}: matter blob exceeds maximum size")]
+	MatterTooLarge { index: usize },
+	#[error("op {index}: matter hash is not the blake2-256 of its blob")]
+	MatterHashMismatch { index: usize },
+	#[error("op {index}: matter has no references to release")]
+	RefUnderflow { index: usize },
+	#[error("backend failed to write the batch")]
+	Backend,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WriteOp {
+	/// Stores a matter under the blake2-256 of its blob.
+	PutMatter(H256, Matter),
+	/// Stores an object entry, taking a reference to every matter it names and releasing those
+	/// of the entry it replaces.
+	PutObject(ObjectKey, ObjectValue),
+	/// Removes an object entry and releases the matters it names.
+	RemoveObject(ObjectKey),
+	/// Adds a reference to a matter for a holder outside object state, such as an archive.
+	IncRef(H256),
+	/// Releases a reference to a matter; the count is removed when it reaches zero.
+	DecRef(H256),
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BatchReceipt {
+	pub matters: u32,
+	pub objects: u32,
+	pub removed: u32,
+	pub refs: u32,
+}
+
+pub trait StateWriter: StateProvider {
+	fn _put(&mut self, key: &[u8], value: Vec<u8>);
+	fn _remove(&mut self, key: &[u8]);
+
+	/// Writes the changes of a committed `Overlay`, `None` removing the key. Backends with atomic
+	/// batches override this so a commit lands all at once, and fail with `WriteError::Backend`
+	/// when it does not land.
+	fn _commit(&mut self, changes: BTreeMap<Vec<u8>, Option<Vec<u8>>>) -> Result<(), WriteError> {
+		for (key, value) in changes {
+			match value {
+				Some(value) => self._put(&key, value),
+				None => self._remove(&key),
+			}
+		}
+		Ok(())
+	}
+
+	/// Applies every op or none: ops are checked and staged in an `Overlay` first, and the
+	/// backend is only touched once the whole batch has been accepted.
+	fn apply_batch(&mut self, ops: Vec<WriteOp>) -> Result<BatchReceipt, WriteError>
+	where
+		Self: Sized,
+	{
+		let mut overlay = Overlay::new(self);
+		let mut receipt = BatchReceipt::default();
+		for (index, op) in ops.into_iter().enumerate() {
+			match overlay.stage(index, op)? {
+				WriteOp::PutMatter(..) => receipt.matters += 1,
+				WriteOp::PutObject(..) => receipt.objects += 1,
+				WriteOp::RemoveObject(_) => receipt.removed += 1,
+				WriteOp::IncRef(_) | WriteOp::DecRef(_) => receipt.refs += 1,
+			}
+		}
+		overlay.commit()?;
+		Ok(receipt)
+	}
+}
+
+/// Buffered writes on top of a provider. Reads see staged writes first; nothing reaches the
+/// backend until `commit`, and dropping the overlay discards everything.
+pub struct Overlay<'a, P> {
+	inner: &'a mut P,
+	changes: Layer,
+}
+
+impl<'a, P: StateProvider> Overlay<'a, P> {
+	pub fn new(inner: &'a mut P) -> Self {
+		Self { inner, changes: BTreeMap::new() }
+	}
+
+	pub fn len(&self) -> usize {
+		self.changes.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.changes.is_empty()
+	}
+
+	pub fn put(&mut self, key: Vec<u8>, value: Vec<u8>) {
+		self.changes.insert(key, Some(value));
+	}
+
+	pub fn remove(&mut self, key: Vec<u8>) {
+		self.changes.insert(key, None);
+	}
+
+	/// Checks `op` and stages it, returning the op back for bookkeeping.
+	pub fn stage(&mut self, index: usize, op: WriteOp) -> Result<WriteOp, WriteError> {
+		match &op {
+			WriteOp::PutMatter(hash, matter) => {
+				if matter.blob.len() > Constants::MATTER_BLOB_MAX {
+					return Err(WriteError::MatterTooLarge { index });
+				}
+				if Blake2Hasher::hash(&matter.blob) != *hash {
+					return Err(WriteError::MatterHashMismatch { index });
+				}
+				let key = matter_storage_key(hash);
+				self.put(key, MatterValue::Matter(matter.clone()).encode());
+			},
+			WriteOp::PutObject(key, value) => {
+				let matches = match (key, value) {
+					(ObjectKey::Sota(_), ObjectValue::Sota(_)) |
+					(ObjectKey::Snapshot(_), ObjectValue::Snapshot(_)) |
+					(ObjectKey::Tails(_), ObjectValue::Tails(_)) |
+					(ObjectKey::Facets(_), ObjectValue::Facets(_)) |
+					(ObjectKey::Heads(_), ObjectValue::Heads(_)) => true,
+					(ObjectKey::Facet(k), ObjectValue::Facet(f)) => k.sel() == f.sel,
+					_ => false,
+				};
+				if !matches {
+					return Err(WriteError::KeyValueMismatch { index });
+				}
+				if !value.is_canonical() {
+					return Err(WriteError::NonCanonical { index });
+				}
+				let storage_key = object_storage_key(key.clone());
+				self.release(index, &storage_key)?;
+				self.matters_named(value).iter().for_each(|hash| self.inc_ref(hash));
+				self.put(storage_key, value.encode());
+			},
+			WriteOp::RemoveObject(key) => {
+				let storage_key = object_storage_key(key.clone());
+				self.release(index, &storage_key)?;
+				self.remove(storage_key);
+			},
+			WriteOp::IncRef(hash) => self.inc_ref(hash),
+			WriteOp::DecRef(hash) => self.dec_ref(index, hash)?,
+		}
+		Ok(op)
+	}
+
+	/// Matters an entry holds references to: the hash of every facet, and the snapshot elements
+	/// naming a stored matter. A matter must be put before the snapshots naming it, or those
+	/// elements are not counted.
+	fn matters_named(&self, value: &ObjectValue) -> Vec<H256> {
+		match value {
+			ObjectValue::Facets(facets) => facets.facets.iter().map(|f| f.hash).collect(),
+			ObjectValue::Facet(facet) => sp_std::vec![facet.hash],
+			ObjectValue::Snapshot(snap) => snap
+				.elems
+				.iter()
+				.filter(|hash| self._get(&matter_storage_key(hash)).is_some())
+				.copied()
+				.collect(),
+			_ => Vec::new(),
+		}
+	}
+
+	/// Releases the references of the entry stored under `storage_key`, if any.
+	fn release(&mut self, index: usize, storage_key: &[u8]) -> Result<(), WriteError> {
+		let Some(old) = self._get(storage_key) else {
+			return Ok(());
+		};
+		let Ok(old) = ObjectValue::decode(&mut &old[..]) else {
+			return Ok(());
+		};
+		self.matters_named(&old).iter().try_for_each(|hash| self.dec_ref(index, hash))
+	}
+
+	fn inc_ref(&mut self, hash: &H256) {
+		let refs = self._get_matter_refs(hash).saturating_add(1);
+		self.put(matter_refs_storage_key(hash), refs.encode());
+	}
+
+	fn dec_ref(&mut self, index: usize, hash: &H256) -> Result<(), WriteError> {
+		match self._get_matter_refs(hash) {
+			0 => return Err(WriteError::RefUnderflow { index }),
+			1 => self.remove(matter_refs_storage_key(hash)),
+			refs => self.put(matter_refs_storage_key(hash), (refs - 1).encode()),
+		}
+		Ok(())
+	}
+
+	pub fn commit(self) -> Result<(), WriteError>
+	where
+		P: StateWriter,
+	{
+		self.inner._commit(self.changes)
+	}
+}
+
+impl<P: StateProvider> StateProvider for Overlay<'_, P> {
+	fn _get(&self, key: &[u8]) -> Option<Vec<u8>> {
+		Layered { layers: core::slice::from_ref(&self.changes), inner: &*self.inner }._get(key)
+	}
+
+	fn _next_key(&self, key: &[u8]) -> Option<Vec<u8>> {
+		Layered { layers: core::slice::from_ref(&self.changes), inner: &*self.inner }._next_key(key)
+	}
+}
+
+type Layer = BTreeMap<Vec<u8>, Option<Vec<u8>>>;
+
+/// Reads through a stack of layers, bottom first, before `inner`: the merge behind both
+/// `Overlay` and `OverlayState`.
+struct Layered<'a, P> {
+	layers: &'a [Layer],
+	inner: &'a P,
+}
+
+impl<P: StateProvider> StateProvider for Layered<'_, P> {
+	fn _get(&self, key: &[u8]) -> Option<Vec<u8>> {
+		match self.layers.iter().rev().find_map(|layer| layer.get(key)) {
+			Some(value) => value.clone(),
+			None => self.inner._get(key),
+		}
+	}
+
+	fn _next_key(&self, key: &[u8]) -> Option<Vec<u8>> {
+		let mut cursor = key.to_vec();
+		loop {
+			let after = (Bound::Excluded(&cursor[..]), Bound::Unbounded);
+			let staged = self
+				.layers
+				.iter()
+				.filter_map(|layer| layer.range::<[u8], _>(after).next().map(|(k, _)| k))
+				.min()
+				.cloned();
+			let next = match (staged, self.inner._next_key(&cursor)) {
+				(Some(staged), Some(inner)) => staged.min(inner),
+				(staged, inner) => staged.or(inner)?,
+			};
+			// The top layer holding the key decides; keys in no layer come from `inner`. Keys
+			// removed in that layer are skipped.
+			match self.layers.iter().rev().find_map(|layer| layer.get(&next)) {
+				Some(None) => cursor = next,
+				_ => return Some(next),
+			}
+		}
+	}
+}
+
+/// Owned state with a stack of write layers. Writes go to the top layer and reads see the
+/// layers top-down before the inner state. `begin` opens a nested layer; `commit` folds the
+/// top layer into the one below it, or into the inner state for the outermost one; `discard`
+/// drops the top layer's writes.
+pub struct OverlayState<S> {
+	inner: S,
+	layers: Vec<Layer>,
+}
+
+impl<S> OverlayState<S> {
+	pub fn new(inner: S) -> Self {
+		Self { inner, layers: sp_std::vec![Layer::new()] }
+	}
+
+	/// Number of open layers, at least 1.
+	pub fn depth(&self) -> usize {
+		self.layers.len()
+	}
+
+	pub fn begin(&mut self) {
+		self.layers.push(Layer::new());
+	}
+
+	pub fn discard(&mut self) {
+		self.layers.pop();
+		if self.layers.is_empty() {
+			self.layers.push(Layer::new());
+		}
+	}
+
+	/// Fails only when the inner state rejects the outermost layer, which is then dropped.
+	pub fn commit(&mut self) -> Result<(), WriteError>
+	where
+		S: StateWriter,
+	{
+		let top = self.layers.pop().unwrap_or_default();
+		match self.layers.last_mut() {
+			Some(below) => below.extend(top),
+			None => {
+				self.layers.push(Layer::new());
+				self.inner._commit(top)?;
+			},
+		}
+		Ok(())
+	}
+
+	/// The inner state; uncommitted writes are dropped.
+	pub fn into_inner(self) -> S {
+		self.inner
+	}
+
+	fn top(&mut self) -> &mut Layer {
+		self.layers.last_mut().expect("at least one layer")
+	}
+}
+
+impl<S: StateProvider> StateProvider for OverlayState<S> {
+	fn _get(&self, key: &[u8]) -> Option<Vec<u8>> {
+		Layered { layers: &self.layers, inner: &self.inner }._get(key)
+	}
+
+	fn _next_key(&self, key: &[u8]) -> Option<Vec<u8>> {
+		Layered { layers: &self.layers, inner: &self.inner }._next_key(key)
+	}
+}
+
+impl<S: StateProvider> StateWriter for OverlayState<S> {
+	fn _put(&mut self, key: &[u8], value: Vec<u8>) {
+		self.top().insert(key.to_vec(), Some(value));
+	}
+
+	fn _remove(&mut self, key: &[u8]) {
+		self.top().insert(key.to_vec(), None);
+	}
+
+	fn _commit(&mut self, changes: BTreeMap<Vec<u8>, Option<Vec<u8>>>) -> Result<(), WriteError> {
+		self.top().extend(changes);
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		reader::StateError,
+		state::{Arcs, Facets, OidRev, OidRevSel, Snapshot, Sota},
+		to_mime, Arc, Descriptor, Facet, Rev, StateReader, OID,
+	};
+
+	#[derive(Default)]
+	struct MemStore(BTreeMap<Vec<u8>, Vec<u8>>);
+
+	fn json_matter(blob: &[u8]) -> (H256, Matter) {
+		let matter = Matter { form: 1, mime: to_mime(b"application/json"), blob: blob.to_vec() };
+		(Blake2Hasher::hash(blob), matter)
+	}
+
+	impl StateProvider for MemStore {
+		fn _get(&self, key: &[u8]) -> Option<Vec<u8>> {
+			self.0.get(key).cloned()
+		}
+
+		fn _next_key(&self, key: &[u8]) -> Option<Vec<u8>> {
+			let after = (Bound::Excluded(key), Bound::Unbounded);
+			self.0.range::<[u8], _>(after).next().map(|(k, _)| k.clone())
+		}
+	}
+
+	impl StateWriter for MemStore {
+		fn _put(&mut self, key: &[u8], value: Vec<u8>) {
+			self.0.insert(key.to_vec(), value);
+		}
+
+		fn _remove(&mut self, key: &[u8]) {
+			self.0.remove(key);
+		}
+	}
+
+	#[test]
+	fn batch_is_all_or_nothing() {
+		let oid = OID { universe: 1, set: 2, id: 3 };
+		let desc = Descriptor { rev: Rev(1), ..Default::default() };
+		let sota = ObjectValue::Sota(Sota { desc: desc.clone(), ..Default::default() });
+		let arc = |id| Arc { kind: 0, data: 0, rel: 1, set: 2, id };
+		let unsorted = ObjectValue::Tails(Arcs { arcs: vec![arc(2), arc(1)] });
+		let key = |rev| ObjectKey::Sota(OidRev::new(&oid, Rev(rev)));
+		let tails = ObjectKey::Tails(OidRev::new(&oid, Rev(1)));
+
+		let mut store = MemStore::default();
+		let bad =
+			vec![WriteOp::PutObject(key(0), sota.clone()), WriteOp::PutObject(tails, unsorted)];
+		assert_eq!(store.apply_batch(bad), Err(WriteError::NonCanonical { index: 1 }));
+		assert!(store.0.is_empty());
+
+		let receipt = store.apply_batch(vec![WriteOp::PutObject(key(0), sota)]).unwrap();
+		assert_eq!(receipt, BatchReceipt { objects: 1, ..Default::default() });
+		let read = StateReader::<StateError>::get_descriptor(&store, &oid, Rev::LATEST);
+		assert_eq!(read.unwrap(), desc);
+
+		let matter = Matter { form: 1, mime: to_mime(b"application/json"), blob: b"{}".to_vec() };
+		let hash = Blake2Hasher::hash(&matter.blob);
+		let facet = |sel| ObjectKey::Facet(OidRevSel::new(&oid, Rev(1), sel));
+		let value = ObjectValue::Facet(Facet { sel: 7, hash });
+		let bad = vec![WriteOp::PutObject(facet(8), value.clone())];
+		assert_eq!(store.apply_batch(bad), Err(WriteError::KeyValueMismatch { index: 0 }));
+		let bad = vec![WriteOp::PutMatter([5; 32], matter.clone())];
+		assert_eq!(store.apply_batch(bad), Err(WriteError::MatterHashMismatch { index: 0 }));
+		let ops =
+			vec![WriteOp::PutMatter(hash, matter.clone()), WriteOp::PutObject(facet(7), value)];
+		store.apply_batch(ops).unwrap();
+		let read = StateReader::<StateError>::get_facet(&store, &oid, Rev(1), 7);
+		assert_eq!(read.unwrap(), matter);
+	}
+
+	#[test]
+	fn counts_matter_refs() {
+		let (a, b) = (json_matter(b"[]"), json_matter(b"{}"));
+		let (a, b, ma, mb) = (a.0, b.0, a.1, b.1);
+		let mut store = MemStore::default();
+		let ops = vec![
+			WriteOp::PutMatter(a, ma),
+			WriteOp::PutMatter(b, mb),
+			WriteOp::IncRef(a),
+			WriteOp::IncRef(a),
+			WriteOp::DecRef(a),
+		];
+		let receipt = store.apply_batch(ops).unwrap();
+		assert_eq!(receipt.refs, 3);
+		assert_eq!(store._get_matter_refs(&a), 1);
+		assert_eq!(store.find_orphans().collect::<Vec<_>>(), vec![b]);
+
+		let bad = vec![WriteOp::IncRef(b), WriteOp::DecRef(a), WriteOp::DecRef(a)];
+		assert_eq!(store.apply_batch(bad), Err(WriteError::RefUnderflow { index: 2 }));
+		store.apply_batch(vec![WriteOp::DecRef(a)]).unwrap();
+		assert_eq!(store.find_orphans().count(), 2);
+	}
+
+	#[test]
+	fn objects_hold_matter_refs() {
+		let oid = OID { universe: 1, set: 2, id: 3 };
+		let (a, ma) = json_matter(b"[]");
+		let (b, mb) = json_matter(b"{}");
+		let snapshot = |elems| {
+			let key = ObjectKey::Snapshot(OidRev::new(&oid, Rev(1)));
+			WriteOp::PutObject(key, ObjectValue::Snapshot(Snapshot { elems, ..Default::default() }))
+		};
+		let facets_key = ObjectKey::Facets(OidRev::new(&oid, Rev(1)));
+		let facets = Facets { facets: vec![Facet { sel: 1, hash: a }, Facet { sel: 2, hash: b }] };
+
+		let mut store = MemStore::default();
+		let ops = vec![
+			WriteOp::PutMatter(a, ma),
+			WriteOp::PutMatter(b, mb),
+			// The second element names no stored matter, so it holds no reference.
+			snapshot(vec![a, [7; 32]]),
+			WriteOp::PutObject(facets_key.clone(), ObjectValue::Facets(facets)),
+		];
+		let receipt = store.apply_batch(ops).unwrap();
+		assert_eq!(receipt.refs, 0);
+		assert_eq!((store._get_matter_refs(&a), store._get_matter_refs(&b)), (2, 1));
+		assert_eq!(store._get_matter_refs(&[7; 32]), 0);
+
+		// Replacing an entry swaps its references.
+		store.apply_batch(vec![snapshot(vec![b])]).unwrap();
+		assert_eq!((store._get_matter_refs(&a), store._get_matter_refs(&b)), (1, 2));
+		store.apply_batch(vec![WriteOp::RemoveObject(facets_key)]).unwrap();
+		assert_eq!(store.find_orphans().collect::<Vec<_>>(), vec![a]);
+		assert_eq!(store._get_matter_refs(&b), 1);
+
+		// A release below zero fails the batch.
+		store._remove(&matter_refs_storage_key(&b));
+		let remove = WriteOp::RemoveObject(ObjectKey::Snapshot(OidRev::new(&oid, Rev(1))));
+		assert_eq!(store.apply_batch(vec![remove]), Err(WriteError::RefUnderflow { index: 0 }));
+	}
+
+	#[test]
+	fn overlay_state_layers() {
+		let oid = |id| OID { universe: 1, set: 2, id };
+		let key = |id| ObjectKey::Sota(OidRev::new(&oid(id), Rev::LATEST));
+		let put = |id, rev| {
+			let desc = Descriptor { rev: Rev(rev), ..Default::default() };
+			WriteOp::PutObject(key(id), ObjectValue::Sota(Sota { desc, ..Default::default() }))
+		};
+		let rev = |state: &OverlayState<MemStore>, id| {
+			StateReader::<StateError>::get_descriptor(state, &oid(id), Rev::LATEST)
+				.map(|d| d.rev.get())
+				.ok()
+		};
+
+		let mut state = OverlayState::new(MemStore::default());
+		state.apply_batch(vec![put(1, 1)]).unwrap();
+		state.begin();
+		state.apply_batch(vec![put(1, 2), put(2, 1)]).unwrap();
+		assert_eq!((rev(&state, 1), rev(&state, 2)), (Some(2), Some(1)));
+		state.discard();
+		assert_eq!((rev(&state, 1), rev(&state, 2)), (Some(1), None));
+
+		state.begin();
+		state.apply_batch(vec![WriteOp::RemoveObject(key(1)), put(3, 1)]).unwrap();
+		let ids = |state: &OverlayState<MemStore>| {
+			state.iter_objects_in_set(1, 2).map(|o| o.id).collect::<Vec<_>>()
+		};
+		assert_eq!(ids(&state), vec![3]);
+		// A put in a higher layer revives a key removed below it.
+		state.begin();
+		state.apply_batch(vec![put(1, 3)]).unwrap();
+		assert_eq!(ids(&state), vec![1, 3]);
+		state.discard();
+		state.commit().unwrap();
+		assert_eq!(state.depth(), 1);
+		assert!(state.inner.0.is_empty());
+		state.commit().unwrap();
+		assert_eq!(state.into_inner().0.len(), 1);
+	}
+}